@@ -0,0 +1,463 @@
+//! Offline tests for connector mappings.
+//!
+//! A fixtures file describes test cases for the connectors of a subgraph: the inputs of a
+//! connector (`$args`, `$this`, `$batch`, `$config`, `$context` and the client request headers)
+//! and the HTTP response returned by the upstream API. Each test case builds the HTTP request with
+//! `make_request` and maps the response with `RawResponse::map_response`, without any network
+//! access, and compares the result to the `expected` snapshot of the test case.
+//!
+//! Connectors which resolve entities are mapped the way the router maps the first representation
+//! of an `_entities` fetch, so `$args`, `$this` or `$batch` should hold that representation.
+//!
+//! Response bodies are given as the JSON value the router would decode from the upstream
+//! response. Other formats (such as `responseFormat: "xml"`) are decoded by the router, so their
+//! fixtures must contain the decoded value rather than the raw body.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use apollo_compiler::Name;
+use apollo_compiler::Schema;
+use apollo_federation::connectors::ConnectSpec;
+use apollo_federation::connectors::Connector;
+use apollo_federation::connectors::EntityResolver;
+use apollo_federation::connectors::ProblemLocation;
+use apollo_federation::connectors::runtime::http_json_transport::HttpRequest;
+use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
+use apollo_federation::connectors::runtime::http_json_transport::make_request;
+use apollo_federation::connectors::runtime::inputs::ContextReader;
+use apollo_federation::connectors::runtime::inputs::RequestInputs;
+use apollo_federation::connectors::runtime::key::ResponseKey;
+use apollo_federation::connectors::runtime::mapping::Problem;
+use apollo_federation::connectors::runtime::responses::MappedResponse;
+use apollo_federation::connectors::runtime::responses::RawResponse;
+use apollo_federation::connectors::runtime::responses::handle_raw_response;
+use apollo_federation::connectors::validation::Severity;
+use apollo_federation::connectors::validation::validate;
+use apollo_federation::error::FederationError;
+use apollo_federation::link::Link;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use serde_json_bytes::ByteString;
+use serde_json_bytes::Map;
+use serde_json_bytes::Value;
+
+/// A test case of a fixtures file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestCase {
+    /// The name of the test case, used in the output
+    name: String,
+    /// The connector to test, as `Type.field` or `Type`
+    connector: String,
+    #[serde(default)]
+    args: Map<ByteString, Value>,
+    #[serde(default)]
+    this: Map<ByteString, Value>,
+    #[serde(default)]
+    batch: Vec<Map<ByteString, Value>>,
+    #[serde(default)]
+    config: Option<HashMap<String, JsonValue>>,
+    #[serde(default)]
+    context: Map<ByteString, Value>,
+    /// The headers of the client request
+    #[serde(default)]
+    headers: HashMap<String, StringOrList>,
+    /// The response of the upstream API
+    response: FixtureResponse,
+    /// The expected request and mapped data, written with `--update`
+    #[serde(default)]
+    expected: Option<JsonValue>,
+}
+
+/// The HTTP response returned by the upstream API
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixtureResponse {
+    #[serde(default = "default_status")]
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, StringOrList>,
+    /// The decoded body, which is JSON for most APIs
+    #[serde(default)]
+    body: Value,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+struct FixtureContext<'a>(&'a Map<ByteString, Value>);
+
+impl ContextReader for &FixtureContext<'_> {
+    fn get_key(&self, key: &str) -> Option<Value> {
+        self.0.get(key).cloned()
+    }
+}
+
+/// The result of a test case
+pub(crate) struct TestResult {
+    pub(crate) name: String,
+    pub(crate) actual: JsonValue,
+    pub(crate) expected: Option<JsonValue>,
+}
+
+impl TestResult {
+    pub(crate) fn passed(&self) -> bool {
+        self.expected.as_ref() == Some(&self.actual)
+    }
+}
+
+/// Runs the test cases of a fixtures file against the connectors of a subgraph schema. With
+/// `update`, the `expected` snapshots of the fixtures file are replaced by the actual results.
+pub(crate) fn run_connector_tests(
+    subgraph_path: &Path,
+    fixtures_path: &Path,
+    update: bool,
+) -> Result<Vec<TestResult>, FederationError> {
+    let (schema, connectors) = load_connectors(subgraph_path)?;
+
+    let fixtures = std::fs::read_to_string(fixtures_path).map_err(|e| {
+        FederationError::internal(format!("could not read {}: {e}", fixtures_path.display()))
+    })?;
+    let mut fixtures: JsonValue = serde_json::from_str(&fixtures).map_err(|e| {
+        FederationError::internal(format!(
+            "invalid fixtures file {}: {e}",
+            fixtures_path.display()
+        ))
+    })?;
+    let Some(test_cases) = fixtures.get_mut("tests").and_then(JsonValue::as_array_mut) else {
+        return Err(FederationError::internal(
+            "the fixtures file must contain a `tests` array",
+        ));
+    };
+
+    let mut results = Vec::with_capacity(test_cases.len());
+    for test_case in test_cases.iter_mut() {
+        let case: TestCase = serde_json::from_value(test_case.clone())
+            .map_err(|e| FederationError::internal(format!("invalid test case: {e}")))?;
+        let actual = run_test_case(&schema, &connectors, &case)?;
+        if update {
+            test_case["expected"] = actual.clone();
+        }
+        results.push(TestResult {
+            name: case.name,
+            expected: if update {
+                Some(actual.clone())
+            } else {
+                case.expected
+            },
+            actual,
+        });
+    }
+
+    if update {
+        let fixtures = serde_json::to_string_pretty(&fixtures)
+            .map_err(|e| FederationError::internal(format!("could not serialize fixtures: {e}")))?;
+        std::fs::write(fixtures_path, fixtures + "\n").map_err(|e| {
+            FederationError::internal(format!("could not write {}: {e}", fixtures_path.display()))
+        })?;
+    }
+    Ok(results)
+}
+
+fn load_connectors(subgraph_path: &Path) -> Result<(Schema, Vec<Connector>), FederationError> {
+    let sdl = std::fs::read_to_string(subgraph_path).map_err(|e| {
+        FederationError::internal(format!("could not read {}: {e}", subgraph_path.display()))
+    })?;
+    let file_name = subgraph_path.to_string_lossy();
+    let result = validate(sdl, &file_name);
+    let errors: Vec<_> = result
+        .errors
+        .iter()
+        .filter(|message| message.code.severity() == Severity::Error)
+        .map(|message| message.message.as_str())
+        .collect();
+    if !errors.is_empty() {
+        return Err(FederationError::internal(format!(
+            "invalid connectors in {}:\n{}",
+            subgraph_path.display(),
+            errors.join("\n")
+        )));
+    }
+
+    let spec = Link::for_identity(&result.schema, &ConnectSpec::identity())
+        .and_then(|(link, _)| ConnectSpec::try_from(&link.url.version).ok())
+        .ok_or_else(|| {
+            FederationError::internal(format!(
+                "{} does not link the connect spec",
+                subgraph_path.display()
+            ))
+        })?;
+    let subgraph_name = subgraph_path
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "subgraph".to_string());
+    let connectors = Connector::from_schema(&result.schema, &subgraph_name, spec)?;
+    Ok((result.schema, connectors))
+}
+
+fn run_test_case(
+    schema: &Schema,
+    connectors: &[Connector],
+    case: &TestCase,
+) -> Result<JsonValue, FederationError> {
+    let mut matching = connectors
+        .iter()
+        .filter(|connector| connector.id.has_selector(&case.connector));
+    let (Some(connector), None) = (matching.next(), matching.next()) else {
+        return Err(FederationError::internal(format!(
+            "test `{}`: `{}` must match exactly one connector",
+            case.name, case.connector
+        )));
+    };
+    let mut connector = connector.clone();
+    if let Some(config) = &case.config {
+        connector.config = Some(Arc::new(config.clone()));
+    }
+
+    let client_headers = header_map(&case.headers)
+        .map_err(|e| FederationError::internal(format!("test `{}`: {e}", case.name)))?;
+    let context = FixtureContext(&case.context);
+    let key = response_key(schema, &connector, case)
+        .map_err(|e| FederationError::internal(format!("test `{}`: {e}", case.name)))?;
+
+    let inputs = key
+        .inputs()
+        .clone()
+        .merger(&connector.request_variable_keys)
+        .config(connector.config.as_ref())
+        .context(&context)
+        .request(&connector.request_headers, &client_headers)
+        .merge();
+    let (request, _) = make_request(&connector.transport, inputs, &client_headers, &None)
+        .map_err(|e| FederationError::internal(format!("test `{}`: {e}", case.name)))?;
+    let TransportRequest::Http(HttpRequest {
+        inner: request,
+        debug: (_, request_problems),
+    }) = request;
+
+    let mut response = http::Response::builder().status(case.response.status);
+    if let Some(headers) = response.headers_mut() {
+        *headers = header_map(&case.response.headers)
+            .map_err(|e| FederationError::internal(format!("test `{}`: {e}", case.name)))?;
+    }
+    let (parts, _) = response
+        .body(())
+        .map_err(|e| FederationError::internal(format!("test `{}`: {e}", case.name)))?
+        .into_parts();
+    let path = key.path_string();
+    let raw = RawResponse::Data {
+        parts,
+        data: case.response.body.clone(),
+        key,
+        debug_request: (None, Vec::new()),
+    };
+    let (mapped, _) = handle_raw_response(raw, &connector, &context, &None, &client_headers);
+
+    let mut problems: Vec<(ProblemLocation, Problem)> = request_problems;
+    let mut output = serde_json::Map::new();
+    output.insert("path".to_string(), json!(path));
+    output.insert("request".to_string(), request_json(&request));
+    match mapped {
+        MappedResponse::Data {
+            data,
+            problems: response_problems,
+            ..
+        } => {
+            output.insert("data".to_string(), json!(data));
+            problems.extend(
+                response_problems
+                    .into_iter()
+                    .map(|problem| (ProblemLocation::Selection, problem)),
+            );
+        }
+        MappedResponse::Error { error, .. } => {
+            output.insert(
+                "error".to_string(),
+                json!({
+                    "message": error.message,
+                    "extensions": error.extensions(),
+                }),
+            );
+        }
+    }
+    if !problems.is_empty() {
+        // Problems are aggregated in a hash map, so they are sorted for stable snapshots
+        problems.sort_by(|(a_location, a), (b_location, b)| {
+            (a_location, &a.path, &a.message).cmp(&(b_location, &b.path, &b.message))
+        });
+        output.insert(
+            "problems".to_string(),
+            problems
+                .into_iter()
+                .map(|(location, problem)| {
+                    json!({
+                        "location": location,
+                        "message": problem.message,
+                        "path": problem.path,
+                        "count": problem.count,
+                    })
+                })
+                .collect(),
+        );
+    }
+    Ok(JsonValue::Object(output))
+}
+
+/// The key the router would use for a request of the connector, so that the response is mapped
+/// the same way.
+fn response_key(
+    schema: &Schema,
+    connector: &Connector,
+    case: &TestCase,
+) -> Result<ResponseKey, String> {
+    let selection = Arc::new(connector.selection.clone());
+    let inputs = RequestInputs {
+        args: case.args.clone(),
+        this: case.this.clone(),
+        batch: case.batch.clone(),
+    };
+    Ok(match &connector.entity_resolver {
+        None => ResponseKey::RootField {
+            name: connector.id.test_name(),
+            selection,
+            inputs,
+        },
+        Some(EntityResolver::Explicit | EntityResolver::TypeSingle) => ResponseKey::Entity {
+            index: 0,
+            selection,
+            inputs,
+        },
+        Some(EntityResolver::Implicit) => ResponseKey::EntityField {
+            index: 0,
+            field_name: connector.id.test_name(),
+            typename: case
+                .connector
+                .split_once('.')
+                .and_then(|(type_name, _)| Name::new(type_name).ok()),
+            selection,
+            inputs,
+        },
+        Some(EntityResolver::TypeBatch) => ResponseKey::BatchEntity {
+            keys: connector
+                .resolvable_key(schema)?
+                .ok_or_else(|| format!("`{}` has no key", case.connector))?,
+            selection,
+            inputs,
+        },
+    })
+}
+
+fn header_map(headers: &HashMap<String, StringOrList>) -> Result<HeaderMap, String> {
+    let mut header_map = HeaderMap::new();
+    // Sorted so that repeated runs produce requests with the same header order
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort_by_key(|(name, _)| name.as_str());
+    for (name, values) in headers {
+        let name = HeaderName::try_from(name.as_str()).map_err(|e| format!("{name}: {e}"))?;
+        let values = match values {
+            StringOrList::String(value) => std::slice::from_ref(value),
+            StringOrList::List(values) => values.as_slice(),
+        };
+        for value in values {
+            header_map.append(
+                name.clone(),
+                HeaderValue::try_from(value.as_str()).map_err(|e| format!("{name}: {e}"))?,
+            );
+        }
+    }
+    Ok(header_map)
+}
+
+fn request_json(request: &http::Request<String>) -> JsonValue {
+    let mut headers = serde_json::Map::new();
+    for name in request.headers().keys() {
+        let values: Vec<_> = request
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+            .collect();
+        let value = match values.as_slice() {
+            [value] => json!(value),
+            _ => json!(values),
+        };
+        headers.insert(name.to_string(), value);
+    }
+
+    let mut output = serde_json::Map::new();
+    output.insert("method".to_string(), json!(request.method().as_str()));
+    output.insert("uri".to_string(), json!(request.uri().to_string()));
+    if !headers.is_empty() {
+        output.insert("headers".to_string(), JsonValue::Object(headers));
+    }
+    if !request.body().is_empty() {
+        let body = serde_json::from_str(request.body())
+            .unwrap_or_else(|_| JsonValue::String(request.body().clone()));
+        output.insert("body".to_string(), body);
+    }
+    JsonValue::Object(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connectors_fixtures() {
+        let results = run_connector_tests(
+            Path::new("./fixtures/connectors/users.graphql"),
+            Path::new("./fixtures/connectors/users.json"),
+            false,
+        )
+        .unwrap();
+        for result in &results {
+            assert!(
+                result.passed(),
+                "{}: {}",
+                result.name,
+                serde_json::to_string_pretty(&result.actual).unwrap()
+            );
+        }
+        assert_eq!(results.len(), 4);
+    }
+
+    #[test]
+    fn test_entity_connectors_fixtures() {
+        let results = run_connector_tests(
+            Path::new("./fixtures/connectors/products.graphql"),
+            Path::new("./fixtures/connectors/products.json"),
+            false,
+        )
+        .unwrap();
+        for result in &results {
+            assert!(
+                result.passed(),
+                "{}: {}",
+                result.name,
+                serde_json::to_string_pretty(&result.actual).unwrap()
+            );
+        }
+        // Each kind of entity resolver is mapped with the key the router would use
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.actual["path"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["_entities/0", "_entities/0/reviews", "_entities"]
+        );
+    }
+}
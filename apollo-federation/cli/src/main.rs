@@ -11,6 +11,10 @@ use apollo_federation::Supergraph;
 use apollo_federation::composition::validate_satisfiability;
 use apollo_federation::connectors::expand::ExpansionResult;
 use apollo_federation::connectors::expand::expand_connectors;
+use apollo_federation::connectors::openapi::OpenApiOptions;
+use apollo_federation::connectors::openapi::generate_connectors;
+use apollo_federation::connectors::validation::Severity;
+use apollo_federation::connectors::validation::validate;
 use apollo_federation::correctness::CorrectnessError;
 use apollo_federation::error::FederationError;
 use apollo_federation::error::SingleFederationError;
@@ -28,6 +32,8 @@ use tracing_subscriber::prelude::*;
 mod bench;
 use bench::BenchOutput;
 use bench::run_bench;
+mod connectors_test;
+use connectors_test::run_connector_tests;
 
 #[derive(Parser)]
 struct QueryPlannerArgs {
@@ -136,6 +142,37 @@ enum Command {
         #[arg(long)]
         filter_prefix: Option<String>,
     },
+
+    /// Work with the connectors of a subgraph schema
+    Connectors {
+        #[command(subcommand)]
+        command: ConnectorsCommand,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum ConnectorsCommand {
+    /// Run connector mappings against recorded HTTP responses and compare the results to snapshots
+    Test {
+        /// The path to the connector-enabled subgraph schema file
+        subgraph_schema: PathBuf,
+        /// The path to the JSON fixtures file
+        fixtures: PathBuf,
+        /// Write the actual results to the fixtures file as the expected snapshots
+        #[arg(long, default_value_t = false)]
+        update: bool,
+    },
+    /// Generate a connector-enabled subgraph schema from an OpenAPI 3 document in JSON or YAML
+    Generate {
+        /// The path to the OpenAPI document, or `-` for stdin
+        openapi_document: PathBuf,
+        /// The name of the generated `@source`
+        #[arg(long, default_value = "api")]
+        source_name: String,
+        /// The base URL of the API, instead of the first server URL of the document
+        #[arg(long)]
+        base_url: Option<String>,
+    },
 }
 
 impl QueryPlannerArgs {
@@ -209,6 +246,26 @@ fn main() -> ExitCode {
             destination_dir.as_ref(),
             filter_prefix.as_deref(),
         ),
+        Command::Connectors {
+            command:
+                ConnectorsCommand::Test {
+                    subgraph_schema,
+                    fixtures,
+                    update,
+                },
+        } => match cmd_connectors_test(&subgraph_schema, &fixtures, update) {
+            // Failed tests are already reported, so they don't go through the error output
+            Ok(false) => return ExitCode::FAILURE,
+            result => result.map(|_| ()),
+        },
+        Command::Connectors {
+            command:
+                ConnectorsCommand::Generate {
+                    openapi_document,
+                    source_name,
+                    base_url,
+                },
+        } => cmd_connectors_generate(&openapi_document, source_name, base_url),
     };
     match result {
         Err(error) => {
@@ -504,6 +561,74 @@ fn cmd_bench(
     Ok(())
 }
 
+fn cmd_connectors_test(
+    subgraph_path: &Path,
+    fixtures_path: &Path,
+    update: bool,
+) -> Result<bool, FederationError> {
+    let results = run_connector_tests(subgraph_path, fixtures_path, update)?;
+    let mut failures = 0;
+    for result in &results {
+        if result.passed() {
+            println!("[PASS] {}", result.name);
+            continue;
+        }
+        failures += 1;
+        println!("[FAIL] {}", result.name);
+        let actual = serde_json::to_string_pretty(&result.actual).unwrap_or_default();
+        match &result.expected {
+            Some(expected) => {
+                let expected = serde_json::to_string_pretty(expected).unwrap_or_default();
+                let diff = similar::TextDiff::from_lines(&expected, &actual);
+                print!(
+                    "{}",
+                    diff.unified_diff()
+                        .context_radius(3)
+                        .header("expected", "actual")
+                );
+            }
+            None => println!("no expected snapshot, run with --update to record:\n{actual}"),
+        }
+    }
+    println!("\n{} passed, {} failed", results.len() - failures, failures);
+    Ok(failures == 0)
+}
+
+fn cmd_connectors_generate(
+    document_path: &Path,
+    source_name: String,
+    base_url: Option<String>,
+) -> Result<(), FederationError> {
+    let document = read_input(document_path);
+    let generated = generate_connectors(
+        &document,
+        &OpenApiOptions {
+            source_name,
+            base_url,
+        },
+    )
+    .map_err(|err| FederationError::internal(err.to_string()))?;
+    for warning in &generated.warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    let file_name = document_path.to_string_lossy();
+    let errors: Vec<_> = validate(generated.schema.clone(), &file_name)
+        .errors
+        .into_iter()
+        .filter(|message| message.code.severity() == Severity::Error)
+        .map(|message| message.message)
+        .collect();
+    if !errors.is_empty() {
+        return Err(SingleFederationError::InvalidSubgraph {
+            message: errors.join("\n"),
+        }
+        .into());
+    }
+    print!("{}", generated.schema);
+    Ok(())
+}
+
 #[test]
 fn test_bench() {
     insta::assert_json_snapshot!(
@@ -17,6 +17,7 @@ use nom::sequence::pair;
 use nom::sequence::preceded;
 use nom::sequence::tuple;
 
+use super::ArrowMethodNames;
 use super::ExternalVarPaths;
 use super::ParseResult;
 use super::PathList;
@@ -309,6 +310,33 @@ impl LitExpr {
     }
 }
 
+impl ArrowMethodNames for LitExpr {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        let mut names = vec![];
+        match self {
+            Self::String(_) | Self::Number(_) | Self::Bool(_) | Self::Null => {}
+            Self::Object(map) => {
+                for value in map.values() {
+                    names.extend(value.arrow_method_names());
+                }
+            }
+            Self::Array(vec) => {
+                for value in vec {
+                    names.extend(value.arrow_method_names());
+                }
+            }
+            Self::Path(path) => {
+                names.extend(path.arrow_method_names());
+            }
+            Self::LitPath(literal, subpath) => {
+                names.extend(literal.arrow_method_names());
+                names.extend(subpath.arrow_method_names());
+            }
+        }
+        names
+    }
+}
+
 impl ExternalVarPaths for LitExpr {
     fn external_var_paths(&self) -> Vec<&PathSelection> {
         let mut paths = vec![];
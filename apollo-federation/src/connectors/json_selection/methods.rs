@@ -8,6 +8,7 @@ use super::MethodArgs;
 use super::VarsWithPathsMap;
 use super::immutable::InputPath;
 use super::location::WithRange;
+use crate::connectors::ConnectSpec;
 
 mod common;
 
@@ -48,6 +49,18 @@ pub(super) enum ArrowMethod {
     Gt,
     Lt,
     Not,
+    Split,
+    Join,
+    Lower,
+    Upper,
+    Trim,
+    Replace,
+    RegexMatch,
+    RegexCapture,
+    ParseInt,
+    ParseFloat,
+    ToDate,
+    FormatDate,
 
     // Future methods:
     TypeOf,
@@ -167,6 +180,18 @@ impl std::ops::Deref for ArrowMethod {
             Self::Gt => &public::GtMethod,
             Self::Lt => &public::LtMethod,
             Self::Not => &public::NotMethod,
+            Self::Split => &public::SplitMethod,
+            Self::Join => &public::JoinMethod,
+            Self::Lower => &public::LowerMethod,
+            Self::Upper => &public::UpperMethod,
+            Self::Trim => &public::TrimMethod,
+            Self::Replace => &public::ReplaceMethod,
+            Self::RegexMatch => &public::RegexMatchMethod,
+            Self::RegexCapture => &public::RegexCaptureMethod,
+            Self::ParseInt => &public::ParseIntMethod,
+            Self::ParseFloat => &public::ParseFloatMethod,
+            Self::ToDate => &public::ToDateMethod,
+            Self::FormatDate => &public::FormatDateMethod,
 
             // Future methods:
             Self::TypeOf => &future::TypeOfMethod,
@@ -224,6 +249,18 @@ impl ArrowMethod {
             "ne" => Some(Self::Ne),
             "gt" => Some(Self::Gt),
             "lt" => Some(Self::Lt),
+            "split" => Some(Self::Split),
+            "join" => Some(Self::Join),
+            "lower" => Some(Self::Lower),
+            "upper" => Some(Self::Upper),
+            "trim" => Some(Self::Trim),
+            "replace" => Some(Self::Replace),
+            "regexMatch" => Some(Self::RegexMatch),
+            "regexCapture" => Some(Self::RegexCapture),
+            "parseInt" => Some(Self::ParseInt),
+            "parseFloat" => Some(Self::ParseFloat),
+            "toDate" => Some(Self::ToDate),
+            "formatDate" => Some(Self::FormatDate),
             _ => None,
         };
 
@@ -259,6 +296,39 @@ impl ArrowMethod {
                 | Self::Gt
                 | Self::Lt
                 | Self::Not
+                | Self::Split
+                | Self::Join
+                | Self::Lower
+                | Self::Upper
+                | Self::Trim
+                | Self::Replace
+                | Self::RegexMatch
+                | Self::RegexCapture
+                | Self::ParseInt
+                | Self::ParseFloat
+                | Self::ToDate
+                | Self::FormatDate
         )
     }
+
+    /// The first version of the connect spec which allows this method in
+    /// connector schemas. Validation reports uses in older versions, so that
+    /// schemas don't depend on methods their version doesn't define.
+    pub(super) const fn available_since(&self) -> ConnectSpec {
+        match self {
+            Self::Split
+            | Self::Join
+            | Self::Lower
+            | Self::Upper
+            | Self::Trim
+            | Self::Replace
+            | Self::RegexMatch
+            | Self::RegexCapture
+            | Self::ParseInt
+            | Self::ParseFloat
+            | Self::ToDate
+            | Self::FormatDate => ConnectSpec::V0_3,
+            _ => ConnectSpec::V0_1,
+        }
+    }
 }
@@ -1,8 +1,15 @@
+use apollo_compiler::collections::IndexMap;
 use serde_json::Number;
 use serde_json_bytes::Value as JSON;
 use shape::Shape;
+use shape::ShapeCase;
+use shape::location::SourceId;
 
 use crate::connectors::ApplyToError;
+use crate::connectors::json_selection::ApplyToInternal;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::helpers::json_type_name;
 use crate::connectors::json_selection::immutable::InputPath;
 use crate::connectors::json_selection::location::Ranged;
 use crate::connectors::json_selection::location::WithRange;
@@ -43,6 +50,168 @@ pub(crate) fn number_value_as_float(
     }
 }
 
+/// Returns the input of a method which operates on strings.
+pub(crate) fn string_input<'a>(
+    method_name: &WithRange<String>,
+    data: &'a JSON,
+    input_path: &InputPath<JSON>,
+) -> Result<&'a str, ApplyToError> {
+    match data {
+        JSON::String(s) => Ok(s.as_str()),
+        _ => Err(ApplyToError::new(
+            format!(
+                "Method ->{} requires a string input, not {}",
+                method_name.as_ref(),
+                json_type_name(data),
+            ),
+            input_path.to_vec(),
+            method_name.range(),
+        )),
+    }
+}
+
+/// Evaluates the arguments of a method which takes between `min` and `max`
+/// string arguments.
+pub(crate) fn string_args(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    (min, max): (usize, usize),
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<Vec<String>>, Vec<ApplyToError>) {
+    let args = method_args
+        .map(|args| args.args.as_slice())
+        .unwrap_or_default();
+    if args.len() < min || args.len() > max {
+        return (
+            None,
+            vec![ApplyToError::new(
+                format!(
+                    "Method ->{} requires {}, but {} were provided",
+                    method_name.as_ref(),
+                    expected_string_args(min, max),
+                    args.len(),
+                ),
+                input_path.to_vec(),
+                method_name.range(),
+            )],
+        );
+    }
+
+    let mut errors = Vec::new();
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        let (value, arg_errors) = arg.apply_to_path(data, vars, input_path);
+        errors.extend(arg_errors);
+        match value {
+            Some(JSON::String(s)) => values.push(s.as_str().to_string()),
+            value => {
+                errors.push(ApplyToError::new(
+                    format!(
+                        "Method ->{} requires a string argument, but received {}",
+                        method_name.as_ref(),
+                        value.map_or("null".to_string(), |value| value.to_string()),
+                    ),
+                    input_path.to_vec(),
+                    method_name.range(),
+                ));
+                return (None, errors);
+            }
+        }
+    }
+    (Some(values), errors)
+}
+
+fn expected_string_args(min: usize, max: usize) -> String {
+    let plural = if max == 1 { "" } else { "s" };
+    if min == max {
+        format!("{max} string argument{plural}")
+    } else if min == 0 {
+        format!("at most {max} string argument{plural}")
+    } else {
+        format!("{min} to {max} string arguments")
+    }
+}
+
+/// Whether a shape may be accepted by `expected`. Unknown and named shapes are
+/// accepted, as they can only be checked after name resolution.
+pub(crate) fn may_accept(expected: &Shape, shape: &Shape) -> bool {
+    shape.is_unknown() || matches!(shape.case(), ShapeCase::Name(_, _)) || expected.accepts(shape)
+}
+
+/// Checks the input shape of a method, returning an error shape if the input
+/// cannot match `expected`, described as `description` in the error.
+pub(crate) fn check_input_shape(
+    method_name: &WithRange<String>,
+    input_shape: &Shape,
+    expected: &Shape,
+    description: &str,
+    source_id: &SourceId,
+) -> Result<(), Shape> {
+    if may_accept(expected, input_shape) {
+        Ok(())
+    } else {
+        Err(Shape::error(
+            format!(
+                "Method ->{} requires {} input",
+                method_name.as_ref(),
+                description
+            ),
+            method_name.shape_location(source_id),
+        ))
+    }
+}
+
+/// Computes the shapes of the arguments of a method which takes between `min`
+/// and `max` string arguments, returning an error shape if they don't match.
+pub(crate) fn string_arg_shapes(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    (min, max): (usize, usize),
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Result<Vec<Shape>, Shape> {
+    let args = method_args
+        .map(|args| args.args.as_slice())
+        .unwrap_or_default();
+    if args.len() < min || args.len() > max {
+        return Err(Shape::error(
+            format!(
+                "Method ->{} requires {}, but {} were provided",
+                method_name.as_ref(),
+                expected_string_args(min, max),
+                args.len(),
+            ),
+            method_name.shape_location(source_id),
+        ));
+    }
+
+    args.iter()
+        .map(|arg| {
+            let shape = arg.compute_output_shape(
+                input_shape.clone(),
+                dollar_shape.clone(),
+                named_var_shapes,
+                source_id,
+            );
+            if may_accept(&Shape::string([]), &shape) {
+                Ok(shape)
+            } else {
+                Err(Shape::error(
+                    format!(
+                        "Method ->{} requires string arguments",
+                        method_name.as_ref()
+                    ),
+                    arg.shape_location(source_id),
+                ))
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,324 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+use time::Date;
+use time::OffsetDateTime;
+use time::PrimitiveDateTime;
+use time::format_description::BorrowedFormatItem;
+use time::format_description::well_known::Iso8601;
+use time::format_description::well_known::Rfc3339;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::helpers::json_type_name;
+use crate::connectors::json_selection::helpers::vec_push;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_arg_shapes;
+use crate::connectors::json_selection::methods::common::string_args;
+use crate::impl_arrow_method;
+
+// Dates are represented as RFC 3339 strings, such as "2024-01-31T12:00:00Z".
+// Formats use the format description syntax of the `time` crate, such as
+// "[year]-[month]-[day] [hour]:[minute]", documented at
+// https://time-rs.github.io/book/api/format-description.html.
+
+fn parse_format<'a>(
+    method_name: &WithRange<String>,
+    format: &'a str,
+) -> Result<Vec<BorrowedFormatItem<'a>>, String> {
+    time::format_description::parse(format).map_err(|err| {
+        format!(
+            "Method ->{} received an invalid date format: {}",
+            method_name.as_ref(),
+            err
+        )
+    })
+}
+
+/// Parses a date from a string, using `format` if provided, or from a Unix
+/// timestamp in seconds. Dates without a time are at midnight, and dates
+/// without an offset are in UTC.
+fn parse_date(
+    method_name: &WithRange<String>,
+    data: &JSON,
+    format: Option<&[BorrowedFormatItem]>,
+) -> Result<OffsetDateTime, String> {
+    match data {
+        JSON::String(s) => {
+            let s = s.as_str();
+            let parsed = match format {
+                Some(format) => OffsetDateTime::parse(s, format)
+                    .or_else(|_| PrimitiveDateTime::parse(s, format).map(|d| d.assume_utc()))
+                    .or_else(|_| Date::parse(s, format).map(|d| d.midnight().assume_utc())),
+                None => OffsetDateTime::parse(s, &Rfc3339).or_else(|_| {
+                    Date::parse(s, &Iso8601::DEFAULT).map(|d| d.midnight().assume_utc())
+                }),
+            };
+            parsed.map_err(|_| {
+                format!(
+                    "Method ->{} could not parse {} as a date",
+                    method_name.as_ref(),
+                    data
+                )
+            })
+        }
+        // Fractional seconds are rounded to milliseconds, as floats can't
+        // represent nanoseconds accurately
+        JSON::Number(number) => number
+            .as_i64()
+            .map(|seconds| seconds as i128 * 1_000_000_000)
+            .or_else(|| {
+                number
+                    .as_f64()
+                    .map(|seconds| (seconds * 1e3).round() as i128 * 1_000_000)
+            })
+            .and_then(|nanos| OffsetDateTime::from_unix_timestamp_nanos(nanos).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Method ->{} could not convert {} to a date",
+                    method_name.as_ref(),
+                    number
+                )
+            }),
+        _ => Err(format!(
+            "Method ->{} requires a string or number input, not {}",
+            method_name.as_ref(),
+            json_type_name(data),
+        )),
+    }
+}
+
+fn date_error(
+    method_name: &WithRange<String>,
+    errors: Vec<ApplyToError>,
+    message: String,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    (
+        None,
+        vec_push(
+            errors,
+            ApplyToError::new(message, input_path.to_vec(), method_name.range()),
+        ),
+    )
+}
+
+/// Checks the input and format argument shapes of a date method.
+fn date_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    arg_count: (usize, usize),
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::one([Shape::string([]), Shape::float([])], []),
+        "a string or number",
+        source_id,
+    ) {
+        return error;
+    }
+    if let Err(error) = string_arg_shapes(
+        method_name,
+        method_args,
+        arg_count,
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    ) {
+        return error;
+    }
+    Shape::string(method_name.shape_location(source_id))
+}
+
+impl_arrow_method!(ToDateMethod, to_date_method, to_date_shape);
+/// Converts a date to an RFC 3339 string. The input is a string in the format
+/// given as argument, or RFC 3339 (or a plain ISO 8601 date) without argument,
+/// or a number of seconds since the Unix epoch.
+///
+/// $->echo("31/01/2024")->toDate("[day]/[month]/[year]")   would result in "2024-01-31T00:00:00Z"
+/// $->echo(1706702400)->toDate                             would result in "2024-01-31T12:00:00Z"
+fn to_date_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let (args, errors) = string_args(method_name, method_args, (0, 1), data, vars, input_path);
+    let Some(args) = args else {
+        return (None, errors);
+    };
+    let format = match args.first().map(|format| parse_format(method_name, format)) {
+        Some(Ok(format)) => Some(format),
+        Some(Err(message)) => return date_error(method_name, errors, message, input_path),
+        None => None,
+    };
+
+    match parse_date(method_name, data, format.as_deref()).and_then(|date| {
+        date.format(&Rfc3339).map_err(|err| {
+            format!(
+                "Method ->{} could not format the date: {}",
+                method_name.as_ref(),
+                err
+            )
+        })
+    }) {
+        Ok(date) => (Some(JSON::String(date.into())), errors),
+        Err(message) => date_error(method_name, errors, message, input_path),
+    }
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn to_date_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    date_shape(
+        method_name,
+        method_args,
+        (0, 1),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    )
+}
+
+impl_arrow_method!(FormatDateMethod, format_date_method, format_date_shape);
+/// Formats a date with the format given as argument. The input is an RFC 3339
+/// string (or a plain ISO 8601 date), or a number of seconds since the Unix
+/// epoch.
+///
+/// $->echo("2024-01-31T12:00:00Z")->formatDate("[month]/[day]/[year]")   would result in "01/31/2024"
+fn format_date_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let (args, errors) = string_args(method_name, method_args, (1, 1), data, vars, input_path);
+    let Some([format]) = args.as_deref() else {
+        return (None, errors);
+    };
+    let format = match parse_format(method_name, format) {
+        Ok(format) => format,
+        Err(message) => return date_error(method_name, errors, message, input_path),
+    };
+
+    match parse_date(method_name, data, None).and_then(|date| {
+        date.format(&format).map_err(|err| {
+            format!(
+                "Method ->{} could not format the date: {}",
+                method_name.as_ref(),
+                err
+            )
+        })
+    }) {
+        Ok(date) => (Some(JSON::String(date.into())), errors),
+        Err(message) => date_error(method_name, errors, message, input_path),
+    }
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn format_date_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    date_shape(
+        method_name,
+        method_args,
+        (1, 1),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!("2024-01-31T12:00:00Z"), "$->toDate", json!("2024-01-31T12:00:00Z"))]
+    #[case(json!("2024-01-31T12:00:00.5+02:00"), "$->toDate", json!("2024-01-31T12:00:00.5+02:00"))]
+    #[case(json!("2024-01-31"), "$->toDate", json!("2024-01-31T00:00:00Z"))]
+    #[case(json!(1706702400), "$->toDate", json!("2024-01-31T12:00:00Z"))]
+    #[case(json!(1706702400.25), "$->toDate", json!("2024-01-31T12:00:00.25Z"))]
+    #[case(json!("31/01/2024"), "$->toDate('[day]/[month]/[year]')", json!("2024-01-31T00:00:00Z"))]
+    #[case(json!("31/01/2024 13:45"), "$->toDate('[day]/[month]/[year] [hour]:[minute]')", json!("2024-01-31T13:45:00Z"))]
+    #[case(json!("2024-01-31T12:00:00Z"), "$->formatDate('[month]/[day]/[year]')", json!("01/31/2024"))]
+    #[case(json!("2024-01-31T12:00:00+02:00"), "$->formatDate('[hour]:[minute] [offset_hour sign:mandatory]')", json!("12:00 +02"))]
+    #[case(json!(0), "$->formatDate('[year]-[month]-[day]')", json!("1970-01-01"))]
+    #[case(json!("31/01/2024"), "$->toDate('[day]/[month]/[year]')->formatDate('[year][month][day]')", json!("20240131"))]
+    fn date_methods(#[case] input: JSON, #[case] selection: &str, #[case] expected: JSON) {
+        assert_eq!(
+            selection!(selection).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(json!("yesterday"), "$->toDate", "Method ->toDate could not parse \"yesterday\" as a date")]
+    #[case(json!("2024-01-31"), "$->toDate('[day]/[month]/[year]')", "Method ->toDate could not parse \"2024-01-31\" as a date")]
+    #[case(json!(true), "$->toDate", "Method ->toDate requires a string or number input, not boolean")]
+    #[case(json!("2024-01-31"), "$->formatDate", "Method ->formatDate requires 1 string argument, but 0 were provided")]
+    #[case(json!("2024-01-31"), "$->formatDate('[hour')", "Method ->formatDate received an invalid date format: unclosed opening bracket at byte index 0")]
+    fn date_method_errors(#[case] input: JSON, #[case] selection: &str, #[case] expected: &str) {
+        let (result, errors) = selection!(selection).apply_to(&input);
+        assert_eq!(result, None);
+        assert_eq!(
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>(),
+            vec![expected]
+        );
+    }
+
+    fn get_shape(format: &str, input: Shape) -> Shape {
+        format_date_shape(
+            &WithRange::new("formatDate".to_string(), Some(0..10)),
+            Some(&MethodArgs {
+                args: vec![WithRange::new(LitExpr::String(format.to_string()), None)],
+                range: None,
+            }),
+            input,
+            Shape::none(),
+            &IndexMap::default(),
+            &SourceId::new("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn date_shape_should_return_string() {
+        assert_eq!(
+            get_shape("[year]", Shape::int([])),
+            Shape::string([SourceId::new("test".to_string()).location(0..10)])
+        );
+    }
+
+    #[test]
+    fn date_shape_should_error_on_bool_input() {
+        assert!(get_shape("[year]", Shape::bool([])).is_error());
+    }
+}
@@ -0,0 +1,195 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_arg_shapes;
+use crate::connectors::json_selection::methods::common::string_args;
+use crate::impl_arrow_method;
+
+impl_arrow_method!(JoinMethod, join_method, join_shape);
+/// Joins an array of scalar values into a single string using a separator.
+/// Unlike ->joinNotNull, null values are kept as empty strings, so the number
+/// of separators always matches the length of the array.
+///
+/// $->echo(["a", "b", "c"])->join(",")     would result in "a,b,c"
+/// $->echo(["a", null, "c"])->join(",")    would result in "a,,c"
+fn join_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let (args, mut errors) = string_args(method_name, method_args, (1, 1), data, vars, input_path);
+    let Some([separator]) = args.as_deref() else {
+        return (None, errors);
+    };
+
+    let values = match data {
+        JSON::Array(values) => values.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    let mut joined = Vec::with_capacity(values.len());
+    for value in values {
+        match value {
+            JSON::Bool(b) => joined.push(b.to_string()),
+            JSON::Number(number) => joined.push(number.to_string()),
+            JSON::String(s) => joined.push(s.as_str().to_string()),
+            JSON::Null => joined.push(String::new()),
+            JSON::Array(_) | JSON::Object(_) => {
+                errors.push(ApplyToError::new(
+                    format!(
+                        "Method ->{} requires an array of scalar values as input",
+                        method_name.as_ref()
+                    ),
+                    input_path.to_vec(),
+                    method_name.range(),
+                ));
+                return (None, errors);
+            }
+        }
+    }
+
+    (
+        Some(JSON::String(joined.join(separator.as_str()).into())),
+        errors,
+    )
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn join_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    let scalar = Shape::one(
+        [
+            Shape::string([]),
+            Shape::int([]),
+            Shape::float([]),
+            Shape::bool([]),
+            Shape::null([]),
+        ],
+        [],
+    );
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::one([scalar.clone(), Shape::list(scalar, [])], []),
+        "an array of scalar values as",
+        source_id,
+    ) {
+        return error;
+    }
+    if let Err(error) = string_arg_shapes(
+        method_name,
+        method_args,
+        (1, 1),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    ) {
+        return error;
+    }
+    Shape::string(method_name.shape_location(source_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!(["a", "b", "c"]), ",", json!("a,b,c"))]
+    #[case(json!([1, 2.5, true]), " ", json!("1 2.5 true"))]
+    #[case(json!(["a", null, "c"]), ",", json!("a,,c"))]
+    #[case(json!([]), ",", json!(""))]
+    #[case(json!("a"), ",", json!("a"))]
+    #[case(json!(null), ",", json!(""))]
+    fn join_should_combine_arrays_with_a_separator(
+        #[case] input: JSON,
+        #[case] separator: &str,
+        #[case] expected: JSON,
+    ) {
+        assert_eq!(
+            selection!(&format!("$->join('{}')", separator)).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[test]
+    fn join_should_error_on_nested_arrays() {
+        assert_eq!(
+            selection!("$->join(',')").apply_to(&json!([[1, 2]])),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->join requires an array of scalar values as input",
+                    "path": ["->join"],
+                    "range": [3, 7],
+                }))]
+            ),
+        );
+    }
+
+    fn get_shape(args: Vec<WithRange<LitExpr>>, input: Shape) -> Shape {
+        join_shape(
+            &WithRange::new("join".to_string(), Some(0..4)),
+            Some(&MethodArgs { args, range: None }),
+            input,
+            Shape::none(),
+            &IndexMap::default(),
+            &SourceId::new("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn join_shape_should_return_string() {
+        assert_eq!(
+            get_shape(
+                vec![WithRange::new(LitExpr::String(",".to_string()), None)],
+                Shape::list(Shape::int([]), []),
+            ),
+            Shape::string([SourceId::new("test".to_string()).location(0..4)])
+        );
+    }
+
+    #[test]
+    fn join_shape_should_error_on_list_of_lists() {
+        assert_eq!(
+            get_shape(
+                vec![WithRange::new(LitExpr::String(",".to_string()), None)],
+                Shape::list(Shape::list(Shape::string([]), []), []),
+            ),
+            Shape::error(
+                "Method ->join requires an array of scalar values as input",
+                [SourceId::new("test".to_string()).location(0..4)]
+            )
+        );
+    }
+
+    #[test]
+    fn join_shape_should_error_without_separator() {
+        assert_eq!(
+            get_shape(vec![], Shape::list(Shape::string([]), [])),
+            Shape::error(
+                "Method ->join requires 1 string argument, but 0 were provided",
+                [SourceId::new("test".to_string()).location(0..4)]
+            )
+        );
+    }
+}
@@ -39,3 +39,22 @@ mod lt;
 pub(crate) use lt::LtMethod;
 mod not;
 pub(crate) use not::NotMethod;
+mod split;
+pub(crate) use split::SplitMethod;
+mod join;
+pub(crate) use join::JoinMethod;
+mod string_transform;
+pub(crate) use string_transform::LowerMethod;
+pub(crate) use string_transform::TrimMethod;
+pub(crate) use string_transform::UpperMethod;
+mod replace;
+pub(crate) use replace::ReplaceMethod;
+mod regex;
+pub(crate) use regex::RegexCaptureMethod;
+pub(crate) use regex::RegexMatchMethod;
+mod parse_number;
+pub(crate) use parse_number::ParseFloatMethod;
+pub(crate) use parse_number::ParseIntMethod;
+mod date;
+pub(crate) use date::FormatDateMethod;
+pub(crate) use date::ToDateMethod;
@@ -0,0 +1,350 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json::Number;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::ApplyToInternal;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::helpers::json_type_name;
+use crate::connectors::json_selection::helpers::vec_push;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::may_accept;
+use crate::impl_arrow_method;
+
+fn parse_error(
+    method_name: &WithRange<String>,
+    message: String,
+    input_path: &InputPath<JSON>,
+) -> ApplyToError {
+    ApplyToError::new(message, input_path.to_vec(), method_name.range())
+}
+
+fn input_type_error(
+    method_name: &WithRange<String>,
+    data: &JSON,
+    input_path: &InputPath<JSON>,
+) -> ApplyToError {
+    parse_error(
+        method_name,
+        format!(
+            "Method ->{} requires a string or number input, not {}",
+            method_name.as_ref(),
+            json_type_name(data),
+        ),
+        input_path,
+    )
+}
+
+fn string_or_number() -> Shape {
+    Shape::one([Shape::string([]), Shape::float([])], [])
+}
+
+impl_arrow_method!(ParseIntMethod, parse_int_method, parse_int_shape);
+/// Parses a string as an integer, with an optional radix between 2 and 36
+/// (10 by default). Surrounding whitespace is ignored. Numbers are truncated
+/// to integers.
+///
+/// $->echo("42")->parseInt             would result in 42
+/// $->echo("ff")->parseInt(16)         would result in 255
+/// $->echo(4.7)->parseInt              would result in 4
+fn parse_int_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let mut errors = Vec::new();
+    let radix = match method_args.map(|args| args.args.as_slice()) {
+        None | Some([]) => 10,
+        Some([arg]) => {
+            let (value, arg_errors) = arg.apply_to_path(data, vars, input_path);
+            errors.extend(arg_errors);
+            match value.as_ref().and_then(JSON::as_u64) {
+                Some(radix @ 2..=36) => radix as u32,
+                _ => {
+                    let message = format!(
+                        "Method ->{} requires a radix between 2 and 36, but received {}",
+                        method_name.as_ref(),
+                        value.map_or("null".to_string(), |value| value.to_string()),
+                    );
+                    return (
+                        None,
+                        vec_push(errors, parse_error(method_name, message, input_path)),
+                    );
+                }
+            }
+        }
+        Some(args) => {
+            let message = format!(
+                "Method ->{} requires at most one argument, but {} were provided",
+                method_name.as_ref(),
+                args.len(),
+            );
+            return (None, vec![parse_error(method_name, message, input_path)]);
+        }
+    };
+
+    let message = match data {
+        JSON::String(s) => match i64::from_str_radix(s.as_str().trim(), radix) {
+            Ok(value) => return (Some(JSON::Number(value.into())), errors),
+            Err(_) => format!(
+                "Method ->{} could not parse {} as an integer",
+                method_name.as_ref(),
+                data,
+            ),
+        },
+        JSON::Number(number) if number.is_i64() || number.is_u64() => {
+            return (Some(data.clone()), errors);
+        }
+        JSON::Number(number) => {
+            let value = number.as_f64().unwrap_or_default().trunc();
+            if value >= i64::MIN as f64 && value <= i64::MAX as f64 {
+                return (Some(JSON::Number((value as i64).into())), errors);
+            }
+            format!(
+                "Method ->{} could not convert {} to an integer",
+                method_name.as_ref(),
+                number,
+            )
+        }
+        _ => {
+            return (
+                None,
+                vec_push(errors, input_type_error(method_name, data, input_path)),
+            );
+        }
+    };
+    (
+        None,
+        vec_push(errors, parse_error(method_name, message, input_path)),
+    )
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn parse_int_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &string_or_number(),
+        "a string or number",
+        source_id,
+    ) {
+        return error;
+    }
+
+    match method_args.map(|args| args.args.as_slice()) {
+        None | Some([]) => {}
+        Some([arg]) => {
+            let radix_shape =
+                arg.compute_output_shape(input_shape, dollar_shape, named_var_shapes, source_id);
+            if !may_accept(&Shape::int([]), &radix_shape) {
+                return Shape::error(
+                    format!(
+                        "Method ->{} requires an integer radix",
+                        method_name.as_ref()
+                    ),
+                    arg.shape_location(source_id),
+                );
+            }
+        }
+        Some(args) => {
+            return Shape::error(
+                format!(
+                    "Method ->{} requires at most one argument, but {} were provided",
+                    method_name.as_ref(),
+                    args.len(),
+                ),
+                method_name.shape_location(source_id),
+            );
+        }
+    }
+
+    Shape::int(method_name.shape_location(source_id))
+}
+
+impl_arrow_method!(ParseFloatMethod, parse_float_method, parse_float_shape);
+/// Parses a string as a floating point number. Surrounding whitespace is
+/// ignored. Numbers are returned unchanged.
+///
+/// $->echo("2.5")->parseFloat          would result in 2.5
+/// $->echo("1e3")->parseFloat          would result in 1000.0
+fn parse_float_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    _vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    if method_args.is_some() {
+        let message = format!(
+            "Method ->{} does not take any arguments",
+            method_name.as_ref()
+        );
+        return (None, vec![parse_error(method_name, message, input_path)]);
+    }
+
+    match data {
+        JSON::String(s) => match s
+            .as_str()
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+        {
+            Some(value) => (Some(JSON::Number(value)), Vec::new()),
+            None => {
+                let message = format!(
+                    "Method ->{} could not parse {} as a number",
+                    method_name.as_ref(),
+                    data,
+                );
+                (None, vec![parse_error(method_name, message, input_path)])
+            }
+        },
+        JSON::Number(_) => (Some(data.clone()), Vec::new()),
+        _ => (None, vec![input_type_error(method_name, data, input_path)]),
+    }
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn parse_float_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    _dollar_shape: Shape,
+    _named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    if method_args.is_some() {
+        return Shape::error(
+            format!(
+                "Method ->{} does not take any arguments",
+                method_name.as_ref()
+            ),
+            method_name.shape_location(source_id),
+        );
+    }
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &string_or_number(),
+        "a string or number",
+        source_id,
+    ) {
+        return error;
+    }
+    Shape::float(method_name.shape_location(source_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!("42"), "$->parseInt", json!(42))]
+    #[case(json!(" -7 "), "$->parseInt", json!(-7))]
+    #[case(json!("ff"), "$->parseInt(16)", json!(255))]
+    #[case(json!("101"), "$->parseInt(2)", json!(5))]
+    #[case(json!(42), "$->parseInt", json!(42))]
+    #[case(json!(4.7), "$->parseInt", json!(4))]
+    #[case(json!("2.5"), "$->parseFloat", json!(2.5))]
+    #[case(json!(" 1e3 "), "$->parseFloat", json!(1000.0))]
+    #[case(json!("-0.5"), "$->parseFloat", json!(-0.5))]
+    #[case(json!(2), "$->parseFloat", json!(2))]
+    fn parse_number_methods(#[case] input: JSON, #[case] selection: &str, #[case] expected: JSON) {
+        assert_eq!(
+            selection!(selection).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(json!("abc"), "$->parseInt", "Method ->parseInt could not parse \"abc\" as an integer")]
+    #[case(json!("1.5"), "$->parseInt", "Method ->parseInt could not parse \"1.5\" as an integer")]
+    #[case(json!("1"), "$->parseInt(1)", "Method ->parseInt requires a radix between 2 and 36, but received 1")]
+    #[case(json!("1"), "$->parseInt(10, 2)", "Method ->parseInt requires at most one argument, but 2 were provided")]
+    #[case(json!(true), "$->parseInt", "Method ->parseInt requires a string or number input, not boolean")]
+    #[case(json!("abc"), "$->parseFloat", "Method ->parseFloat could not parse \"abc\" as a number")]
+    #[case(json!("NaN"), "$->parseFloat", "Method ->parseFloat could not parse \"NaN\" as a number")]
+    #[case(json!(null), "$->parseFloat", "Method ->parseFloat requires a string or number input, not null")]
+    fn parse_number_errors(#[case] input: JSON, #[case] selection: &str, #[case] expected: &str) {
+        let (result, errors) = selection!(selection).apply_to(&input);
+        assert_eq!(result, None);
+        assert_eq!(
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>(),
+            vec![expected]
+        );
+    }
+
+    #[test]
+    fn parse_int_shape_should_return_int() {
+        let source_id = SourceId::new("test".to_string());
+        assert_eq!(
+            parse_int_shape(
+                &WithRange::new("parseInt".to_string(), Some(0..8)),
+                Some(&MethodArgs {
+                    args: vec![WithRange::new(LitExpr::Number(16.into()), None)],
+                    range: None
+                }),
+                Shape::string([]),
+                Shape::none(),
+                &IndexMap::default(),
+                &source_id,
+            ),
+            Shape::int([source_id.location(0..8)])
+        );
+    }
+
+    #[test]
+    fn parse_int_shape_should_error_on_string_radix() {
+        assert!(
+            parse_int_shape(
+                &WithRange::new("parseInt".to_string(), Some(0..8)),
+                Some(&MethodArgs {
+                    args: vec![WithRange::new(LitExpr::String("16".to_string()), None)],
+                    range: None
+                }),
+                Shape::string([]),
+                Shape::none(),
+                &IndexMap::default(),
+                &SourceId::new("test".to_string()),
+            )
+            .is_error()
+        );
+    }
+
+    #[test]
+    fn parse_float_shape_should_error_on_object_input() {
+        let source_id = SourceId::new("test".to_string());
+        assert_eq!(
+            parse_float_shape(
+                &WithRange::new("parseFloat".to_string(), Some(0..10)),
+                None,
+                Shape::empty_object([]),
+                Shape::none(),
+                &IndexMap::default(),
+                &source_id,
+            ),
+            Shape::error(
+                "Method ->parseFloat requires a string or number input",
+                [source_id.location(0..10)]
+            )
+        );
+    }
+}
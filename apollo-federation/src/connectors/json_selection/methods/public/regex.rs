@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use apollo_compiler::collections::IndexMap;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_arg_shapes;
+use crate::connectors::json_selection::methods::common::string_args;
+use crate::connectors::json_selection::methods::common::string_input;
+use crate::impl_arrow_method;
+
+/// The most patterns kept compiled at once. Patterns usually come from the
+/// schema, so there are few of them, but they can also come from arguments.
+const REGEX_CACHE_CAPACITY: usize = 256;
+
+/// Compiled patterns, so that a method applied to every item of a list or
+/// response doesn't compile its pattern each time.
+static REGEX_CACHE: LazyLock<Mutex<HashMap<String, Regex>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    if let Some(regex) = REGEX_CACHE.lock().get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)?;
+    let mut cache = REGEX_CACHE.lock();
+    if cache.len() >= REGEX_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+/// Evaluates the input and the pattern argument of a regex method.
+fn regex_input<'a>(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &'a JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<(&'a str, Regex)>, Vec<ApplyToError>) {
+    let input = match string_input(method_name, data, input_path) {
+        Ok(input) => input,
+        Err(error) => return (None, vec![error]),
+    };
+    let (args, mut errors) = string_args(method_name, method_args, (1, 1), data, vars, input_path);
+    let Some([pattern]) = args.as_deref() else {
+        return (None, errors);
+    };
+
+    match compile(pattern) {
+        Ok(regex) => (Some((input, regex)), errors),
+        Err(err) => {
+            errors.push(ApplyToError::new(
+                format!(
+                    "Method ->{} received an invalid regular expression: {}",
+                    method_name.as_ref(),
+                    err
+                ),
+                input_path.to_vec(),
+                method_name.range(),
+            ));
+            (None, errors)
+        }
+    }
+}
+
+/// Checks the input and pattern argument shapes of a regex method.
+fn regex_shape_error(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Option<Shape> {
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::string([]),
+        "a string",
+        source_id,
+    ) {
+        return Some(error);
+    }
+    string_arg_shapes(
+        method_name,
+        method_args,
+        (1, 1),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    )
+    .err()
+}
+
+impl_arrow_method!(RegexMatchMethod, regex_match_method, regex_match_shape);
+/// Returns whether a string contains a match of a regular expression. Use the
+/// ^ and $ anchors to match the whole string.
+///
+/// $->echo("abc-123")->regexMatch("[0-9]+")        would result in true
+/// $->echo("abc-123")->regexMatch("^[0-9]+$")      would result in false
+fn regex_match_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    match regex_input(method_name, method_args, data, vars, input_path) {
+        (Some((input, regex)), errors) => (Some(JSON::Bool(regex.is_match(input))), errors),
+        (None, errors) => (None, errors),
+    }
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn regex_match_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    regex_shape_error(
+        method_name,
+        method_args,
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    )
+    .unwrap_or_else(|| Shape::bool(method_name.shape_location(source_id)))
+}
+
+impl_arrow_method!(
+    RegexCaptureMethod,
+    regex_capture_method,
+    regex_capture_shape
+);
+/// Returns the first match of a regular expression in a string, as an array
+/// of the whole match followed by the capture groups. Groups which did not
+/// participate in the match are null. Returns null if there is no match.
+///
+/// $->echo("/users/123")->regexCapture("/users/([0-9]+)")     would result in ["/users/123", "123"]
+/// $->echo("/users/123")->regexCapture("/users/([0-9]+)")->last  would result in "123"
+fn regex_capture_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let (input, errors) = regex_input(method_name, method_args, data, vars, input_path);
+    let Some((input, regex)) = input else {
+        return (None, errors);
+    };
+
+    let captures = match regex.captures(input) {
+        Some(captures) => JSON::Array(
+            captures
+                .iter()
+                .map(|group| {
+                    group.map_or(JSON::Null, |group| {
+                        JSON::String(group.as_str().to_string().into())
+                    })
+                })
+                .collect(),
+        ),
+        None => JSON::Null,
+    };
+    (Some(captures), errors)
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn regex_capture_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    regex_shape_error(
+        method_name,
+        method_args,
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    )
+    .unwrap_or_else(|| {
+        Shape::one(
+            [
+                Shape::list(Shape::one([Shape::string([]), Shape::null([])], []), []),
+                Shape::null([]),
+            ],
+            method_name.shape_location(source_id),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!("abc-123"), "$->regexMatch('[0-9]+')", json!(true))]
+    #[case(json!("abc-123"), "$->regexMatch('^[0-9]+$')", json!(false))]
+    #[case(json!("ABC"), "$->regexMatch('(?i)abc')", json!(true))]
+    #[case(json!("/users/123"), "$->regexCapture('/users/([0-9]+)')", json!(["/users/123", "123"]))]
+    #[case(json!("/users/123"), "$->regexCapture('/users/([0-9]+)')->last", json!("123"))]
+    #[case(json!("a"), "$->regexCapture('(a)|(b)')", json!(["a", "a", null]))]
+    #[case(json!("abc"), "$->regexCapture('[0-9]')", json!(null))]
+    fn regex_methods(#[case] input: JSON, #[case] selection: &str, #[case] expected: JSON) {
+        assert_eq!(
+            selection!(selection).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[test]
+    fn regex_methods_should_error_on_invalid_pattern() {
+        let (result, errors) = selection!("$->regexMatch('(')").apply_to(&json!("("));
+        assert_eq!(result, None);
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0]
+                .message()
+                .starts_with("Method ->regexMatch received an invalid regular expression")
+        );
+    }
+
+    #[test]
+    fn regex_methods_should_error_on_non_string_input() {
+        assert_eq!(
+            selection!("$->regexCapture('a')").apply_to(&json!(null)),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->regexCapture requires a string input, not null",
+                    "path": ["->regexCapture"],
+                    "range": [3, 15],
+                }))]
+            ),
+        );
+    }
+
+    fn get_shape(pattern: &str, input: Shape) -> Shape {
+        regex_match_shape(
+            &WithRange::new("regexMatch".to_string(), Some(0..10)),
+            Some(&MethodArgs {
+                args: vec![WithRange::new(LitExpr::String(pattern.to_string()), None)],
+                range: None,
+            }),
+            input,
+            Shape::none(),
+            &IndexMap::default(),
+            &SourceId::new("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn regex_shape_should_return_bool() {
+        assert_eq!(
+            get_shape("[a-z]+", Shape::string([])),
+            Shape::bool([SourceId::new("test".to_string()).location(0..10)])
+        );
+    }
+
+    #[test]
+    fn regex_shape_should_error_on_bool_input() {
+        assert!(get_shape("[a-z]+", Shape::bool([])).is_error());
+    }
+
+    #[test]
+    fn regex_methods_should_reuse_compiled_patterns() {
+        let pattern = "^reused-[0-9]+$";
+        let first = compile(pattern).unwrap();
+        assert!(REGEX_CACHE.lock().contains_key(pattern));
+        assert_eq!(compile(pattern).unwrap().as_str(), first.as_str());
+    }
+}
@@ -0,0 +1,163 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_arg_shapes;
+use crate::connectors::json_selection::methods::common::string_args;
+use crate::connectors::json_selection::methods::common::string_input;
+use crate::impl_arrow_method;
+
+impl_arrow_method!(ReplaceMethod, replace_method, replace_shape);
+/// Replaces every occurrence of a substring with another string. The substring
+/// is matched literally; use ->regexCapture to match patterns.
+///
+/// $->echo("a-b-c")->replace("-", "_")     would result in "a_b_c"
+fn replace_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let input = match string_input(method_name, data, input_path) {
+        Ok(input) => input,
+        Err(error) => return (None, vec![error]),
+    };
+    let (args, mut errors) = string_args(method_name, method_args, (2, 2), data, vars, input_path);
+    let Some([from, to]) = args.as_deref() else {
+        return (None, errors);
+    };
+
+    if from.is_empty() {
+        errors.push(ApplyToError::new(
+            format!(
+                "Method ->{} requires a non-empty string to replace",
+                method_name.as_ref()
+            ),
+            input_path.to_vec(),
+            method_name.range(),
+        ));
+        return (None, errors);
+    }
+
+    (
+        Some(JSON::String(input.replace(from.as_str(), to).into())),
+        errors,
+    )
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn replace_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::string([]),
+        "a string",
+        source_id,
+    ) {
+        return error;
+    }
+    if let Err(error) = string_arg_shapes(
+        method_name,
+        method_args,
+        (2, 2),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    ) {
+        return error;
+    }
+    Shape::string(method_name.shape_location(source_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!("a-b-c"), "$->replace('-', '_')", json!("a_b_c"))]
+    #[case(json!("aaa"), "$->replace('aa', 'b')", json!("ba"))]
+    #[case(json!("abc"), "$->replace('x', 'y')", json!("abc"))]
+    #[case(json!("a.b"), "$->replace('.', '')", json!("ab"))]
+    fn replace_should_replace_all_occurrences(
+        #[case] input: JSON,
+        #[case] selection: &str,
+        #[case] expected: JSON,
+    ) {
+        assert_eq!(
+            selection!(selection).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[test]
+    fn replace_should_error_with_one_argument() {
+        assert_eq!(
+            selection!("$->replace('-')").apply_to(&json!("a-b")),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->replace requires 2 string arguments, but 1 were provided",
+                    "path": ["->replace"],
+                    "range": [3, 10],
+                }))]
+            ),
+        );
+    }
+
+    #[test]
+    fn replace_should_error_on_empty_substring() {
+        assert_eq!(
+            selection!("$->replace('', '-')").apply_to(&json!("ab")),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->replace requires a non-empty string to replace",
+                    "path": ["->replace"],
+                    "range": [3, 10],
+                }))]
+            ),
+        );
+    }
+
+    #[test]
+    fn replace_shape_should_return_string() {
+        let source_id = SourceId::new("test".to_string());
+        assert_eq!(
+            replace_shape(
+                &WithRange::new("replace".to_string(), Some(0..7)),
+                Some(&MethodArgs {
+                    args: vec![
+                        WithRange::new(LitExpr::String("-".to_string()), None),
+                        WithRange::new(LitExpr::String("_".to_string()), None),
+                    ],
+                    range: None
+                }),
+                Shape::string([]),
+                Shape::none(),
+                &IndexMap::default(),
+                &source_id,
+            ),
+            Shape::string([source_id.location(0..7)])
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_arg_shapes;
+use crate::connectors::json_selection::methods::common::string_args;
+use crate::connectors::json_selection::methods::common::string_input;
+use crate::impl_arrow_method;
+
+impl_arrow_method!(SplitMethod, split_method, split_shape);
+/// Splits a string into an array of strings using a separator. An empty
+/// separator splits the string into its characters.
+///
+/// $->echo("a,b,c")->split(",")        would result in ["a", "b", "c"]
+/// $->echo("abc")->split("")           would result in ["a", "b", "c"]
+fn split_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    data: &JSON,
+    vars: &VarsWithPathsMap,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    let input = match string_input(method_name, data, input_path) {
+        Ok(input) => input,
+        Err(error) => return (None, vec![error]),
+    };
+    let (args, errors) = string_args(method_name, method_args, (1, 1), data, vars, input_path);
+    let Some([separator]) = args.as_deref() else {
+        return (None, errors);
+    };
+
+    let parts: Vec<JSON> = if separator.is_empty() {
+        input
+            .chars()
+            .map(|c| JSON::String(c.to_string().into()))
+            .collect()
+    } else {
+        input
+            .split(separator)
+            .map(|part| JSON::String(part.to_string().into()))
+            .collect()
+    };
+    (Some(JSON::Array(parts)), errors)
+}
+#[allow(dead_code)] // method type-checking disabled until we add name resolution
+fn split_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    input_shape: Shape,
+    dollar_shape: Shape,
+    named_var_shapes: &IndexMap<&str, Shape>,
+    source_id: &SourceId,
+) -> Shape {
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::string([]),
+        "a string",
+        source_id,
+    ) {
+        return error;
+    }
+    if let Err(error) = string_arg_shapes(
+        method_name,
+        method_args,
+        (1, 1),
+        input_shape,
+        dollar_shape,
+        named_var_shapes,
+        source_id,
+    ) {
+        return error;
+    }
+    Shape::list(Shape::string([]), method_name.shape_location(source_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::connectors::json_selection::lit_expr::LitExpr;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case(json!("a,b,c"), ",", json!(["a", "b", "c"]))]
+    #[case(json!("a, b"), ", ", json!(["a", "b"]))]
+    #[case(json!("abc"), "", json!(["a", "b", "c"]))]
+    #[case(json!("abc"), ",", json!(["abc"]))]
+    #[case(json!(""), ",", json!([""]))]
+    #[case(json!("a,,b,"), ",", json!(["a", "", "b", ""]))]
+    fn split_should_split_strings_by_separator(
+        #[case] input: JSON,
+        #[case] separator: &str,
+        #[case] expected: JSON,
+    ) {
+        assert_eq!(
+            selection!(&format!("$->split('{}')", separator)).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[test]
+    fn split_should_error_on_non_string_input() {
+        assert_eq!(
+            selection!("$->split(',')").apply_to(&json!(["a", "b"])),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->split requires a string input, not array",
+                    "path": ["->split"],
+                    "range": [3, 8],
+                }))]
+            ),
+        );
+    }
+
+    #[test]
+    fn split_should_error_without_separator() {
+        assert_eq!(
+            selection!("$->split").apply_to(&json!("a,b")),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->split requires 1 string argument, but 0 were provided",
+                    "path": ["->split"],
+                    "range": [3, 8],
+                }))]
+            ),
+        );
+    }
+
+    #[test]
+    fn split_should_error_on_non_string_separator() {
+        assert_eq!(
+            selection!("$->split(1)").apply_to(&json!("a1b")),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->split requires a string argument, but received 1",
+                    "path": ["->split"],
+                    "range": [3, 8],
+                }))]
+            ),
+        );
+    }
+
+    fn get_shape(args: Vec<WithRange<LitExpr>>, input: Shape) -> Shape {
+        split_shape(
+            &WithRange::new("split".to_string(), Some(0..5)),
+            Some(&MethodArgs { args, range: None }),
+            input,
+            Shape::none(),
+            &IndexMap::default(),
+            &SourceId::new("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn split_shape_should_return_list_of_strings() {
+        assert_eq!(
+            get_shape(
+                vec![WithRange::new(LitExpr::String(",".to_string()), None)],
+                Shape::string([]),
+            ),
+            Shape::list(
+                Shape::string([]),
+                [SourceId::new("test".to_string()).location(0..5)]
+            )
+        );
+    }
+
+    #[test]
+    fn split_shape_should_allow_unknown_input() {
+        assert!(
+            !get_shape(
+                vec![WithRange::new(LitExpr::String(",".to_string()), None)],
+                Shape::unknown([]),
+            )
+            .is_error()
+        );
+    }
+
+    #[test]
+    fn split_shape_should_error_on_non_string_input() {
+        assert_eq!(
+            get_shape(
+                vec![WithRange::new(LitExpr::String(",".to_string()), None)],
+                Shape::int([]),
+            ),
+            Shape::error(
+                "Method ->split requires a string input",
+                [SourceId::new("test".to_string()).location(0..5)]
+            )
+        );
+    }
+
+    #[test]
+    fn split_shape_should_error_on_non_string_separator() {
+        assert!(
+            get_shape(
+                vec![WithRange::new(LitExpr::Bool(true), None)],
+                Shape::string([])
+            )
+            .is_error()
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use apollo_compiler::collections::IndexMap;
+use serde_json_bytes::Value as JSON;
+use shape::Shape;
+use shape::ShapeCase;
+use shape::location::SourceId;
+
+use crate::connectors::json_selection::ApplyToError;
+use crate::connectors::json_selection::MethodArgs;
+use crate::connectors::json_selection::VarsWithPathsMap;
+use crate::connectors::json_selection::immutable::InputPath;
+use crate::connectors::json_selection::location::Ranged;
+use crate::connectors::json_selection::location::WithRange;
+use crate::connectors::json_selection::methods::common::check_input_shape;
+use crate::connectors::json_selection::methods::common::string_input;
+use crate::impl_arrow_method;
+
+/// This module exports methods which transform a string without taking any
+/// arguments (lower, upper, trim).
+///
+/// Examples:
+/// $->echo("Hello")->lower         results in "hello"
+/// $->echo("Hello")->upper         results in "HELLO"
+/// $->echo("  Hello  ")->trim      results in "Hello"
+fn string_transform_method(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    transform: impl Fn(&str) -> String,
+    data: &JSON,
+    input_path: &InputPath<JSON>,
+) -> (Option<JSON>, Vec<ApplyToError>) {
+    if method_args.is_some() {
+        return (
+            None,
+            vec![ApplyToError::new(
+                format!(
+                    "Method ->{} does not take any arguments",
+                    method_name.as_ref()
+                ),
+                input_path.to_vec(),
+                method_name.range(),
+            )],
+        );
+    }
+
+    match string_input(method_name, data, input_path) {
+        Ok(input) => (Some(JSON::String(transform(input).into())), Vec::new()),
+        Err(error) => (None, vec![error]),
+    }
+}
+
+fn string_transform_shape(
+    method_name: &WithRange<String>,
+    method_args: Option<&MethodArgs>,
+    transform: impl Fn(&str) -> String,
+    input_shape: Shape,
+    source_id: &SourceId,
+) -> Shape {
+    if method_args.is_some() {
+        return Shape::error(
+            format!(
+                "Method ->{} does not take any arguments",
+                method_name.as_ref()
+            ),
+            method_name.shape_location(source_id),
+        );
+    }
+    if let Err(error) = check_input_shape(
+        method_name,
+        &input_shape,
+        &Shape::string([]),
+        "a string",
+        source_id,
+    ) {
+        return error;
+    }
+
+    match input_shape.case() {
+        ShapeCase::String(Some(value)) => {
+            Shape::string_value(&transform(value), method_name.shape_location(source_id))
+        }
+        _ => Shape::string(method_name.shape_location(source_id)),
+    }
+}
+
+macro_rules! string_transform_method {
+    ($struct_name:ident, $fn_name:ident, $shape_fn_name:ident, $transform:expr) => {
+        impl_arrow_method!($struct_name, $fn_name, $shape_fn_name);
+        fn $fn_name(
+            method_name: &WithRange<String>,
+            method_args: Option<&MethodArgs>,
+            data: &JSON,
+            _vars: &VarsWithPathsMap,
+            input_path: &InputPath<JSON>,
+        ) -> (Option<JSON>, Vec<ApplyToError>) {
+            string_transform_method(method_name, method_args, $transform, data, input_path)
+        }
+        #[allow(dead_code)] // method type-checking disabled until we add name resolution
+        fn $shape_fn_name(
+            method_name: &WithRange<String>,
+            method_args: Option<&MethodArgs>,
+            input_shape: Shape,
+            _dollar_shape: Shape,
+            _named_var_shapes: &IndexMap<&str, Shape>,
+            source_id: &SourceId,
+        ) -> Shape {
+            string_transform_shape(method_name, method_args, $transform, input_shape, source_id)
+        }
+    };
+}
+
+string_transform_method!(LowerMethod, lower_method, lower_shape, str::to_lowercase);
+string_transform_method!(UpperMethod, upper_method, upper_shape, str::to_uppercase);
+string_transform_method!(TrimMethod, trim_method, trim_shape, |s: &str| s
+    .trim()
+    .to_string());
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::selection;
+
+    #[rstest::rstest]
+    #[case("$->lower", json!("Hello World"), json!("hello world"))]
+    #[case("$->upper", json!("Hello World"), json!("HELLO WORLD"))]
+    #[case("$->upper", json!("straße"), json!("STRASSE"))]
+    #[case("$->trim", json!("  Hello\n"), json!("Hello"))]
+    #[case("$->trim->lower", json!(" ABC "), json!("abc"))]
+    fn string_transforms(#[case] selection: &str, #[case] input: JSON, #[case] expected: JSON) {
+        assert_eq!(
+            selection!(selection).apply_to(&input),
+            (Some(expected), vec![]),
+        );
+    }
+
+    #[test]
+    fn string_transforms_should_error_on_non_string_input() {
+        assert_eq!(
+            selection!("$->lower").apply_to(&json!(1)),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->lower requires a string input, not number",
+                    "path": ["->lower"],
+                    "range": [3, 8],
+                }))]
+            ),
+        );
+    }
+
+    #[test]
+    fn string_transforms_should_error_on_arguments() {
+        assert_eq!(
+            selection!("$->trim(' ')").apply_to(&json!(" a ")),
+            (
+                None,
+                vec![ApplyToError::from_json(&json!({
+                    "message": "Method ->trim does not take any arguments",
+                    "path": ["->trim"],
+                    "range": [3, 7],
+                }))]
+            ),
+        );
+    }
+
+    fn get_shape(input: Shape) -> Shape {
+        upper_shape(
+            &WithRange::new("upper".to_string(), Some(0..5)),
+            None,
+            input,
+            Shape::none(),
+            &IndexMap::default(),
+            &SourceId::new("test".to_string()),
+        )
+    }
+
+    #[test]
+    fn string_transform_shape_should_transform_known_values() {
+        assert_eq!(
+            get_shape(Shape::string_value("abc", [])),
+            Shape::string_value("ABC", [SourceId::new("test".to_string()).location(0..5)])
+        );
+        assert_eq!(
+            get_shape(Shape::string([])),
+            Shape::string([SourceId::new("test".to_string()).location(0..5)])
+        );
+    }
+
+    #[test]
+    fn string_transform_shape_should_error_on_non_string_input() {
+        assert_eq!(
+            get_shape(Shape::bool([])),
+            Shape::error(
+                "Method ->upper requires a string input",
+                [SourceId::new("test".to_string()).location(0..5)]
+            )
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 use std::hash::Hash;
+use std::ops::Range;
 use std::str::FromStr;
 
 use itertools::Itertools;
@@ -30,6 +31,8 @@ use super::location::WithRange;
 use super::location::merge_ranges;
 use super::location::new_span;
 use super::location::ranged_span;
+use super::methods::ArrowMethod;
+use crate::connectors::ConnectSpec;
 use crate::connectors::Namespace;
 use crate::connectors::variable::VariableNamespace;
 use crate::connectors::variable::VariableReference;
@@ -78,6 +81,12 @@ pub(crate) trait ExternalVarPaths {
     fn external_var_paths(&self) -> Vec<&PathSelection>;
 }
 
+/// Collects the names of all `->method`s applied in a selection, including
+/// those within method arguments.
+pub(crate) trait ArrowMethodNames {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>>;
+}
+
 // JSONSelection     ::= PathSelection | NakedSubSelection
 // NakedSubSelection ::= NamedSelection* StarSelection?
 
@@ -221,6 +230,31 @@ impl JSONSelection {
             .into_iter()
             .flat_map(|var_path| var_path.variable_reference())
     }
+
+    /// The names and ranges of the `->method`s in this selection which the
+    /// given version of the connect spec doesn't allow.
+    pub(crate) fn methods_unavailable_in(
+        &self,
+        spec: ConnectSpec,
+    ) -> Vec<(&str, Option<Range<usize>>)> {
+        self.arrow_method_names()
+            .into_iter()
+            .filter(|name| {
+                ArrowMethod::lookup(name.as_ref())
+                    .is_some_and(|method| method.available_since() > spec)
+            })
+            .map(|name| (name.as_str(), name.range()))
+            .collect()
+    }
+}
+
+impl ArrowMethodNames for JSONSelection {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        match self {
+            JSONSelection::Named(subselect) => subselect.arrow_method_names(),
+            JSONSelection::Path(path) => path.arrow_method_names(),
+        }
+    }
 }
 
 impl ExternalVarPaths for JSONSelection {
@@ -417,6 +451,16 @@ impl NamedSelection {
     }
 }
 
+impl ArrowMethodNames for NamedSelection {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        match self {
+            Self::Field(_, _, Some(sub)) | Self::Group(_, sub) => sub.arrow_method_names(),
+            Self::Path { path, .. } => path.arrow_method_names(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 impl ExternalVarPaths for NamedSelection {
     fn external_var_paths(&self) -> Vec<&PathSelection> {
         match self {
@@ -522,6 +566,12 @@ impl ExternalVarPaths for PathSelection {
     }
 }
 
+impl ArrowMethodNames for PathSelection {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        self.path.arrow_method_names()
+    }
+}
+
 impl From<PathList> for PathSelection {
     fn from(path: PathList) -> Self {
         Self {
@@ -850,6 +900,33 @@ impl ExternalVarPaths for PathList {
     }
 }
 
+impl ArrowMethodNames for PathList {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        let mut names = Vec::new();
+        match self {
+            PathList::Var(_, rest) | PathList::Key(_, rest) => {
+                names.extend(rest.arrow_method_names());
+            }
+            PathList::Expr(expr, rest) => {
+                names.extend(expr.arrow_method_names());
+                names.extend(rest.arrow_method_names());
+            }
+            PathList::Method(name, opt_args, rest) => {
+                names.push(name);
+                if let Some(args) = opt_args {
+                    for lit_arg in &args.args {
+                        names.extend(lit_arg.arrow_method_names());
+                    }
+                }
+                names.extend(rest.arrow_method_names());
+            }
+            PathList::Selection(sub) => names.extend(sub.arrow_method_names()),
+            PathList::Empty => {}
+        }
+        names
+    }
+}
+
 // SubSelection ::= "{" NakedSubSelection "}"
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -958,6 +1035,16 @@ impl ExternalVarPaths for SubSelection {
     }
 }
 
+impl ArrowMethodNames for SubSelection {
+    fn arrow_method_names(&self) -> Vec<&WithRange<String>> {
+        let mut names = Vec::new();
+        for selection in &self.selections {
+            names.extend(selection.arrow_method_names());
+        }
+        names
+    }
+}
+
 // Alias ::= Key ":"
 
 #[derive(Debug, PartialEq, Eq, Clone)]
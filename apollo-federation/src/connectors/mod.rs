@@ -31,6 +31,7 @@ mod header;
 mod id;
 mod json_selection;
 mod models;
+pub mod openapi;
 pub use models::ProblemLocation;
 pub mod runtime;
 pub(crate) mod spec;
@@ -50,7 +51,9 @@ pub use json_selection::SubSelection;
 pub use models::CustomConfiguration;
 pub use models::Header;
 pub use spec::ConnectHTTPArguments;
+pub use spec::ConnectPaginationArguments;
 pub use spec::ConnectSpec;
+pub use spec::PaginationMode;
 pub use spec::SourceHTTPArguments;
 pub use string_template::Error as StringTemplateError;
 pub use string_template::StringTemplate;
@@ -65,6 +68,7 @@ pub use self::models::HeaderSource;
 pub use self::models::HttpJsonTransport;
 pub use self::models::MakeUriError;
 pub use self::models::OriginatingDirective;
+pub use self::models::RESPONSE_FORMATS;
 pub use self::models::SourceName;
 pub use self::spec::connect::ConnectBatchArguments;
 use crate::schema::position::ObjectFieldDefinitionPosition;
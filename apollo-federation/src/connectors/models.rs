@@ -24,6 +24,7 @@ pub use self::headers::OriginatingDirective;
 pub use self::http_json_transport::HTTPMethod;
 pub use self::http_json_transport::HttpJsonTransport;
 pub use self::http_json_transport::MakeUriError;
+pub use self::http_json_transport::RESPONSE_FORMATS;
 pub use self::problem_location::ProblemLocation;
 pub use self::source::SourceName;
 use super::ConnectId;
@@ -528,6 +529,8 @@ mod tests {
                     source_query_params: None,
                     connect_path: None,
                     connect_query_params: None,
+                    response_format: None,
+                    pagination: None,
                 },
                 selection: Named(
                     SubSelection {
@@ -647,6 +650,8 @@ mod tests {
                     source_query_params: None,
                     connect_path: None,
                     connect_query_params: None,
+                    response_format: None,
+                    pagination: None,
                 },
                 selection: Named(
                     SubSelection {
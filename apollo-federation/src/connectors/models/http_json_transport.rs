@@ -24,6 +24,7 @@ use crate::connectors::StringTemplate;
 use crate::connectors::json_selection::ExternalVarPaths;
 use crate::connectors::models::Header;
 use crate::connectors::spec::ConnectHTTPArguments;
+use crate::connectors::spec::ConnectPaginationArguments;
 use crate::connectors::spec::SourceHTTPArguments;
 use crate::connectors::string_template;
 use crate::connectors::string_template::UriString;
@@ -31,6 +32,9 @@ use crate::connectors::string_template::write_value;
 use crate::connectors::variable::VariableReference;
 use crate::error::FederationError;
 
+/// The formats that `responseFormat` can name, ignoring case. The router has a decoder for each.
+pub const RESPONSE_FORMATS: [&str; 5] = ["json", "text", "xml", "csv", "ndjson"];
+
 #[derive(Clone, Debug, Default)]
 pub struct HttpJsonTransport {
     pub source_template: Option<StringTemplate>,
@@ -42,6 +46,10 @@ pub struct HttpJsonTransport {
     pub source_query_params: Option<JSONSelection>,
     pub connect_path: Option<JSONSelection>,
     pub connect_query_params: Option<JSONSelection>,
+    /// The format of the response body, overriding the format given by its `Content-Type`
+    pub response_format: Option<String>,
+    /// Settings for requesting every page of a paginated endpoint
+    pub pagination: Option<ConnectPaginationArguments>,
 }
 
 impl HttpJsonTransport {
@@ -88,6 +96,8 @@ impl HttpJsonTransport {
             source_query_params: source.and_then(|s| s.query_params.clone()),
             connect_path: http.path,
             connect_query_params: http.query_params,
+            response_format: http.response_format,
+            pagination: http.pagination,
         })
     }
 
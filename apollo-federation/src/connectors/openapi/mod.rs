@@ -0,0 +1,650 @@
+//! Generation of connector schemas from OpenAPI documents.
+//!
+//! [`generate_connectors`] reads an OpenAPI 3 document and writes a subgraph schema with a
+//! `@source` for the API and a `@connect` field for each operation with a JSON response: `GET`
+//! operations become `Query` fields, and other operations `Mutation` fields. Path and query
+//! parameters become field arguments, and JSON request bodies an `input` argument. Response and
+//! request body schemas become object and input types, with selections mapping between the JSON
+//! property names and the GraphQL field names. A type with an `id` property that can be fetched by
+//! an operation such as `GET /users/{userId}` becomes an entity with `@key(fields: "id")`, resolved
+//! by that operation.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+use self::types::FieldType;
+use self::types::ObjectDef;
+use self::types::TypeRef;
+use self::types::Types;
+use self::types::field_name;
+use self::types::is_nullable;
+use self::types::selection_key;
+use self::types::type_name;
+use self::types::unique_name;
+
+#[cfg(test)]
+mod tests;
+mod types;
+
+/// The operations converted to connectors, with the name of their `@connect` HTTP method
+const METHODS: [(&str, &str); 5] = [
+    ("get", "GET"),
+    ("post", "POST"),
+    ("put", "PUT"),
+    ("patch", "PATCH"),
+    ("delete", "DELETE"),
+];
+
+/// Options for [`generate_connectors`]
+#[derive(Debug, Clone)]
+pub struct OpenApiOptions {
+    /// The name of the generated `@source`
+    pub source_name: String,
+    /// The base URL of the API, replacing the first server URL of the document
+    pub base_url: Option<String>,
+}
+
+impl Default for OpenApiOptions {
+    fn default() -> Self {
+        Self {
+            source_name: "api".to_string(),
+            base_url: None,
+        }
+    }
+}
+
+/// A subgraph schema generated from an OpenAPI document
+#[derive(Debug, Clone)]
+pub struct GeneratedConnectors {
+    /// The subgraph schema
+    pub schema: String,
+    /// The parts of the document that could not be converted, such as operations without JSON
+    /// responses
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum OpenApiError {
+    #[error("Error parsing OpenAPI document: {0}")]
+    Parse(String),
+    #[error("Unsupported OpenAPI version `{0}`, only OpenAPI 3 documents are supported")]
+    UnsupportedVersion(String),
+    #[error("The OpenAPI document has no server URL, a base URL must be provided")]
+    MissingBaseUrl,
+    #[error("The base URL `{0}` is not an absolute HTTP URL")]
+    InvalidBaseUrl(String),
+    #[error("The OpenAPI document has no GET operations with JSON responses")]
+    NoQueries,
+}
+
+/// Generates a subgraph schema with connectors for the operations of an OpenAPI 3 document, in
+/// JSON or YAML.
+pub fn generate_connectors(
+    document: &str,
+    options: &OpenApiOptions,
+) -> Result<GeneratedConnectors, OpenApiError> {
+    let document = parse_document(document)?;
+    match document.get("openapi").and_then(JsonValue::as_str) {
+        Some(version) if version.starts_with("3.") => {}
+        Some(version) => return Err(OpenApiError::UnsupportedVersion(version.to_string())),
+        None => {
+            let version = document
+                .get("swagger")
+                .and_then(JsonValue::as_str)
+                .unwrap_or("unknown");
+            return Err(OpenApiError::UnsupportedVersion(version.to_string()));
+        }
+    }
+
+    let base_url = match &options.base_url {
+        Some(base_url) => base_url.clone(),
+        None => server_url(&document).ok_or(OpenApiError::MissingBaseUrl)?,
+    };
+    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+        return Err(OpenApiError::InvalidBaseUrl(base_url));
+    }
+
+    let mut generator = Generator::new(&document);
+    for (path, item) in document
+        .get("paths")
+        .and_then(JsonValue::as_object)
+        .into_iter()
+        .flatten()
+    {
+        let Some(item) = generator.types.resolve(item) else {
+            continue;
+        };
+        for (key, method) in METHODS {
+            if let Some(operation) = item.get(key) {
+                generator.add_operation(path, item, method, operation);
+            }
+        }
+    }
+    generator.add_entities();
+    generator.types.remove_unselected_fields(
+        generator
+            .operations
+            .iter()
+            .map(|operation| &operation.return_type),
+    );
+
+    if !generator
+        .operations
+        .iter()
+        .any(|operation| !operation.mutation)
+    {
+        return Err(OpenApiError::NoQueries);
+    }
+    Ok(GeneratedConnectors {
+        schema: generator.print(&options.source_name, base_url.trim_end_matches('/')),
+        warnings: generator.types.warnings,
+    })
+}
+
+fn parse_document(document: &str) -> Result<JsonValue, OpenApiError> {
+    if document.trim_start().starts_with('{') {
+        return serde_json::from_str(document).map_err(|err| OpenApiError::Parse(err.to_string()));
+    }
+    serde_yaml::from_str(document).map_err(|err| OpenApiError::Parse(err.to_string()))
+}
+
+/// Returns the URL of the first server of the document, with its variables set to their defaults
+fn server_url(document: &JsonValue) -> Option<String> {
+    let server = document.get("servers")?.as_array()?.first()?;
+    let mut url = server.get("url")?.as_str()?.to_string();
+    for (name, variable) in server
+        .get("variables")
+        .and_then(JsonValue::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(default) = variable.get("default").and_then(JsonValue::as_str) {
+            url = url.replace(&format!("{{{name}}}"), default);
+        }
+    }
+    Some(url)
+}
+
+enum PathSegment {
+    Literal(String),
+    Argument(String),
+}
+
+struct Argument {
+    name: String,
+    ty: FieldType,
+    description: Option<String>,
+}
+
+/// An operation converted to a `Query` or `Mutation` field with a connector
+struct Operation {
+    mutation: bool,
+    name: String,
+    description: Option<String>,
+    method: &'static str,
+    path: Vec<PathSegment>,
+    arguments: Vec<Argument>,
+    /// The query parameters, with the arguments providing their values
+    query_params: Vec<(String, String)>,
+    /// The argument providing the request body
+    body: Option<String>,
+    return_type: FieldType,
+    entity: bool,
+}
+
+struct Generator<'a> {
+    types: Types<'a>,
+    operations: Vec<Operation>,
+    query_names: HashSet<String>,
+    mutation_names: HashSet<String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(document: &'a JsonValue) -> Self {
+        Self {
+            types: Types::new(document),
+            operations: Vec::new(),
+            query_names: HashSet::new(),
+            mutation_names: HashSet::new(),
+        }
+    }
+
+    fn add_operation(
+        &mut self,
+        path: &str,
+        item: &'a JsonValue,
+        method: &'static str,
+        operation: &'a JsonValue,
+    ) {
+        let label = format!("{method} {path}");
+        let Some(response) = self.response_schema(operation) else {
+            self.types.warn(format!(
+                "`{label}` has no JSON success response and is skipped"
+            ));
+            return;
+        };
+        let request_body = operation
+            .get("requestBody")
+            .and_then(|body| self.types.resolve(body));
+        let body_schema = request_body.and_then(json_schema);
+        let body_required = request_body
+            .and_then(|body| body.get("required"))
+            .and_then(JsonValue::as_bool)
+            == Some(true);
+        if request_body.is_some() && body_schema.is_none() {
+            if body_required {
+                self.types.warn(format!(
+                    "`{label}` has a request body without a JSON schema and is skipped"
+                ));
+                return;
+            }
+            self.types.warn(format!(
+                "`{label}` has an optional request body without a JSON schema, which is not sent"
+            ));
+        }
+
+        let mutation = method != "GET";
+        let name = operation
+            .get("operationId")
+            .and_then(JsonValue::as_str)
+            .map(field_name)
+            .unwrap_or_else(|| field_name(&format!("{method} {path}")));
+        let names = if mutation {
+            &mut self.mutation_names
+        } else {
+            &mut self.query_names
+        };
+        let name = unique_name(names, name);
+        let hint = type_name(&name);
+
+        let mut arguments = Vec::new();
+        let mut argument_names = HashSet::new();
+        let mut path_arguments = HashMap::new();
+        let mut query_params = Vec::new();
+        for parameter in self.parameters(item, operation) {
+            let (Some(parameter_name), Some(location)) = (
+                parameter.get("name").and_then(JsonValue::as_str),
+                parameter.get("in").and_then(JsonValue::as_str),
+            ) else {
+                continue;
+            };
+            if location != "path" && location != "query" {
+                self.types.warn(format!(
+                    "`{label}` has a {location} parameter `{parameter_name}`, which is not sent"
+                ));
+                continue;
+            }
+            let argument_name = unique_name(&mut argument_names, field_name(parameter_name));
+            let (ty, nullable) = match parameter.get("schema") {
+                Some(schema) => (
+                    self.types.convert(
+                        schema,
+                        &format!("{hint}{}", type_name(parameter_name)),
+                        true,
+                    ),
+                    is_nullable(schema),
+                ),
+                None => (TypeRef::scalar("String"), false),
+            };
+            let required = parameter.get("required").and_then(JsonValue::as_bool) == Some(true);
+            if location == "path" {
+                path_arguments.insert(parameter_name, argument_name.clone());
+            } else {
+                query_params.push((parameter_name.to_string(), argument_name.clone()));
+            }
+            arguments.push(Argument {
+                name: argument_name,
+                ty: FieldType {
+                    ty,
+                    non_null: location == "path" || (required && !nullable),
+                },
+                description: description(parameter),
+            });
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = path;
+        while let Some((literal, after)) = rest.split_once('{') {
+            let Some((parameter, after)) = after.split_once('}') else {
+                break;
+            };
+            if !literal.is_empty() {
+                segments.push(PathSegment::Literal(literal.to_string()));
+            }
+            let argument = match path_arguments.get(parameter) {
+                Some(argument) => argument.clone(),
+                None => {
+                    self.types.warn(format!(
+                        "`{label}` has an undeclared path parameter `{parameter}`, which is a `String!` argument"
+                    ));
+                    let argument = unique_name(&mut argument_names, field_name(parameter));
+                    path_arguments.insert(parameter, argument.clone());
+                    arguments.push(Argument {
+                        name: argument.clone(),
+                        ty: FieldType {
+                            ty: TypeRef::scalar("String"),
+                            non_null: true,
+                        },
+                        description: None,
+                    });
+                    argument
+                }
+            };
+            segments.push(PathSegment::Argument(argument));
+            rest = after;
+        }
+        if !rest.is_empty() {
+            segments.push(PathSegment::Literal(rest.to_string()));
+        }
+
+        let body = body_schema.map(|schema| {
+            let argument = unique_name(&mut argument_names, "input".to_string());
+            arguments.push(Argument {
+                name: argument.clone(),
+                ty: FieldType {
+                    ty: self.types.convert(schema, &hint, true),
+                    non_null: body_required,
+                },
+                description: request_body.and_then(description),
+            });
+            argument
+        });
+
+        let return_type = FieldType {
+            ty: self
+                .types
+                .convert(response, &format!("{hint}Response"), false),
+            non_null: false,
+        };
+        self.operations.push(Operation {
+            mutation,
+            name,
+            description: description(operation),
+            method,
+            path: segments,
+            arguments,
+            query_params,
+            body,
+            return_type,
+            entity: false,
+        });
+    }
+
+    /// Returns the JSON schema of the first success response of an operation
+    fn response_schema(&self, operation: &'a JsonValue) -> Option<&'a JsonValue> {
+        operation
+            .get("responses")?
+            .as_object()?
+            .iter()
+            .filter(|(status, _)| status.starts_with('2'))
+            .filter_map(|(_, response)| self.types.resolve(response))
+            .find_map(json_schema)
+    }
+
+    /// Returns the parameters of an operation, including the parameters of its path item which it
+    /// doesn't override
+    fn parameters(&self, item: &'a JsonValue, operation: &'a JsonValue) -> Vec<&'a JsonValue> {
+        let mut parameters: Vec<&JsonValue> = Vec::new();
+        for parameter in [item, operation]
+            .into_iter()
+            .filter_map(|object| object.get("parameters").and_then(JsonValue::as_array))
+            .flatten()
+            .filter_map(|parameter| self.types.resolve(parameter))
+        {
+            let is_same = |existing: &&JsonValue| {
+                existing.get("name") == parameter.get("name")
+                    && existing.get("in") == parameter.get("in")
+            };
+            match parameters.iter_mut().find(|existing| is_same(existing)) {
+                Some(existing) => *existing = parameter,
+                None => parameters.push(parameter),
+            }
+        }
+        parameters
+    }
+
+    /// Turns the types returned by lookups of a single object by its `id`, such as
+    /// `GET /users/{userId}`, into entities resolved by those operations
+    fn add_entities(&mut self) {
+        for operation in &mut self.operations {
+            if operation.mutation {
+                continue;
+            }
+            let (TypeRef::Named { name, .. }, [argument], Some(PathSegment::Argument(last))) = (
+                &operation.return_type.ty,
+                operation.arguments.as_mut_slice(),
+                operation.path.last_mut(),
+            ) else {
+                continue;
+            };
+            if argument.name != *last {
+                continue;
+            }
+            let Some(object) = self.types.objects.get_mut(name) else {
+                continue;
+            };
+            let Some(id) = object.fields.iter_mut().find(|field| {
+                field.name == "id"
+                    && field.json_name == "id"
+                    && field.ty.ty == TypeRef::scalar("ID")
+            }) else {
+                continue;
+            };
+            if object.key {
+                continue;
+            }
+            object.key = true;
+            id.ty.non_null = true;
+            argument.name = "id".to_string();
+            argument.ty = id.ty.clone();
+            *last = "id".to_string();
+            operation.entity = true;
+        }
+    }
+
+    fn print(&self, source_name: &str, base_url: &str) -> String {
+        let mut sdl = String::new();
+        let _ = writeln!(
+            sdl,
+            r#"extend schema
+  @link(url: "https://specs.apollo.dev/federation/v2.10", import: ["@key"])
+  @link(
+    url: "https://specs.apollo.dev/connect/v0.2"
+    import: ["@connect", "@source"]
+  )
+  @source(name: {}, http: {{ baseURL: {} }})"#,
+            quoted(source_name),
+            quoted(base_url)
+        );
+        if self.types.uses_json_scalar {
+            let _ = write!(sdl, "\nscalar JSON\n");
+        }
+
+        for (root, mutation) in [("Query", false), ("Mutation", true)] {
+            let mut operations = self
+                .operations
+                .iter()
+                .filter(|operation| operation.mutation == mutation)
+                .peekable();
+            if operations.peek().is_none() {
+                continue;
+            }
+            let _ = write!(sdl, "\ntype {root} {{\n");
+            for operation in operations {
+                self.print_operation(operation, source_name, &mut sdl);
+            }
+            sdl.push_str("}\n");
+        }
+
+        for (name, object) in &self.types.objects {
+            let key = if object.key {
+                " @key(fields: \"id\")"
+            } else {
+                ""
+            };
+            print_object(&format!("type {name}{key}"), object, &mut sdl);
+        }
+        for (name, input) in &self.types.inputs {
+            print_object(&format!("input {name}"), input, &mut sdl);
+        }
+        for (name, definition) in &self.types.enums {
+            sdl.push('\n');
+            print_description(definition.description.as_deref(), "", &mut sdl);
+            let _ = writeln!(sdl, "enum {name} {{");
+            for value in &definition.values {
+                let _ = writeln!(sdl, "  {value}");
+            }
+            sdl.push_str("}\n");
+        }
+        sdl
+    }
+
+    fn print_operation(&self, operation: &Operation, source_name: &str, sdl: &mut String) {
+        print_description(operation.description.as_deref(), "  ", sdl);
+        let _ = write!(sdl, "  {}", operation.name);
+        if operation
+            .arguments
+            .iter()
+            .any(|argument| argument.description.is_some())
+        {
+            sdl.push_str("(\n");
+            for argument in &operation.arguments {
+                print_description(argument.description.as_deref(), "    ", sdl);
+                let _ = writeln!(sdl, "    {}: {}", argument.name, argument.ty);
+            }
+            sdl.push_str("  )");
+        } else if !operation.arguments.is_empty() {
+            let arguments: Vec<_> = operation
+                .arguments
+                .iter()
+                .map(|argument| format!("{}: {}", argument.name, argument.ty))
+                .collect();
+            let _ = write!(sdl, "({})", arguments.join(", "));
+        }
+        let _ = writeln!(sdl, ": {}", operation.return_type);
+
+        let path: String = operation
+            .path
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Literal(literal) => literal.clone(),
+                PathSegment::Argument(argument) => format!("{{$args.{argument}}}"),
+            })
+            .collect();
+        let _ = writeln!(sdl, "    @connect(");
+        let _ = writeln!(sdl, "      source: {}", quoted(source_name));
+        let method = format!("{}: {}", operation.method, quoted(&path));
+        if operation.query_params.is_empty() && operation.body.is_none() {
+            let _ = writeln!(sdl, "      http: {{ {method} }}");
+        } else {
+            let _ = writeln!(sdl, "      http: {{\n        {method}");
+            if !operation.query_params.is_empty() {
+                let query_params: Vec<_> = operation
+                    .query_params
+                    .iter()
+                    .map(|(name, argument)| format!("{}: $args.{argument}", selection_key(name)))
+                    .collect();
+                let _ = writeln!(
+                    sdl,
+                    "        queryParams: {}",
+                    block_string(&query_params.join("\n"), "        ")
+                );
+            }
+            if let Some(body) = &operation.body {
+                let body_type = operation
+                    .arguments
+                    .iter()
+                    .find(|argument| argument.name == *body)
+                    .map(|argument| self.types.body_selection(body, &argument.ty))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    sdl,
+                    "        body: {}",
+                    block_string(&body_type, "        ")
+                );
+            }
+            let _ = writeln!(sdl, "      }}");
+        }
+        let selection = self.types.response_selection(&operation.return_type);
+        let selection = if selection.contains('\n') {
+            block_string(&selection, "      ")
+        } else {
+            quoted(&selection)
+        };
+        let _ = writeln!(sdl, "      selection: {selection}");
+        if operation.entity {
+            let _ = writeln!(sdl, "      entity: true");
+        }
+        let _ = writeln!(sdl, "    )");
+    }
+}
+
+fn print_object(header: &str, object: &ObjectDef, sdl: &mut String) {
+    sdl.push('\n');
+    print_description(object.description.as_deref(), "", sdl);
+    let _ = writeln!(sdl, "{header} {{");
+    for field in &object.fields {
+        print_description(field.description.as_deref(), "  ", sdl);
+        let _ = writeln!(sdl, "  {}: {}", field.name, field.ty);
+    }
+    sdl.push_str("}\n");
+}
+
+fn print_description(description: Option<&str>, indent: &str, sdl: &mut String) {
+    let Some(description) = description else {
+        return;
+    };
+    if description.contains('\n') {
+        let _ = writeln!(sdl, "{indent}{}", block_string(description, indent));
+    } else {
+        let _ = writeln!(sdl, "{indent}{}", quoted(description));
+    }
+}
+
+/// Returns the schema of the JSON content of a request body or response
+fn json_schema(object: &JsonValue) -> Option<&JsonValue> {
+    let content = object.get("content")?.as_object()?;
+    content
+        .iter()
+        .find(|(media_type, _)| {
+            let essence = media_type.split(';').next().unwrap_or_default().trim();
+            essence == "application/json" || essence.ends_with("+json")
+        })
+        .or_else(|| content.iter().find(|(media_type, _)| *media_type == "*/*"))
+        .and_then(|(_, media_type)| media_type.get("schema"))
+}
+
+fn description(object: &JsonValue) -> Option<String> {
+    object
+        .get("summary")
+        .or_else(|| object.get("description"))
+        .and_then(JsonValue::as_str)
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(String::from)
+}
+
+/// Quotes a GraphQL string value
+fn quoted(value: &str) -> String {
+    // JSON string escapes are a subset of GraphQL string escapes
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Formats a GraphQL block string, with its lines indented
+fn block_string(value: &str, indent: &str) -> String {
+    let mut block = "\"\"\"\n".to_string();
+    for line in value.lines() {
+        if line.is_empty() {
+            block.push('\n');
+        } else {
+            let _ = writeln!(block, "{indent}{}", line.replace("\"\"\"", "\\\"\"\""));
+        }
+    }
+    block.push_str(indent);
+    block.push_str("\"\"\"");
+    block
+}
@@ -0,0 +1,157 @@
+use insta::assert_debug_snapshot;
+use insta::assert_snapshot;
+
+use super::*;
+use crate::connectors::validation::Severity;
+use crate::connectors::validation::validate;
+
+fn assert_valid(schema: &str) {
+    let errors: Vec<_> = validate(schema.to_string(), "generated.graphql")
+        .errors
+        .into_iter()
+        .filter(|error| error.code.severity() == Severity::Error)
+        .map(|error| error.message)
+        .collect();
+    assert!(errors.is_empty(), "{errors:#?}\n{schema}");
+}
+
+#[test]
+fn generates_connectors_from_yaml() {
+    let generated = generate_connectors(
+        include_str!("test_data/petstore.yaml"),
+        &OpenApiOptions::default(),
+    )
+    .unwrap();
+    assert_valid(&generated.schema);
+    assert_snapshot!("petstore_schema", generated.schema);
+    assert_debug_snapshot!("petstore_warnings", generated.warnings);
+}
+
+#[test]
+fn generates_connectors_from_json() {
+    let document = r##"{
+      "openapi": "3.1.0",
+      "paths": {
+        "/users/{user_id}": {
+          "get": {
+            "operationId": "get-user",
+            "parameters": [
+              { "name": "user_id", "in": "path", "required": true, "schema": { "type": "string" } }
+            ],
+            "responses": {
+              "200": {
+                "content": {
+                  "application/json": {
+                    "schema": {
+                      "type": "object",
+                      "required": ["id"],
+                      "properties": {
+                        "id": { "type": "string" },
+                        "nickname": { "type": ["string", "null"] },
+                        "friends": {
+                          "type": "array",
+                          "items": { "$ref": "#/components/schemas/Friend" }
+                        }
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          }
+        },
+        "/users/{user_id}/friends": {
+          "put": {
+            "operationId": "get_user",
+            "parameters": [
+              { "name": "user_id", "in": "path", "required": true, "schema": { "type": "string" } },
+              { "name": "page[size]", "in": "query", "schema": { "type": "integer" } }
+            ],
+            "requestBody": {
+              "content": {
+                "application/json": {
+                  "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Friend" } }
+                }
+              }
+            },
+            "responses": {
+              "200": {
+                "content": {
+                  "application/json": {
+                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Friend" } }
+                  }
+                }
+              }
+            }
+          }
+        }
+      },
+      "components": {
+        "schemas": {
+          "Friend": {
+            "type": "object",
+            "properties": {
+              "name": { "type": "string" },
+              "since": { "type": "string", "format": "date" },
+              "status": { "type": "string", "enum": ["close", "not-close"] }
+            }
+          }
+        }
+      }
+    }"##;
+    let generated = generate_connectors(
+        document,
+        &OpenApiOptions {
+            source_name: "users".to_string(),
+            base_url: Some("https://users.example.com/".to_string()),
+        },
+    )
+    .unwrap();
+    assert_valid(&generated.schema);
+    assert_snapshot!("users_schema", generated.schema);
+    assert_debug_snapshot!("users_warnings", generated.warnings);
+}
+
+#[test]
+fn rejects_swagger_documents() {
+    let result = generate_connectors(
+        r#"{ "swagger": "2.0", "paths": {} }"#,
+        &OpenApiOptions::default(),
+    );
+    assert!(matches!(
+        result,
+        Err(OpenApiError::UnsupportedVersion(version)) if version == "2.0"
+    ));
+}
+
+#[test]
+fn requires_an_absolute_base_url() {
+    let document = "openapi: 3.0.0\nservers:\n  - url: /v1\npaths: {}\n";
+    assert!(matches!(
+        generate_connectors(document, &OpenApiOptions::default()),
+        Err(OpenApiError::InvalidBaseUrl(url)) if url == "/v1"
+    ));
+
+    let document = "openapi: 3.0.0\npaths: {}\n";
+    assert!(matches!(
+        generate_connectors(document, &OpenApiOptions::default()),
+        Err(OpenApiError::MissingBaseUrl)
+    ));
+}
+
+#[test]
+fn requires_a_query() {
+    let document = "openapi: 3.0.0\nservers:\n  - url: https://example.com\npaths: {}\n";
+    assert!(matches!(
+        generate_connectors(document, &OpenApiOptions::default()),
+        Err(OpenApiError::NoQueries)
+    ));
+}
+
+#[test]
+fn reports_invalid_documents() {
+    assert!(matches!(
+        generate_connectors("{ \"openapi\": ", &OpenApiOptions::default()),
+        Err(OpenApiError::Parse(_))
+    ));
+}
@@ -0,0 +1,666 @@
+//! Conversion of OpenAPI schema objects to GraphQL types and JSONSelection mappings.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write;
+
+use heck::ToLowerCamelCase;
+use heck::ToUpperCamelCase;
+use indexmap::IndexMap;
+use serde_json::Value as JsonValue;
+
+/// The maximum number of `$ref` or `allOf` indirections followed while resolving a schema
+const MAX_INDIRECTIONS: usize = 32;
+
+const JSON_SCALAR: &str = "JSON";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum TypeKind {
+    Scalar,
+    Enum,
+    Object,
+}
+
+/// A GraphQL type, without its non-null marker
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum TypeRef {
+    Named { name: String, kind: TypeKind },
+    List(Box<FieldType>),
+}
+
+impl TypeRef {
+    pub(super) fn scalar(name: &str) -> Self {
+        Self::Named {
+            name: name.to_string(),
+            kind: TypeKind::Scalar,
+        }
+    }
+}
+
+/// The type of a GraphQL field or argument
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct FieldType {
+    pub(super) ty: TypeRef,
+    pub(super) non_null: bool,
+}
+
+impl FieldType {
+    /// Returns the innermost named type
+    pub(super) fn named_type(&self) -> (&str, TypeKind) {
+        match &self.ty {
+            TypeRef::Named { name, kind } => (name, *kind),
+            TypeRef::List(item) => item.named_type(),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.ty {
+            TypeRef::Named { name, .. } => write!(f, "{name}")?,
+            TypeRef::List(item) => write!(f, "[{item}]")?,
+        }
+        if self.non_null {
+            write!(f, "!")?;
+        }
+        Ok(())
+    }
+}
+
+/// A generated object or input object type
+#[derive(Debug, Default)]
+pub(super) struct ObjectDef {
+    pub(super) description: Option<String>,
+    pub(super) fields: Vec<FieldDef>,
+    /// Whether the type has an `@key` on its `id` field
+    pub(super) key: bool,
+}
+
+#[derive(Debug)]
+pub(super) struct FieldDef {
+    pub(super) name: String,
+    /// The name of the property in the JSON documents of the API
+    pub(super) json_name: String,
+    pub(super) ty: FieldType,
+    pub(super) description: Option<String>,
+}
+
+#[derive(Debug)]
+pub(super) struct EnumDef {
+    pub(super) description: Option<String>,
+    pub(super) values: Vec<String>,
+}
+
+/// The GraphQL types generated from the schemas of an OpenAPI document. Named schemas (referenced
+/// with `$ref`) are converted once, and inline schemas get a type named after where they are used.
+pub(super) struct Types<'a> {
+    document: &'a JsonValue,
+    pub(super) objects: IndexMap<String, ObjectDef>,
+    pub(super) inputs: IndexMap<String, ObjectDef>,
+    pub(super) enums: IndexMap<String, EnumDef>,
+    pub(super) uses_json_scalar: bool,
+    pub(super) warnings: Vec<String>,
+    /// Converted references, by reference and whether they are used as an input
+    references: HashMap<(String, bool), TypeRef>,
+    type_names: HashSet<String>,
+}
+
+impl<'a> Types<'a> {
+    pub(super) fn new(document: &'a JsonValue) -> Self {
+        Self {
+            document,
+            objects: IndexMap::new(),
+            inputs: IndexMap::new(),
+            enums: IndexMap::new(),
+            uses_json_scalar: false,
+            warnings: Vec::new(),
+            references: HashMap::new(),
+            type_names: [
+                "Query",
+                "Mutation",
+                "String",
+                "Int",
+                "Float",
+                "Boolean",
+                "ID",
+                JSON_SCALAR,
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+
+    /// Follows `$ref` pointers to the object they reference. Only references within the document
+    /// are supported.
+    pub(super) fn resolve(&self, value: &'a JsonValue) -> Option<&'a JsonValue> {
+        let mut value = value;
+        for _ in 0..MAX_INDIRECTIONS {
+            let Some(reference) = value.get("$ref").and_then(JsonValue::as_str) else {
+                return Some(value);
+            };
+            value = self.document.pointer(reference.strip_prefix('#')?)?;
+        }
+        None
+    }
+
+    /// Converts a schema to a GraphQL type. The hint is used to name inline object and enum types.
+    pub(super) fn convert(&mut self, schema: &'a JsonValue, hint: &str, input: bool) -> TypeRef {
+        if let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+            return self.convert_reference(reference, input);
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(variants) = schema.get(keyword).and_then(JsonValue::as_array) {
+                // `anyOf: [{ $ref: ... }, { type: "null" }]` is a common way to make a type nullable
+                let variants: Vec<_> = variants
+                    .iter()
+                    .filter(|variant| !is_null_schema(variant))
+                    .collect();
+                if let [variant] = variants.as_slice() {
+                    return self.convert(variant, hint, input);
+                }
+                self.warn(format!(
+                    "`{hint}` uses `{keyword}` and is mapped to the `{JSON_SCALAR}` scalar"
+                ));
+                return self.json_scalar();
+            }
+        }
+
+        if let Some(parts) = schema.get("allOf").and_then(JsonValue::as_array) {
+            if let ([part], None) = (parts.as_slice(), schema.get("properties")) {
+                return self.convert(part, hint, input);
+            }
+        }
+
+        match schema_type(schema) {
+            Some("array") => {
+                let items = schema.get("items").unwrap_or(&JsonValue::Null);
+                let item = self.convert(items, &format!("{hint}Item"), input);
+                TypeRef::List(Box::new(FieldType {
+                    ty: item,
+                    non_null: !is_nullable(items),
+                }))
+            }
+            Some("string") if schema.get("enum").is_some() => self.convert_enum(schema, hint),
+            Some("string") => TypeRef::scalar("String"),
+            Some("integer") => TypeRef::scalar("Int"),
+            Some("number") => TypeRef::scalar("Float"),
+            Some("boolean") => TypeRef::scalar("Boolean"),
+            Some("object") | None => {
+                let properties = self.properties(schema, input);
+                if properties.is_empty() {
+                    return self.json_scalar();
+                }
+                let name = self.type_name(hint, input);
+                self.convert_object(&name, hint, schema, properties, input);
+                TypeRef::Named {
+                    name,
+                    kind: TypeKind::Object,
+                }
+            }
+            Some(_) => self.json_scalar(),
+        }
+    }
+
+    fn convert_reference(&mut self, reference: &str, input: bool) -> TypeRef {
+        let document = self.document;
+        let Some(schema) = reference
+            .strip_prefix('#')
+            .and_then(|pointer| document.pointer(pointer))
+        else {
+            self.warn(format!(
+                "`{reference}` could not be resolved and is mapped to the `{JSON_SCALAR}` scalar"
+            ));
+            return self.json_scalar();
+        };
+
+        // Enums are the same type in inputs and outputs
+        let is_enum = schema_type(schema) == Some("string") && schema.get("enum").is_some();
+        let key = (reference.to_string(), input && !is_enum);
+        if let Some(ty) = self.references.get(&key) {
+            return ty.clone();
+        }
+        let hint = reference
+            .rsplit('/')
+            .next()
+            .map(type_name)
+            .unwrap_or_default();
+
+        let properties = if schema.get("$ref").is_none() && !is_enum {
+            self.properties(schema, input)
+        } else {
+            Vec::new()
+        };
+        if properties.is_empty() {
+            // Guards against references that (indirectly) reference themselves
+            self.references
+                .insert(key.clone(), TypeRef::scalar(JSON_SCALAR));
+            let ty = self.convert(schema, &hint, input);
+            self.references.insert(key, ty.clone());
+            return ty;
+        }
+
+        // The type is registered before its fields are converted, so that recursive types
+        // reference themselves
+        let name = self.type_name(&hint, input);
+        let ty = TypeRef::Named {
+            name: name.clone(),
+            kind: TypeKind::Object,
+        };
+        self.references.insert(key, ty.clone());
+        self.convert_object(&name, &hint, schema, properties, input);
+        ty
+    }
+
+    fn convert_object(
+        &mut self,
+        name: &str,
+        hint: &str,
+        schema: &'a JsonValue,
+        properties: Vec<Property<'a>>,
+        input: bool,
+    ) {
+        let description = self.description(schema);
+        let objects = if input {
+            &mut self.inputs
+        } else {
+            &mut self.objects
+        };
+        objects.insert(
+            name.to_string(),
+            ObjectDef {
+                description,
+                ..Default::default()
+            },
+        );
+
+        let mut field_names = HashSet::new();
+        let mut fields = Vec::with_capacity(properties.len());
+        for property in properties {
+            let field_name = unique_name(&mut field_names, field_name(property.name));
+            let ty = if property.name == "id" && self.is_id_scalar(property.schema) {
+                TypeRef::scalar("ID")
+            } else {
+                self.convert(
+                    property.schema,
+                    &format!("{hint}{}", type_name(property.name)),
+                    input,
+                )
+            };
+            fields.push(FieldDef {
+                name: field_name,
+                json_name: property.name.to_string(),
+                ty: FieldType {
+                    ty,
+                    non_null: property.required && !is_nullable(property.schema),
+                },
+                description: self.description(property.schema),
+            });
+        }
+
+        let objects = if input {
+            &mut self.inputs
+        } else {
+            &mut self.objects
+        };
+        if let Some(object) = objects.get_mut(name) {
+            object.fields = fields;
+        }
+    }
+
+    fn convert_enum(&mut self, schema: &'a JsonValue, hint: &str) -> TypeRef {
+        let mut values = Vec::new();
+        for value in schema
+            .get("enum")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+        {
+            match value.as_str() {
+                Some(value) if is_enum_value(value) => {
+                    if !values.iter().any(|existing| existing == value) {
+                        values.push(value.to_string());
+                    }
+                }
+                // Nullable enums list null as one of their values
+                None if value.is_null() => {}
+                _ => {
+                    self.warn(format!(
+                        "`{hint}` has enum values which are not valid GraphQL names and is mapped to `String`"
+                    ));
+                    return TypeRef::scalar("String");
+                }
+            }
+        }
+        if values.is_empty() {
+            return TypeRef::scalar("String");
+        }
+
+        let name = unique_name(&mut self.type_names, type_name(hint));
+        self.enums.insert(
+            name.clone(),
+            EnumDef {
+                description: self.description(schema),
+                values,
+            },
+        );
+        TypeRef::Named {
+            name,
+            kind: TypeKind::Enum,
+        }
+    }
+
+    /// Collects the properties of an object schema, including the properties of its `allOf`
+    /// schemas. Read-only properties are skipped in inputs, and write-only properties in outputs.
+    fn properties(&self, schema: &'a JsonValue, input: bool) -> Vec<Property<'a>> {
+        let mut properties = Vec::new();
+        self.collect_properties(schema, input, 0, &mut properties);
+        properties
+    }
+
+    fn collect_properties(
+        &self,
+        schema: &'a JsonValue,
+        input: bool,
+        depth: usize,
+        properties: &mut Vec<Property<'a>>,
+    ) {
+        if depth > MAX_INDIRECTIONS {
+            return;
+        }
+        let Some(schema) = self.resolve(schema) else {
+            return;
+        };
+        for part in schema
+            .get("allOf")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+        {
+            self.collect_properties(part, input, depth + 1, properties);
+        }
+
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(JsonValue::as_str)
+            .collect();
+        for (name, property) in schema
+            .get("properties")
+            .and_then(JsonValue::as_object)
+            .into_iter()
+            .flatten()
+        {
+            let resolved = self.resolve(property).unwrap_or(property);
+            let skipped = if input { "readOnly" } else { "writeOnly" };
+            if resolved.get(skipped).and_then(JsonValue::as_bool) == Some(true) {
+                continue;
+            }
+            let property = Property {
+                name,
+                schema: property,
+                required: required.contains(&name.as_str()),
+            };
+            // Properties redefined by a later `allOf` schema replace the earlier ones
+            match properties.iter_mut().find(|existing| existing.name == name) {
+                Some(existing) => *existing = property,
+                None => properties.push(property),
+            }
+        }
+        // Properties may be marked as required by another `allOf` schema than their own
+        for property in properties.iter_mut() {
+            if required.contains(&property.name) {
+                property.required = true;
+            }
+        }
+    }
+
+    fn is_id_scalar(&self, schema: &'a JsonValue) -> bool {
+        self.resolve(schema)
+            .and_then(schema_type)
+            .is_some_and(|ty| ty == "string" || ty == "integer")
+    }
+
+    fn description(&self, schema: &'a JsonValue) -> Option<String> {
+        let schema = self.resolve(schema)?;
+        schema
+            .get("description")
+            .or_else(|| schema.get("title"))
+            .and_then(JsonValue::as_str)
+            .map(str::trim)
+            .filter(|description| !description.is_empty())
+            .map(String::from)
+    }
+
+    fn type_name(&mut self, hint: &str, input: bool) -> String {
+        let name = if input {
+            format!("{}Input", type_name(hint))
+        } else {
+            type_name(hint)
+        };
+        unique_name(&mut self.type_names, name)
+    }
+
+    /// Adds a warning, unless the same warning was already added for another use of a schema
+    pub(super) fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    fn json_scalar(&mut self) -> TypeRef {
+        self.uses_json_scalar = true;
+        TypeRef::scalar(JSON_SCALAR)
+    }
+
+    /// Generates the selection mapping an API response to the given type
+    pub(super) fn response_selection(&self, ty: &FieldType) -> String {
+        let (name, kind) = ty.named_type();
+        if kind != TypeKind::Object {
+            return "$".to_string();
+        }
+        let mut selection = String::new();
+        self.write_selection(name, false, &mut Vec::new(), 0, &mut selection);
+        selection
+    }
+
+    /// Generates the body selection mapping an input argument to the JSON of the API
+    pub(super) fn body_selection(&self, argument: &str, ty: &FieldType) -> String {
+        let (name, kind) = ty.named_type();
+        if kind != TypeKind::Object {
+            return format!("$args.{argument}");
+        }
+        let mut selection = format!("$args.{argument} {{\n");
+        self.write_selection(name, true, &mut Vec::new(), 1, &mut selection);
+        selection.push('}');
+        selection
+    }
+
+    /// Removes the fields of output types which are not part of any selection of the given
+    /// types, because they are circular references
+    pub(super) fn remove_unselected_fields<'b>(
+        &mut self,
+        types: impl Iterator<Item = &'b FieldType>,
+    ) {
+        let mut selected = HashSet::new();
+        for ty in types {
+            let (name, kind) = ty.named_type();
+            if kind == TypeKind::Object {
+                self.collect_selected_fields(name, &mut Vec::new(), &mut selected);
+            }
+        }
+        for (name, object) in &mut self.objects {
+            object.fields.retain(|field| {
+                let is_selected = selected.contains(&(name.clone(), field.name.clone()));
+                if !is_selected {
+                    self.warnings.push(format!(
+                        "`{name}.{}` is a circular reference and is left out",
+                        field.name
+                    ));
+                }
+                is_selected
+            });
+        }
+    }
+
+    fn collect_selected_fields<'b>(
+        &'b self,
+        type_name: &'b str,
+        path: &mut Vec<&'b str>,
+        selected: &mut HashSet<(String, String)>,
+    ) {
+        let Some(object) = self.objects.get(type_name) else {
+            return;
+        };
+        path.push(type_name);
+        for field in &object.fields {
+            let (field_type, kind) = field.ty.named_type();
+            if kind == TypeKind::Object {
+                if path.contains(&field_type) {
+                    continue;
+                }
+                self.collect_selected_fields(field_type, path, selected);
+            }
+            selected.insert((type_name.to_string(), field.name.clone()));
+        }
+        path.pop();
+    }
+
+    fn write_selection<'b>(
+        &'b self,
+        type_name: &'b str,
+        input: bool,
+        path: &mut Vec<&'b str>,
+        depth: usize,
+        selection: &mut String,
+    ) {
+        let objects = if input { &self.inputs } else { &self.objects };
+        let Some(object) = objects.get(type_name) else {
+            return;
+        };
+        path.push(type_name);
+        let indent = "  ".repeat(depth);
+        for field in &object.fields {
+            let key = selection_key(&field.json_name);
+            // Responses select JSON properties into GraphQL fields, and bodies the reverse
+            let mapping = match (field.name == field.json_name, input) {
+                (true, _) => field.name.clone(),
+                (false, false) => format!("{}: {key}", field.name),
+                (false, true) => format!("{key}: {}", field.name),
+            };
+            let (field_type, kind) = field.ty.named_type();
+            if kind != TypeKind::Object {
+                let _ = writeln!(selection, "{indent}{mapping}");
+                continue;
+            }
+            // Connectors can't select circular references, so they are left out of responses and
+            // passed through as is in bodies
+            if path.contains(&field_type) {
+                if input {
+                    let _ = writeln!(selection, "{indent}{mapping}");
+                }
+                continue;
+            }
+            let _ = writeln!(selection, "{indent}{mapping} {{");
+            self.write_selection(field_type, input, path, depth + 1, selection);
+            let _ = writeln!(selection, "{indent}}}");
+        }
+        path.pop();
+    }
+}
+
+struct Property<'a> {
+    name: &'a str,
+    schema: &'a JsonValue,
+    required: bool,
+}
+
+/// Returns the type of a schema, ignoring `null` in OpenAPI 3.1 type arrays
+fn schema_type(schema: &JsonValue) -> Option<&str> {
+    match schema.get("type")? {
+        JsonValue::String(ty) => Some(ty),
+        JsonValue::Array(types) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .find(|ty| *ty != "null"),
+        _ => None,
+    }
+}
+
+fn is_null_schema(schema: &JsonValue) -> bool {
+    schema.get("type").and_then(JsonValue::as_str) == Some("null")
+}
+
+/// Whether a schema allows null, with OpenAPI 3.0 `nullable` or OpenAPI 3.1 type arrays
+pub(super) fn is_nullable(schema: &JsonValue) -> bool {
+    if schema.get("nullable").and_then(JsonValue::as_bool) == Some(true) {
+        return true;
+    }
+    if let Some(JsonValue::Array(types)) = schema.get("type") {
+        return types.iter().any(|ty| ty.as_str() == Some("null"));
+    }
+    ["oneOf", "anyOf"].into_iter().any(|keyword| {
+        schema
+            .get(keyword)
+            .and_then(JsonValue::as_array)
+            .is_some_and(|variants| variants.iter().any(is_null_schema))
+    })
+}
+
+fn is_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_enum_value(value: &str) -> bool {
+    is_identifier(value) && !matches!(value, "true" | "false" | "null")
+}
+
+/// Returns a JSONSelection key for a JSON property name, quoting it if it is not an identifier
+pub(super) fn selection_key(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Converts a name to a valid GraphQL type name in PascalCase
+pub(super) fn type_name(name: &str) -> String {
+    sanitize_name(name.to_upper_camel_case())
+}
+
+/// Converts a name to a valid GraphQL field or argument name in camelCase
+pub(super) fn field_name(name: &str) -> String {
+    sanitize_name(name.to_lower_camel_case())
+}
+
+fn sanitize_name(name: String) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    match name.chars().next() {
+        None => "_".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    }
+}
+
+/// Returns the name, or the name with a numeric suffix if it is already used
+pub(super) fn unique_name(used: &mut HashSet<String>, name: String) -> String {
+    if used.insert(name.clone()) {
+        return name;
+    }
+    (2..)
+        .map(|suffix| format!("{name}{suffix}"))
+        .find(|candidate| used.insert(candidate.clone()))
+        .unwrap_or(name)
+}
@@ -1,5 +1,6 @@
 pub mod debug;
 pub mod errors;
+pub mod form_data;
 pub mod form_encoding;
 pub mod http_json_transport;
 pub mod inputs;
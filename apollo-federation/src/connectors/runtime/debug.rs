@@ -56,6 +56,7 @@ impl ConnectorContext {
         request: Option<Box<ConnectorDebugHttpRequest>>,
         parts: &http::response::Parts,
         body: &[u8],
+        error: &str,
         error_settings: &ConnectorErrorsSettings,
         problems: Vec<(ProblemLocation, Problem)>,
     ) {
@@ -78,6 +79,7 @@ impl ConnectorContext {
                         kind: "invalid".to_string(),
                         content: format!("{:?}", body).into(),
                         selection: None,
+                        error: Some(error.to_string()),
                     },
                     errors: if error_settings.message.is_some()
                         || error_settings.connect_extensions.is_some()
@@ -158,6 +160,9 @@ struct ConnectorDebugBody {
     kind: String,
     content: serde_json_bytes::Value,
     selection: Option<ConnectorDebugSelection>,
+    /// Why the body could not be decoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -237,6 +242,7 @@ impl ConnectorDebugHttpRequest {
                     transformed: selection.transformed,
                     result: selection.result,
                 }),
+                error: None,
             }),
             source_url: if transport.source_template.is_some()
                 || transport.source_path.is_some()
@@ -299,6 +305,7 @@ impl ConnectorDebugHttpResponse {
                     transformed: selection.transformed,
                     result: selection.result,
                 }),
+                error: None,
             },
             errors: if error_settings.message.is_some()
                 || error_settings.connect_extensions.is_some()
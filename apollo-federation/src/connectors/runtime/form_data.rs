@@ -0,0 +1,354 @@
+//! Encoding of `multipart/form-data` request bodies for connectors.
+//!
+//! Values become text parts, while strings holding an upload placeholder (see
+//! [`upload_placeholder`]) become file parts whose contents are streamed from the client request
+//! by the router.
+
+use rand::RngCore;
+use serde_json_bytes::Value;
+
+const UPLOAD_PLACEHOLDER_PREFIX: &str = "<Placeholder for file '";
+const UPLOAD_PLACEHOLDER_SUFFIX: &str = "'>";
+
+/// The value that stands in for the file `file` of a client's multipart request, until the file
+/// is forwarded to a subgraph or connector.
+pub fn upload_placeholder(file: &str) -> String {
+    format!("{UPLOAD_PLACEHOLDER_PREFIX}{file}{UPLOAD_PLACEHOLDER_SUFFIX}")
+}
+
+/// The name of the file that `value` is a placeholder for, if it is one.
+fn parse_upload_placeholder(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(UPLOAD_PLACEHOLDER_PREFIX)?
+        .strip_suffix(UPLOAD_PLACEHOLDER_SUFFIX)
+}
+
+/// A `multipart/form-data` body
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormData {
+    boundary: String,
+    parts: Vec<FormPart>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormPart {
+    /// A part with a known value
+    Value {
+        name: String,
+        value: String,
+        content_type: Option<mime::Mime>,
+    },
+    /// A part with the contents of the file `file` from the client's multipart request
+    Upload { name: String, file: String },
+}
+
+impl FormData {
+    /// Build the parts of a form from the properties of a JSON object.
+    ///
+    /// Scalars become text parts, arrays become one part per item with the same name, and
+    /// objects become JSON parts. Nulls are left out.
+    pub fn from_json(value: &Value) -> Result<Self, &'static str> {
+        let Some(object) = value.as_object() else {
+            return Err("Expected multipart form bodies to be objects");
+        };
+
+        fn add_part(parts: &mut Vec<FormPart>, name: &str, value: &Value, in_array: bool) {
+            let (value, content_type) = match value {
+                Value::Null => return,
+                Value::String(s) => {
+                    if let Some(file) = parse_upload_placeholder(s.as_str()) {
+                        parts.push(FormPart::Upload {
+                            name: name.to_string(),
+                            file: file.to_string(),
+                        });
+                        return;
+                    }
+                    (s.as_str().to_string(), None)
+                }
+                Value::Bool(b) => (b.to_string(), None),
+                Value::Number(n) => (n.to_string(), None),
+                Value::Array(array) if !in_array => {
+                    for value in array {
+                        add_part(parts, name, value, true);
+                    }
+                    return;
+                }
+                Value::Array(_) | Value::Object(_) => {
+                    (value.to_string(), Some(mime::APPLICATION_JSON))
+                }
+            };
+            parts.push(FormPart::Value {
+                name: name.to_string(),
+                value,
+                content_type,
+            });
+        }
+
+        let mut parts = Vec::new();
+        for (name, value) in object {
+            add_part(&mut parts, name.as_str(), value, false);
+        }
+
+        Ok(Self::new(parts, random_boundary))
+    }
+
+    /// Pick a boundary with `next_boundary` that doesn't appear in the parts, so that no value
+    /// can end its part early and add parts of its own.
+    fn new(parts: Vec<FormPart>, mut next_boundary: impl FnMut() -> String) -> Self {
+        loop {
+            let boundary = next_boundary();
+            let in_parts = parts.iter().any(|part| match part {
+                FormPart::Value { name, value, .. } => {
+                    name.contains(&boundary) || value.contains(&boundary)
+                }
+                FormPart::Upload { name, .. } => name.contains(&boundary),
+            });
+            if !in_parts {
+                return Self { boundary, parts };
+            }
+        }
+    }
+
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    pub fn parts(&self) -> &[FormPart] {
+        &self.parts
+    }
+
+    /// Whether any part needs the contents of a file from the client request
+    pub fn has_uploads(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, FormPart::Upload { .. }))
+    }
+
+    /// The value of the `Content-Type` header for this body
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Encode the whole body. Upload parts are left out, since their contents aren't known here.
+    pub fn encode(&self) -> String {
+        let mut encoded = self.encode_values();
+        encoded.push_str(&self.closing_delimiter());
+        encoded
+    }
+
+    /// Encode the parts with known values, without the closing delimiter.
+    pub fn encode_values(&self) -> String {
+        let mut encoded = String::new();
+        for part in &self.parts {
+            if let FormPart::Value {
+                name,
+                value,
+                content_type,
+            } = part
+            {
+                if !encoded.is_empty() {
+                    encoded.push_str("\r\n");
+                }
+                encoded.push_str(&self.part_headers(
+                    name,
+                    None,
+                    content_type.as_ref().map(|mime| mime.essence_str()),
+                ));
+                encoded.push_str(value);
+            }
+        }
+        encoded
+    }
+
+    /// The delimiter and headers to put before the contents of a file, to follow a previous part.
+    pub fn upload_part_headers(
+        &self,
+        name: &str,
+        file_name: Option<&str>,
+        content_type: Option<&str>,
+    ) -> String {
+        format!("\r\n{}", self.part_headers(name, file_name, content_type))
+    }
+
+    /// The delimiter ending the body, to follow the last part.
+    pub fn closing_delimiter(&self) -> String {
+        format!("\r\n--{}--\r\n", self.boundary)
+    }
+
+    fn part_headers(
+        &self,
+        name: &str,
+        file_name: Option<&str>,
+        content_type: Option<&str>,
+    ) -> String {
+        let mut headers = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            self.boundary,
+            escape(name)
+        );
+        if let Some(file_name) = file_name {
+            headers.push_str(&format!("; filename=\"{}\"", escape(file_name)));
+        }
+        headers.push_str("\r\n");
+        if let Some(content_type) = content_type {
+            headers.push_str(&format!("Content-Type: {content_type}\r\n"));
+        }
+        headers.push_str("\r\n");
+        headers
+    }
+}
+
+/// A new boundary for each body, so that it can't be guessed by whoever provides the values or
+/// the uploaded files
+fn random_boundary() -> String {
+    let mut rng = rand::rng();
+    format!("{:016x}{:016x}", rng.next_u64(), rng.next_u64())
+}
+
+/// Escape a name for a quoted `Content-Disposition` parameter, the way browsers do.
+fn escape(name: &str) -> String {
+    name.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+
+    #[test]
+    fn encodes_values() {
+        let form = FormData::from_json(&json!({
+            "a": 1,
+            "b": "two",
+            "c": true,
+            "d": null,
+            "e": [1, [2, 3], { "f": 4 }],
+            "g": { "h": "i" },
+            "quoted \"name\"": "j",
+        }))
+        .unwrap();
+
+        let redact = |s: String| {
+            s.replace(form.boundary(), "[boundary]")
+                .replace("\r\n", "\n")
+        };
+        insta::assert_snapshot!(redact(form.content_type()), @"multipart/form-data; boundary=[boundary]");
+        insta::assert_snapshot!(redact(form.encode()), @r#"
+        --[boundary]
+        Content-Disposition: form-data; name="a"
+
+        1
+        --[boundary]
+        Content-Disposition: form-data; name="b"
+
+        two
+        --[boundary]
+        Content-Disposition: form-data; name="c"
+
+        true
+        --[boundary]
+        Content-Disposition: form-data; name="e"
+
+        1
+        --[boundary]
+        Content-Disposition: form-data; name="e"
+        Content-Type: application/json
+
+        [2,3]
+        --[boundary]
+        Content-Disposition: form-data; name="e"
+        Content-Type: application/json
+
+        {"f":4}
+        --[boundary]
+        Content-Disposition: form-data; name="g"
+        Content-Type: application/json
+
+        {"h":"i"}
+        --[boundary]
+        Content-Disposition: form-data; name="quoted %22name%22"
+
+        j
+        --[boundary]--
+        "#);
+    }
+
+    #[test]
+    fn finds_uploads() {
+        let form = FormData::from_json(&json!({
+            "name": "avatar",
+            "file": upload_placeholder("0"),
+            "files": [upload_placeholder("1"), upload_placeholder("2")],
+        }))
+        .unwrap();
+
+        assert!(form.has_uploads());
+        assert_eq!(
+            form.parts()
+                .iter()
+                .filter_map(|part| match part {
+                    FormPart::Upload { name, file } => Some((name.as_str(), file.as_str())),
+                    FormPart::Value { .. } => None,
+                })
+                .collect::<Vec<_>>(),
+            vec![("file", "0"), ("files", "1"), ("files", "2")]
+        );
+        insta::assert_snapshot!(
+            format!(
+                "{}{}{}",
+                form.encode_values(),
+                form.upload_part_headers("file", Some("a.png"), Some("image/png")),
+                form.closing_delimiter()
+            )
+            .replace(form.boundary(), "[boundary]")
+            .replace("\r\n", "\n"),
+            @r#"
+            --[boundary]
+            Content-Disposition: form-data; name="name"
+
+            avatar
+            --[boundary]
+            Content-Disposition: form-data; name="file"; filename="a.png"
+            Content-Type: image/png
+
+
+            --[boundary]--
+            "#
+        );
+    }
+
+    #[test]
+    fn boundaries_are_random() {
+        let value = json!({ "a": "b", "c": upload_placeholder("0") });
+        assert_ne!(
+            FormData::from_json(&value).unwrap().boundary(),
+            FormData::from_json(&value).unwrap().boundary()
+        );
+    }
+
+    #[test]
+    fn boundaries_are_not_in_the_parts() {
+        let parts = vec![
+            FormPart::Value {
+                name: "a".to_string(),
+                value: "--first".to_string(),
+                content_type: None,
+            },
+            FormPart::Upload {
+                name: "second".to_string(),
+                file: "0".to_string(),
+            },
+        ];
+        let mut boundaries = ["first", "second", "third"].into_iter().map(String::from);
+        let form = FormData::new(parts, || boundaries.next().unwrap());
+        assert_eq!(form.boundary(), "third");
+    }
+
+    #[test]
+    fn requires_an_object() {
+        assert!(FormData::from_json(&json!([1, 2])).is_err());
+    }
+}
@@ -10,6 +10,7 @@ use serde_json_bytes::Value;
 use serde_json_bytes::json;
 use thiserror::Error;
 
+use super::form_data::FormData;
 use super::form_encoding::encode_json_as_form;
 use crate::connectors::ApplyToError;
 use crate::connectors::HTTPMethod;
@@ -91,7 +92,11 @@ pub fn make_request(
         aggregate_apply_to_errors_with_problem_locations(header_apply_to_errors);
 
     let is_form_urlencoded = content_type.as_ref() == Some(&mime::APPLICATION_WWW_FORM_URLENCODED);
+    let is_form_data = content_type
+        .as_ref()
+        .is_some_and(|mime| mime.type_() == mime::MULTIPART && mime.subtype() == mime::FORM_DATA);
 
+    let mut upload_form = None;
     let (json_body, form_body, body, content_length, body_apply_to_errors) =
         if let Some(ref selection) = transport.body {
             let (json_body, apply_to_errors) = selection.apply_with_vars(&json!({}), &inputs);
@@ -102,27 +107,50 @@ pub fn make_request(
                         .map_err(HttpJsonTransportError::FormBodySerialization)?;
                     form_body = Some(encoded.clone());
                     let len = encoded.len();
-                    (encoded, len)
+                    (encoded, Some(len))
+                } else if is_form_data {
+                    let form = FormData::from_json(json_body)
+                        .map_err(HttpJsonTransportError::FormBodySerialization)?;
+                    // the boundary is part of the content type, so it replaces the configured one
+                    let content_type = HeaderValue::try_from(form.content_type())
+                        .map_err(|err| HttpJsonTransportError::InvalidNewRequest(err.into()))?;
+                    if let Some(headers) = request.headers_mut() {
+                        headers.insert(CONTENT_TYPE, content_type);
+                    }
+                    let encoded = form.encode();
+                    form_body = Some(encoded.clone());
+                    if form.has_uploads() {
+                        // files are streamed into the body when the request is sent, so the
+                        // length isn't known yet
+                        upload_form = Some(form);
+                        ("".into(), None)
+                    } else {
+                        let len = encoded.len();
+                        (encoded, Some(len))
+                    }
                 } else {
                     request = request.header(CONTENT_TYPE, mime::APPLICATION_JSON.essence_str());
                     let bytes = serde_json::to_vec(json_body)?;
                     let len = bytes.len();
                     let body_string = serde_json::to_string(json_body)?;
-                    (body_string, len)
+                    (body_string, Some(len))
                 }
             } else {
-                ("".into(), 0)
+                ("".into(), Some(0))
             };
             (json_body, form_body, body, content_length, apply_to_errors)
         } else {
-            (None, None, "".into(), 0, vec![])
+            (None, None, "".into(), Some(0), vec![])
         };
 
-    match method {
-        HTTPMethod::Post | HTTPMethod::Patch | HTTPMethod::Put => {
-            request = request.header(CONTENT_LENGTH, content_length);
-        }
-        _ => {}
+    if let (HTTPMethod::Post | HTTPMethod::Patch | HTTPMethod::Put, Some(content_length)) =
+        (method, content_length)
+    {
+        request = request.header(CONTENT_LENGTH, content_length);
+    }
+
+    if let Some(form) = upload_form {
+        request = request.extension(form);
     }
 
     let request = request
@@ -140,10 +168,14 @@ pub fn make_request(
     let mapping_problems: Vec<Problem> = all_problems.clone().into_iter().map(|(_, p)| p).collect();
 
     let debug_request = debug.as_ref().map(|_| {
-        if is_form_urlencoded {
+        if is_form_urlencoded || is_form_data {
             Box::new(ConnectorDebugHttpRequest::new(
                 &request,
-                "form-urlencoded".to_string(),
+                if is_form_data {
+                    "form-data".to_string()
+                } else {
+                    "form-urlencoded".to_string()
+                },
                 form_body.map(|s| Value::String(s.into())).as_ref(),
                 transport.body.as_ref().map(|body| SelectionData {
                     source: body.to_string(),
@@ -260,6 +292,7 @@ mod tests {
     use crate::connectors::HeaderSource;
     use crate::connectors::JSONSelection;
     use crate::connectors::StringTemplate;
+    use crate::connectors::runtime::form_data::upload_placeholder;
 
     #[test]
     fn test_headers_to_add_no_directives() {
@@ -422,4 +455,100 @@ mod tests {
         let body = req.into_body();
         insta::assert_snapshot!(body, @r#"a=42"#);
     }
+
+    /// The random boundary of a `multipart/form-data` request, to redact it from snapshots
+    fn boundary(req: &http::Request<String>) -> String {
+        let content_type = req.headers()[CONTENT_TYPE].to_str().unwrap();
+        content_type.split_once("boundary=").unwrap().1.to_string()
+    }
+
+    #[test]
+    fn make_request_form_data() {
+        let mut vars = IndexMap::default();
+        vars.insert("$args".to_string(), json!({ "a": 42, "b": ["c", "d"] }));
+        let headers = vec![Header::from_values(
+            "content-type".parse().unwrap(),
+            HeaderSource::Value("multipart/form-data".parse().unwrap()),
+            OriginatingDirective::Connect,
+        )];
+
+        let req = super::make_request(
+            &HttpJsonTransport {
+                source_template: None,
+                connect_template: StringTemplate::from_str("http://localhost:8080/").unwrap(),
+                method: HTTPMethod::Post,
+                headers,
+                body: Some(JSONSelection::parse("$args { a b }").unwrap()),
+                ..Default::default()
+            },
+            vars,
+            &Default::default(),
+            &None,
+        )
+        .unwrap();
+
+        let TransportRequest::Http(HttpRequest { inner: req, .. }) = req.0;
+        let form_boundary = boundary(&req);
+        insta::assert_snapshot!(format!("{:#?}", req.headers()).replace(&form_boundary, "[boundary]"), @r#"
+        {
+            "content-type": "multipart/form-data; boundary=[boundary]",
+            "content-length": "288",
+        }
+        "#);
+        assert!(req.extensions().get::<FormData>().is_none());
+        insta::assert_snapshot!(req.into_body().replace(&form_boundary, "[boundary]").replace("\r\n", "\n"), @r#"
+        --[boundary]
+        Content-Disposition: form-data; name="a"
+
+        42
+        --[boundary]
+        Content-Disposition: form-data; name="b"
+
+        c
+        --[boundary]
+        Content-Disposition: form-data; name="b"
+
+        d
+        --[boundary]--
+        "#);
+    }
+
+    #[test]
+    fn make_request_form_data_with_uploads() {
+        let mut vars = IndexMap::default();
+        vars.insert(
+            "$args".to_string(),
+            json!({ "name": "avatar", "file": upload_placeholder("0") }),
+        );
+        let headers = vec![Header::from_values(
+            "content-type".parse().unwrap(),
+            HeaderSource::Value("multipart/form-data".parse().unwrap()),
+            OriginatingDirective::Connect,
+        )];
+
+        let req = super::make_request(
+            &HttpJsonTransport {
+                source_template: None,
+                connect_template: StringTemplate::from_str("http://localhost:8080/").unwrap(),
+                method: HTTPMethod::Post,
+                headers,
+                body: Some(JSONSelection::parse("$args { name file }").unwrap()),
+                ..Default::default()
+            },
+            vars,
+            &Default::default(),
+            &None,
+        )
+        .unwrap();
+
+        let TransportRequest::Http(HttpRequest { inner: req, .. }) = req.0;
+        insta::assert_snapshot!(format!("{:#?}", req.headers()).replace(&boundary(&req), "[boundary]"), @r#"
+        {
+            "content-type": "multipart/form-data; boundary=[boundary]",
+        }
+        "#);
+        let form = req.extensions().get::<FormData>().unwrap();
+        assert!(form.has_uploads());
+        assert_eq!(req.body(), "");
+    }
 }
@@ -9,8 +9,10 @@ use itertools::Itertools;
 use super::errors::ERRORS_ARGUMENT_NAME;
 use super::errors::ErrorsArguments;
 use super::http::HTTP_ARGUMENT_NAME;
+use super::http::PAGINATION_ARGUMENT_NAME;
 use super::http::PATH_ARGUMENT_NAME;
 use super::http::QUERY_PARAMS_ARGUMENT_NAME;
+use super::http::RESPONSE_FORMAT_ARGUMENT_NAME;
 use crate::connectors::ConnectorPosition;
 use crate::connectors::ObjectFieldDefinitionPosition;
 use crate::connectors::OriginatingDirective;
@@ -29,6 +31,8 @@ pub(crate) const CONNECT_SELECTION_ARGUMENT_NAME: Name = name!("selection");
 pub(crate) const CONNECT_ENTITY_ARGUMENT_NAME: Name = name!("entity");
 pub(crate) const CONNECT_HTTP_NAME_IN_SPEC: Name = name!("ConnectHTTP");
 pub(crate) const CONNECT_BATCH_NAME_IN_SPEC: Name = name!("ConnectBatch");
+pub(crate) const CONNECT_PAGINATION_NAME_IN_SPEC: Name = name!("ConnectPagination");
+pub(crate) const CONNECT_PAGINATION_MODE_NAME_IN_SPEC: Name = name!("ConnectPaginationMode");
 pub(crate) const CONNECT_BODY_ARGUMENT_NAME: Name = name!("body");
 pub(crate) const BATCH_ARGUMENT_NAME: Name = name!("batch");
 pub(crate) const IS_SUCCESS_ARGUMENT_NAME: Name = name!("isSuccess");
@@ -279,6 +283,13 @@ pub struct ConnectHTTPArguments {
     pub(crate) path: Option<JSONSelection>,
     /// A [`JSONSelection`] that should resolve to an object to convert to query params.
     pub(crate) query_params: Option<JSONSelection>,
+
+    /// The format of the response body, such as `xml` or `csv`, overriding the format given by
+    /// its `Content-Type` header.
+    pub(crate) response_format: Option<String>,
+
+    /// Settings for requesting every page of a paginated endpoint.
+    pub(crate) pagination: Option<ConnectPaginationArguments>,
 }
 
 impl TryFrom<(&ObjectNode, &Name)> for ConnectHTTPArguments {
@@ -297,6 +308,8 @@ impl TryFrom<(&ObjectNode, &Name)> for ConnectHTTPArguments {
             .map_err(|err| FederationError::internal(err.to_string()))?;
         let mut path = None;
         let mut query_params = None;
+        let mut response_format = None;
+        let mut pagination = None;
         for (name, value) in values {
             let name = name.as_str();
 
@@ -350,6 +363,25 @@ impl TryFrom<(&ObjectNode, &Name)> for ConnectHTTPArguments {
                     JSONSelection::parse(value)
                         .map_err(|e| FederationError::internal(e.message))?,
                 );
+            } else if name == RESPONSE_FORMAT_ARGUMENT_NAME.as_str() {
+                let value = value.as_str().ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`{}` field in `@{directive_name}` directive's `http` field is not a string",
+                        RESPONSE_FORMAT_ARGUMENT_NAME
+                    ))
+                })?;
+                response_format = Some(value.to_string());
+            } else if name == PAGINATION_ARGUMENT_NAME.as_str() {
+                let value = value.as_object().ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`{}` field in `@{directive_name}` directive's `http` field is not an object",
+                        PAGINATION_ARGUMENT_NAME
+                    ))
+                })?;
+                pagination = Some(ConnectPaginationArguments::try_from((
+                    value,
+                    directive_name,
+                ))?);
             }
         }
 
@@ -363,6 +395,8 @@ impl TryFrom<(&ObjectNode, &Name)> for ConnectHTTPArguments {
             headers,
             path,
             query_params,
+            response_format,
+            pagination,
         })
     }
 }
@@ -403,6 +437,132 @@ impl TryFrom<(&ObjectNode, &Name)> for ConnectBatchArguments {
     }
 }
 
+/// The number of pages requested when `maxPages` is not set
+const DEFAULT_MAX_PAGES: usize = 10;
+
+/// Settings for a connector requesting every page of a paginated endpoint
+#[derive(Clone, Debug)]
+pub struct ConnectPaginationArguments {
+    /// How the request for the next page is made
+    pub mode: PaginationMode,
+
+    /// A [`JSONSelection`] of the items in each page. The items of all pages are concatenated
+    /// into the array given to the connector's `selection`. Without it, each page is an item.
+    pub items: Option<JSONSelection>,
+
+    /// The maximum number of pages to request, including the first one
+    pub max_pages: usize,
+}
+
+/// How the request for the next page is made
+#[derive(Clone, Debug)]
+pub enum PaginationMode {
+    /// Sets a query parameter to a cursor selected from the previous page. Stops when there is
+    /// no cursor.
+    Cursor {
+        cursor: JSONSelection,
+        param: String,
+    },
+
+    /// Increases an offset query parameter by the page size. Stops when a page has fewer items
+    /// than the page size.
+    Offset { param: String, page_size: usize },
+
+    /// Follows the `rel="next"` URL of the `Link` header. Stops when there is none.
+    Link,
+}
+
+impl TryFrom<(&ObjectNode, &Name)> for ConnectPaginationArguments {
+    type Error = FederationError;
+
+    fn try_from((values, directive_name): (&ObjectNode, &Name)) -> Result<Self, FederationError> {
+        let mut mode = None;
+        let mut items = None;
+        let mut cursor = None;
+        let mut param = None;
+        let mut page_size = None;
+        let mut max_pages = DEFAULT_MAX_PAGES;
+        for (name, value) in values {
+            let name = name.as_str();
+
+            if name == "mode" {
+                let value = value.as_enum().ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`mode` field in `@{directive_name}` directive's `pagination` field is not an enum value"
+                    ))
+                })?;
+                mode = Some(value.clone());
+            } else if name == "param" {
+                let value = value.as_str().ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`param` field in `@{directive_name}` directive's `pagination` field is not a string"
+                    ))
+                })?;
+                param = Some(value.to_string());
+            } else if name == "items" || name == "cursor" {
+                let value = value.as_str().ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`{name}` field in `@{directive_name}` directive's `pagination` field is not a string"
+                    ))
+                })?;
+                let selection = JSONSelection::parse(value)
+                    .map_err(|e| FederationError::internal(e.message))?;
+                if name == "items" {
+                    items = Some(selection);
+                } else {
+                    cursor = Some(selection);
+                }
+            } else if name == "pageSize" || name == "maxPages" {
+                let value = value
+                    .to_i32()
+                    .and_then(|i| usize::try_from(i).ok())
+                    .filter(|i| *i > 0)
+                    .ok_or_else(|| {
+                        FederationError::internal(format!(
+                            "`{name}` field in `@{directive_name}` directive's `pagination` field is not a positive integer"
+                        ))
+                    })?;
+                if name == "pageSize" {
+                    page_size = Some(value);
+                } else {
+                    max_pages = value;
+                }
+            }
+        }
+
+        let mode = match mode.as_ref().map(Name::as_str) {
+            Some("CURSOR") => PaginationMode::Cursor {
+                cursor: cursor.ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`cursor` field in `@{directive_name}` directive's `pagination` field is required for the `CURSOR` mode"
+                    ))
+                })?,
+                param: param.unwrap_or_else(|| "cursor".to_string()),
+            },
+            Some("OFFSET") => PaginationMode::Offset {
+                param: param.unwrap_or_else(|| "offset".to_string()),
+                page_size: page_size.ok_or_else(|| {
+                    FederationError::internal(format!(
+                        "`pageSize` field in `@{directive_name}` directive's `pagination` field is required for the `OFFSET` mode"
+                    ))
+                })?,
+            },
+            Some("LINK") => PaginationMode::Link,
+            _ => {
+                return Err(FederationError::internal(format!(
+                    "`mode` field in `@{directive_name}` directive's `pagination` field must be one of `CURSOR`, `OFFSET` or `LINK`"
+                )));
+            }
+        };
+
+        Ok(Self {
+            mode,
+            items,
+            max_pages,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use apollo_compiler::Schema;
@@ -495,6 +655,8 @@ mod tests {
                         headers: [],
                         path: None,
                         query_params: None,
+                        response_format: None,
+                        pagination: None,
                     },
                 ),
                 selection: Named(
@@ -559,6 +721,8 @@ mod tests {
                         headers: [],
                         path: None,
                         query_params: None,
+                        response_format: None,
+                        pagination: None,
                     },
                 ),
                 selection: Named(
@@ -630,4 +794,64 @@ mod tests {
             connect.is_success.unwrap();
         }
     }
+
+    #[test]
+    fn it_parses_pagination_arguments() {
+        let values = |pairs: &[(&str, Value)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (Name::new(name).unwrap(), Node::new(value.clone())))
+                .collect::<Vec<_>>()
+        };
+        let directive_name = name!(connect);
+
+        let pagination = ConnectPaginationArguments::try_from((
+            values(&[
+                ("mode", Value::Enum(name!(CURSOR))),
+                ("cursor", Value::String("$.meta.next".into())),
+                ("items", Value::String("$.results".into())),
+            ])
+            .as_slice(),
+            &directive_name,
+        ))
+        .unwrap();
+        assert!(matches!(
+            pagination.mode,
+            PaginationMode::Cursor { ref param, .. } if param == "cursor"
+        ));
+        assert!(pagination.items.is_some());
+        assert_eq!(pagination.max_pages, DEFAULT_MAX_PAGES);
+
+        let pagination = ConnectPaginationArguments::try_from((
+            values(&[
+                ("mode", Value::Enum(name!(OFFSET))),
+                ("param", Value::String("skip".into())),
+                ("pageSize", Value::Int(25.into())),
+                ("maxPages", Value::Int(3.into())),
+            ])
+            .as_slice(),
+            &directive_name,
+        ))
+        .unwrap();
+        assert!(matches!(
+            pagination.mode,
+            PaginationMode::Offset { ref param, page_size: 25 } if param == "skip"
+        ));
+        assert_eq!(pagination.max_pages, 3);
+
+        assert!(
+            ConnectPaginationArguments::try_from((
+                values(&[("mode", Value::Enum(name!(OFFSET)))]).as_slice(),
+                &directive_name,
+            ))
+            .is_err()
+        );
+        assert!(
+            ConnectPaginationArguments::try_from((
+                values(&[("mode", Value::Enum(name!(PAGES)))]).as_slice(),
+                &directive_name,
+            ))
+            .is_err()
+        );
+    }
 }
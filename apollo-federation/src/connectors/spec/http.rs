@@ -10,5 +10,8 @@ pub(crate) const HEADERS_ARGUMENT_NAME: Name = name!("headers");
 
 pub(crate) const PATH_ARGUMENT_NAME: Name = name!("path");
 pub(crate) const QUERY_PARAMS_ARGUMENT_NAME: Name = name!("queryParams");
+pub(crate) const RESPONSE_FORMAT_ARGUMENT_NAME: Name = name!("responseFormat");
+pub(crate) const PAGINATION_ARGUMENT_NAME: Name = name!("pagination");
+pub(crate) const RESPONSE_SCHEMA_ARGUMENT_NAME: Name = name!("responseSchema");
 
 pub(crate) const URL_PATH_TEMPLATE_SCALAR_NAME: Name = name!("URLTemplate");
@@ -15,6 +15,8 @@ use apollo_compiler::ast::Directive;
 use apollo_compiler::ast::Value;
 use apollo_compiler::name;
 pub use connect::ConnectHTTPArguments;
+pub use connect::ConnectPaginationArguments;
+pub use connect::PaginationMode;
 pub(crate) use connect::extract_connect_directive_arguments;
 pub use source::SourceHTTPArguments;
 pub(crate) use source::extract_source_directive_arguments;
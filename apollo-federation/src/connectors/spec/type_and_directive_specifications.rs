@@ -16,6 +16,8 @@ use super::connect::CONNECT_BODY_ARGUMENT_NAME;
 use super::connect::CONNECT_DIRECTIVE_NAME_IN_SPEC;
 use super::connect::CONNECT_ENTITY_ARGUMENT_NAME;
 use super::connect::CONNECT_HTTP_NAME_IN_SPEC;
+use super::connect::CONNECT_PAGINATION_MODE_NAME_IN_SPEC;
+use super::connect::CONNECT_PAGINATION_NAME_IN_SPEC;
 use super::connect::CONNECT_SELECTION_ARGUMENT_NAME;
 use super::connect::CONNECT_SOURCE_ARGUMENT_NAME;
 use super::connect::IS_SUCCESS_ARGUMENT_NAME;
@@ -27,8 +29,11 @@ use super::http::HTTP_HEADER_MAPPING_FROM_ARGUMENT_NAME;
 use super::http::HTTP_HEADER_MAPPING_NAME_ARGUMENT_NAME;
 use super::http::HTTP_HEADER_MAPPING_NAME_IN_SPEC;
 use super::http::HTTP_HEADER_MAPPING_VALUE_ARGUMENT_NAME;
+use super::http::PAGINATION_ARGUMENT_NAME;
 use super::http::PATH_ARGUMENT_NAME;
 use super::http::QUERY_PARAMS_ARGUMENT_NAME;
+use super::http::RESPONSE_FORMAT_ARGUMENT_NAME;
+use super::http::RESPONSE_SCHEMA_ARGUMENT_NAME;
 use super::http::URL_PATH_TEMPLATE_SCALAR_NAME;
 use super::source::BaseUrl;
 use super::source::SOURCE_DIRECTIVE_NAME_IN_SPEC;
@@ -41,6 +46,8 @@ use crate::schema::FederationSchema;
 use crate::schema::type_and_directive_specification::ArgumentSpecification;
 use crate::schema::type_and_directive_specification::DirectiveArgumentSpecification;
 use crate::schema::type_and_directive_specification::DirectiveSpecification;
+use crate::schema::type_and_directive_specification::EnumTypeSpecification;
+use crate::schema::type_and_directive_specification::EnumValueSpecification;
 use crate::schema::type_and_directive_specification::InputObjectTypeSpecification;
 use crate::schema::type_and_directive_specification::ScalarTypeSpecification;
 use crate::schema::type_and_directive_specification::TypeAndDirectiveSpecification;
@@ -114,6 +121,9 @@ fn http_header_mapping_spec() -> InputObjectTypeSpecification {
 //   headers: [HTTPHeaderMapping!]
 //   path: JSONSelection
 //   queryParams: JSONSelection
+//   responseFormat: String
+//   pagination: ConnectPagination
+//   responseSchema: String
 // }
 fn connect_http_spec() -> InputObjectTypeSpecification {
     InputObjectTypeSpecification {
@@ -192,6 +202,107 @@ fn connect_http_spec() -> InputObjectTypeSpecification {
                     },
                     default_value: Default::default(),
                 },
+                ArgumentSpecification {
+                    name: RESPONSE_FORMAT_ARGUMENT_NAME,
+                    get_type: |_, _| Ok(ty!(String)),
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: PAGINATION_ARGUMENT_NAME,
+                    get_type: |s, _| {
+                        let name = link(s)?.type_name_in_schema(&CONNECT_PAGINATION_NAME_IN_SPEC);
+                        Ok(Type::Named(name))
+                    },
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: RESPONSE_SCHEMA_ARGUMENT_NAME,
+                    get_type: |_, _| Ok(ty!(String)),
+                    default_value: Default::default(),
+                },
+            ])
+        },
+    }
+}
+
+// enum ConnectPaginationMode {
+//   CURSOR
+//   OFFSET
+//   LINK
+// }
+fn connect_pagination_mode_spec() -> EnumTypeSpecification {
+    EnumTypeSpecification {
+        name: CONNECT_PAGINATION_MODE_NAME_IN_SPEC,
+        values: vec![
+            EnumValueSpecification {
+                name: name!("CURSOR"),
+                description: None,
+            },
+            EnumValueSpecification {
+                name: name!("OFFSET"),
+                description: None,
+            },
+            EnumValueSpecification {
+                name: name!("LINK"),
+                description: None,
+            },
+        ],
+    }
+}
+
+// input ConnectPagination {
+//   mode: ConnectPaginationMode!
+//   items: JSONSelection
+//   cursor: JSONSelection
+//   param: String
+//   pageSize: Int
+//   maxPages: Int
+// }
+fn connect_pagination_spec() -> InputObjectTypeSpecification {
+    InputObjectTypeSpecification {
+        name: CONNECT_PAGINATION_NAME_IN_SPEC,
+        fields: |_| {
+            Vec::from_iter([
+                ArgumentSpecification {
+                    name: name!(mode),
+                    get_type: |s, _| {
+                        let name =
+                            link(s)?.type_name_in_schema(&CONNECT_PAGINATION_MODE_NAME_IN_SPEC);
+                        Ok(Type::NonNullNamed(name))
+                    },
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: name!(items),
+                    get_type: |s, _| {
+                        let name = link(s)?.type_name_in_schema(&JSON_SELECTION_SCALAR_NAME);
+                        Ok(Type::Named(name))
+                    },
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: name!(cursor),
+                    get_type: |s, _| {
+                        let name = link(s)?.type_name_in_schema(&JSON_SELECTION_SCALAR_NAME);
+                        Ok(Type::Named(name))
+                    },
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: name!(param),
+                    get_type: |_, _| Ok(ty!(String)),
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: name!(pageSize),
+                    get_type: |_, _| Ok(ty!(Int)),
+                    default_value: Default::default(),
+                },
+                ArgumentSpecification {
+                    name: name!(maxPages),
+                    get_type: |_, _| Ok(ty!(Int)),
+                    default_value: Default::default(),
+                },
             ])
         },
     }
@@ -293,6 +404,8 @@ pub(crate) fn type_specifications() -> Vec<Box<dyn TypeAndDirectiveSpecification
         Box::new(json_selection_spec()),
         Box::new(url_path_template_spec()),
         Box::new(http_header_mapping_spec()),
+        Box::new(connect_pagination_mode_spec()),
+        Box::new(connect_pagination_spec()),
         Box::new(connect_http_spec()),
         Box::new(connect_batch_spec()),
         Box::new(connector_errors_spec()),
@@ -536,6 +649,21 @@ mod tests {
           value: [String!]
         }
 
+        enum connect__ConnectPaginationMode {
+          CURSOR
+          OFFSET
+          LINK
+        }
+
+        input connect__ConnectPagination {
+          mode: connect__ConnectPaginationMode!
+          items: connect__JSONSelection
+          cursor: connect__JSONSelection
+          param: String
+          pageSize: Int
+          maxPages: Int
+        }
+
         input connect__ConnectHTTP {
           GET: connect__URLTemplate
           POST: connect__URLTemplate
@@ -546,6 +674,9 @@ mod tests {
           headers: [connect__HTTPHeaderMapping!]
           path: connect__JSONSelection
           queryParams: connect__JSONSelection
+          responseFormat: String
+          pagination: connect__ConnectPagination
+          responseSchema: String
         }
 
         input connect__ConnectBatch {
@@ -622,6 +753,21 @@ mod tests {
           value: [String!]
         }
 
+        enum connect__ConnectPaginationMode {
+          CURSOR
+          OFFSET
+          LINK
+        }
+
+        input connect__ConnectPagination {
+          mode: connect__ConnectPaginationMode!
+          items: connect__JSONSelection
+          cursor: connect__JSONSelection
+          param: String
+          pageSize: Int
+          maxPages: Int
+        }
+
         input connect__ConnectHTTP {
           GET: connect__URLTemplate
           POST: connect__URLTemplate
@@ -632,6 +778,9 @@ mod tests {
           headers: [connect__HTTPHeaderMapping!]
           path: connect__JSONSelection
           queryParams: connect__JSONSelection
+          responseFormat: String
+          pagination: connect__ConnectPagination
+          responseSchema: String
         }
 
         input connect__ConnectBatch {
@@ -713,6 +862,21 @@ mod tests {
           value: [String!]
         }
 
+        enum connect__ConnectPaginationMode {
+          CURSOR
+          OFFSET
+          LINK
+        }
+
+        input connect__ConnectPagination {
+          mode: connect__ConnectPaginationMode!
+          items: Mapping
+          cursor: Mapping
+          param: String
+          pageSize: Int
+          maxPages: Int
+        }
+
         input ConnectHTTP {
           GET: connect__URLTemplate
           POST: connect__URLTemplate
@@ -723,6 +887,9 @@ mod tests {
           headers: [connect__HTTPHeaderMapping!]
           path: Mapping
           queryParams: Mapping
+          responseFormat: String
+          pagination: connect__ConnectPagination
+          responseSchema: String
         }
 
         input connect__ConnectBatch {
@@ -810,6 +977,21 @@ mod tests {
           value: [String!]
         }
 
+        enum connect__ConnectPaginationMode {
+          CURSOR
+          OFFSET
+          LINK
+        }
+
+        input connect__ConnectPagination {
+          mode: connect__ConnectPaginationMode!
+          items: connect__JSONSelection
+          cursor: connect__JSONSelection
+          param: String
+          pageSize: Int
+          maxPages: Int
+        }
+
         input connect__ConnectBatch {
           maxSize: Int
         }
@@ -11,9 +11,11 @@ use itertools::Itertools;
 use multi_try::MultiTry;
 
 use self::entity::validate_entity_arg;
+use self::response_schema::ResponseSchema;
 use self::selection::Selection;
 use super::Code;
 use super::Message;
+use super::Severity;
 use super::coordinates::ConnectDirectiveCoordinate;
 use super::errors::ErrorsCoordinate;
 use crate::connectors::Namespace;
@@ -28,13 +30,23 @@ use crate::connectors::validation::graphql::SchemaInfo;
 
 mod entity;
 mod http;
+mod pagination;
+mod response_schema;
 mod selection;
 
+/// The fields resolved by all `@connect` directives, as (ObjectName, FieldName) pairs, along with
+/// any warnings from checking those directives.
+pub(super) struct SeenFields {
+    pub(super) fields: Vec<(Name, Name)>,
+    pub(super) warnings: Vec<Message>,
+}
+
 pub(super) fn fields_seen_by_all_connects(
     schema: &SchemaInfo,
     all_source_names: &[SourceName],
-) -> Result<Vec<(Name, Name)>, Vec<Message>> {
+) -> Result<SeenFields, Vec<Message>> {
     let mut messages = Vec::new();
+    let mut warnings = Vec::new();
     let mut connects = Vec::new();
 
     for extended_type in schema.types.values().filter(|ty| !ty.is_built_in()) {
@@ -50,7 +62,8 @@ pub(super) fn fields_seen_by_all_connects(
     let mut seen_fields = Vec::new();
     for connect in connects {
         match connect.type_check() {
-            Ok(seen_fields_for_connect) => {
+            Ok((seen_fields_for_connect, warnings_for_connect)) => {
+                warnings.extend(warnings_for_connect);
                 seen_fields.extend(
                     seen_fields_for_connect
                         .into_iter()
@@ -64,8 +77,12 @@ pub(super) fn fields_seen_by_all_connects(
     }
 
     if messages.is_empty() {
-        Ok(seen_fields)
+        Ok(SeenFields {
+            fields: seen_fields,
+            warnings,
+        })
     } else {
+        messages.extend(warnings);
         Err(messages)
     }
 }
@@ -74,6 +91,7 @@ pub(super) fn fields_seen_by_all_connects(
 struct Connect<'schema> {
     selection: Selection<'schema>,
     http: Http<'schema>,
+    response_schema: Option<ResponseSchema<'schema>>,
     errors: Errors<'schema>,
     coordinate: ConnectDirectiveCoordinate<'schema>,
     schema: &'schema SchemaInfo<'schema>,
@@ -141,12 +159,13 @@ impl<'schema> Connect<'schema> {
     /// Parse the `@connect` directive and run just enough checks to be able to use it at runtime.
     /// More advanced checks are done in [`Self::type_check`].
     ///
-    /// Three sub-pieces are parsed:
+    /// Four sub-pieces are parsed:
     /// 1. `@connect(http:)` with [`Http::parse`]
     /// 2. `@connect(source:)` with [`validate_source_name`]
     /// 3. `@connect(selection:)` with [`Selection::parse`]
+    /// 4. `@connect(http: {responseSchema:})` with [`ResponseSchema::parse`]
     ///
-    /// `selection` and `source` are _always_ checked and their errors are returned.
+    /// `selection`, `source` and `responseSchema` are _always_ checked and their errors are returned.
     /// The order these two run in doesn't matter.
     /// `http` can't be validated without knowing whether a `source` was set, so it's only checked if `source` is valid.
     fn parse(
@@ -170,13 +189,14 @@ impl<'schema> Connect<'schema> {
             }]);
         }
 
-        let (selection, http, errors) = Selection::parse(coordinate, schema)
+        let (selection, http, response_schema, errors) = Selection::parse(coordinate, schema)
             .map_err(|err| vec![err])
             .and_try(
                 validate_source_name(coordinate, source_names, schema)
                     .map_err(|err| vec![err])
                     .and_then(|source_name| Http::parse(coordinate, source_name.as_ref(), schema)),
             )
+            .and_try(ResponseSchema::parse(coordinate, schema).map_err(|err| vec![err]))
             .and_try(Errors::parse(
                 ErrorsCoordinate::Connect {
                     connect: coordinate,
@@ -188,13 +208,15 @@ impl<'schema> Connect<'schema> {
         Ok(Self {
             selection,
             http,
+            response_schema,
             errors,
             coordinate,
             schema,
         })
     }
 
-    fn type_check(self) -> Result<Vec<ResolvedField>, Vec<Message>> {
+    /// Type-check the parsed `@connect`, returning the fields it resolves and any warnings.
+    fn type_check(self) -> Result<(Vec<ResolvedField>, Vec<Message>), Vec<Message>> {
         let mut messages = Vec::new();
 
         let all_variables = self
@@ -235,6 +257,12 @@ impl<'schema> Connect<'schema> {
                 .flatten(),
         );
 
+        let response_schema_messages = self
+            .response_schema
+            .as_ref()
+            .map(|response_schema| response_schema.type_check(&self.selection, self.schema))
+            .unwrap_or_default();
+
         let mut seen: Vec<ResolvedField> = match self.selection.type_check(self.schema) {
             // TODO: use ResolvedField struct at all levels
             Ok(seen) => seen
@@ -276,10 +304,14 @@ impl<'schema> Connect<'schema> {
             }
         }
 
-        if messages.is_empty() {
-            Ok(seen)
-        } else {
+        messages.extend(response_schema_messages);
+        if messages
+            .iter()
+            .any(|message| message.code.severity() == Severity::Error)
+        {
             Err(messages)
+        } else {
+            Ok((seen, messages))
         }
     }
 }
@@ -9,12 +9,16 @@ use apollo_compiler::ast::Value;
 use multi_try::MultiTry;
 use shape::Shape;
 
+use super::pagination::validate_pagination;
 use crate::connectors::HTTPMethod;
 use crate::connectors::Namespace;
+use crate::connectors::RESPONSE_FORMATS;
 use crate::connectors::SourceName;
+use crate::connectors::spec::ConnectSpec;
 use crate::connectors::spec::connect::CONNECT_BODY_ARGUMENT_NAME;
 use crate::connectors::spec::connect::CONNECT_SOURCE_ARGUMENT_NAME;
 use crate::connectors::spec::http::HTTP_ARGUMENT_NAME;
+use crate::connectors::spec::http::RESPONSE_FORMAT_ARGUMENT_NAME;
 use crate::connectors::string_template;
 use crate::connectors::string_template::Part;
 use crate::connectors::string_template::StringTemplate;
@@ -48,11 +52,13 @@ impl<'schema> Http<'schema> {
     /// Parse the `@connect(http:)` argument and run just enough checks to be able to use the
     /// argument at runtime. More advanced checks are done in [`Self::type_check`].
     ///
-    /// Three sub-pieces are always parsed, and the errors from _all_ of those pieces are returned
+    /// Five sub-pieces are always parsed, and the errors from _all_ of those pieces are returned
     /// together in the event of failure:
     /// 1. `http.body` with [`Body::parse`]
     /// 2. `http.headers` with [`Headers::parse`]
     /// 3. `http.<METHOD>` (for example, `http.GET`) with [`Transport::parse`]
+    /// 4. `http.pagination` with [`validate_pagination`]
+    /// 5. `http.responseFormat` with [`validate_response_format`]
     ///
     /// The order these pieces run in doesn't matter and shouldn't affect the output.
     pub(super) fn parse(
@@ -92,8 +98,12 @@ impl<'schema> Http<'schema> {
                 source_name,
                 schema,
             ))
+            .and_try(validate_pagination(http_arg, coordinate, schema))
+            .and_try(
+                validate_response_format(http_arg, coordinate, schema).map_err(|err| vec![err]),
+            )
             .map_err(|nested| nested.into_iter().flatten().collect())
-            .map(|(body, headers, transport)| Self {
+            .map(|(body, headers, transport, (), ())| Self {
                 body,
                 headers,
                 transport,
@@ -367,6 +377,54 @@ impl<'schema> Transport<'schema> {
     }
 }
 
+/// Check that the `responseFormat` of `@connect(http:)`, if there is one, names a format the
+/// router has a decoder for.
+fn validate_response_format(
+    http_arg: &[(Name, Node<Value>)],
+    coordinate: ConnectDirectiveCoordinate,
+    schema: &SchemaInfo,
+) -> Result<(), Message> {
+    let Some((_, value)) = http_arg
+        .iter()
+        .find(|(name, _)| name == &RESPONSE_FORMAT_ARGUMENT_NAME)
+    else {
+        return Ok(());
+    };
+    let locations = value
+        .line_column_range(&schema.sources)
+        .into_iter()
+        .collect();
+    let spec = schema.connect_link.spec();
+    if spec < ConnectSpec::V0_3 {
+        return Err(Message {
+            code: Code::InvalidResponseFormat,
+            message: format!(
+                "`{RESPONSE_FORMAT_ARGUMENT_NAME}` in {coordinate} is not available in connect spec version {spec}, update the version in `@link` to use it",
+                coordinate = ConnectHTTPCoordinate::from(coordinate),
+            ),
+            locations,
+        });
+    }
+    if value.as_str().is_some_and(|format| {
+        RESPONSE_FORMATS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(format))
+    }) {
+        return Ok(());
+    }
+    Err(Message {
+        code: Code::InvalidResponseFormat,
+        message: format!(
+            "`{RESPONSE_FORMAT_ARGUMENT_NAME}` in {coordinate} must be one of {formats}.",
+            coordinate = ConnectHTTPCoordinate::from(coordinate),
+            formats = RESPONSE_FORMATS
+                .map(|format| format!("`{format}`"))
+                .join(", "),
+        ),
+        locations,
+    })
+}
+
 /// Additional validation rules when using `@connect` without `source:`
 fn validate_absolute_connect_url(
     url: &StringTemplate,
@@ -0,0 +1,175 @@
+//! Validation of `@connect(http: {pagination:})`
+
+use std::fmt::Display;
+
+use apollo_compiler::Name;
+use apollo_compiler::Node;
+use apollo_compiler::ast::Value;
+
+use crate::connectors::spec::ConnectSpec;
+use crate::connectors::spec::http::HTTP_ARGUMENT_NAME;
+use crate::connectors::spec::http::PAGINATION_ARGUMENT_NAME;
+use crate::connectors::validation::Code;
+use crate::connectors::validation::Message;
+use crate::connectors::validation::coordinates::ConnectDirectiveCoordinate;
+use crate::connectors::validation::expression::parse_mapping_argument;
+use crate::connectors::validation::graphql::SchemaInfo;
+
+const MODES: [&str; 3] = ["CURSOR", "OFFSET", "LINK"];
+
+/// Check the `pagination` argument of `@connect(http:)`, if there is one.
+///
+/// `items` and `cursor` are applied to each page of the response on its own, so they can't use
+/// any variables.
+pub(super) fn validate_pagination(
+    http_arg: &[(Name, Node<Value>)],
+    connect: ConnectDirectiveCoordinate,
+    schema: &SchemaInfo,
+) -> Result<(), Vec<Message>> {
+    let Some((_, value)) = http_arg
+        .iter()
+        .find(|(name, _)| name == &PAGINATION_ARGUMENT_NAME)
+    else {
+        return Ok(());
+    };
+    let coordinate = PaginationCoordinate {
+        connect,
+        field: None,
+    };
+    let locations = |node: &Node<Value>| {
+        node.line_column_range(&schema.sources)
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+    let spec = schema.connect_link.spec();
+    if spec < ConnectSpec::V0_3 {
+        return Err(vec![Message {
+            code: Code::InvalidPagination,
+            message: format!(
+                "{coordinate} is not available in connect spec version {spec}, update the version in `@link` to use it"
+            ),
+            locations: locations(value),
+        }]);
+    }
+    let Some(fields) = value.as_object() else {
+        return Err(vec![Message {
+            code: Code::InvalidPagination,
+            message: format!("{coordinate} must be an object."),
+            locations: locations(value),
+        }]);
+    };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    };
+
+    let mut messages = Vec::new();
+    for name in ["items", "cursor"] {
+        let Some(value) = field(name) else {
+            continue;
+        };
+        let coordinate = PaginationCoordinate {
+            connect,
+            field: Some(name),
+        };
+        match parse_mapping_argument(value, coordinate, Code::InvalidPagination, schema) {
+            Ok(mapping) => {
+                if let Some(reference) = mapping.expression.expression.variable_references().next()
+                {
+                    messages.push(Message {
+                        code: Code::InvalidPagination,
+                        message: format!(
+                            "{coordinate} can't use `{namespace}`, it is only given each page of the response.",
+                            namespace = reference.namespace.namespace
+                        ),
+                        locations: locations(value),
+                    });
+                }
+            }
+            Err(message) => messages.push(message),
+        }
+    }
+
+    for name in ["pageSize", "maxPages"] {
+        let Some(value) = field(name) else {
+            continue;
+        };
+        if value.to_i32().is_none_or(|value| value <= 0) {
+            messages.push(Message {
+                code: Code::InvalidPagination,
+                message: format!(
+                    "{coordinate} must be a positive integer.",
+                    coordinate = PaginationCoordinate {
+                        connect,
+                        field: Some(name),
+                    }
+                ),
+                locations: locations(value),
+            });
+        }
+    }
+
+    let mode = field("mode");
+    let mode_name = mode.and_then(|mode| mode.as_enum());
+    let required = match mode_name.map(Name::as_str) {
+        Some("CURSOR") => Some("cursor"),
+        Some("OFFSET") => Some("pageSize"),
+        Some("LINK") => None,
+        _ => {
+            messages.push(Message {
+                code: Code::InvalidPagination,
+                message: format!(
+                    "{coordinate} must be one of {modes}.",
+                    coordinate = PaginationCoordinate {
+                        connect,
+                        field: Some("mode"),
+                    },
+                    modes = MODES.map(|mode| format!("`{mode}`")).join(", "),
+                ),
+                locations: locations(mode.unwrap_or(value)),
+            });
+            None
+        }
+    };
+    if let Some(required) = required.filter(|required| field(required).is_none()) {
+        messages.push(Message {
+            code: Code::InvalidPagination,
+            message: format!(
+                "{coordinate} must set `{required}` for the `{mode}` mode.",
+                mode = mode_name.map(Name::as_str).unwrap_or_default()
+            ),
+            locations: locations(value),
+        });
+    }
+
+    if messages.is_empty() {
+        Ok(())
+    } else {
+        Err(messages)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PaginationCoordinate<'schema> {
+    connect: ConnectDirectiveCoordinate<'schema>,
+    field: Option<&'schema str>,
+}
+
+impl Display for PaginationCoordinate<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let connect_directive_name = &self.connect.directive.name;
+        let element = &self.connect.element;
+        match self.field {
+            Some(field) => write!(
+                f,
+                "`@{connect_directive_name}({HTTP_ARGUMENT_NAME}: {{{PAGINATION_ARGUMENT_NAME}: {{{field}:}}}})` on `{element}`"
+            ),
+            None => write!(
+                f,
+                "`@{connect_directive_name}({HTTP_ARGUMENT_NAME}: {{{PAGINATION_ARGUMENT_NAME}:}})` on `{element}`"
+            ),
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Checking `@connect(selection:)` against `@connect(http: {responseSchema:})`
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use apollo_compiler::Node;
+use apollo_compiler::ast::Type;
+use apollo_compiler::ast::Value;
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::schema::ObjectType;
+use itertools::Itertools;
+use shape::Shape;
+use shape::ShapeCase;
+
+use super::Code;
+use super::Message;
+use super::selection::Selection;
+use crate::connectors::id::ConnectedElement;
+use crate::connectors::spec::ConnectSpec;
+use crate::connectors::spec::http::HTTP_ARGUMENT_NAME;
+use crate::connectors::spec::http::RESPONSE_SCHEMA_ARGUMENT_NAME;
+use crate::connectors::validation::coordinates::ConnectDirectiveCoordinate;
+use crate::connectors::validation::coordinates::SelectionCoordinate;
+use crate::connectors::validation::graphql::SchemaInfo;
+use crate::connectors::validation::json_schema::JsonSchema;
+use crate::connectors::validation::json_schema::operation_response_pointer;
+
+/// How deep to look for required properties that the selection doesn't use.
+const MAX_REQUIRED_PROPERTY_DEPTH: usize = 8;
+
+/// The `@connect(http: {responseSchema:})` argument, which is either an inline JSON Schema or a
+/// `document#/json/pointer` reference to a JSON Schema or an OpenAPI operation in one of the
+/// documents passed to validation.
+pub(super) struct ResponseSchema<'schema> {
+    value: &'schema str,
+    node: &'schema Node<Value>,
+    coordinate: ResponseSchemaCoordinate<'schema>,
+}
+
+impl<'schema> ResponseSchema<'schema> {
+    pub(super) fn parse(
+        connect: ConnectDirectiveCoordinate<'schema>,
+        schema: &SchemaInfo,
+    ) -> Result<Option<Self>, Message> {
+        let Some((_, node)) = connect
+            .directive
+            .specified_argument_by_name(&HTTP_ARGUMENT_NAME)
+            .and_then(|arg| arg.as_object())
+            .and_then(|http_arg| {
+                http_arg
+                    .iter()
+                    .find(|(name, _)| name == &RESPONSE_SCHEMA_ARGUMENT_NAME)
+            })
+        else {
+            return Ok(None);
+        };
+        let coordinate = ResponseSchemaCoordinate { connect };
+        let spec = schema.connect_link.spec();
+        if spec < ConnectSpec::V0_3 {
+            return Err(Message {
+                code: Code::InvalidResponseSchema,
+                message: format!(
+                    "{coordinate} is not available in connect spec version {spec}, update the version in `@link` to use it"
+                ),
+                locations: node
+                    .line_column_range(&schema.sources)
+                    .into_iter()
+                    .collect(),
+            });
+        }
+        let Some(value) = node.as_str() else {
+            return Err(Message {
+                code: Code::GraphQLError,
+                message: format!("{coordinate} must be a string."),
+                locations: node
+                    .line_column_range(&schema.sources)
+                    .into_iter()
+                    .collect(),
+            });
+        };
+
+        Ok(Some(Self {
+            value,
+            node,
+            coordinate,
+        }))
+    }
+
+    /// Check that everything the selection maps exists in the response schema with a type that
+    /// fits the GraphQL field it's mapped to, and warn about required properties of the response
+    /// that match a field of the GraphQL type but that the selection never uses.
+    pub(super) fn type_check(&self, selection: &Selection, schema: &SchemaInfo) -> Vec<Message> {
+        let (root, pointer) = match self.resolve(schema) {
+            Ok(resolved) => resolved,
+            Err(message) => return vec![message],
+        };
+        let Some(target) = root.pointer(&pointer) else {
+            return vec![self.error(
+                format!("`{pointer}` doesn't exist in the response schema"),
+                schema,
+            )];
+        };
+
+        let mut json_schema = JsonSchema::new(&root);
+        let response_shape = json_schema.shape(target, &[]);
+        let output_shape = selection.output_shape(response_shape.clone());
+
+        let mut checker = OutputChecker {
+            schema,
+            selection,
+            coordinate: SelectionCoordinate::from(self.coordinate.connect),
+            messages: Vec::new(),
+        };
+        let (ty, label) = match self.coordinate.connect.element {
+            ConnectedElement::Field { field_def, .. } => (
+                field_def.ty.clone(),
+                self.coordinate.connect.element.to_string(),
+            ),
+            ConnectedElement::Type { type_def } => (
+                Type::Named(type_def.name.clone()),
+                type_def.name.to_string(),
+            ),
+        };
+        checker.check(&output_shape, &ty, &label, true);
+        let mut messages = checker.messages;
+
+        let mut unmapped = Vec::new();
+        if let Some(object) = schema.get_object(ty.inner_named_type()) {
+            UnmappedProperties {
+                schema,
+                json_schema: &mut json_schema,
+                target,
+                response_shape: &response_shape,
+                selection,
+                output_shape: &output_shape,
+                unmapped: &mut unmapped,
+            }
+            .find(target, object, &[]);
+        }
+        if !unmapped.is_empty() {
+            messages.push(Message {
+                code: Code::UnmappedResponseField,
+                message: format!(
+                    "In {coordinate}: the response schema requires {properties}, but the selection never uses {them}.",
+                    coordinate = self.coordinate,
+                    properties = unmapped.iter().map(|path| format!("`{path}`")).join(", "),
+                    them = if unmapped.len() == 1 { "it" } else { "them" },
+                ),
+                locations: self
+                    .node
+                    .line_column_range(&schema.sources)
+                    .into_iter()
+                    .collect(),
+            });
+        }
+
+        messages
+    }
+
+    /// Find the document the response schema lives in, and a JSON pointer to it.
+    fn resolve<'a>(
+        &self,
+        schema: &'a SchemaInfo,
+    ) -> Result<(Cow<'a, serde_json::Value>, String), Message> {
+        if self.value.trim_start().starts_with('{') {
+            return serde_json::from_str(self.value)
+                .map(|value| (Cow::Owned(value), String::new()))
+                .map_err(|err| self.error(format!("is not valid JSON: {err}"), schema));
+        }
+
+        let (document_name, pointer) = self.value.split_once('#').unwrap_or((self.value, ""));
+        let Some(document) = schema.documents.get(document_name) else {
+            return Err(Message {
+                code: Code::UnresolvedResponseSchema,
+                message: format!(
+                    "{coordinate} refers to the document `{document_name}`, which wasn't provided, so the selection can't be checked against it.",
+                    coordinate = self.coordinate,
+                ),
+                locations: self
+                    .node
+                    .line_column_range(&schema.sources)
+                    .into_iter()
+                    .collect(),
+            });
+        };
+        if document.pointer(pointer).is_none() {
+            return Err(self.error(
+                format!("refers to `{pointer}`, which doesn't exist in `{document_name}`"),
+                schema,
+            ));
+        }
+        let is_operation = document
+            .pointer(pointer)
+            .is_some_and(|target| target.get("responses").is_some());
+        if !is_operation {
+            return Ok((Cow::Borrowed(document), pointer.to_string()));
+        }
+        operation_response_pointer(document, pointer)
+            .map(|pointer| (Cow::Borrowed(document), pointer))
+            .ok_or_else(|| {
+                self.error(
+                    format!("refers to an operation in `{document_name}` without a successful JSON response"),
+                    schema,
+                )
+            })
+    }
+
+    fn error(&self, problem: String, schema: &SchemaInfo) -> Message {
+        Message {
+            code: Code::InvalidResponseSchema,
+            message: format!("{coordinate} {problem}.", coordinate = self.coordinate),
+            locations: self
+                .node
+                .line_column_range(&schema.sources)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Walks the output shape of a selection alongside the GraphQL type it should produce.
+struct OutputChecker<'a> {
+    schema: &'a SchemaInfo<'a>,
+    selection: &'a Selection<'a>,
+    coordinate: SelectionCoordinate<'a>,
+    messages: Vec<Message>,
+}
+
+impl OutputChecker<'_> {
+    /// Check `shape` against `ty`, where `label` names the field (or type) being checked. Arrays
+    /// are allowed at the root for any type, since connectors map over array responses.
+    fn check(&mut self, shape: &Shape, ty: &Type, label: &str, root: bool) {
+        match shape.case() {
+            ShapeCase::Error(shape::Error { message, .. }) => {
+                self.push(format!("{message} in the response schema"), shape);
+            }
+            ShapeCase::One(shapes) => {
+                for inner in shapes.iter() {
+                    self.check(
+                        &inner.with_locations(shape.locations.clone()),
+                        ty,
+                        label,
+                        root,
+                    );
+                }
+            }
+            ShapeCase::All(shapes) => {
+                for inner in shapes.iter() {
+                    self.check(
+                        &inner.with_locations(shape.locations.clone()),
+                        ty,
+                        label,
+                        root,
+                    );
+                }
+            }
+            ShapeCase::Array { prefix, tail } => {
+                let item_ty = match ty {
+                    Type::List(item_ty) | Type::NonNullList(item_ty) => item_ty.as_ref(),
+                    _ if root => ty,
+                    _ => {
+                        self.mismatch(shape, ty, label);
+                        return;
+                    }
+                };
+                for item in prefix.iter().chain(Some(tail)) {
+                    self.check(item, item_ty, label, false);
+                }
+            }
+            ShapeCase::Object { fields, .. } => {
+                match self.schema.types.get(ty.inner_named_type()) {
+                    Some(ExtendedType::Object(object)) => {
+                        for (name, field_shape) in fields {
+                            if let Some(field) = object.fields.get(name.as_str()) {
+                                let label = format!("{}.{}", object.name, field.name);
+                                self.check(field_shape, &field.ty, &label, false);
+                            }
+                        }
+                    }
+                    Some(ExtendedType::Scalar(scalar)) if !scalar.is_built_in() => {}
+                    _ => self.mismatch(shape, ty, label),
+                }
+            }
+            ShapeCase::Bool(_) | ShapeCase::String(_) | ShapeCase::Int(_) | ShapeCase::Float => {
+                if !self.accepts(ty, shape) {
+                    self.mismatch(shape, ty, label);
+                }
+            }
+            ShapeCase::Null | ShapeCase::None | ShapeCase::Unknown | ShapeCase::Name(_, _) => {}
+        }
+    }
+
+    /// Whether a field of type `ty` can hold a scalar value of this shape.
+    fn accepts(&self, ty: &Type, shape: &Shape) -> bool {
+        match self.schema.types.get(ty.inner_named_type()) {
+            Some(ExtendedType::Scalar(scalar)) => match scalar.name.as_str() {
+                "Int" => matches!(shape.case(), ShapeCase::Int(_)),
+                "Float" => matches!(shape.case(), ShapeCase::Int(_) | ShapeCase::Float),
+                "String" => matches!(shape.case(), ShapeCase::String(_)),
+                "ID" => matches!(shape.case(), ShapeCase::String(_) | ShapeCase::Int(_)),
+                "Boolean" => matches!(shape.case(), ShapeCase::Bool(_)),
+                _ => true,
+            },
+            Some(ExtendedType::Enum(_)) => matches!(shape.case(), ShapeCase::String(_)),
+            Some(_) => false,
+            // Unknown types are reported elsewhere
+            None => true,
+        }
+    }
+
+    fn mismatch(&mut self, shape: &Shape, ty: &Type, label: &str) {
+        let kind = match shape.case() {
+            ShapeCase::Bool(_) => "a boolean",
+            ShapeCase::String(_) => "a string",
+            ShapeCase::Int(_) => "an integer",
+            ShapeCase::Float => "a number",
+            ShapeCase::Array { .. } => "an array",
+            ShapeCase::Object { .. } => "an object",
+            _ => "a different type",
+        };
+        self.push(
+            format!("`{label}` is `{ty}`, but the response schema has {kind} here"),
+            shape,
+        );
+    }
+
+    fn push(&mut self, problem: impl Display, shape: &Shape) {
+        let message = format!("In {coordinate}: {problem}.", coordinate = self.coordinate);
+        let locations = self.selection.locations(&shape.locations, self.schema);
+        if self
+            .messages
+            .iter()
+            .any(|existing| existing.message == message && existing.locations == locations)
+        {
+            return;
+        }
+        self.messages.push(Message {
+            code: Code::ResponseSchemaMismatch,
+            message,
+            locations,
+        });
+    }
+}
+
+/// Finds required properties of the response that the selection doesn't use, by leaving each one
+/// out of the response shape and checking whether the selection's output gains an error.
+///
+/// Only properties named like a field of the GraphQL type at the same place are considered, since
+/// most APIs return far more than any one connector needs.
+struct UnmappedProperties<'a, 'b> {
+    schema: &'b SchemaInfo<'b>,
+    json_schema: &'b mut JsonSchema<'a>,
+    target: &'a serde_json::Value,
+    response_shape: &'b Shape,
+    selection: &'b Selection<'b>,
+    output_shape: &'b Shape,
+    unmapped: &'b mut Vec<String>,
+}
+
+impl<'a> UnmappedProperties<'a, '_> {
+    fn find(&mut self, schema: &'a serde_json::Value, object: &ObjectType, path: &[String]) {
+        if path.len() >= MAX_REQUIRED_PROPERTY_DEPTH {
+            return;
+        }
+        for (name, property) in self.json_schema.required_properties(schema) {
+            let Some(field) = object.fields.get(name) else {
+                continue;
+            };
+            let mut path = path.to_vec();
+            path.push(name.to_string());
+            let without = self.json_schema.shape(self.target, &path);
+            // Recursive schemas stop being expanded at some depth, so there's nothing to leave out
+            if &without == self.response_shape {
+                continue;
+            }
+            // The selection uses a property if leaving it out of the response changes its output
+            if &self.selection.output_shape(without) != self.output_shape {
+                if let Some(object) = self.schema.get_object(field.ty.inner_named_type()) {
+                    self.find(property, object, &path);
+                }
+            } else {
+                self.unmapped.push(path.join("."));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ResponseSchemaCoordinate<'schema> {
+    connect: ConnectDirectiveCoordinate<'schema>,
+}
+
+impl Display for ResponseSchemaCoordinate<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`@{connect_directive_name}({HTTP_ARGUMENT_NAME}: {{{RESPONSE_SCHEMA_ARGUMENT_NAME}:}})` on `{element}`",
+            connect_directive_name = self.connect.directive.name,
+            element = self.connect.element
+        )
+    }
+}
@@ -7,10 +7,14 @@ use std::ops::Range;
 use apollo_compiler::Node;
 use apollo_compiler::ast::FieldDefinition;
 use apollo_compiler::ast::Value;
+use apollo_compiler::collections::IndexMap;
 use apollo_compiler::parser::LineColumn;
 use apollo_compiler::schema::ExtendedType;
 use apollo_compiler::schema::ObjectType;
 use itertools::Itertools;
+use shape::Shape;
+use shape::location::Location;
+use shape::location::SourceId;
 
 use self::variables::VariableResolver;
 use super::Code;
@@ -154,6 +158,36 @@ impl<'schema> Selection<'schema> {
             .variable_references()
             .map(|var_ref| var_ref.namespace.namespace)
     }
+
+    /// Compute the shape this selection produces when applied to a response body of the given
+    /// shape. Locations in the result refer to [`Self::source_id`].
+    pub(super) fn output_shape(&self, response_shape: Shape) -> Shape {
+        self.parsed
+            .compute_output_shape(response_shape, &IndexMap::default(), &Self::source_id())
+    }
+
+    /// Convert shape locations within the selection to locations in the schema, highlighting the
+    /// whole selection if none of them point into it.
+    pub(super) fn locations<'a>(
+        &self,
+        locations: impl IntoIterator<Item = &'a Location>,
+        schema: &SchemaInfo,
+    ) -> Vec<Range<LineColumn>> {
+        let source_id = Self::source_id();
+        let mut ranges = locations
+            .into_iter()
+            .filter(|location| location.source_id == source_id)
+            .filter_map(|location| subslice_location(&self.node, location.span.clone(), schema))
+            .collect_vec();
+        if ranges.is_empty() {
+            ranges.extend(self.node.line_column_range(&schema.sources));
+        }
+        ranges
+    }
+
+    fn source_id() -> SourceId {
+        SourceId::Other("JSONSelection".into())
+    }
 }
 
 /// Validate variable references in a JSON Selection
@@ -320,6 +320,8 @@ pub(crate) fn validate(
         }
     }
 
+    check_methods_available(expression, context.node, context.code, context.schema)?;
+
     let shape = expression.expression.shape();
 
     let actual_shape = resolve_shape(&shape, context, expression)?;
@@ -596,15 +598,53 @@ pub(crate) fn parse_mapping_argument(
         });
     }
 
+    let expression = Expression {
+        expression: selection,
+        location: 0..string.len(),
+    };
+    check_methods_available(&expression, node, code, schema)?;
+
     Ok(MappingArgument {
-        expression: Expression {
-            expression: selection,
-            location: 0..string.len(),
-        },
+        expression,
         node: node.clone(),
     })
 }
 
+/// Methods are added in new versions of the connect spec, so a schema may only use the ones its
+/// version of the spec defines.
+fn check_methods_available(
+    expression: &Expression,
+    node: &Node<Value>,
+    code: Code,
+    schema: &SchemaInfo,
+) -> Result<(), Message> {
+    let spec = schema.connect_link.spec();
+    let Some((name, range)) = expression
+        .expression
+        .methods_unavailable_in(spec)
+        .into_iter()
+        .next()
+    else {
+        return Ok(());
+    };
+    Err(Message {
+        code,
+        message: format!(
+            "`->{name}` is not available in connect spec version {spec}, update the version in `@link` to use it",
+        ),
+        locations: range
+            .and_then(|range| {
+                subslice_location(
+                    node,
+                    range.start + expression.location.start..range.end + expression.location.start,
+                    schema,
+                )
+            })
+            .into_iter()
+            .collect(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use apollo_compiler::Schema;
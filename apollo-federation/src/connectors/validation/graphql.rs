@@ -20,6 +20,8 @@ pub(crate) struct SchemaInfo<'schema> {
     pub(crate) connect_link: ConnectLink<'schema>,
     /// A lookup map for the Shapes computed from GraphQL types.
     pub(crate) shape_lookup: IndexMap<&'schema str, Shape>,
+    /// Documents that `@connect(http: {responseSchema:})` can refer to, keyed by name.
+    pub(crate) documents: IndexMap<String, serde_json::Value>,
 }
 
 impl<'schema> SchemaInfo<'schema> {
@@ -34,6 +36,7 @@ impl<'schema> SchemaInfo<'schema> {
             lookup: LineColLookup::new(src),
             connect_link,
             shape_lookup: shape::graphql::shapes_for_schema(schema),
+            documents: IndexMap::default(),
         }
     }
 
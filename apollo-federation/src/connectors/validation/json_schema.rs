@@ -0,0 +1,254 @@
+//! Loading JSON Schemas, like the ones `@connect(http: {responseSchema:})` refers to, and
+//! converting them to [`Shape`]s.
+
+use std::collections::HashMap;
+
+use apollo_compiler::collections::IndexMap;
+use itertools::Itertools;
+use serde_json::Value;
+use shape::Shape;
+
+use super::Code;
+use super::Message;
+
+/// How many times a single `$ref` can be expanded within itself before the rest of a recursive
+/// schema is treated as unknown.
+const MAX_REF_EXPANSIONS: usize = 2;
+
+/// Parse the JSON or YAML documents passed to validation.
+pub(super) fn parse_documents(
+    documents: &HashMap<String, String>,
+) -> (IndexMap<String, Value>, Vec<Message>) {
+    let mut parsed = IndexMap::default();
+    let mut messages = Vec::new();
+    for (name, text) in documents.iter().sorted_by_key(|(name, _)| name.as_str()) {
+        let value = serde_json::from_str(text).or_else(|_| serde_yaml::from_str(text));
+        match value {
+            Ok(value) => {
+                parsed.insert(name.clone(), value);
+            }
+            Err(err) => messages.push(Message {
+                code: Code::InvalidResponseSchema,
+                message: format!("The document `{name}` is not valid JSON or YAML: {err}"),
+                locations: Vec::new(),
+            }),
+        }
+    }
+    (parsed, messages)
+}
+
+/// Find the schema of the successful JSON response of the OpenAPI operation at `pointer`,
+/// returning a pointer to it.
+pub(super) fn operation_response_pointer(root: &Value, pointer: &str) -> Option<String> {
+    let responses = root.pointer(pointer)?.get("responses")?.as_object()?;
+    let (status, mut response) = responses
+        .iter()
+        .find(|(status, _)| status.starts_with('2'))?;
+    let mut pointer = format!("{pointer}/responses/{}", escape(status));
+    if let Some(reference) = response.get("$ref").and_then(Value::as_str) {
+        pointer = reference.strip_prefix('#')?.to_string();
+        response = root.pointer(&pointer)?;
+    }
+    let (media_type, content) = response
+        .get("content")?
+        .as_object()?
+        .iter()
+        .find(|(media_type, _)| media_type.contains("json"))?;
+    content.get("schema")?;
+    Some(format!("{pointer}/content/{}/schema", escape(media_type)))
+}
+
+/// Escape a key for use in a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Converts JSON Schemas to [`Shape`]s, resolving local `$ref`s against the document they are
+/// part of.
+///
+/// Objects are treated as closed unless they set `additionalProperties`, so that selecting a
+/// property the schema doesn't declare shows up as an error.
+pub(super) struct JsonSchema<'a> {
+    root: &'a Value,
+    /// The `$ref`s currently being expanded
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> JsonSchema<'a> {
+    pub(super) fn new(root: &'a Value) -> Self {
+        Self {
+            root,
+            expanding: Vec::new(),
+        }
+    }
+
+    /// The shape of values matching `schema`, leaving out the property found by following the
+    /// property names in `omit` (looking through arrays), if any.
+    pub(super) fn shape(&mut self, schema: &'a Value, omit: &[String]) -> Shape {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let expansions = self
+                .expanding
+                .iter()
+                .filter(|expanding| **expanding == reference)
+                .count();
+            let Some(target) = self.resolve(reference) else {
+                return Shape::unknown([]);
+            };
+            if expansions >= MAX_REF_EXPANSIONS {
+                return Shape::unknown([]);
+            }
+            self.expanding.push(reference);
+            let shape = self.shape(target, omit);
+            self.expanding.pop();
+            return shape;
+        }
+
+        let mut parts = Vec::new();
+        let types = types(schema);
+        if !types.is_empty() {
+            let mut shapes = types
+                .into_iter()
+                .map(|ty| match ty {
+                    "string" => Shape::string([]),
+                    "integer" => Shape::int([]),
+                    "number" => Shape::float([]),
+                    "boolean" => Shape::bool([]),
+                    "null" => Shape::null([]),
+                    "array" => Shape::list(
+                        schema
+                            .get("items")
+                            .map_or_else(|| Shape::unknown([]), |items| self.shape(items, omit)),
+                        [],
+                    ),
+                    "object" => self.object(schema, omit),
+                    _ => Shape::unknown([]),
+                })
+                .collect_vec();
+            if schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+                shapes.push(Shape::null([]));
+            }
+            parts.push(Shape::one(shapes, []));
+        }
+        if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+            let shapes = all_of
+                .iter()
+                .map(|part| self.shape(part, omit))
+                .collect_vec();
+            parts.push(Shape::all(shapes, []));
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(alternatives) = schema.get(key).and_then(Value::as_array) {
+                let shapes = alternatives
+                    .iter()
+                    .map(|alternative| self.shape(alternative, omit))
+                    .collect_vec();
+                parts.push(Shape::one(shapes, []));
+            }
+        }
+
+        match parts.len() {
+            0 => Shape::unknown([]),
+            1 => parts.pop().unwrap_or_else(|| Shape::unknown([])),
+            _ => Shape::all(parts, []),
+        }
+    }
+
+    fn object(&mut self, schema: &'a Value, omit: &[String]) -> Shape {
+        let mut fields = Shape::empty_map();
+        for (name, property) in schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            let shape = match omit.split_first() {
+                Some((omitted, [])) if omitted == name => continue,
+                Some((omitted, rest)) if omitted == name => self.shape(property, rest),
+                _ => self.shape(property, &[]),
+            };
+            fields.insert(name.clone(), shape);
+        }
+        let rest = match schema.get("additionalProperties") {
+            Some(Value::Bool(true)) => Shape::unknown([]),
+            Some(additional @ Value::Object(_)) => self.shape(additional, &[]),
+            _ => Shape::none(),
+        };
+        Shape::object(fields, rest, [])
+    }
+
+    /// The properties that `schema` requires, with their schemas. Arrays are looked through to
+    /// the properties their items require.
+    pub(super) fn required_properties(&self, schema: &'a Value) -> Vec<(&'a str, &'a Value)> {
+        let parts = self.parts(schema, 0);
+        if let Some(items) = parts.iter().find_map(|part| part.get("items")) {
+            return self.required_properties(items);
+        }
+        parts
+            .iter()
+            .flat_map(|part| part.get("required").and_then(Value::as_array))
+            .flatten()
+            .filter_map(Value::as_str)
+            .unique()
+            .filter_map(|name| {
+                parts
+                    .iter()
+                    .find_map(|part| part.get("properties")?.get(name))
+                    .map(|property| (name, property))
+            })
+            .collect()
+    }
+
+    /// `schema` and everything it is made of through `$ref` and `allOf`.
+    fn parts(&self, schema: &'a Value, depth: usize) -> Vec<&'a Value> {
+        if depth > 16 {
+            return Vec::new();
+        }
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            return self
+                .resolve(reference)
+                .map(|target| self.parts(target, depth + 1))
+                .unwrap_or_default();
+        }
+        let mut parts = vec![schema];
+        for part in schema
+            .get("allOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            parts.extend(self.parts(part, depth + 1));
+        }
+        parts
+    }
+
+    /// Resolve a `$ref` within the current document. References to other documents aren't
+    /// supported.
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        self.root.pointer(reference.strip_prefix('#')?)
+    }
+}
+
+/// The `type`s a schema allows, inferring them from other keywords if it doesn't say.
+fn types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ if schema.get("properties").is_some() => vec!["object"],
+        _ if schema.get("items").is_some() => vec!["array"],
+        _ => schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|value| match value {
+                Value::String(_) => Some("string"),
+                Value::Number(number) if number.is_i64() || number.is_u64() => Some("integer"),
+                Value::Number(_) => Some("number"),
+                Value::Bool(_) => Some("boolean"),
+                Value::Null => Some("null"),
+                _ => None,
+            })
+            .unique()
+            .collect(),
+    }
+}
@@ -6,10 +6,12 @@ mod errors;
 mod expression;
 mod graphql;
 mod http;
+mod json_schema;
 mod link;
 mod schema;
 mod source;
 
+use std::collections::HashMap;
 use std::ops::Range;
 
 use apollo_compiler::Name;
@@ -23,8 +25,10 @@ use strum_macros::IntoStaticStr;
 
 use crate::connectors::ConnectSpec;
 use crate::connectors::spec::source::SOURCE_DIRECTIVE_NAME_IN_SPEC;
+use crate::connectors::validation::connect::SeenFields;
 use crate::connectors::validation::connect::fields_seen_by_all_connects;
 use crate::connectors::validation::graphql::SchemaInfo;
+use crate::connectors::validation::json_schema::parse_documents;
 use crate::connectors::validation::link::ConnectLink;
 use crate::connectors::validation::source::SourceDirective;
 
@@ -48,7 +52,18 @@ pub struct ValidationResult {
 ///
 /// This function attempts to collect as many validation errors as possible, so it does not bail
 /// out as soon as it encounters one.
-pub fn validate(mut source_text: String, file_name: &str) -> ValidationResult {
+pub fn validate(source_text: String, file_name: &str) -> ValidationResult {
+    validate_with_documents(source_text, file_name, &HashMap::new())
+}
+
+/// Like [`validate`], but with JSON or YAML documents (such as OpenAPI descriptions) that
+/// `@connect(http: {responseSchema:})` references can point into, keyed by the name used in
+/// those references.
+pub fn validate_with_documents(
+    mut source_text: String,
+    file_name: &str,
+    documents: &HashMap<String, String>,
+) -> ValidationResult {
     let schema = SchemaBuilder::new()
         .adopt_orphan_extensions()
         .parse(&source_text, file_name)
@@ -73,9 +88,12 @@ pub fn validate(mut source_text: String, file_name: &str) -> ValidationResult {
         }
         Some(Ok(link)) => link,
     };
-    let schema_info = SchemaInfo::new(&schema, &source_text, link);
+    let mut schema_info = SchemaInfo::new(&schema, &source_text, link);
+    let (documents, mut messages) = parse_documents(documents);
+    schema_info.documents = documents;
 
-    let (source_directives, mut messages) = SourceDirective::find(&schema_info);
+    let (source_directives, source_messages) = SourceDirective::find(&schema_info);
+    messages.extend(source_messages);
     let all_source_names = source_directives
         .iter()
         .map(|directive| directive.name.clone())
@@ -86,13 +104,10 @@ pub fn validate(mut source_text: String, file_name: &str) -> ValidationResult {
     }
 
     match fields_seen_by_all_connects(&schema_info, &all_source_names) {
-        Ok(fields_seen_by_connectors) => {
+        Ok(SeenFields { fields, warnings }) => {
+            messages.extend(warnings);
             // Don't run schema-wide checks if any connectors failed to validate
-            messages.extend(schema::validate(
-                &schema_info,
-                file_name,
-                fields_seen_by_connectors,
-            ))
+            messages.extend(schema::validate(&schema_info, file_name, fields))
         }
         Err(errs) => {
             messages.extend(errs);
@@ -277,12 +292,29 @@ pub enum Code {
     ConnectBatchAndThis,
     /// Invalid URL property
     InvalidUrlProperty,
+    /// The `http.pagination` provided in `@connect` was not valid.
+    InvalidPagination,
+    /// The `http.responseFormat` provided in `@connect` is not a format the router can decode.
+    InvalidResponseFormat,
+    /// The `http.responseSchema` provided in `@connect`, or a document it refers to, was not valid.
+    InvalidResponseSchema,
+    /// The `http.responseSchema` in `@connect` refers to a document that wasn't provided, so the
+    /// selection can't be checked against it.
+    UnresolvedResponseSchema,
+    /// The `selection` in `@connect` doesn't match the `http.responseSchema`, either because it
+    /// selects something the response doesn't have or because of a type mismatch.
+    ResponseSchemaMismatch,
+    /// A property that the `http.responseSchema` in `@connect` requires, and that matches a field
+    /// of the GraphQL type, is never used by the `selection`.
+    UnmappedResponseField,
 }
 
 impl Code {
     pub fn severity(&self) -> Severity {
         match self {
-            Self::NoSourceImport => Severity::Warning,
+            Self::NoSourceImport | Self::UnresolvedResponseSchema | Self::UnmappedResponseField => {
+                Severity::Warning
+            }
             _ => Severity::Error,
         }
     }
@@ -330,4 +362,64 @@ mod test_validate_source {
             });
         });
     }
+    #[test]
+    fn validates_selections_against_openapi_documents() {
+        let schema = r#"
+            extend schema
+            @link(url: "https://specs.apollo.dev/connect/v0.3", import: ["@connect", "@source"])
+            @source(name: "api", http: { baseURL: "http://127.0.0.1" })
+
+            type Query {
+                users: [User]
+                @connect(
+                    source: "api"
+                    http: { GET: "/users", responseSchema: "openapi.yaml#/paths/~1users/get" }
+                    selection: "id name: fullName"
+                )
+            }
+
+            type User {
+                id: ID!
+                name: String
+            }
+        "#;
+        let document = r##"
+            openapi: 3.0.0
+            paths:
+              /users:
+                get:
+                  responses:
+                    "200":
+                      content:
+                        application/json:
+                          schema:
+                            type: array
+                            items:
+                              $ref: "#/components/schemas/User"
+            components:
+              schemas:
+                User:
+                  type: object
+                  properties:
+                    id:
+                      type: integer
+                    name:
+                      type: string
+        "##;
+        let documents = HashMap::from([("openapi.yaml".to_string(), document.to_string())]);
+
+        let result = validate_with_documents(schema.to_string(), "schema.graphql", &documents);
+
+        assert_snapshot!(format!("{:#?}", result.errors), @r###"
+        [
+            Message {
+                code: ResponseSchemaMismatch,
+                message: "In `@connect(selection:)` on `Query.users`: field `fullName` not found in the response schema.",
+                locations: [
+                    11:42..11:50,
+                ],
+            },
+        ]
+        "###);
+    }
 }
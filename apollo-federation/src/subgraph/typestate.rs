@@ -1190,6 +1190,8 @@ mod tests {
                 name!("__TypeKind"),
                 name!("connect__ConnectBatch"),
                 name!("connect__ConnectHTTP"),
+                name!("connect__ConnectPagination"),
+                name!("connect__ConnectPaginationMode"),
                 name!("connect__ConnectorErrors"),
                 name!("connect__HTTPHeaderMapping"),
                 name!("connect__JSONSelection"),
@@ -1,10 +1,13 @@
 //! Environment variable expansion in the configuration file
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
 use std::env::VarError;
 use std::fs;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
 use proteus::Parser;
@@ -12,12 +15,14 @@ use proteus::TransformBuilder;
 use serde_json::Value;
 
 use super::ConfigurationError;
+use super::secrets::Secrets;
 
 #[derive(buildstructor::Builder, Clone)]
 pub(crate) struct Expansion {
     prefix: Option<String>,
     supported_modes: Vec<String>,
     override_configs: Vec<Override>,
+    secrets: Option<Arc<Secrets>>,
     #[cfg(test)]
     mocked_env_vars: HashMap<String, String>,
 }
@@ -36,6 +41,16 @@ pub(crate) struct Override {
     mocked_env_vars: HashMap<String, String>,
 }
 
+/// What the expansion of a configuration read from its secret provider.
+#[derive(Default)]
+struct SecretReads {
+    /// Every secret the configuration uses, found or not
+    keys: RefCell<HashSet<String>>,
+    /// A failure of the provider. Shell expansion uses the default value of a variable on any
+    /// error, so this is kept here to fail the expansion anyway.
+    failure: RefCell<Option<ConfigurationError>>,
+}
+
 #[derive(Clone)]
 pub(crate) enum ValueType {
     String,
@@ -81,15 +96,18 @@ impl Expansion {
         Self::default_builder().build()
     }
 
+    /// Without `secrets`, secrets are read from the provider configured with environment
+    /// variables, if any.
     #[builder]
     pub(crate) fn default_new(
         #[cfg_attr(not(test), allow(unused))] mocked_env_vars: HashMap<String, String>,
+        secrets: Option<Arc<Secrets>>,
     ) -> Result<Self, ConfigurationError> {
         let prefix = Expansion::prefix_from_env()?;
 
         let supported_expansion_modes = match env::var("APOLLO_ROUTER_CONFIG_SUPPORTED_MODES") {
             Ok(v) => v,
-            Err(VarError::NotPresent) => "env,file".to_string(),
+            Err(VarError::NotPresent) => "env,file,secret".to_string(),
             Err(VarError::NotUnicode(_)) => Err(ConfigurationError::InvalidExpansionModeConfig)?,
         };
         let supported_modes = supported_expansion_modes
@@ -107,6 +125,10 @@ impl Expansion {
             Vec::new()
         };
 
+        let secrets = match secrets {
+            Some(secrets) => Some(secrets),
+            None => Secrets::from_env()?,
+        };
         let builder = Expansion::builder();
         #[cfg(test)]
         let builder = builder.mocked_env_vars(mocked_env_vars);
@@ -122,6 +144,7 @@ impl Expansion {
         Ok(builder
             .and_prefix(prefix)
             .supported_modes(supported_modes)
+            .and_secrets(secrets)
             .override_config(
                 Override::builder()
                     .config_path("telemetry.apollo.endpoint")
@@ -200,7 +223,10 @@ fn dev_mode_defaults() -> Vec<Override> {
 }
 
 impl Expansion {
-    fn context_fn(&self) -> impl Fn(&str) -> Result<Option<String>, ConfigurationError> + '_ {
+    fn context_fn<'a>(
+        &'a self,
+        secret_reads: &'a SecretReads,
+    ) -> impl Fn(&str) -> Result<Option<String>, ConfigurationError> + 'a {
         move |key: &str| {
             if !self
                 .supported_modes
@@ -228,10 +254,43 @@ impl Expansion {
                     }
                 });
             }
+            if let Some(key) = key.strip_prefix("secret.") {
+                return self.expand_secret(key, secret_reads);
+            }
             Err(ConfigurationError::InvalidExpansionModeConfig)
         }
     }
 
+    fn expand_secret(
+        &self,
+        key: &str,
+        secret_reads: &SecretReads,
+    ) -> Result<Option<String>, ConfigurationError> {
+        let error = |cause: &str| ConfigurationError::CannotExpandVariable {
+            key: key.to_string(),
+            cause: cause.to_string(),
+        };
+        let value = match &self.secrets {
+            Some(secrets) => {
+                secret_reads.keys.borrow_mut().insert(key.to_string());
+                secrets.get(key).map_err(|cause| error(&cause))
+            }
+            None => Err(error(
+                "no secret provider is configured, set APOLLO_ROUTER_SECRETS_AGENT_SOCKET",
+            )),
+        };
+        match value {
+            Ok(Some(value)) => Ok(Some(value)),
+            // A missing secret is an error, so that a default value applies
+            Ok(None) => Err(error("secret not found")),
+            Err(failure) => {
+                let message = failure.to_string();
+                secret_reads.failure.borrow_mut().get_or_insert(failure);
+                Err(error(&message))
+            }
+        }
+    }
+
     pub(crate) fn expand_env(&self, key: &str) -> Result<Option<String>, ConfigurationError> {
         match self.prefix.as_ref() {
             None => self.get_env(key),
@@ -258,7 +317,11 @@ impl Expansion {
     ) -> Result<serde_json::Value, ConfigurationError> {
         let mut configuration = configuration.clone();
         self.defaults(&mut configuration)?;
-        self.visit(&mut configuration)?;
+        let secret_reads = SecretReads::default();
+        self.visit(&mut configuration, &secret_reads)?;
+        if let Some(secrets) = &self.secrets {
+            secrets.track(secret_reads.keys.into_inner());
+        }
         Ok(configuration)
     }
 
@@ -285,24 +348,31 @@ impl Expansion {
         Ok(())
     }
 
-    fn visit(&self, value: &mut Value) -> Result<(), ConfigurationError> {
+    fn visit(
+        &self,
+        value: &mut Value,
+        secret_reads: &SecretReads,
+    ) -> Result<(), ConfigurationError> {
         let mut expanded: Option<String> = None;
         match value {
             Value::String(value) => {
-                let new_value =
-                    shellexpand::env_with_context(value, self.context_fn()).map_err(|e| e.cause)?;
+                let new_value = shellexpand::env_with_context(value, self.context_fn(secret_reads))
+                    .map_err(|e| e.cause)?;
+                if let Some(failure) = secret_reads.failure.take() {
+                    return Err(failure);
+                }
                 if &new_value != value {
                     expanded = Some(new_value.to_string());
                 }
             }
             Value::Array(a) => {
                 for v in a {
-                    self.visit(v)?
+                    self.visit(v, secret_reads)?
                 }
             }
             Value::Object(o) => {
                 for v in o.values_mut() {
-                    self.visit(v)?
+                    self.visit(v, secret_reads)?
                 }
             }
             _ => {}
@@ -326,6 +396,9 @@ pub(crate) fn coerce(expanded: &str) -> Value {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
     use insta::assert_yaml_snapshot;
     use serde_json::Value;
     use serde_json::json;
@@ -334,6 +407,8 @@ mod test {
     use crate::configuration::expansion::Override;
     use crate::configuration::expansion::ValueType;
     use crate::configuration::expansion::dev_mode_defaults;
+    use crate::configuration::secrets::Secrets;
+    use crate::configuration::secrets::tests::MockProvider;
 
     #[test]
     fn test_override_precedence() {
@@ -524,6 +599,52 @@ mod test {
         })
     }
 
+    #[test]
+    fn test_secrets() {
+        let provider = MockProvider::default();
+        provider
+            .secrets
+            .lock()
+            .insert("kv/data/redis#password".into(), "hunter2".into());
+        let unavailable = provider.unavailable.clone();
+        let expansion = Expansion::builder()
+            .supported_mode("secret")
+            .secrets(Arc::new(Secrets::new(
+                Box::new(provider),
+                Duration::from_secs(60),
+                Duration::from_secs(60),
+            )))
+            .build();
+
+        let value = json!({
+            "password": "${secret.kv/data/redis#password}",
+            "defaulted": "${secret.kv/data/missing#password:-default}"
+        });
+        assert_eq!(
+            expansion.expand(&value).expect("expansion must succeed"),
+            json!({ "password": "hunter2", "defaulted": "default" })
+        );
+        assert!(
+            expansion
+                .expand(&json!({ "password": "${secret.kv/data/missing#password}" }))
+                .is_err()
+        );
+
+        let without_provider = Expansion::builder().supported_mode("secret").build();
+        assert!(without_provider.expand(&value).is_err());
+
+        // Only a missing secret falls back to the default, provider failures fail the expansion
+        unavailable.store(true, std::sync::atomic::Ordering::SeqCst);
+        let error = expansion
+            .expand(&json!({ "defaulted": "${secret.kv/data/other#password:-default}" }))
+            .expect_err("provider failures must not use the default");
+        assert!(
+            error
+                .to_string()
+                .contains("the secret provider is unavailable")
+        );
+    }
+
     #[test]
     fn test_dev_mode() {
         let expansion = Expansion::builder()
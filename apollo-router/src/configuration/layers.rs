@@ -0,0 +1,630 @@
+//! Configuration made of several YAML files.
+//!
+//! Files are deep-merged in order, each overriding the files before it. A file may list other
+//! files under a top-level `include` key: they are merged first, in order, and the including
+//! file is merged over them. Include paths are relative to the including file.
+//!
+//! Mappings are merged key by key, and any other value replaces the value it overrides. Lists
+//! are replaced too, unless their key ends with a merge rule:
+//! * `key+` appends the items to the list,
+//! * `key-` removes the items equal to one of the given items from the list.
+//!
+//! Merge rules are only read when several files are merged, and never from keys which the
+//! configuration schema defines with the trailing `+` or `-`.
+
+use std::fmt::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use serde_json::Value as JsonValue;
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+
+use super::ConfigurationError;
+use super::schema::generate_config_schema;
+
+const INCLUDE: &str = "include";
+
+/// How deep `$ref`s and combinators of the configuration schema are followed
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+static CONFIGURATION_SCHEMA: LazyLock<JsonValue> =
+    LazyLock::new(|| serde_json::to_value(generate_config_schema()).unwrap_or_default());
+
+/// Configuration files deep-merged in order, remembering which file set each value.
+#[derive(Debug)]
+pub(crate) struct LayeredConfiguration {
+    root: Node,
+    /// Every file involved, in the order they were read
+    files: Vec<PathBuf>,
+    /// The content of the only file, when it was used unchanged
+    raw: Option<String>,
+}
+
+#[derive(Debug)]
+enum Node {
+    /// A value other than a mapping or a list, with the index of the file that set it
+    Scalar(Value, usize),
+    Mapping(Vec<Entry>),
+    Sequence(Vec<Node>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: Value,
+    rule: Rule,
+    node: Node,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rule {
+    Replace,
+    Append,
+    Remove,
+}
+
+impl LayeredConfiguration {
+    /// Reads and merges the configuration files, and the files they include.
+    pub(crate) fn load(paths: &[PathBuf]) -> Result<Self, ConfigurationError> {
+        let mut loader = Loader::default();
+        for path in paths {
+            loader.load(path)?;
+        }
+        let raw = match (loader.files.as_slice(), loader.rewritten) {
+            ([file], false) => {
+                Some(std::fs::read_to_string(file).map_err(|e| file_error(file, e))?)
+            }
+            _ => None,
+        };
+
+        // A single file is used as written, so a key only ends with a merge rule when layering
+        let layered = loader.layers.len() > 1;
+        let mut root = None;
+        for (file, value) in loader.layers {
+            let schema = layered.then(|| SchemaLocation::root(&CONFIGURATION_SCHEMA));
+            root = merge(
+                root,
+                Node::from_value(value, file, schema.as_ref()),
+                Rule::Replace,
+            );
+        }
+        Ok(Self {
+            root: root.unwrap_or(Node::Mapping(Vec::new())),
+            files: loader.files,
+            raw,
+        })
+    }
+
+    /// Every file the configuration was read from, including the included files.
+    pub(crate) fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The merged configuration, as YAML.
+    pub(crate) fn to_yaml(&self) -> Result<String, ConfigurationError> {
+        if let Some(raw) = &self.raw {
+            return Ok(raw.clone());
+        }
+        serde_yaml::to_string(&self.root.to_value()).map_err(|e| {
+            ConfigurationError::InvalidConfiguration {
+                message: "failed to write the merged configuration",
+                error: e.to_string(),
+            }
+        })
+    }
+
+    /// The merged configuration, as YAML with a comment naming the file each value comes from.
+    pub(crate) fn to_annotated_yaml(&self) -> String {
+        let mut output = String::new();
+        match &self.root {
+            Node::Mapping(entries) if !entries.is_empty() => {
+                self.write_entries(&mut output, entries, 0)
+            }
+            _ => output.push_str("{}\n"),
+        }
+        output
+    }
+
+    fn write_entries(&self, output: &mut String, entries: &[Entry], indent: usize) {
+        for entry in entries {
+            let _ = write!(output, "{:indent$}{}:", "", scalar_to_string(&entry.key));
+            self.write_value(output, &entry.node, indent);
+        }
+    }
+
+    fn write_items(&self, output: &mut String, items: &[Node], indent: usize) {
+        for item in items {
+            let _ = write!(output, "{:indent$}-", "");
+            self.write_value(output, item, indent);
+        }
+    }
+
+    /// Writes a value after its key or list marker, nested values being indented below it.
+    fn write_value(&self, output: &mut String, node: &Node, indent: usize) {
+        match node {
+            Node::Scalar(value, file) => {
+                let _ = writeln!(
+                    output,
+                    " {}  # {}",
+                    scalar_to_string(value),
+                    self.files[*file].display()
+                );
+            }
+            Node::Mapping(entries) if entries.is_empty() => output.push_str(" {}\n"),
+            Node::Sequence(items) if items.is_empty() => output.push_str(" []\n"),
+            Node::Mapping(entries) => {
+                output.push('\n');
+                self.write_entries(output, entries, indent + 2);
+            }
+            Node::Sequence(items) => {
+                output.push('\n');
+                self.write_items(output, items, indent + 2);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Loader {
+    /// The content of every file, without `include`, in merge order
+    layers: Vec<(usize, Value)>,
+    files: Vec<PathBuf>,
+    /// The files being read, to detect include cycles
+    stack: Vec<PathBuf>,
+    /// Whether a file included others
+    rewritten: bool,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path) -> Result<(), ConfigurationError> {
+        let canonical = path.canonicalize().map_err(|e| file_error(path, e))?;
+        if self.stack.contains(&canonical) {
+            return Err(file_error(path, "the file includes itself"));
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| file_error(path, e))?;
+        let mapping = match serde_yaml::from_str(&content) {
+            Ok(Value::Null) => Mapping::new(),
+            Ok(Value::Mapping(mapping)) => mapping,
+            Ok(_) => return Err(file_error(path, "the configuration must be a mapping")),
+            Err(_) if content.trim().is_empty() => Mapping::new(),
+            Err(e) => return Err(file_error(path, e)),
+        };
+        let file = self.files.len();
+        self.files.push(path.to_path_buf());
+
+        // Removed without `Mapping::remove`, which would move the last key in its place
+        let mut include = None;
+        let mapping: Mapping = mapping
+            .into_iter()
+            .filter_map(|(key, value)| {
+                if key.as_str() == Some(INCLUDE) {
+                    include = Some(value);
+                    None
+                } else {
+                    Some((key, value))
+                }
+            })
+            .collect();
+        if let Some(include) = include {
+            self.rewritten = true;
+            let includes = match include {
+                Value::String(include) => vec![include],
+                Value::Sequence(items) => items
+                    .into_iter()
+                    .map(|item| match item {
+                        Value::String(include) => Ok(include),
+                        _ => Err(file_error(path, "`include` must only contain file paths")),
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(file_error(path, "`include` must be a file path or a list")),
+            };
+            let directory = path.parent().unwrap_or(Path::new(""));
+            self.stack.push(canonical);
+            for include in includes {
+                self.load(&directory.join(include))?;
+            }
+            self.stack.pop();
+        }
+
+        self.layers.push((file, Value::Mapping(mapping)));
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Reads merge rules from the keys of lists which `schema` doesn't define as written, or from
+    /// no key without a schema.
+    fn from_value(value: Value, file: usize, schema: Option<&SchemaLocation>) -> Self {
+        match value {
+            Value::Mapping(mapping) => Node::Mapping(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let (key, rule) = match schema {
+                            Some(schema) if value.is_sequence() => split_rule(key, schema),
+                            _ => (key, Rule::Replace),
+                        };
+                        let property = schema.map(|schema| schema.property(&key));
+                        let node = Node::from_value(value, file, property.as_ref());
+                        Entry { key, rule, node }
+                    })
+                    .collect(),
+            ),
+            Value::Sequence(items) => {
+                let items_schema = schema.map(SchemaLocation::items);
+                Node::Sequence(
+                    items
+                        .into_iter()
+                        .map(|item| Node::from_value(item, file, items_schema.as_ref()))
+                        .collect(),
+                )
+            }
+            value => Node::Scalar(value, file),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Node::Scalar(value, _) => value.clone(),
+            Node::Mapping(entries) => Value::Mapping(
+                entries
+                    .iter()
+                    .map(|entry| (entry.key.clone(), entry.node.to_value()))
+                    .collect(),
+            ),
+            Node::Sequence(items) => Value::Sequence(items.iter().map(Node::to_value).collect()),
+        }
+    }
+}
+
+/// Removes the merge rule from the key of a list, unless the schema defines the key as written.
+fn split_rule(key: Value, schema: &SchemaLocation) -> (Value, Rule) {
+    match key {
+        Value::String(mut name)
+            if name.len() > 1
+                && (name.ends_with('+') || name.ends_with('-'))
+                && !schema.defines(&name) =>
+        {
+            let rule = match name.pop() {
+                Some('+') => Rule::Append,
+                _ => Rule::Remove,
+            };
+            (Value::String(name), rule)
+        }
+        key => (key, Rule::Replace),
+    }
+}
+
+/// The schemas a value of the configuration may match, found by following `$ref`s and
+/// combinators from the root of the configuration schema.
+struct SchemaLocation<'a> {
+    root: &'a JsonValue,
+    schemas: Vec<&'a JsonValue>,
+}
+
+impl<'a> SchemaLocation<'a> {
+    fn root(root: &'a JsonValue) -> Self {
+        Self::new(root, [root])
+    }
+
+    fn new(root: &'a JsonValue, schemas: impl IntoIterator<Item = &'a JsonValue>) -> Self {
+        let mut resolved = Vec::new();
+        for schema in schemas {
+            resolve(root, schema, 0, &mut resolved);
+        }
+        Self {
+            root,
+            schemas: resolved,
+        }
+    }
+
+    /// Whether a schema names `key` among its properties
+    fn defines(&self, key: &str) -> bool {
+        self.schemas
+            .iter()
+            .any(|schema| schema.pointer(&property_pointer(key)).is_some())
+    }
+
+    /// The schemas of the value of `key`
+    fn property(&self, key: &Value) -> Self {
+        let pointer = key.as_str().map(property_pointer);
+        Self::new(
+            self.root,
+            self.schemas.iter().copied().filter_map(|schema| {
+                pointer
+                    .as_deref()
+                    .and_then(|pointer| schema.pointer(pointer))
+                    .or_else(|| schema.get("additionalProperties").filter(|s| s.is_object()))
+            }),
+        )
+    }
+
+    /// The schemas of the items of a list
+    fn items(&self) -> Self {
+        Self::new(
+            self.root,
+            self.schemas
+                .iter()
+                .copied()
+                .filter_map(|schema| schema.get("items").filter(|s| s.is_object())),
+        )
+    }
+}
+
+fn property_pointer(key: &str) -> String {
+    format!("/properties/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn resolve<'a>(
+    root: &'a JsonValue,
+    schema: &'a JsonValue,
+    depth: usize,
+    resolved: &mut Vec<&'a JsonValue>,
+) {
+    if depth > MAX_SCHEMA_DEPTH {
+        return;
+    }
+    if let Some(target) = schema
+        .get("$ref")
+        .and_then(JsonValue::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+    {
+        resolve(root, target, depth + 1, resolved);
+    }
+    for combinator in ["allOf", "anyOf", "oneOf"] {
+        for schema in schema
+            .get(combinator)
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+        {
+            resolve(root, schema, depth + 1, resolved);
+        }
+    }
+    resolved.push(schema);
+}
+
+/// Merges `overlay` over `base`. Merge rules nested in `overlay` are applied even without a
+/// base, so that the result never contains any.
+fn merge(base: Option<Node>, overlay: Node, rule: Rule) -> Option<Node> {
+    match (base, overlay, rule) {
+        (Some(Node::Sequence(mut items)), Node::Sequence(added), Rule::Append) => {
+            items.extend(
+                added
+                    .into_iter()
+                    .filter_map(|item| merge(None, item, Rule::Replace)),
+            );
+            Some(Node::Sequence(items))
+        }
+        (Some(Node::Sequence(mut items)), Node::Sequence(removed), Rule::Remove) => {
+            let removed = removed.iter().map(Node::to_value).collect::<Vec<_>>();
+            items.retain(|item| !removed.contains(&item.to_value()));
+            Some(Node::Sequence(items))
+        }
+        (base, Node::Sequence(_), Rule::Remove) => base,
+        (Some(Node::Mapping(mut entries)), Node::Mapping(overrides), _) => {
+            for entry in overrides {
+                let position = entries.iter().position(|base| base.key == entry.key);
+                let base = position.map(|position| entries.remove(position).node);
+                if let Some(node) = merge(base, entry.node, entry.rule) {
+                    let merged = Entry {
+                        key: entry.key,
+                        rule: Rule::Replace,
+                        node,
+                    };
+                    match position {
+                        Some(position) => entries.insert(position, merged),
+                        None => entries.push(merged),
+                    }
+                }
+            }
+            Some(Node::Mapping(entries))
+        }
+        (_, Node::Mapping(entries), _) => merge(
+            Some(Node::Mapping(Vec::new())),
+            Node::Mapping(entries),
+            Rule::Replace,
+        ),
+        (_, Node::Sequence(items), _) => Some(Node::Sequence(
+            items
+                .into_iter()
+                .filter_map(|item| merge(None, item, Rule::Replace))
+                .collect(),
+        )),
+        (_, scalar, _) => Some(scalar),
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    let yaml = serde_yaml::to_string(value).unwrap_or_default();
+    yaml.trim_start_matches("---")
+        .trim_start_matches('\n')
+        .trim_end()
+        .to_string()
+}
+
+fn file_error(path: &Path, error: impl ToString) -> ConfigurationError {
+    ConfigurationError::ConfigurationFile {
+        path: path.display().to_string(),
+        error: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn merged(layers: &LayeredConfiguration) -> serde_json::Value {
+        serde_yaml::from_str(&layers.to_yaml().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn merges_files_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(
+            dir.path(),
+            "base.yaml",
+            r#"
+supergraph:
+  listen: 0.0.0.0:4000
+  introspection: false
+cors:
+  origins: [https://a.example.com, https://b.example.com]
+  allow_headers: [x-base]
+"#,
+        );
+        let production = write(
+            dir.path(),
+            "production.yaml",
+            r#"
+supergraph:
+  listen: 0.0.0.0:80
+cors:
+  origins-: [https://a.example.com]
+  allow_headers+: [x-production]
+"#,
+        );
+
+        let layers = LayeredConfiguration::load(&[base, production]).unwrap();
+        assert_eq!(
+            merged(&layers),
+            serde_json::json!({
+                "supergraph": { "listen": "0.0.0.0:80", "introspection": false },
+                "cors": {
+                    "origins": ["https://b.example.com"],
+                    "allow_headers": ["x-base", "x-production"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn merges_included_files_first() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "shared/telemetry.yaml",
+            "include: ../common.yaml\ntelemetry:\n  instrumentation:\n    spans:\n      mode: spec_compliant\n",
+        );
+        write(
+            dir.path(),
+            "common.yaml",
+            "supergraph:\n  listen: 0.0.0.0:4000\n  path: /graphql\n",
+        );
+        let staging = write(
+            dir.path(),
+            "staging.yaml",
+            "include:\n  - shared/telemetry.yaml\nsupergraph:\n  path: /\n",
+        );
+
+        let layers = LayeredConfiguration::load(std::slice::from_ref(&staging)).unwrap();
+        assert_eq!(
+            merged(&layers),
+            serde_json::json!({
+                "supergraph": { "listen": "0.0.0.0:4000", "path": "/" },
+                "telemetry": { "instrumentation": { "spans": { "mode": "spec_compliant" } } }
+            })
+        );
+        assert_eq!(
+            layers.files(),
+            [
+                staging,
+                dir.path().join("shared/telemetry.yaml"),
+                dir.path().join("shared/../common.yaml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn annotates_values_with_their_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(
+            dir.path(),
+            "base.yaml",
+            "supergraph:\n  listen: 0.0.0.0:4000\ncors:\n  origins: [https://a.example.com]\n",
+        );
+        let local = write(
+            dir.path(),
+            "local.yaml",
+            "supergraph:\n  listen: 127.0.0.1:4000\ncors:\n  origins+: [http://localhost]\n",
+        );
+
+        let layers = LayeredConfiguration::load(&[base.clone(), local.clone()]).unwrap();
+        let (base, local) = (base.display(), local.display());
+        assert_eq!(
+            layers.to_annotated_yaml(),
+            format!(
+                "supergraph:\n  listen: \"127.0.0.1:4000\"  # {local}\ncors:\n  origins:\n    - \"https://a.example.com\"  # {base}\n    - \"http://localhost\"  # {local}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_a_single_file_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "# comment\nsupergraph:\n  listen: 0.0.0.0:4000\n";
+        let path = write(dir.path(), "router.yaml", content);
+
+        let layers = LayeredConfiguration::load(&[path]).unwrap();
+        assert_eq!(layers.to_yaml().unwrap(), content);
+    }
+
+    #[test]
+    fn reads_merge_rules_only_when_layering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "router.yaml",
+            "cors:\n  origins+: [https://a.example.com]\n",
+        );
+
+        let layers = LayeredConfiguration::load(&[path]).unwrap();
+        assert_eq!(
+            merged(&layers),
+            serde_json::json!({ "cors": { "origins+": ["https://a.example.com"] } })
+        );
+    }
+
+    #[test]
+    fn reads_merge_rules_only_from_keys_missing_from_the_schema() {
+        let schema = serde_json::json!({
+            "properties": { "plugin": { "$ref": "#/definitions/Plugin" } },
+            "definitions": {
+                "Plugin": {
+                    "anyOf": [{ "properties": { "names+": { "type": "array" } } }]
+                }
+            }
+        });
+        let plugin = SchemaLocation::root(&schema).property(&Value::from("plugin"));
+
+        assert_eq!(
+            split_rule(Value::from("names+"), &plugin),
+            (Value::from("names+"), Rule::Replace)
+        );
+        assert_eq!(
+            split_rule(Value::from("others+"), &plugin),
+            (Value::from("others"), Rule::Append)
+        );
+    }
+
+    #[test]
+    fn rejects_include_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.yaml", "include: b.yaml\n");
+        write(dir.path(), "b.yaml", "include: a.yaml\n");
+
+        let error = LayeredConfiguration::load(&[dir.path().join("a.yaml")]).unwrap_err();
+        assert!(error.to_string().contains("the file includes itself"));
+    }
+}
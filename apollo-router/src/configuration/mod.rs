@@ -19,7 +19,12 @@ use itertools::Either;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 pub(crate) use persisted_queries::PersistedQueries;
+pub(crate) use persisted_queries::PersistedQueriesClientName;
+pub(crate) use persisted_queries::PersistedQueriesLearning;
+pub(crate) use persisted_queries::PersistedQueriesLocalManifest;
 pub(crate) use persisted_queries::PersistedQueriesPrewarmQueryPlanCache;
+pub(crate) use persisted_queries::PersistedQueriesRemoteManifest;
+pub(crate) use persisted_queries::PersistedQueriesRemoteManifests;
 #[cfg(test)]
 pub(crate) use persisted_queries::PersistedQueriesSafelist;
 use regex::Regex;
@@ -45,11 +50,13 @@ pub(crate) use self::experimental::Discussed;
 pub(crate) use self::schema::generate_config_schema;
 pub(crate) use self::schema::generate_upgrade;
 pub(crate) use self::schema::validate_yaml_configuration;
+use self::secrets::Secrets;
 use self::server::Server;
 use self::subgraph::SubgraphConfiguration;
 use crate::ApolloRouterError;
 use crate::cache::DEFAULT_CACHE_CAPACITY;
 use crate::configuration::cooperative_cancellation::CooperativeCancellation;
+use crate::configuration::safe_rollout::SafeRollout;
 use crate::graphql;
 use crate::notification::Notify;
 use crate::plugin::plugins;
@@ -67,10 +74,13 @@ pub(crate) mod cooperative_cancellation;
 pub(crate) mod cors;
 pub(crate) mod expansion;
 mod experimental;
+pub(crate) mod layers;
 pub(crate) mod metrics;
 pub(crate) mod mode;
 mod persisted_queries;
+pub(crate) mod safe_rollout;
 pub(crate) mod schema;
+pub(crate) mod secrets;
 pub(crate) mod server;
 pub(crate) mod shared;
 pub(crate) mod subgraph;
@@ -118,6 +128,9 @@ pub enum ConfigurationError {
 
     /// could not load certificate authorities: {error}
     CertificateAuthorities { error: String },
+
+    /// could not load configuration file {path}: {error}
+    ConfigurationFile { path: String, error: String },
 }
 
 impl From<proteus::Error> for ConfigurationError {
@@ -563,6 +576,11 @@ impl Configuration {
                     message: "persisted queries must be enabled to enable logging unknown operations",
                     error: "either set persisted_queries.log_unknown: false or persisted_queries.enabled: true in your router yaml configuration".into()
                 });
+            } else if self.persisted_queries.experimental_learning.is_some() {
+                return Err(ConfigurationError::InvalidConfiguration {
+                    message: "persisted queries must be enabled to learn operations",
+                    error: "either remove persisted_queries.experimental_learning or set persisted_queries.enabled: true, with a manifest source, in your router yaml configuration".into()
+                });
             }
         }
 
@@ -570,6 +588,18 @@ impl Configuration {
     }
 }
 
+impl Configuration {
+    /// Parses configuration from a string in YAML syntax, expanding its secrets with `secrets`,
+    /// which then tracks the secrets this configuration uses.
+    pub(crate) fn from_str_with_secrets(
+        s: &str,
+        secrets: Arc<Secrets>,
+    ) -> Result<Self, ConfigurationError> {
+        let expansion = Expansion::default_builder().secrets(secrets).build()?;
+        schema::validate_yaml_configuration(s, expansion, schema::Mode::Upgrade)?.validate()
+    }
+}
+
 /// Parse configuration from a string in YAML syntax
 impl FromStr for Configuration {
     type Err = ConfigurationError;
@@ -908,6 +938,9 @@ pub(crate) struct QueryPlanning {
     ///
     /// See [`CooperativeCancellation`] for more details.
     pub(crate) experimental_cooperative_cancellation: CooperativeCancellation,
+
+    /// Validates a new supergraph schema against known operations before activating it
+    pub(crate) experimental_safe_rollout: SafeRollout,
 }
 
 #[buildstructor::buildstructor]
@@ -921,6 +954,7 @@ impl QueryPlanning {
         experimental_paths_limit: Option<u32>,
         experimental_reuse_query_plans: Option<bool>,
         experimental_cooperative_cancellation: Option<CooperativeCancellation>,
+        experimental_safe_rollout: Option<SafeRollout>,
     ) -> Self {
         Self {
             cache: cache.unwrap_or_default(),
@@ -930,6 +964,7 @@ impl QueryPlanning {
             experimental_reuse_query_plans: experimental_reuse_query_plans.unwrap_or_default(),
             experimental_cooperative_cancellation: experimental_cooperative_cancellation
                 .unwrap_or_default(),
+            experimental_safe_rollout: experimental_safe_rollout.unwrap_or_default(),
         }
     }
 }
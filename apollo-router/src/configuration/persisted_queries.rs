@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
@@ -20,10 +24,20 @@ pub struct PersistedQueries {
 
     /// Enables using a local copy of the persisted query manifest to safelist operations
     #[serde(alias = "experimental_local_manifests")]
-    pub local_manifests: Option<Vec<String>>,
+    pub local_manifests: Option<Vec<PersistedQueriesLocalManifest>>,
 
     /// Enables hot reloading of the local persisted query manifests
     pub hot_reload: bool,
+
+    /// Enables polling persisted query manifests from HTTP(S) URLs
+    pub remote_manifests: Option<PersistedQueriesRemoteManifests>,
+
+    /// Where the client name used to scope persisted query operations is read from
+    pub client_name: PersistedQueriesClientName,
+
+    /// Experimental feature to record the freeform operations seen in traffic and export them as a
+    /// persisted query manifest
+    pub experimental_learning: Option<PersistedQueriesLearning>,
 }
 
 #[cfg(test)]
@@ -37,15 +51,26 @@ impl PersistedQueries {
         local_manifests: Option<Vec<String>>,
         hot_reload: Option<bool>,
         experimental_prewarm_query_plan_cache: Option<PersistedQueriesPrewarmQueryPlanCache>,
+        remote_manifests: Option<PersistedQueriesRemoteManifests>,
+        client_name: Option<PersistedQueriesClientName>,
+        experimental_learning: Option<PersistedQueriesLearning>,
     ) -> Self {
         Self {
             enabled: enabled.unwrap_or_else(default_pq),
             safelist: safelist.unwrap_or_default(),
             log_unknown: log_unknown.unwrap_or_else(default_log_unknown),
-            local_manifests,
+            local_manifests: local_manifests.map(|paths| {
+                paths
+                    .into_iter()
+                    .map(PersistedQueriesLocalManifest::Path)
+                    .collect()
+            }),
             experimental_prewarm_query_plan_cache: experimental_prewarm_query_plan_cache
                 .unwrap_or_default(),
             hot_reload: hot_reload.unwrap_or_default(),
+            remote_manifests,
+            client_name: client_name.unwrap_or_default(),
+            experimental_learning,
         }
     }
 }
@@ -73,6 +98,194 @@ impl PersistedQueriesSafelist {
     }
 }
 
+/// A local persisted query manifest
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, untagged)]
+pub enum PersistedQueriesLocalManifest {
+    /// The path of the manifest
+    Path(String),
+    /// A manifest whose operations are only available to a single client
+    ClientScoped {
+        /// The path of the manifest
+        path: String,
+        /// The name of the client the operations of the manifest are available to
+        client_name: String,
+    },
+}
+
+impl PersistedQueriesLocalManifest {
+    pub(crate) fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::ClientScoped { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn client_name(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::ClientScoped { client_name, .. } => Some(client_name),
+        }
+    }
+}
+
+/// Persisted Queries (PQ) remote manifests configuration
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PersistedQueriesRemoteManifests {
+    /// The URLs of the persisted query manifests. Operations from every manifest are merged
+    pub urls: Vec<PersistedQueriesRemoteManifest>,
+
+    /// Headers sent with every manifest request, for example to authenticate with the server
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// How often the manifests are polled for changes (defaults to 30s)
+    #[serde(
+        deserialize_with = "humantime_serde::deserialize",
+        default = "default_poll_interval"
+    )]
+    #[schemars(with = "String", default = "default_poll_interval")]
+    pub poll_interval: Duration,
+
+    /// The maximum random delay added to each poll interval, so that router instances don't
+    /// poll at the same time (defaults to 5s)
+    #[serde(
+        deserialize_with = "humantime_serde::deserialize",
+        default = "default_poll_jitter"
+    )]
+    #[schemars(with = "String", default = "default_poll_jitter")]
+    pub jitter: Duration,
+
+    /// The timeout of each manifest request (defaults to 30s)
+    #[serde(
+        deserialize_with = "humantime_serde::deserialize",
+        default = "default_request_timeout"
+    )]
+    #[schemars(with = "String", default = "default_request_timeout")]
+    pub timeout: Duration,
+}
+
+#[cfg(test)]
+#[buildstructor::buildstructor]
+impl PersistedQueriesRemoteManifests {
+    #[builder]
+    pub(crate) fn new(
+        urls: Vec<String>,
+        headers: HashMap<String, String>,
+        poll_interval: Option<Duration>,
+        jitter: Option<Duration>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            urls: urls
+                .into_iter()
+                .map(PersistedQueriesRemoteManifest::Url)
+                .collect(),
+            headers,
+            poll_interval: poll_interval.unwrap_or_else(default_poll_interval),
+            jitter: jitter.unwrap_or_else(default_poll_jitter),
+            timeout: timeout.unwrap_or_else(default_request_timeout),
+        }
+    }
+}
+
+/// A persisted query manifest polled from a URL
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, untagged)]
+pub enum PersistedQueriesRemoteManifest {
+    /// The URL of the manifest
+    Url(String),
+    /// A manifest whose operations are only available to a single client
+    ClientScoped {
+        /// The URL of the manifest
+        url: String,
+        /// The name of the client the operations of the manifest are available to
+        client_name: String,
+    },
+}
+
+impl PersistedQueriesRemoteManifest {
+    pub(crate) fn url(&self) -> &str {
+        match self {
+            Self::Url(url) | Self::ClientScoped { url, .. } => url,
+        }
+    }
+
+    pub(crate) fn client_name(&self) -> Option<&str> {
+        match self {
+            Self::Url(_) => None,
+            Self::ClientScoped { client_name, .. } => Some(client_name),
+        }
+    }
+}
+
+/// Persisted Queries (PQ) client name configuration
+///
+/// The client name is read from the first of these that is set: the
+/// `apollo_persisted_queries::client_name` context entry, the configured header, the
+/// `clientLibrary` request extension if enabled, and the telemetry client name.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub struct PersistedQueriesClientName {
+    /// The header the client name is read from
+    pub header: Option<String>,
+
+    /// Enabling this field reads the client name from the `clientLibrary` request extension
+    /// sent by clients supporting enhanced client awareness (disabled by default)
+    pub client_library: bool,
+}
+
+#[cfg(test)]
+#[buildstructor::buildstructor]
+impl PersistedQueriesClientName {
+    #[builder]
+    pub(crate) fn new(header: Option<String>, client_library: Option<bool>) -> Self {
+        Self {
+            header,
+            client_library: client_library.unwrap_or_default(),
+        }
+    }
+}
+
+/// Persisted Queries (PQ) learning mode configuration
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PersistedQueriesLearning {
+    /// The file the learned persisted query manifest is written to. If the file already exists on
+    /// startup, its operations are loaded and learning continues from them
+    pub output_path: PathBuf,
+
+    /// How often the learned manifest is written to disk (defaults to 60s). It is also written
+    /// when the router shuts down
+    #[serde(
+        deserialize_with = "humantime_serde::deserialize",
+        default = "default_export_interval"
+    )]
+    #[schemars(with = "String", default = "default_export_interval")]
+    pub export_interval: Duration,
+
+    /// The maximum number of distinct operations recorded (defaults to 10000)
+    #[serde(default = "default_max_learned_operations")]
+    pub max_operations: usize,
+}
+
+#[cfg(test)]
+#[buildstructor::buildstructor]
+impl PersistedQueriesLearning {
+    #[builder]
+    pub(crate) fn new(
+        output_path: PathBuf,
+        export_interval: Option<Duration>,
+        max_operations: Option<usize>,
+    ) -> Self {
+        Self {
+            output_path,
+            export_interval: export_interval.unwrap_or_else(default_export_interval),
+            max_operations: max_operations.unwrap_or_else(default_max_learned_operations),
+        }
+    }
+}
+
 /// Persisted Queries (PQ) query plan cache prewarm configuration
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, default)]
@@ -93,6 +306,9 @@ impl Default for PersistedQueries {
             local_manifests: None,
             hot_reload: false,
             experimental_prewarm_query_plan_cache: PersistedQueriesPrewarmQueryPlanCache::default(),
+            remote_manifests: None,
+            client_name: PersistedQueriesClientName::default(),
+            experimental_learning: None,
         }
     }
 }
@@ -130,3 +346,23 @@ const fn default_require_id() -> bool {
 const fn default_log_unknown() -> bool {
     false
 }
+
+const fn default_poll_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+const fn default_poll_jitter() -> Duration {
+    crate::polling::DEFAULT_POLL_JITTER
+}
+
+const fn default_request_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+const fn default_export_interval() -> Duration {
+    Duration::from_secs(60)
+}
+
+const fn default_max_learned_operations() -> usize {
+    10_000
+}
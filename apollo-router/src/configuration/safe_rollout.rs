@@ -0,0 +1,27 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Validates a new supergraph schema against known operations before activating it
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct SafeRollout {
+    /// When true, schema reloads plan the operations from the persisted query manifest and the
+    /// query plan cache against the new schema, and are rejected if too many of them fail.
+    pub(crate) enabled: bool,
+    /// The share of known operations, between 0 and 1, that may fail validation or planning on
+    /// the new schema. The reload is rejected if more operations fail. Defaults to 0.
+    pub(crate) max_failure_ratio: f64,
+    /// The maximum number of failed operations listed in the rejection report. Defaults to 20.
+    pub(crate) max_reported_failures: usize,
+}
+
+impl Default for SafeRollout {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_failure_ratio: 0.0,
+            max_reported_failures: 20,
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Secrets expanded in the configuration file with `${secret.<path>#<field>}`.
+//!
+//! Secrets are read from a secret provider and cached. Each configuration source has its own
+//! [`Secrets`], and the secrets used by the last configuration it read are read again
+//! periodically, so that the configuration can be reloaded when one of them rotates.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures::prelude::*;
+use parking_lot::Mutex;
+use tokio_stream::wrappers::IntervalStream;
+
+use super::ConfigurationError;
+
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A source of secrets for configuration expansion.
+pub(crate) trait SecretProvider: Send + Sync {
+    /// Reads a secret, `None` meaning that it does not exist.
+    fn read(&self, key: &str) -> Result<Option<String>, String>;
+}
+
+/// Secrets read from a provider, cached for a time.
+pub(crate) struct Secrets {
+    provider: Box<dyn SecretProvider>,
+    ttl: Duration,
+    refresh_interval: Duration,
+    cache: Mutex<HashMap<String, CachedSecret>>,
+    /// The secrets used by the last configuration that was read
+    tracked: Mutex<HashSet<String>>,
+}
+
+struct CachedSecret {
+    value: Option<String>,
+    read_at: Instant,
+}
+
+impl Secrets {
+    pub(crate) fn new(
+        provider: Box<dyn SecretProvider>,
+        ttl: Duration,
+        refresh_interval: Duration,
+    ) -> Self {
+        Self {
+            provider,
+            ttl,
+            refresh_interval,
+            cache: Default::default(),
+            tracked: Default::default(),
+        }
+    }
+
+    /// The secrets configured with `APOLLO_ROUTER_SECRETS_*` environment variables, if any.
+    ///
+    /// Each configuration source reads its secrets from its own instance, so that it only tracks
+    /// the secrets of its own configuration.
+    pub(crate) fn from_env() -> Result<Option<Arc<Secrets>>, ConfigurationError> {
+        let Ok(socket) = env::var("APOLLO_ROUTER_SECRETS_AGENT_SOCKET") else {
+            return Ok(None);
+        };
+        let duration = |name: &str, default: Duration| match env::var(name) {
+            Ok(value) => humantime::parse_duration(&value).map_err(|e| {
+                ConfigurationError::InvalidConfiguration {
+                    message: "could not configure the secret provider",
+                    error: format!("invalid duration for {name}: {e}"),
+                }
+            }),
+            Err(_) => Ok(default),
+        };
+        let provider = AgentProvider {
+            socket: socket.into(),
+            token: env::var("APOLLO_ROUTER_SECRETS_AGENT_TOKEN").ok(),
+        };
+        Ok(Some(Arc::new(Secrets::new(
+            Box::new(provider),
+            duration("APOLLO_ROUTER_SECRETS_CACHE_TTL", DEFAULT_CACHE_TTL)?,
+            duration(
+                "APOLLO_ROUTER_SECRETS_REFRESH_INTERVAL",
+                DEFAULT_REFRESH_INTERVAL,
+            )?,
+        ))))
+    }
+
+    /// Reads a secret, from the cache while it is fresh.
+    pub(crate) fn get(&self, key: &str) -> Result<Option<String>, String> {
+        if let Some(cached) = self
+            .cache
+            .lock()
+            .get(key)
+            .filter(|cached| cached.read_at.elapsed() < self.ttl)
+        {
+            return Ok(cached.value.clone());
+        }
+        let value = self.provider.read(key)?;
+        self.cache.lock().insert(
+            key.to_string(),
+            CachedSecret {
+                value: value.clone(),
+                read_at: Instant::now(),
+            },
+        );
+        Ok(value)
+    }
+
+    /// Sets the secrets used by the configuration that was just read, so that only those are
+    /// refreshed. Other secrets are forgotten.
+    pub(crate) fn track(&self, keys: HashSet<String>) {
+        self.cache.lock().retain(|key, _| keys.contains(key));
+        *self.tracked.lock() = keys;
+    }
+
+    /// Reads again every tracked secret, and returns whether any of them changed.
+    ///
+    /// A secret that can't be read keeps its cached value.
+    pub(crate) fn refresh(&self) -> bool {
+        let keys = self.tracked.lock().iter().cloned().collect::<Vec<_>>();
+        let mut changed = false;
+        for key in keys {
+            match self.provider.read(&key) {
+                Ok(value) => {
+                    let mut cache = self.cache.lock();
+                    let previous = cache.insert(
+                        key,
+                        CachedSecret {
+                            value: value.clone(),
+                            read_at: Instant::now(),
+                        },
+                    );
+                    changed |= previous.is_none_or(|previous| previous.value != value);
+                }
+                Err(error) => tracing::error!("could not refresh secret `{key}`: {error}"),
+            }
+        }
+        changed
+    }
+
+    /// A stream with an item each time a secret rotates.
+    pub(crate) fn rotations(self: Arc<Self>) -> impl Stream<Item = ()> + Send + use<> {
+        let mut interval = tokio::time::interval(self.refresh_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        IntervalStream::new(interval)
+            // The first tick is immediate
+            .skip(1)
+            .filter_map(move |_| {
+                let secrets = self.clone();
+                async move {
+                    let changed = tokio::task::spawn_blocking(move || secrets.refresh())
+                        .await
+                        .unwrap_or_default();
+                    if changed {
+                        tracing::info!("a secret rotated, reloading the configuration");
+                    }
+                    changed.then_some(())
+                }
+            })
+    }
+}
+
+/// Reads secrets from a local secrets agent exposing the Vault HTTP API on a Unix socket, such
+/// as Vault Agent. Keys are the path of a secret, without the `/v1/` prefix, and the name of a
+/// field of the secret: `kv/data/redis#password`.
+struct AgentProvider {
+    socket: PathBuf,
+    token: Option<String>,
+}
+
+impl SecretProvider for AgentProvider {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        let (path, field) = key
+            .rsplit_once('#')
+            .ok_or_else(|| format!("secret `{key}` must be referenced as `<path>#<field>`"))?;
+        let (status, body) = self.get(&format!("/v1/{}", path.trim_start_matches('/')))?;
+        if status == http::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(format!("the secrets agent responded with status {status}"));
+        }
+        let body: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| format!("the secrets agent responded with invalid JSON: {e}"))?;
+        Ok(secret_field(&body, field))
+    }
+}
+
+impl AgentProvider {
+    #[cfg(unix)]
+    fn get(&self, path: &str) -> Result<(http::StatusCode, bytes::Bytes), String> {
+        use http_body_util::BodyExt;
+        use http_body_util::Empty;
+        use hyper_util::client::legacy::Client;
+        use hyperlocal::UnixConnector;
+
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        let error = |e: &dyn std::fmt::Display| {
+            format!(
+                "could not reach the secrets agent at {}: {e}",
+                self.socket.display()
+            )
+        };
+        let mut request = http::Request::get(hyperlocal::Uri::new(&self.socket, path))
+            .header("X-Vault-Request", "true");
+        if let Some(token) = &self.token {
+            request = request.header("X-Vault-Token", token);
+        }
+        let request = request
+            .body(Empty::<bytes::Bytes>::new())
+            .map_err(|e| error(&e))?;
+
+        // Secrets are read while expanding the configuration, which is synchronous but may run on
+        // the runtime of the router, so the request runs on a thread and runtime of its own.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .map_err(|e| error(&e))?;
+                    runtime.block_on(async {
+                        let client = Client::builder(hyper_util::rt::TokioExecutor::new())
+                            .build(UnixConnector);
+                        let response = async {
+                            let response = client.request(request).await.map_err(|e| error(&e))?;
+                            let status = response.status();
+                            let body = response
+                                .into_body()
+                                .collect()
+                                .await
+                                .map_err(|e| error(&e))?
+                                .to_bytes();
+                            Ok::<_, String>((status, body))
+                        };
+                        tokio::time::timeout(TIMEOUT, response)
+                            .await
+                            .map_err(|e| error(&e))?
+                    })
+                })
+                .join()
+                .map_err(|_| error(&"the request panicked"))?
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn get(&self, _path: &str) -> Result<(http::StatusCode, bytes::Bytes), String> {
+        Err("secrets agents are only supported on Unix sockets".to_string())
+    }
+}
+
+fn secret_field(body: &serde_json::Value, field: &str) -> Option<String> {
+    let data = &body["data"];
+    // Version 2 of the key/value secrets engine nests the fields of the secret in `data.data`
+    let value = data["data"].get(field).or_else(|| data.get(field))?;
+    Some(match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    /// Serves secrets from a map, counting reads. Reads fail while it is unavailable.
+    #[derive(Clone, Default)]
+    pub(crate) struct MockProvider {
+        pub(crate) secrets: Arc<Mutex<HashMap<String, String>>>,
+        pub(crate) reads: Arc<AtomicUsize>,
+        pub(crate) unavailable: Arc<AtomicBool>,
+    }
+
+    impl SecretProvider for MockProvider {
+        fn read(&self, key: &str) -> Result<Option<String>, String> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err("the secret provider is unavailable".to_string());
+            }
+            Ok(self.secrets.lock().get(key).cloned())
+        }
+    }
+
+    #[test]
+    fn caches_secrets_and_detects_rotations() {
+        let provider = MockProvider::default();
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "old".into());
+        let secrets = Secrets::new(
+            Box::new(provider.clone()),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(secrets.get("kv/redis#password").unwrap().unwrap(), "old");
+        assert_eq!(secrets.get("kv/redis#password").unwrap().unwrap(), "old");
+        assert_eq!(provider.reads.load(Ordering::SeqCst), 1);
+        secrets.track(HashSet::from(["kv/redis#password".to_string()]));
+        assert!(!secrets.refresh());
+
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "new".into());
+        assert!(secrets.refresh());
+        assert_eq!(secrets.get("kv/redis#password").unwrap().unwrap(), "new");
+        assert!(!secrets.refresh());
+    }
+
+    #[test]
+    fn forgets_secrets_the_configuration_no_longer_uses() {
+        let provider = MockProvider::default();
+        let secrets = Secrets::new(
+            Box::new(provider.clone()),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        secrets.get("kv/old#password").unwrap();
+        secrets.track(HashSet::from(["kv/old#password".to_string()]));
+        secrets.get("kv/new#password").unwrap();
+        secrets.track(HashSet::from(["kv/new#password".to_string()]));
+        assert_eq!(provider.reads.load(Ordering::SeqCst), 2);
+
+        secrets.refresh();
+        assert_eq!(provider.reads.load(Ordering::SeqCst), 3);
+        assert!(!secrets.cache.lock().contains_key("kv/old#password"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rotations_are_checked_every_refresh_interval() {
+        let provider = MockProvider::default();
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "old".into());
+        let secrets = Arc::new(Secrets::new(
+            Box::new(provider.clone()),
+            Duration::from_secs(60),
+            Duration::from_secs(10),
+        ));
+        secrets.get("kv/redis#password").unwrap();
+        secrets.track(HashSet::from(["kv/redis#password".to_string()]));
+        let mut rotations = secrets.rotations().boxed();
+
+        // Nothing rotated, so there is no item
+        assert!(
+            tokio::time::timeout(Duration::from_secs(25), rotations.next())
+                .await
+                .is_err()
+        );
+
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "new".into());
+        let start = tokio::time::Instant::now();
+        assert!(rotations.next().await.is_some());
+        assert!(start.elapsed() <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn reads_key_value_secrets() {
+        let v1 = serde_json::json!({ "data": { "password": "one", "port": 6379 } });
+        let v2 = serde_json::json!({ "data": { "data": { "password": "two" }, "metadata": {} } });
+        assert_eq!(secret_field(&v1, "password").unwrap(), "one");
+        assert_eq!(secret_field(&v1, "port").unwrap(), "6379");
+        assert_eq!(secret_field(&v2, "password").unwrap(), "two");
+        assert!(secret_field(&v2, "user").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_secrets_from_an_agent_socket() {
+        use std::io::BufRead;
+        use std::io::BufReader;
+        use std::io::Write;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("agent.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines();
+                let request_line = lines.next().unwrap().unwrap();
+                let token = lines
+                    .map_while(Result::ok)
+                    .take_while(|line| !line.is_empty())
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(": ")?;
+                        name.eq_ignore_ascii_case("x-vault-token")
+                            .then(|| value.to_string())
+                    });
+                let response = if request_line.starts_with("GET /v1/kv/data/redis ") {
+                    let body = r#"{"data":{"data":{"password":"hunter2"}}}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string()
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push((request_line, token));
+            }
+            requests
+        });
+
+        let provider = AgentProvider {
+            socket,
+            token: Some("token".to_string()),
+        };
+        assert_eq!(
+            provider.read("kv/data/redis#password").unwrap().unwrap(),
+            "hunter2"
+        );
+        assert!(provider.read("kv/data/missing#password").unwrap().is_none());
+        assert!(provider.read("kv/data/redis").is_err());
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0],
+            (
+                "GET /v1/kv/data/redis HTTP/1.1".to_string(),
+                Some("token".to_string())
+            )
+        );
+    }
+}
@@ -1228,3 +1228,26 @@ fn find_struct_name(lines: &[&str], line_number: usize) -> Option<String> {
         })
         .next()
 }
+
+#[test]
+fn learning_requires_persisted_queries() {
+    let error = Configuration::builder()
+        .persisted_query(
+            PersistedQueries::builder()
+                .enabled(false)
+                .experimental_learning(
+                    PersistedQueriesLearning::builder()
+                        .output_path(PathBuf::from("learned.json"))
+                        .build(),
+                )
+                .build(),
+        )
+        .build()
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("persisted queries must be enabled to learn operations"),
+        "{error}"
+    );
+}
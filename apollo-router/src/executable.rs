@@ -27,10 +27,13 @@ use crate::configuration::Discussed;
 use crate::configuration::expansion::Expansion;
 use crate::configuration::generate_config_schema;
 use crate::configuration::generate_upgrade;
+use crate::configuration::layers::LayeredConfiguration;
 use crate::configuration::schema::Mode;
 use crate::configuration::validate_yaml_configuration;
 use crate::metrics::meter_provider_internal;
 use crate::plugin::plugins;
+use crate::plugins::record_replay::runner::ReplayOptions;
+use crate::plugins::rhai::test_runner::TestOptions;
 use crate::plugins::telemetry::reload::init_telemetry;
 use crate::registry::OciConfig;
 use crate::router::ConfigurationSource;
@@ -108,6 +111,26 @@ extern "C" fn drop_ad_hoc_profiler() {
 enum Commands {
     /// Configuration subcommands.
     Config(ConfigSubcommandArgs),
+
+    /// Rhai script subcommands.
+    Rhai(RhaiSubcommandArgs),
+
+    /// Replay recordings made by the record plugin against mocked subgraphs, and report
+    /// differences with the recorded responses and subgraph requests.
+    Replay {
+        /// A recording file, or a directory of recording files.
+        #[clap(value_parser)]
+        recordings: PathBuf,
+
+        /// The router configuration to replay the recordings with.
+        #[clap(long = "config")]
+        config_path: Option<PathBuf>,
+
+        /// The supergraph schema to replay the recordings with. Defaults to the schema stored in
+        /// each recording.
+        #[clap(long = "supergraph")]
+        supergraph_path: Option<PathBuf>,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -134,9 +157,11 @@ enum ConfigSubcommand {
     },
     /// Validate existing Router configuration file
     Validate {
-        /// The location of the config to validate.
-        #[clap(value_parser, env = "APOLLO_ROUTER_CONFIG_PATH")]
-        config_path: PathBuf,
+        /// The locations of the config files to validate, merged in order. The merged
+        /// configuration is printed when it is made of several files. Defaults to the paths
+        /// of APOLLO_ROUTER_CONFIG_PATH, separated like the entries of PATH.
+        #[clap(value_parser)]
+        config_paths: Vec<PathBuf>,
     },
     /// List all the available experimental configurations with related GitHub discussion
     Experimental,
@@ -144,6 +169,36 @@ enum ConfigSubcommand {
     Preview,
 }
 
+#[derive(Args, Debug)]
+struct RhaiSubcommandArgs {
+    /// Subcommands
+    #[clap(subcommand)]
+    command: RhaiSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum RhaiSubcommand {
+    /// Run Rhai scripts against fixture requests and responses.
+    Test {
+        /// The directory where Rhai scripts can be found.
+        #[clap(value_parser)]
+        scripts: PathBuf,
+
+        /// The main entry point for Rhai script evaluation.
+        #[clap(long, default_value = "main.rhai")]
+        main: String,
+
+        /// The directory containing the fixture files. Defaults to the `tests` directory
+        /// inside the scripts directory.
+        #[clap(long)]
+        fixtures: Option<PathBuf>,
+
+        /// Supergraph schema made available to scripts as `apollo_sdl`.
+        #[clap(long)]
+        supergraph: Option<PathBuf>,
+    },
+}
+
 /// Options for the router
 #[derive(Parser, Debug)]
 #[clap(name = "router", about = "Apollo federation router")]
@@ -160,7 +215,7 @@ pub struct Opt {
     // FIXME: when upgrading to router 2.0 we should put this value in an Option
     log_level: String,
 
-    /// Reload locally provided configuration and supergraph files automatically.  This only affects watching of local files and does not affect supergraphs and configuration provided by GraphOS through Uplink, which is always reloaded immediately.
+    /// Reload locally provided configuration and supergraph files automatically, and poll supergraph URLs and OCI references for changes.  This does not affect supergraphs and configuration provided by GraphOS through Uplink, which is always reloaded immediately.
     #[clap(
         alias = "hr",
         long = "hot-reload",
@@ -169,14 +224,13 @@ pub struct Opt {
     )]
     hot_reload: bool,
 
-    /// Configuration location relative to the project directory.
-    #[clap(
-        short,
-        long = "config",
-        value_parser,
-        env = "APOLLO_ROUTER_CONFIG_PATH"
-    )]
-    config_path: Option<PathBuf>,
+    /// Configuration location relative to the project directory. May be given several times, each file being deep-merged over the previous ones. Defaults to the paths of APOLLO_ROUTER_CONFIG_PATH, separated like the entries of PATH.
+    #[clap(short, long = "config", value_parser)]
+    config_paths: Vec<PathBuf>,
+
+    /// The configuration locations of APOLLO_ROUTER_CONFIG_PATH, used without --config.
+    #[clap(skip = config_paths_from_env())]
+    env_config_paths: Vec<PathBuf>,
 
     /// Enable development mode.
     #[clap(env = "APOLLO_ROUTER_DEV", long = "dev", action(ArgAction::SetTrue))]
@@ -195,6 +249,24 @@ pub struct Opt {
     #[clap(env = "APOLLO_ROUTER_SUPERGRAPH_URLS", value_delimiter = ',')]
     supergraph_urls: Option<Vec<Url>>,
 
+    /// How often the supergraph URLs are polled for changes when hot reloading is enabled. Defaults to 10s.
+    #[clap(
+        long,
+        default_value = "10s",
+        value_parser = humantime::parse_duration,
+        env = "APOLLO_ROUTER_SUPERGRAPH_URLS_POLL_INTERVAL"
+    )]
+    supergraph_urls_poll_interval: Duration,
+
+    /// The maximum random delay added to each poll of the supergraph URLs, so that router instances don't poll at the same time. Defaults to 5s.
+    #[clap(
+        long,
+        default_value = "5s",
+        value_parser = humantime::parse_duration,
+        env = "APOLLO_ROUTER_SUPERGRAPH_URLS_POLL_JITTER"
+    )]
+    supergraph_urls_poll_jitter: Duration,
+
     /// Subcommands
     #[clap(subcommand)]
     command: Option<Commands>,
@@ -230,6 +302,19 @@ pub struct Opt {
     // TODO: Update name to be final public name
     graph_artifact_reference: Option<String>,
 
+    /// How often the OCI reference is polled for a new digest when hot reloading is enabled. References pinned to a digest are never polled. Defaults to 30s.
+    #[clap(
+        long,
+        default_value = "30s",
+        value_parser = humantime::parse_duration,
+        env
+    )]
+    graph_artifact_poll_interval: Duration,
+
+    /// The path of a PEM encoded public key. When set, OCI bundles are only applied if they are signed with the matching private key.
+    #[clap(long, env)]
+    graph_artifact_public_key: Option<PathBuf>,
+
     /// Disable sending anonymous usage information to Apollo.
     #[clap(long, env = "APOLLO_TELEMETRY_DISABLED", value_parser = FalseyValueParser::new())]
     anonymous_telemetry_disabled: bool,
@@ -300,6 +385,20 @@ impl Opt {
                 .graph_artifact_reference
                 .clone()
                 .ok_or(Self::err_require_opt(" GRAPH_ARTIFACT_REFERENCE"))?,
+            watch: self.hot_reload,
+            poll_interval: self.graph_artifact_poll_interval,
+            public_key: self
+                .graph_artifact_public_key
+                .as_ref()
+                .map(|path| {
+                    std::fs::read_to_string(path).map_err(|err| {
+                        anyhow!(
+                            "failed to read the OCI public key at '{}': {err}",
+                            path.display()
+                        )
+                    })
+                })
+                .transpose()?,
         })
     }
 
@@ -441,17 +540,38 @@ impl Executable {
                 Ok(())
             }
             Some(Commands::Config(ConfigSubcommandArgs {
-                command: ConfigSubcommand::Validate { config_path },
+                command: ConfigSubcommand::Validate { config_paths },
             })) => {
-                let config_string = std::fs::read_to_string(config_path)?;
+                let config_paths = if config_paths.is_empty() {
+                    &opt.env_config_paths
+                } else {
+                    config_paths
+                };
+                if config_paths.is_empty() {
+                    return Err(anyhow!(
+                        "no configuration to validate, pass its path or set APOLLO_ROUTER_CONFIG_PATH"
+                    ));
+                }
+                let layers = LayeredConfiguration::load(config_paths)?;
                 validate_yaml_configuration(
-                    &config_string,
+                    &layers.to_yaml()?,
                     Expansion::default()?,
                     Mode::NoUpgrade,
                 )?
                 .validate()?;
 
-                println!("Configuration at path {:?} is valid!", config_path);
+                match layers.files() {
+                    [config_path] => {
+                        println!("Configuration at path {:?} is valid!", config_path)
+                    }
+                    files => {
+                        print!("{}", layers.to_annotated_yaml());
+                        println!(
+                            "\nConfiguration merged from {} files is valid!",
+                            files.len()
+                        );
+                    }
+                }
 
                 Ok(())
             }
@@ -475,6 +595,64 @@ impl Executable {
                 Discussed::new().print_preview();
                 Ok(())
             }
+            Some(Commands::Rhai(RhaiSubcommandArgs {
+                command:
+                    RhaiSubcommand::Test {
+                        scripts,
+                        main,
+                        fixtures,
+                        supergraph,
+                    },
+            })) => {
+                let supergraph_sdl = supergraph
+                    .as_ref()
+                    .map(std::fs::read_to_string)
+                    .transpose()?;
+                let report = crate::plugins::rhai::test_runner::run(TestOptions {
+                    scripts: scripts.clone(),
+                    main: main.clone(),
+                    fixtures: fixtures.clone().unwrap_or_else(|| scripts.join("tests")),
+                    supergraph_sdl,
+                })
+                .await
+                .map_err(|e| anyhow!("{e}"))?;
+                print!("{report}");
+                if report.failed() > 0 {
+                    Err(anyhow!("{} Rhai test(s) failed", report.failed()))
+                } else {
+                    Ok(())
+                }
+            }
+            Some(Commands::Replay {
+                recordings,
+                config_path,
+                supergraph_path,
+            }) => {
+                let configuration = config_path
+                    .as_ref()
+                    .map(std::fs::read_to_string)
+                    .transpose()?;
+                let supergraph_sdl = supergraph_path
+                    .as_ref()
+                    .map(std::fs::read_to_string)
+                    .transpose()?;
+                let summary = crate::plugins::record_replay::runner::run(ReplayOptions {
+                    recordings: recordings.clone(),
+                    configuration,
+                    supergraph_sdl,
+                })
+                .await
+                .map_err(|e| anyhow!("{e}"))?;
+                print!("{summary}");
+                let differences = summary.differed + summary.failed;
+                if differences > 0 {
+                    Err(anyhow!(
+                        "{differences} recording(s) did not replay as recorded"
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
             None => Self::inner_start(shutdown, schema, config, license, opt).await,
         };
 
@@ -499,31 +677,32 @@ impl Executable {
         let current_directory = std::env::current_dir()?;
         // Enable hot reload when dev mode is enabled
         opt.hot_reload = opt.hot_reload || opt.dev;
+        if opt.config_paths.is_empty() {
+            opt.config_paths = std::mem::take(&mut opt.env_config_paths);
+        }
 
-        let configuration = match (config, opt.config_path.as_ref()) {
-            (Some(_), Some(_)) => {
+        let configuration = match (config, opt.config_paths.is_empty()) {
+            (Some(_), false) => {
                 return Err(anyhow!(
                     "--config and APOLLO_ROUTER_CONFIG_PATH cannot be used when a custom configuration source is in use"
                 ));
             }
-            (Some(config), None) => config,
-            #[allow(clippy::blocks_in_conditions)]
-            _ => opt
-                .config_path
-                .as_ref()
-                .map(|path| {
-                    let path = if path.is_relative() {
-                        current_directory.join(path)
-                    } else {
-                        path.to_path_buf()
-                    };
-
-                    ConfigurationSource::File {
-                        path,
-                        watch: opt.hot_reload,
-                    }
-                })
-                .unwrap_or_default(),
+            (Some(config), true) => config,
+            (None, true) => Default::default(),
+            (None, false) => ConfigurationSource::Files {
+                paths: opt
+                    .config_paths
+                    .iter()
+                    .map(|path| {
+                        if path.is_relative() {
+                            current_directory.join(path)
+                        } else {
+                            path.to_path_buf()
+                        }
+                    })
+                    .collect(),
+                watch: opt.hot_reload,
+            },
         };
 
         let apollo_telemetry_msg = if opt.anonymous_telemetry_disabled {
@@ -579,14 +758,11 @@ impl Executable {
                 tracing::info!("{apollo_router_msg}");
                 tracing::info!("{apollo_telemetry_msg}");
 
-                if opt.hot_reload {
-                    tracing::warn!(
-                        "Schema hot reloading is disabled for --supergraph-urls / APOLLO_ROUTER_SUPERGRAPH_URLS."
-                    );
-                }
-
                 SchemaSource::URLs {
                     urls: supergraph_urls.clone(),
+                    watch: opt.hot_reload,
+                    poll_interval: opt.supergraph_urls_poll_interval,
+                    jitter: opt.supergraph_urls_poll_jitter,
                 }
             }
             (_, None, None, _, Some(apollo_key_path)) => {
@@ -766,6 +942,18 @@ impl Executable {
     }
 }
 
+/// The paths of APOLLO_ROUTER_CONFIG_PATH. Several paths are separated like the entries of PATH,
+/// so that a path may contain commas.
+fn config_paths_from_env() -> Vec<PathBuf> {
+    std::env::var_os("APOLLO_ROUTER_CONFIG_PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn graph_os() -> bool {
     crate::services::APOLLO_KEY.lock().is_some()
         && crate::services::APOLLO_GRAPH_REF.lock().is_some()
@@ -798,8 +986,22 @@ fn setup_panic_handler() {
 
 #[cfg(test)]
 mod tests {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use crate::executable::Opt;
     use crate::executable::add_log_filter;
 
+    #[test]
+    fn config_paths_may_contain_commas() {
+        let opt = Opt::parse_from(["router", "--config", "a,b.yaml", "-c", "c.yaml"]);
+        assert_eq!(
+            opt.config_paths,
+            vec![PathBuf::from("a,b.yaml"), PathBuf::from("c.yaml")]
+        );
+    }
+
     #[test]
     fn simplest_logging_modifications() {
         for level in ["off", "error", "warn", "info", "debug", "trace"] {
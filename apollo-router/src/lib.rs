@@ -70,6 +70,7 @@ pub(crate) mod logging;
 pub(crate) mod notification;
 mod orbiter;
 mod plugins;
+mod polling;
 pub(crate) mod protocols;
 mod query_planner;
 mod router;
@@ -1,25 +1,48 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use apollo_federation::connectors::runtime::errors::Error;
+use apollo_federation::connectors::runtime::http_json_transport::HttpRequest;
+use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
+use apollo_federation::connectors::runtime::http_json_transport::TransportResponse;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use tower::BoxError;
 use tower::ServiceBuilder;
 use tower::ServiceExt;
 
-use super::subgraph::AuthConfig;
+use super::oauth2::OAuth2ClientCredentialsConfig;
+use super::oauth2::TokenSource;
+use super::subgraph::AWSSigV4Config;
+use crate::layers::ServiceBuilderExt;
 use crate::plugins::authentication::subgraph::SigningParamsConfig;
+use crate::plugins::connectors::http_request::clone_request;
+use crate::plugins::file_uploads;
 use crate::services::connector;
 use crate::services::connector_service::ConnectorSourceRef;
 
 pub(super) struct ConnectorAuth {
     pub(super) signing_params: Arc<HashMap<ConnectorSourceRef, Arc<SigningParamsConfig>>>,
+    pub(super) token_sources: Arc<HashMap<ConnectorSourceRef, Arc<TokenSource>>>,
 }
 
 impl ConnectorAuth {
     pub(super) fn connector_request_service(
         &self,
         service: connector::request_service::BoxService,
+        source_name: String,
     ) -> connector::request_service::BoxService {
+        if let Some(tokens) = source_name
+            .parse::<ConnectorSourceRef>()
+            .ok()
+            .and_then(|source_ref| self.token_sources.get(&source_ref).cloned())
+        {
+            return oauth2_service(service, tokens);
+        }
+
         let signing_params = self.signing_params.clone();
         ServiceBuilder::new()
             .map_request(move |req: connector::request_service::Request| {
@@ -43,11 +66,365 @@ impl ConnectorAuth {
     }
 }
 
+/// Adds an OAuth2 bearer token to connector requests. When a request is rejected with a 401,
+/// the token is refreshed and the request is replayed once.
+///
+/// Requests streaming files the client uploaded are not replayed, as that would mean buffering
+/// every file: their 401 is returned, and the next request gets a fresh token.
+fn oauth2_service(
+    service: connector::request_service::BoxService,
+    tokens: Arc<TokenSource>,
+) -> connector::request_service::BoxService {
+    let service = ServiceBuilder::new().buffered().service(service);
+    tower::service_fn(move |request: connector::request_service::Request| {
+        let service = service.clone();
+        let tokens = tokens.clone();
+        async move {
+            let token = match tokens.token().await {
+                Ok(token) => token,
+                Err(e) => return Ok(token_error(request, e)),
+            };
+            let TransportRequest::Http(http_request) = &request.transport_request;
+            let replay = (!file_uploads::has_uploads(&http_request.inner))
+                .then(|| clone_connector_request(&request));
+            let response = service
+                .clone()
+                .oneshot(with_bearer_token(request, &token)?)
+                .await?;
+
+            let unauthorized = matches!(
+                &response.transport_result,
+                Ok(TransportResponse::Http(http_response))
+                    if http_response.inner.status == StatusCode::UNAUTHORIZED
+            );
+            if !unauthorized {
+                return Ok(response);
+            }
+
+            tokens.invalidate(&token);
+            let Some(replay) = replay else {
+                return Ok(response);
+            };
+            let token = match tokens.token().await {
+                Ok(token) => token,
+                Err(e) => return Ok(token_error(replay, e)),
+            };
+            service.oneshot(with_bearer_token(replay, &token)?).await
+        }
+    })
+    .boxed()
+}
+
+fn with_bearer_token(
+    mut request: connector::request_service::Request,
+    token: &str,
+) -> Result<connector::request_service::Request, BoxError> {
+    let TransportRequest::Http(http_request) = &mut request.transport_request;
+    http_request.inner.headers_mut().insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {token}"))?,
+    );
+    Ok(request)
+}
+
+fn clone_connector_request(
+    request: &connector::request_service::Request,
+) -> connector::request_service::Request {
+    let TransportRequest::Http(http_request) = &request.transport_request;
+    connector::request_service::Request {
+        context: request.context.clone(),
+        connector: request.connector.clone(),
+        service_name: request.service_name.clone(),
+        transport_request: TransportRequest::Http(HttpRequest {
+            inner: clone_request(&http_request.inner),
+            debug: http_request.debug.clone(),
+        }),
+        key: request.key.clone(),
+        mapping_problems: request.mapping_problems.clone(),
+        supergraph_request: request.supergraph_request.clone(),
+        retain_upstream_body: request.retain_upstream_body,
+    }
+}
+
+fn token_error(
+    request: connector::request_service::Request,
+    error: BoxError,
+) -> connector::request_service::Response {
+    tracing::error!(
+        connector = request.connector.id.label.as_str(),
+        error = %error,
+        "could not get an OAuth2 token"
+    );
+    let error = Error::TransportFailure(format!("could not get an OAuth2 token: {error}"));
+    connector::request_service::Response::error_builder()
+        .context(request.context)
+        .connector(request.connector)
+        .message(error.to_string())
+        .error(error)
+        .response_key(request.key)
+        .build()
+}
+
 /// Configure connector authentication
 #[derive(Clone, Debug, Default, JsonSchema, Deserialize)]
 #[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub(crate) struct Config {
     #[serde(default)]
     /// Create a configuration that will apply only to a specific source.
-    pub(crate) sources: HashMap<String, AuthConfig>,
+    pub(crate) sources: HashMap<String, SourceAuthConfig>,
+}
+
+/// Configure the authentication of a connector source
+#[derive(Clone, Debug, JsonSchema, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) enum SourceAuthConfig {
+    #[serde(rename = "aws_sig_v4")]
+    AWSSigV4(AWSSigV4Config),
+    #[serde(rename = "oauth2_client_credentials")]
+    OAuth2ClientCredentials(OAuth2ClientCredentialsConfig),
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::name;
+    use apollo_federation::connectors::ConnectId;
+    use apollo_federation::connectors::ConnectSpec;
+    use apollo_federation::connectors::Connector;
+    use apollo_federation::connectors::HttpJsonTransport;
+    use apollo_federation::connectors::JSONSelection;
+    use apollo_federation::connectors::runtime::form_data::FormData;
+    use apollo_federation::connectors::runtime::form_data::upload_placeholder;
+    use apollo_federation::connectors::runtime::http_json_transport::HttpResponse;
+    use apollo_federation::connectors::runtime::key::ResponseKey;
+    use apollo_federation::connectors::runtime::responses::MappedResponse;
+    use http::header::CONTENT_TYPE;
+    use parking_lot::Mutex;
+    use serde_json_bytes::Value;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    use super::*;
+    use crate::Context;
+    use crate::services::router;
+
+    fn connector() -> Arc<Connector> {
+        Arc::new(Connector {
+            spec: ConnectSpec::V0_1,
+            id: ConnectId::new(
+                "subgraph_name".into(),
+                None,
+                name!(Query),
+                name!(a),
+                0,
+                "test label",
+            ),
+            transport: HttpJsonTransport {
+                source_template: "http://localhost/api".parse().ok(),
+                connect_template: "/path".parse().unwrap(),
+                ..Default::default()
+            },
+            selection: JSONSelection::parse("f").unwrap(),
+            entity_resolver: None,
+            config: Default::default(),
+            max_requests: None,
+            batch_settings: None,
+            request_headers: Default::default(),
+            response_headers: Default::default(),
+            request_variable_keys: Default::default(),
+            response_variable_keys: Default::default(),
+            error_settings: Default::default(),
+        })
+    }
+
+    fn key() -> ResponseKey {
+        ResponseKey::RootField {
+            name: "a".to_string(),
+            inputs: Default::default(),
+            selection: Arc::new(JSONSelection::parse("$.data").unwrap()),
+        }
+    }
+
+    fn token_response(token: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "access_token": token,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+        }))
+    }
+
+    async fn token_source(server: &MockServer) -> Arc<TokenSource> {
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(token_response("expired"))
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(token_response("fresh"))
+            .mount(server)
+            .await;
+        let config = serde_json::from_value(serde_json::json!({
+            "token_url": format!("{}/token", server.uri()),
+            "client_id": "client",
+            "client_secret": "s3cret",
+        }))
+        .unwrap();
+        Arc::new(TokenSource::new(config).unwrap())
+    }
+
+    fn request(inner: http::Request<String>) -> connector::request_service::Request {
+        connector::request_service::Request {
+            context: Context::new(),
+            connector: connector(),
+            service_name: "subgraph_name".to_string(),
+            transport_request: TransportRequest::Http(HttpRequest {
+                inner,
+                debug: Default::default(),
+            }),
+            key: key(),
+            mapping_problems: vec![],
+            supergraph_request: Default::default(),
+            retain_upstream_body: false,
+        }
+    }
+
+    /// An upstream that rejects the `expired` token, recording the requests it receives with the
+    /// bodies that were sent
+    fn upstream(
+        received: Arc<Mutex<Vec<http::Request<String>>>>,
+    ) -> connector::request_service::BoxService {
+        tower::service_fn(move |request: connector::request_service::Request| {
+            let received = received.clone();
+            async move {
+                let TransportRequest::Http(http_request) = &request.transport_request;
+                let (parts, body) = file_uploads::connector_request_wrapper(
+                    clone_request(&http_request.inner),
+                    &request.context,
+                )
+                .await?
+                .into_parts();
+                let body = router::body::into_string(body).await?;
+                let status = if parts.headers[AUTHORIZATION] == "Bearer expired" {
+                    StatusCode::UNAUTHORIZED
+                } else {
+                    StatusCode::OK
+                };
+                received.lock().push(http::Request::from_parts(parts, body));
+
+                let (inner, _) = http::Response::builder()
+                    .status(status)
+                    .body(())
+                    .unwrap()
+                    .into_parts();
+                Ok::<_, BoxError>(connector::request_service::Response {
+                    context: request.context,
+                    connector: request.connector,
+                    transport_result: Ok(TransportResponse::Http(HttpResponse { inner })),
+                    mapped_response: MappedResponse::Data {
+                        data: Value::Null,
+                        key: request.key,
+                        problems: vec![],
+                    },
+                    upstream_body: None,
+                    errors: Vec::new(),
+                })
+            }
+        })
+        .boxed()
+    }
+
+    fn assert_ok(response: &connector::request_service::Response) {
+        assert!(matches!(
+            &response.transport_result,
+            Ok(TransportResponse::Http(HttpResponse { inner })) if inner.status == StatusCode::OK
+        ));
+    }
+
+    #[tokio::test]
+    async fn refreshes_the_token_and_replays_once_on_401() {
+        let server = MockServer::start().await;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let service = oauth2_service(upstream(received.clone()), token_source(&server).await);
+
+        let response = service
+            .oneshot(request(
+                http::Request::post("http://localhost/api/path")
+                    .header("x-custom", "kept")
+                    .body("{\"a\":1}".to_string())
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        assert_ok(&response);
+        let received = received.lock();
+        assert_eq!(
+            received
+                .iter()
+                .map(|request| request.headers()[AUTHORIZATION].to_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["Bearer expired", "Bearer fresh"]
+        );
+        // The replay is the same request
+        assert_eq!(received[1].method(), http::Method::POST);
+        assert_eq!(received[1].uri(), "http://localhost/api/path");
+        assert_eq!(received[1].headers()["x-custom"], "kept");
+        assert_eq!(received[1].body(), "{\"a\":1}");
+    }
+
+    #[tokio::test]
+    async fn does_not_replay_uploads_on_401() {
+        let server = MockServer::start().await;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let tokens = token_source(&server).await;
+        let service = oauth2_service(upstream(received.clone()), tokens.clone());
+
+        let form = FormData::from_json(&serde_json_bytes::json!({
+            "name": "avatar",
+            "file": upload_placeholder("0"),
+        }))
+        .unwrap();
+        let inner = http::Request::post("http://localhost/api/path")
+            .header(CONTENT_TYPE, form.content_type())
+            .extension(form)
+            .body(String::new())
+            .unwrap();
+        let mut upload_request = request(inner);
+        upload_request.context = file_uploads::test_context(&[("0", "file contents")]).await;
+
+        let response = service.oneshot(upload_request).await.unwrap();
+
+        assert!(matches!(
+            &response.transport_result,
+            Ok(TransportResponse::Http(HttpResponse { inner }))
+                if inner.status == StatusCode::UNAUTHORIZED
+        ));
+        {
+            let received = received.lock();
+            assert_eq!(received.len(), 1);
+            let body = received[0].body();
+            assert!(
+                body.contains(
+                    "filename=\"0.txt\"\r\nContent-Type: text/plain\r\n\r\nfile contents"
+                ),
+                "{body}"
+            );
+        }
+
+        // The rejected token was refreshed for the next request
+        let response = oauth2_service(upstream(received.clone()), tokens)
+            .oneshot(request(
+                http::Request::get("http://localhost/api/path")
+                    .body(String::new())
+                    .unwrap(),
+            ))
+            .await
+            .unwrap();
+        assert_ok(&response);
+        assert_eq!(received.lock()[1].headers()[AUTHORIZATION], "Bearer fresh");
+    }
 }
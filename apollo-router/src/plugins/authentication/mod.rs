@@ -24,7 +24,10 @@ use tower::ServiceBuilder;
 use tower::ServiceExt;
 use url::Url;
 
+use self::connector::SourceAuthConfig;
 use self::jwks::JwksManager;
+use self::oauth2::TokenSource;
+use self::subgraph::AuthConfig;
 use self::subgraph::SigningParams;
 use self::subgraph::SigningParamsConfig;
 use self::subgraph::SubgraphAuth;
@@ -48,6 +51,8 @@ pub(crate) mod jwks;
 
 pub(crate) mod connector;
 
+pub(crate) mod oauth2;
+
 pub(crate) mod subgraph;
 
 mod error;
@@ -267,14 +272,14 @@ impl PluginPrivate for AuthenticationPlugin {
     fn connector_request_service(
         &self,
         service: crate::services::connector::request_service::BoxService,
-        _: String,
+        source_name: String,
     ) -> crate::services::connector::request_service::BoxService {
         // Return without layering if no connector config was defined
         let Some(connector_auth) = &self.connector else {
             return service;
         };
 
-        connector_auth.connector_request_service(service)
+        connector_auth.connector_request_service(service, source_name)
     }
 }
 
@@ -368,18 +373,30 @@ impl AuthenticationPlugin {
 
         let mut signing_params: HashMap<ConnectorSourceRef, Arc<SigningParamsConfig>> =
             Default::default();
+        let mut token_sources: HashMap<ConnectorSourceRef, Arc<TokenSource>> = Default::default();
         for (s, source_config) in connector_conf.sources {
             let source_ref: ConnectorSourceRef = s.parse()?;
-            signing_params.insert(
-                source_ref.clone(),
-                make_signing_params(&source_config, &source_ref.subgraph_name)
-                    .await
-                    .map(Arc::new)?,
-            );
+            match source_config {
+                SourceAuthConfig::AWSSigV4(config) => {
+                    signing_params.insert(
+                        source_ref.clone(),
+                        make_signing_params(
+                            &AuthConfig::AWSSigV4(config),
+                            &source_ref.subgraph_name,
+                        )
+                        .await
+                        .map(Arc::new)?,
+                    );
+                }
+                SourceAuthConfig::OAuth2ClientCredentials(config) => {
+                    token_sources.insert(source_ref, Arc::new(TokenSource::new(config)?));
+                }
+            }
         }
 
         Ok(Some(ConnectorAuth {
             signing_params: Arc::new(signing_params),
+            token_sources: Arc::new(token_sources),
         }))
     }
 }
@@ -346,8 +346,10 @@ impl CacheControl {
         self.private
     }
 
-    // We don't support revalidation yet
-    #[allow(dead_code)]
+    pub(crate) fn public(&self) -> bool {
+        self.public
+    }
+
     pub(crate) fn should_revalidate(&self) -> bool {
         if self.no_cache {
             return true;
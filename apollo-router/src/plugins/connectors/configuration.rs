@@ -11,8 +11,11 @@ use schemars::schema::SchemaObject;
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::decoders::decoder;
 use super::incompatible::warn_incompatible_plugins;
 use crate::Configuration;
+use crate::configuration::InMemoryCache;
+use crate::configuration::RedisCache;
 use crate::plugins::connectors::plugin::PLUGIN_NAME;
 use crate::services::connector_service::ConnectorSourceRef;
 
@@ -87,11 +90,27 @@ pub(crate) struct SourceConfiguration {
     /// The maximum number of requests for this source
     pub(crate) max_requests_per_operation: Option<usize>,
 
+    /// Caches the responses to `GET` requests for this source according to their
+    /// `Cache-Control` headers
+    pub(crate) cache: SourceCacheConfiguration,
+
     /// Other values that can be used by connectors via `{$config.<key>}`
     #[serde(rename = "$config")]
     pub(crate) custom: CustomConfiguration,
 }
 
+/// HTTP cache configuration for a connector source
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct SourceCacheConfiguration {
+    /// Enables the HTTP cache for this source
+    pub(crate) enabled: bool,
+    /// Configures the in memory cache
+    pub(crate) in_memory: InMemoryCache,
+    /// Configures and activates the Redis cache
+    pub(crate) redis: Option<RedisCache>,
+}
+
 fn uri_schema(_generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
     SchemaObject {
         instance_type: Some(InstanceType::String.into()),
@@ -114,6 +133,7 @@ pub(crate) fn apply_config(
     // Enabling connectors might end up interfering with other router features, so we insert warnings
     // into the logs for any incompatibilities found.
     warn_incompatible_plugins(router_config, &connectors);
+    warn_unknown_response_formats(&connectors);
 
     let Some(config) = router_config.apollo_plugins.plugins.get(PLUGIN_NAME) else {
         return connectors;
@@ -161,3 +181,19 @@ pub(crate) fn apply_config(
     }
     connectors
 }
+
+/// Warns about connectors with a `responseFormat` that no decoder handles, which composition
+/// rejects but which supergraphs composed by older versions may still have. Their responses are
+/// decoded according to their content type instead.
+fn warn_unknown_response_formats(connectors: &Connectors) {
+    for connector in connectors.by_service_name.values() {
+        if let Some(format) = connector.transport.response_format.as_deref() {
+            if decoder(format).is_none() {
+                tracing::warn!(
+                    connector = connector.id.label.as_str(),
+                    "unknown response format `{format}`, responses will be decoded according to their content type"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,502 @@
+//! Decoders turning connector response bodies into JSON values for the `selection` mapping.
+//!
+//! A decoder is chosen by the `responseFormat` field of `@connect(http:)` when it is set, and
+//! otherwise by the `Content-Type` header of the response.
+//!
+//! The set of decoders is fixed: there is one for each of the
+//! [`RESPONSE_FORMATS`](apollo_federation::connectors::RESPONSE_FORMATS) that composition accepts
+//! in `responseFormat`. Supporting another format means adding a decoder here and its name there.
+
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+use mime::Mime;
+use serde_json_bytes::ByteString;
+use serde_json_bytes::Map;
+use serde_json_bytes::Value;
+use xmlparser::ElementEnd;
+use xmlparser::Token;
+use xmlparser::Tokenizer;
+
+/// Converts a response body in a given format into a JSON value.
+pub(crate) trait ResponseDecoder: Send + Sync {
+    /// The name used to select this decoder with `responseFormat`.
+    fn name(&self) -> &'static str;
+
+    /// Whether this decoder handles responses with this content type.
+    fn accepts(&self, content_type: &Mime) -> bool;
+
+    /// Decodes the body, returning a description of the problem if it is malformed.
+    fn decode(&self, body: &[u8], content_type: Option<&Mime>) -> Result<Value, String>;
+}
+
+static DECODERS: &[&dyn ResponseDecoder] = &[
+    &JsonDecoder,
+    &TextDecoder,
+    &XmlDecoder,
+    &CsvDecoder,
+    &NdjsonDecoder,
+];
+
+/// Looks up a decoder by its `responseFormat` name.
+pub(crate) fn decoder(name: &str) -> Option<&'static dyn ResponseDecoder> {
+    DECODERS
+        .iter()
+        .copied()
+        .find(|decoder| decoder.name().eq_ignore_ascii_case(name))
+}
+
+/// Selects the decoder for a response. An unknown `format` falls back to the content type, and
+/// a missing content type is treated as JSON. Returns `None` when no decoder handles the response.
+pub(crate) fn decoder_for_response(
+    format: Option<&str>,
+    content_type: Option<&Mime>,
+) -> Option<&'static dyn ResponseDecoder> {
+    if let Some(decoder) = format.and_then(decoder) {
+        return Some(decoder);
+    }
+    match content_type {
+        None => Some(&JsonDecoder),
+        Some(content_type) => DECODERS
+            .iter()
+            .copied()
+            .find(|decoder| decoder.accepts(content_type)),
+    }
+}
+
+/// Decodes the body into a string using the charset of the content type, defaulting to UTF-8.
+fn decode_text(body: &[u8], content_type: Option<&Mime>) -> Result<String, String> {
+    let encoding = content_type
+        .and_then(|ct| Encoding::for_label(ct.get_param("charset")?.as_str().as_bytes()))
+        .unwrap_or(UTF_8);
+    let (decoded, _, had_errors) = encoding.decode(body);
+    if had_errors {
+        return Err(format!("the body is not valid {}", encoding.name()));
+    }
+    Ok(decoded.into_owned())
+}
+
+struct JsonDecoder;
+
+impl ResponseDecoder for JsonDecoder {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn accepts(&self, content_type: &Mime) -> bool {
+        content_type.subtype() == mime::JSON || content_type.suffix() == Some(mime::JSON)
+    }
+
+    fn decode(&self, body: &[u8], _content_type: Option<&Mime>) -> Result<Value, String> {
+        serde_json::from_slice(body).map_err(|e| e.to_string())
+    }
+}
+
+/// Returns the body as a JSON string.
+struct TextDecoder;
+
+impl ResponseDecoder for TextDecoder {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn accepts(&self, content_type: &Mime) -> bool {
+        content_type.type_() == mime::TEXT && content_type.subtype() == mime::PLAIN
+    }
+
+    fn decode(&self, body: &[u8], content_type: Option<&Mime>) -> Result<Value, String> {
+        decode_text(body, content_type).map(|text| Value::String(text.into()))
+    }
+}
+
+/// Converts an XML document into an object with the root element as its only key.
+///
+/// Elements holding only text become strings, and empty elements become `null`. Other elements
+/// become objects where attributes are keyed by `@name`, text is keyed by `#text`, and child
+/// elements are keyed by their name, with repeated children collected into an array. Namespace
+/// prefixes are dropped from names.
+struct XmlDecoder;
+
+#[derive(Default)]
+struct XmlElement {
+    name: String,
+    attributes: Map<ByteString, Value>,
+    children: Vec<(String, Value)>,
+    text: String,
+}
+
+impl XmlElement {
+    fn into_value(self) -> Value {
+        let text = self.text.trim();
+        if self.attributes.is_empty() && self.children.is_empty() {
+            return if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text.into())
+            };
+        }
+
+        let mut object = self.attributes;
+        for (name, child) in self.children {
+            match object.get_mut(name.as_str()) {
+                Some(Value::Array(values)) => values.push(child),
+                Some(existing) => {
+                    let first = existing.take();
+                    *existing = Value::Array(vec![first, child]);
+                }
+                None => {
+                    object.insert(name, child);
+                }
+            }
+        }
+        if !text.is_empty() {
+            object.insert("#text", Value::String(text.into()));
+        }
+        Value::Object(object)
+    }
+}
+
+impl ResponseDecoder for XmlDecoder {
+    fn name(&self) -> &'static str {
+        "xml"
+    }
+
+    fn accepts(&self, content_type: &Mime) -> bool {
+        (matches!(content_type.type_().as_str(), "application" | "text")
+            && content_type.subtype() == mime::XML)
+            || content_type.suffix() == Some(mime::XML)
+    }
+
+    fn decode(&self, body: &[u8], content_type: Option<&Mime>) -> Result<Value, String> {
+        let text = decode_text(body, content_type)?;
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root = None;
+
+        for token in Tokenizer::from(text.as_str()) {
+            match token.map_err(|e| e.to_string())? {
+                Token::ElementStart { local, .. } => {
+                    if root.is_some() {
+                        return Err("the document has more than one root element".to_string());
+                    }
+                    stack.push(XmlElement {
+                        name: local.to_string(),
+                        ..Default::default()
+                    });
+                }
+                Token::Attribute { local, value, .. } => {
+                    if let Some(element) = stack.last_mut() {
+                        element.attributes.insert(
+                            format!("@{local}"),
+                            Value::String(unescape_xml(value.as_str())?.into()),
+                        );
+                    }
+                }
+                Token::ElementEnd {
+                    end: ElementEnd::Open,
+                    ..
+                } => {}
+                Token::ElementEnd { .. } => {
+                    let Some(element) = stack.pop() else {
+                        return Err("unexpected closing tag".to_string());
+                    };
+                    let name = element.name.clone();
+                    let value = element.into_value();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push((name, value)),
+                        None => {
+                            let mut object = Map::new();
+                            object.insert(name, value);
+                            root = Some(Value::Object(object));
+                        }
+                    }
+                }
+                Token::Text { text } => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&unescape_xml(text.as_str())?);
+                    }
+                }
+                Token::Cdata { text, .. } => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(text.as_str());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err("the document ended before all elements were closed".to_string());
+        }
+        root.ok_or_else(|| "the document has no root element".to_string())
+    }
+}
+
+/// Replaces the predefined entities and character references in XML text.
+fn unescape_xml(text: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(';')
+            .ok_or_else(|| "unterminated entity reference".to_string())?;
+        let entity = &rest[..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity.strip_prefix('#') {
+                Some(code) => match code.strip_prefix('x') {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => code.parse().ok(),
+                }
+                .and_then(char::from_u32),
+                None => None,
+            },
+        };
+        result.push(character.ok_or_else(|| format!("unknown entity `&{entity};`"))?);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Converts CSV into an array of objects keyed by the column names in the header row. All values
+/// are strings.
+struct CsvDecoder;
+
+impl ResponseDecoder for CsvDecoder {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn accepts(&self, content_type: &Mime) -> bool {
+        content_type.type_() == mime::TEXT && content_type.subtype() == mime::CSV
+    }
+
+    fn decode(&self, body: &[u8], content_type: Option<&Mime>) -> Result<Value, String> {
+        let text = decode_text(body, content_type)?;
+        let mut records = parse_csv(text.trim_start_matches('\u{feff}'))?.into_iter();
+        let Some(header) = records.next() else {
+            return Ok(Value::Array(Vec::new()));
+        };
+
+        records
+            .enumerate()
+            .map(|(index, record)| {
+                if record.len() != header.len() {
+                    return Err(format!(
+                        "row {} has {} fields but the header has {}",
+                        index + 2,
+                        record.len(),
+                        header.len()
+                    ));
+                }
+                Ok(Value::Object(
+                    header
+                        .iter()
+                        .zip(record)
+                        .map(|(name, value)| (name.as_str().into(), Value::String(value.into())))
+                        .collect(),
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
+    }
+}
+
+/// Splits CSV text into records following RFC 4180. Blank lines are skipped.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Converts newline-delimited JSON into an array with one value per non-blank line.
+struct NdjsonDecoder;
+
+impl ResponseDecoder for NdjsonDecoder {
+    fn name(&self) -> &'static str {
+        "ndjson"
+    }
+
+    fn accepts(&self, content_type: &Mime) -> bool {
+        matches!(
+            content_type.subtype().as_str(),
+            "ndjson" | "x-ndjson" | "jsonl" | "x-jsonl" | "jsonlines" | "x-jsonlines"
+        )
+    }
+
+    fn decode(&self, body: &[u8], content_type: Option<&Mime>) -> Result<Value, String> {
+        decode_text(body, content_type)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| format!("line {}: {e}", index + 1))
+            })
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+
+    fn decode(format: Option<&str>, content_type: &str, body: &str) -> Result<Value, String> {
+        let content_type = content_type.parse::<Mime>().ok();
+        decoder_for_response(format, content_type.as_ref())
+            .expect("a decoder")
+            .decode(body.as_bytes(), content_type.as_ref())
+    }
+
+    #[test]
+    fn selects_decoders() {
+        let name = |format: Option<&str>, content_type: Option<&str>| {
+            let content_type = content_type.map(|ct| ct.parse::<Mime>().unwrap());
+            decoder_for_response(format, content_type.as_ref()).map(|decoder| decoder.name())
+        };
+
+        assert_eq!(name(None, None), Some("json"));
+        assert_eq!(name(None, Some("application/hal+json")), Some("json"));
+        assert_eq!(name(None, Some("text/plain")), Some("text"));
+        assert_eq!(name(None, Some("text/xml")), Some("xml"));
+        assert_eq!(name(None, Some("application/atom+xml")), Some("xml"));
+        assert_eq!(name(None, Some("text/csv; charset=utf-8")), Some("csv"));
+        assert_eq!(name(None, Some("application/x-ndjson")), Some("ndjson"));
+        assert_eq!(name(None, Some("image/png")), None);
+        assert_eq!(name(Some("CSV"), Some("text/plain")), Some("csv"));
+        assert_eq!(name(Some("yaml"), Some("text/xml")), Some("xml"));
+    }
+
+    #[test]
+    fn decodes_every_format_accepted_by_composition() {
+        for format in apollo_federation::connectors::RESPONSE_FORMATS {
+            assert_eq!(
+                decoder(format).map(|decoder| decoder.name()),
+                Some(format),
+                "no decoder for `{format}`"
+            );
+        }
+        assert_eq!(
+            DECODERS.len(),
+            apollo_federation::connectors::RESPONSE_FORMATS.len()
+        );
+    }
+
+    #[test]
+    fn decodes_xml() {
+        let body = r#"<?xml version="1.0"?>
+            <!-- a comment -->
+            <ns:books count="2">
+              <book id="1"><title>Dune</title><tag>a</tag><tag>b</tag></book>
+              <book id="2"><title><![CDATA[Tom & Jerry]]></title><empty/></book>
+              <note lang="en">Fish &amp; chips &#x263A;</note>
+            </ns:books>"#;
+
+        assert_eq!(
+            decode(None, "application/xml", body).unwrap(),
+            json!({
+                "books": {
+                    "@count": "2",
+                    "book": [
+                        { "@id": "1", "title": "Dune", "tag": ["a", "b"] },
+                        { "@id": "2", "title": "Tom & Jerry", "empty": null }
+                    ],
+                    "note": { "@lang": "en", "#text": "Fish & chips \u{263A}" }
+                }
+            })
+        );
+
+        assert!(decode(None, "application/xml", "<a><b></a>").is_err());
+        assert!(decode(None, "application/xml", "<a>").is_err());
+        assert!(decode(None, "application/xml", "<a>&nope;</a>").is_err());
+    }
+
+    #[test]
+    fn decodes_csv() {
+        let body = "\u{feff}id,name,notes\r\n1,Ada,\"says \"\"hi\"\", twice\"\r\n\r\n2,Grace,\"multi\nline\"\r\n";
+
+        assert_eq!(
+            decode(None, "text/csv", body).unwrap(),
+            json!([
+                { "id": "1", "name": "Ada", "notes": "says \"hi\", twice" },
+                { "id": "2", "name": "Grace", "notes": "multi\nline" }
+            ])
+        );
+        assert_eq!(decode(None, "text/csv", "").unwrap(), json!([]));
+        assert!(decode(None, "text/csv", "a,b\n1\n").is_err());
+        assert!(decode(None, "text/csv", "a,b\n1,\"2\n").is_err());
+    }
+
+    #[test]
+    fn decodes_ndjson() {
+        assert_eq!(
+            decode(None, "application/x-ndjson", "{\"a\":1}\n\n[2]\n").unwrap(),
+            json!([{ "a": 1 }, [2]])
+        );
+        assert!(decode(None, "application/x-ndjson", "{\"a\":1}\n{").is_err());
+    }
+
+    #[test]
+    fn decodes_charsets() {
+        let content_type = "text/csv; charset=iso-8859-1".parse::<Mime>().unwrap();
+        assert_eq!(
+            decoder("csv")
+                .unwrap()
+                .decode(b"name\nJos\xe9\n", Some(&content_type))
+                .unwrap(),
+            json!([{ "name": "José" }])
+        );
+        assert!(
+            decoder("text")
+                .unwrap()
+                .decode(b"\xff\xfe\xfd", None)
+                .is_err()
+        );
+    }
+}
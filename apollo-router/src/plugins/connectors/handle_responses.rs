@@ -15,8 +15,6 @@ use apollo_federation::connectors::runtime::responses::MappedResponse;
 use apollo_federation::connectors::runtime::responses::RawResponse;
 use apollo_federation::connectors::runtime::responses::handle_raw_response;
 use axum::body::HttpBody;
-use encoding_rs::Encoding;
-use encoding_rs::UTF_8;
 use http::header::CONTENT_LENGTH;
 use http::header::CONTENT_TYPE;
 use mime::Mime;
@@ -28,6 +26,8 @@ use tracing::Span;
 use crate::Context;
 use crate::graphql;
 use crate::json_ext::Path;
+use crate::plugins::connectors::decoders::decoder_for_response;
+use crate::plugins::connectors::pagination::MergedPages;
 use crate::plugins::telemetry::config_new::attributes::HTTP_RESPONSE_BODY;
 use crate::plugins::telemetry::config_new::attributes::HTTP_RESPONSE_HEADERS;
 use crate::plugins::telemetry::config_new::attributes::HTTP_RESPONSE_STATUS;
@@ -77,7 +77,11 @@ pub(crate) async fn process_response<T: HttpBody>(
     ),
     debug_context: &Option<Arc<Mutex<ConnectorContext>>>,
     supergraph_request: Arc<http::Request<crate::graphql::Request>>,
+    retain_upstream_body: bool,
 ) -> connector::request_service::Response {
+    let mut upstream_body = None;
+    let mut errors = Vec::new();
+
     let (mapped_response, result) = match result {
         // This occurs when we short-circuit the request when over the limit
         Err(error) => {
@@ -99,6 +103,15 @@ pub(crate) async fn process_response<T: HttpBody>(
         Ok(response) => {
             let (parts, body) = response.into_parts();
 
+            // A page that failed after others were fetched is reported next to their data
+            if let Some(error) = parts
+                .extensions
+                .get::<MergedPages>()
+                .and_then(|pages| pages.error.as_ref())
+            {
+                errors.push(error.to_runtime_error(&connector, &response_key));
+            }
+
             let result = Ok(TransportResponse::Http(HttpResponse {
                 inner: parts.clone(),
             }));
@@ -117,12 +130,17 @@ pub(crate) async fn process_response<T: HttpBody>(
             )
             .await
             {
-                Ok(data) => RawResponse::Data {
-                    parts,
-                    data,
-                    key: response_key,
-                    debug_request,
-                },
+                Ok(data) => {
+                    if retain_upstream_body {
+                        upstream_body = Some(data.clone());
+                    }
+                    RawResponse::Data {
+                        parts,
+                        data,
+                        key: response_key,
+                        debug_request,
+                    }
+                }
                 Err(error) => RawResponse::Error {
                     error,
                     key: response_key,
@@ -156,11 +174,16 @@ pub(crate) async fn process_response<T: HttpBody>(
         connector: connector.clone(),
         transport_result: result,
         mapped_response,
+        upstream_body,
+        errors,
     }
 }
 
+/// Merges the mapped responses of the requests of a connector, with `additional_errors` reported
+/// next to their data.
 pub(crate) fn aggregate_responses(
     responses: Vec<MappedResponse>,
+    additional_errors: Vec<RuntimeError>,
 ) -> Result<Response, HandleResponseError> {
     let mut data = serde_json_bytes::Map::new();
     let mut errors = Vec::new();
@@ -169,6 +192,7 @@ pub(crate) fn aggregate_responses(
     for mapped in responses {
         mapped.add_to_data(&mut data, &mut errors, count)?;
     }
+    errors.extend(additional_errors);
 
     let data = if data.is_empty() {
         Value::Null
@@ -261,6 +285,8 @@ async fn deserialize_response<T: HttpBody>(
                     key: response_key.clone(),
                     problems: vec![],
                 },
+                upstream_body: None,
+                errors: Vec::new(),
             };
             if event.condition.evaluate_response(&response) {
                 Some(event.level)
@@ -334,58 +360,34 @@ async fn deserialize_response<T: HttpBody>(
         .get(CONTENT_TYPE)
         .and_then(|h| h.to_str().ok()?.parse::<Mime>().ok());
 
-    if content_type.is_none()
-        || content_type
-            .as_ref()
-            .is_some_and(|ct| ct.subtype() == mime::JSON || ct.suffix() == Some(mime::JSON))
-    {
-        // Treat any JSON-y like content types as JSON
-        // Also, because the HTTP spec says we should effectively "guess" the content type if there is no content type (None), we're going to guess it is JSON if the server has not specified one
-        match serde_json::from_slice::<Value>(body) {
-            Ok(json_data) => Ok(json_data),
-            Err(_) => {
-                if let Some(debug_context) = debug_context {
-                    debug_context.lock().push_invalid_response(
-                        debug_request.0.clone(),
-                        parts,
-                        body,
-                        &connector.error_settings,
-                        debug_request.1.clone(),
-                    );
-                }
-                Err(make_err())
-            }
-        }
-    } else if content_type
-        .as_ref()
-        .is_some_and(|ct| ct.type_() == mime::TEXT && ct.subtype() == mime::PLAIN)
-    {
-        // Plain text we can't parse as JSON so we'll instead return it as a JSON string
-        // Before we can do that, we need to figure out the charset and attempt to decode the string
-        let encoding = content_type
-            .as_ref()
-            .and_then(|ct| Encoding::for_label(ct.get_param("charset")?.as_str().as_bytes()))
-            .unwrap_or(UTF_8);
-        let (decoded_body, _, had_errors) = encoding.decode(body);
-
-        if had_errors {
+    // Merged pages are always JSON, whatever the format of each page
+    let response_format = if parts.extensions.get::<MergedPages>().is_some() {
+        None
+    } else {
+        connector.transport.response_format.as_deref()
+    };
+    // Because the HTTP spec says we should effectively "guess" the content type if there is no
+    // content type, we're going to guess it is JSON if the server has not specified one
+    let Some(decoder) = decoder_for_response(response_format, content_type.as_ref()) else {
+        // For any other content types, all we can do is treat it as a JSON null cause we don't know what it is
+        return Ok(Value::Null);
+    };
+
+    decoder
+        .decode(body, content_type.as_ref())
+        .map_err(|error| {
             if let Some(debug_context) = debug_context {
                 debug_context.lock().push_invalid_response(
                     debug_request.0.clone(),
                     parts,
                     body,
+                    &error,
                     &connector.error_settings,
                     debug_request.1.clone(),
                 );
             }
-            return Err(make_err());
-        }
-
-        Ok(Value::String(decoded_body.into_owned().into()))
-    } else {
-        // For any other content types, all we can do is treat it as a JSON null cause we don't know what it is
-        Ok(Value::Null)
-    }
+            make_err()
+        })
 }
 
 #[cfg(test)]
@@ -467,30 +469,35 @@ mod tests {
                 .unwrap(),
         );
 
-        let res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response2),
-                response_key2,
-                connector,
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response2),
+                    response_key2,
+                    connector,
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         assert_debug_snapshot!(res, @r###"
@@ -577,30 +584,35 @@ mod tests {
                 .unwrap(),
         );
 
-        let res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response2),
-                response_key2,
-                connector,
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response2),
+                    response_key2,
+                    connector,
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         assert_debug_snapshot!(res, @r###"
@@ -707,19 +719,23 @@ mod tests {
                 .unwrap(),
         );
 
-        let res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         assert_debug_snapshot!(res, @r#"
@@ -822,30 +838,35 @@ mod tests {
                 .unwrap(),
         );
 
-        let res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response2),
-                response_key2,
-                connector,
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response2),
+                    response_key2,
+                    connector,
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         assert_debug_snapshot!(res, @r###"
@@ -964,52 +985,59 @@ mod tests {
                 .unwrap(),
         );
 
-        let mut res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response_plaintext),
-                response_key_plaintext,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response2),
-                response_key2,
-                connector.clone(),
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request.clone(),
-            )
-            .await
-            .mapped_response,
-            process_response(
-                Ok(response3),
-                response_key3,
-                connector,
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let mut res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response_plaintext),
+                    response_key_plaintext,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response2),
+                    response_key2,
+                    connector.clone(),
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request.clone(),
+                    false,
+                )
+                .await
+                .mapped_response,
+                process_response(
+                    Ok(response3),
+                    response_key3,
+                    connector,
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         // Overwrite error IDs to avoid random Uuid mismatch.
@@ -1211,19 +1239,23 @@ mod tests {
                 .unwrap(),
         );
 
-        let res = super::aggregate_responses(vec![
-            process_response(
-                Ok(response1),
-                response_key1,
-                connector,
-                &Context::default(),
-                (None, Default::default()),
-                &None,
-                supergraph_request,
-            )
-            .await
-            .mapped_response,
-        ])
+        let res = super::aggregate_responses(
+            vec![
+                process_response(
+                    Ok(response1),
+                    response_key1,
+                    connector,
+                    &Context::default(),
+                    (None, Default::default()),
+                    &None,
+                    supergraph_request,
+                    false,
+                )
+                .await
+                .mapped_response,
+            ],
+            vec![],
+        )
         .unwrap();
 
         assert_debug_snapshot!(res, @r###"
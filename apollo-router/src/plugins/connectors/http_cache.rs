@@ -0,0 +1,539 @@
+//! HTTP caching of connector GET requests
+//!
+//! Responses are stored according to their `Cache-Control` header. Fresh responses are served
+//! from the cache, and stale responses with an `ETag` are revalidated with `If-None-Match`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use apollo_federation::connectors::Connector;
+use apollo_federation::connectors::runtime::errors::Error;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
+use http::header::CONTENT_LENGTH;
+use http::header::ETAG;
+use http::header::IF_NONE_MATCH;
+use http::header::VARY;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tower::BoxError;
+
+use super::configuration::SourceCacheConfiguration;
+use crate::cache::storage::CacheStorage;
+use crate::cache::storage::ValueType;
+use crate::plugins::cache::cache_control::CacheControl;
+use crate::services::router;
+use crate::services::router::body::RouterBody;
+
+const CACHE_KIND: &str = "connector.http";
+
+/// Whether a connector response was served from the HTTP cache of its source. Set in the
+/// response extensions of sources with caching enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CacheStatus {
+    /// The response was served from the cache without a request
+    Hit,
+    /// The response was not in the cache, or could not be used
+    Miss,
+    /// The cached response was used after the upstream confirmed it with a `304 Not Modified`
+    Revalidated,
+}
+
+impl CacheStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Miss => "miss",
+            CacheStatus::Revalidated => "revalidated",
+        }
+    }
+}
+
+/// The HTTP caches of the connector sources, keyed by `subgraph_name.source_name`
+pub(crate) struct HttpCaches(HashMap<String, HttpCache>);
+
+impl fmt::Debug for HttpCaches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HttpCaches")
+            .field(&self.0.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl HttpCaches {
+    pub(crate) async fn new(
+        sources: impl IntoIterator<Item = (String, SourceCacheConfiguration)>,
+    ) -> Result<Self, BoxError> {
+        let mut caches = HashMap::new();
+        for (source, config) in sources {
+            if config.enabled {
+                caches.insert(source, HttpCache::new(config).await?);
+            }
+        }
+        Ok(Self(caches))
+    }
+
+    pub(crate) fn get(&self, source: &str) -> Option<&HttpCache> {
+        self.0.get(source)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The HTTP cache of a connector source
+#[derive(Clone)]
+pub(crate) struct HttpCache {
+    storage: CacheStorage<String, CachedResponse>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    #[serde(with = "base64_body")]
+    body: Vec<u8>,
+    cache_control: CacheControl,
+    /// The values of the request headers listed in the response's `Vary` header
+    vary: Vec<(String, Option<String>)>,
+}
+
+impl ValueType for CachedResponse {
+    fn estimated_size(&self) -> Option<usize> {
+        Some(self.body.len())
+    }
+}
+
+impl HttpCache {
+    async fn new(config: SourceCacheConfiguration) -> Result<Self, BoxError> {
+        Ok(Self {
+            storage: CacheStorage::new(config.in_memory.limit, config.redis, CACHE_KIND).await?,
+        })
+    }
+
+    /// Sends a request with `send`, unless a fresh response is cached. Only `GET` requests are
+    /// cached.
+    pub(crate) async fn send<F, Fut>(
+        &self,
+        mut request: http::Request<String>,
+        connector: &Connector,
+        send: F,
+    ) -> Result<http::Response<RouterBody>, Error>
+    where
+        F: FnOnce(http::Request<String>) -> Fut,
+        Fut: Future<Output = Result<http::Response<RouterBody>, Error>>,
+    {
+        if request.method() != Method::GET {
+            return send(request).await;
+        }
+
+        let source = connector.source_config_key();
+        let key = cache_key(&request, &source);
+        let cached = self
+            .storage
+            .get(&key, |_| Ok(()))
+            .await
+            .filter(|cached| cached.matches(request.headers()));
+
+        if let Some(cached) = &cached {
+            if cached.cache_control.can_use() && !cached.cache_control.should_revalidate() {
+                return Ok(with_status(cached.to_response(), CacheStatus::Hit, &source));
+            }
+            if let Some(etag) = cached.etag() {
+                request.headers_mut().insert(IF_NONE_MATCH, etag);
+            }
+        }
+        let request_headers = request.headers().clone();
+
+        let response = send(request).await?;
+        match (response.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(mut cached)) => {
+                if let Ok(cache_control) = CacheControl::new(response.headers(), None) {
+                    if cache_control.should_store() {
+                        cached.cache_control = cache_control;
+                        self.storage.insert(key, cached.clone()).await;
+                    }
+                }
+                Ok(with_status(
+                    cached.to_response(),
+                    CacheStatus::Revalidated,
+                    &source,
+                ))
+            }
+            (StatusCode::OK, _) => {
+                let (parts, body) = response.into_parts();
+                let body = router::body::into_bytes(body)
+                    .await
+                    .map_err(|e| Error::TransportFailure(e.to_string()))?;
+
+                if let Some(cached) = CachedResponse::new(&parts, &body, &request_headers) {
+                    self.storage.insert(key, cached).await;
+                }
+                let response = http::Response::from_parts(parts, router::body::from_bytes(body));
+                Ok(with_status(response, CacheStatus::Miss, &source))
+            }
+            _ => Ok(with_status(response, CacheStatus::Miss, &source)),
+        }
+    }
+}
+
+impl CachedResponse {
+    /// Returns the response to store, or `None` if it must not be cached
+    fn new(
+        parts: &http::response::Parts,
+        body: &[u8],
+        request_headers: &HeaderMap,
+    ) -> Option<Self> {
+        let cache_control = CacheControl::new(&parts.headers, None).ok()?;
+        // This cache is shared by all clients of the router, so authenticated responses are only
+        // stored when they are explicitly public
+        if !cache_control.should_store()
+            || cache_control.private()
+            || (request_headers.contains_key(AUTHORIZATION) && !cache_control.public())
+        {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for value in parts.headers.get_all(VARY) {
+            for name in value.to_str().ok()?.split(',') {
+                let name = name.trim().to_ascii_lowercase();
+                if name == "*" {
+                    return None;
+                }
+                let value = request_headers
+                    .get(&name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                vary.push((name, value));
+            }
+        }
+
+        Some(Self {
+            status: parts.status.as_u16(),
+            headers: parts
+                .headers
+                .iter()
+                .filter(|(name, _)| **name != CONTENT_LENGTH)
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: body.to_vec(),
+            cache_control,
+            vary,
+        })
+    }
+
+    /// Whether the request headers listed in the `Vary` header of the stored response have the
+    /// same values as in `headers`
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary.iter().all(|(name, value)| {
+            headers.get(name).and_then(|value| value.to_str().ok()) == value.as_deref()
+        })
+    }
+
+    fn etag(&self) -> Option<HeaderValue> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name == ETAG.as_str())
+            .and_then(|(_, value)| HeaderValue::from_str(value).ok())
+    }
+
+    fn to_response(&self) -> http::Response<RouterBody> {
+        let mut response = http::Response::new(router::body::from_bytes(self.body.clone()));
+        *response.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                response.headers_mut().append(name, value);
+            }
+        }
+        response
+    }
+}
+
+/// Marks the response with its cache status, and counts it in the cache metric
+fn with_status(
+    mut response: http::Response<RouterBody>,
+    status: CacheStatus,
+    source: &str,
+) -> http::Response<RouterBody> {
+    u64_counter!(
+        "apollo.router.operations.connectors.http_cache",
+        "Number of connector GET requests to sources with an HTTP cache, by cache status",
+        1,
+        "connector.source" = source.to_string(),
+        "connector.http.cache" = status.as_str()
+    );
+    response.extensions_mut().insert(status);
+    response
+}
+
+/// The cache key covers the whole outbound request: its method, URL, headers and body. The
+/// headers include the ones set from the client request, `$context` or `$config`, or by
+/// coprocessors, any of which can change the response.
+fn cache_key(request: &http::Request<String>, source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(request.method().as_str().as_bytes());
+    hasher.update([0]);
+    hasher.update(request.uri().to_string().as_bytes());
+
+    let mut headers: Vec<_> = request.headers().iter().collect();
+    headers.sort_by(|(a, a_value), (b, b_value)| {
+        (a.as_str(), a_value.as_bytes()).cmp(&(b.as_str(), b_value.as_bytes()))
+    });
+    for (name, value) in headers {
+        hasher.update([0]);
+        hasher.update(name.as_str().as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+    }
+
+    hasher.update([0]);
+    hasher.update(request.body().as_bytes());
+
+    format!("{CACHE_KIND}:{source}:{}", hex::encode(hasher.finalize()))
+}
+
+mod base64_body {
+    use base64::Engine;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub(super) fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(body))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let body = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(body)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use apollo_compiler::name;
+    use apollo_federation::connectors::ConnectId;
+    use apollo_federation::connectors::ConnectSpec;
+    use apollo_federation::connectors::HttpJsonTransport;
+    use apollo_federation::connectors::JSONSelection;
+    use http::header::CACHE_CONTROL;
+
+    use super::*;
+    use crate::metrics::FutureMetricsExt;
+
+    fn connector() -> Connector {
+        Connector {
+            spec: ConnectSpec::V0_1,
+            id: ConnectId::new(
+                "subgraph_name".into(),
+                None,
+                name!(Query),
+                name!(a),
+                0,
+                "test label",
+            ),
+            transport: HttpJsonTransport {
+                source_template: "http://localhost/api".parse().ok(),
+                connect_template: "/path".parse().unwrap(),
+                ..Default::default()
+            },
+            selection: JSONSelection::parse("f").unwrap(),
+            entity_resolver: None,
+            config: Default::default(),
+            max_requests: None,
+            batch_settings: None,
+            request_headers: Default::default(),
+            response_headers: Default::default(),
+            request_variable_keys: Default::default(),
+            response_variable_keys: Default::default(),
+            error_settings: Default::default(),
+        }
+    }
+
+    fn cache() -> HttpCache {
+        HttpCache {
+            storage: CacheStorage::new_in_memory(NonZeroUsize::new(10).unwrap(), CACHE_KIND),
+        }
+    }
+
+    fn get(language: &str) -> http::Request<String> {
+        http::Request::builder()
+            .uri("http://localhost/api/path")
+            .header("accept-language", language)
+            .body(String::new())
+            .unwrap()
+    }
+
+    fn with_header(
+        mut request: http::Request<String>,
+        name: &'static str,
+        value: &'static str,
+    ) -> http::Request<String> {
+        request
+            .headers_mut()
+            .insert(name, HeaderValue::from_static(value));
+        request
+    }
+
+    async fn send(
+        cache: &HttpCache,
+        request: http::Request<String>,
+        calls: &AtomicUsize,
+        respond: impl FnOnce(&http::Request<String>) -> http::Response<RouterBody>,
+    ) -> (CacheStatus, String) {
+        let response = cache
+            .send(request, &connector(), |request| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let response = respond(&request);
+                async move { Ok(response) }
+            })
+            .await
+            .unwrap();
+        let status = *response.extensions().get::<CacheStatus>().unwrap();
+        let body = router::body::into_string(response.into_body())
+            .await
+            .unwrap();
+        (status, body)
+    }
+
+    fn ok(cache_control: &str, body: &'static str) -> http::Response<RouterBody> {
+        http::Response::builder()
+            .header(CACHE_CONTROL, cache_control)
+            .header(VARY, "Accept-Language")
+            .header(ETAG, "\"v1\"")
+            .body(router::body::from_bytes(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_responses_from_the_cache() {
+        let cache = cache();
+        let calls = AtomicUsize::new(0);
+
+        let (status, body) = send(&cache, get("en"), &calls, |_| ok("max-age=60", "first")).await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Miss, "first"));
+
+        let (status, body) = send(&cache, get("en"), &calls, |_| ok("max-age=60", "second")).await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Hit, "first"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A different value for a header listed in `Vary`
+        let (status, body) = send(&cache, get("fr"), &calls, |_| ok("max-age=60", "third")).await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Miss, "third"));
+
+        // A different value for a header that isn't listed in `Vary`
+        let (status, body) = send(
+            &cache,
+            with_header(get("fr"), "x-tenant", "b"),
+            &calls,
+            |_| ok("max-age=60", "fourth"),
+        )
+        .await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Miss, "fourth"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn revalidates_responses_with_etag() {
+        let cache = cache();
+        let calls = AtomicUsize::new(0);
+
+        let (status, _) = send(&cache, get("en"), &calls, |_| ok("no-cache", "first")).await;
+        assert_eq!(status, CacheStatus::Miss);
+
+        let (status, body) = send(&cache, get("en"), &calls, |request| {
+            assert_eq!(request.headers().get(IF_NONE_MATCH).unwrap(), "\"v1\"");
+            http::Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(CACHE_CONTROL, "no-cache")
+                .body(router::body::empty())
+                .unwrap()
+        })
+        .await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Revalidated, "first"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_store_private_or_authenticated_responses() {
+        let cache = cache();
+        let calls = AtomicUsize::new(0);
+
+        send(&cache, get("en"), &calls, |_| {
+            ok("private, max-age=60", "first")
+        })
+        .await;
+        let mut authenticated = get("en");
+        authenticated
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer token"));
+        send(&cache, authenticated, &calls, |_| {
+            ok("max-age=60", "second")
+        })
+        .await;
+
+        let (status, body) = send(&cache, get("en"), &calls, |_| ok("max-age=60", "third")).await;
+        assert_eq!((status, body.as_str()), (CacheStatus::Miss, "third"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn keys_on_every_outbound_header() {
+        async {
+            let cache = cache();
+            let calls = AtomicUsize::new(0);
+            // A header set from `$context`, which the response doesn't list in `Vary`
+            let user = |id| with_header(get("en"), "x-user-id", id);
+
+            let (status, body) =
+                send(&cache, user("1"), &calls, |_| ok("max-age=60", "user 1")).await;
+            assert_eq!((status, body.as_str()), (CacheStatus::Miss, "user 1"));
+
+            let (status, body) =
+                send(&cache, user("2"), &calls, |_| ok("max-age=60", "user 2")).await;
+            assert_eq!((status, body.as_str()), (CacheStatus::Miss, "user 2"));
+
+            let (status, body) =
+                send(&cache, user("1"), &calls, |_| ok("max-age=60", "other")).await;
+            assert_eq!((status, body.as_str()), (CacheStatus::Hit, "user 1"));
+            assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+            let source = connector().source_config_key();
+            assert_counter!(
+                "apollo.router.operations.connectors.http_cache",
+                2,
+                "connector.source" = source.clone(),
+                "connector.http.cache" = "miss"
+            );
+            assert_counter!(
+                "apollo.router.operations.connectors.http_cache",
+                1,
+                "connector.source" = source,
+                "connector.http.cache" = "hit"
+            );
+        }
+        .with_metrics()
+        .await;
+    }
+}
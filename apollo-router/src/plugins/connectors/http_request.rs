@@ -0,0 +1,14 @@
+//! Helpers for the HTTP requests sent by connectors.
+
+/// Copies a connector request so that it can be sent again, for example for the next page or
+/// after refreshing credentials. The extensions are kept, since they can carry parts of the
+/// request that aren't in its body.
+pub(crate) fn clone_request(request: &http::Request<String>) -> http::Request<String> {
+    let mut clone = http::Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    *clone.extensions_mut() = request.extensions().clone();
+    clone
+}
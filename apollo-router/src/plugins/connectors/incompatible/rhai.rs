@@ -34,7 +34,7 @@ impl IncompatiblePlugin for RhaiIncompatPlugin {
     ) {
         tracing::info!(
             subgraphs = subgraphs.into_iter().join(","),
-            message = "rhai scripts which hook into `subgraph_request` or `subgraph_response` won't be used by connector-enabled subgraphs",
+            message = "rhai scripts which hook into `subgraph_request` or `subgraph_response` won't be used by connector-enabled subgraphs; use `connector_service` to customize connector requests",
             see = "https://go.apollo.dev/connectors/incompat",
         );
     }
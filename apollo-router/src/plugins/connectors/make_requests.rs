@@ -82,6 +82,7 @@ fn request_params_to_requests(
             key: response_key,
             mapping_problems,
             supergraph_request: original_request.supergraph_request.clone(),
+            retain_upstream_body: false,
         });
     }
 
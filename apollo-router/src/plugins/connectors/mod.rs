@@ -1,7 +1,11 @@
 pub(crate) mod configuration;
+pub(crate) mod decoders;
 pub(crate) mod handle_responses;
+pub(crate) mod http_cache;
+pub(crate) mod http_request;
 pub(crate) mod incompatible;
 pub(crate) mod make_requests;
+pub(crate) mod pagination;
 pub(crate) mod plugin;
 pub(crate) mod query_plans;
 pub(crate) mod request_limit;
@@ -0,0 +1,574 @@
+//! Requests every page of a paginated connector endpoint and merges the pages into one response.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use apollo_federation::connectors::ConnectPaginationArguments;
+use apollo_federation::connectors::PaginationMode;
+use apollo_federation::connectors::runtime::errors::Error;
+use bytes::Bytes;
+use http::HeaderValue;
+use http::Uri;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use http::header::LINK;
+use mime::Mime;
+use serde_json_bytes::Value;
+use url::Url;
+
+use crate::plugins::connectors::decoders::decoder_for_response;
+use crate::plugins::connectors::http_request::clone_request;
+use crate::plugins::connectors::request_limit::RequestLimit;
+use crate::services::router;
+use crate::services::router::body::RouterBody;
+
+/// Marks a response whose body holds the merged items of every page as JSON, so that it is not
+/// decoded with the connector's `responseFormat`.
+#[derive(Clone, Debug)]
+pub(crate) struct MergedPages {
+    /// Why a page could not be requested. The response holds the items of the pages before it.
+    pub(crate) error: Option<Error>,
+}
+
+/// Requests the pages following `response` and returns a response holding the items of every
+/// page as a JSON array, with the status and headers of the first page.
+///
+/// A first page with an unsuccessful status or a body that cannot be decoded is returned as is,
+/// so that it is mapped like any other connector response. When a later page fails, the items
+/// of the pages before it are returned with the error in [`MergedPages`]. Every page after the
+/// first counts against the request limit.
+pub(crate) async fn paginate<F, Fut>(
+    pagination: &ConnectPaginationArguments,
+    response_format: Option<&str>,
+    request: &http::Request<String>,
+    response: http::Response<RouterBody>,
+    request_limit: Option<Arc<RequestLimit>>,
+    send: F,
+) -> Result<http::Response<RouterBody>, Error>
+where
+    F: Fn(http::Request<String>) -> Fut,
+    Fut: Future<Output = Result<http::Response<RouterBody>, Error>>,
+{
+    let (first_parts, body) = response.into_parts();
+    let body = into_bytes(body).await?;
+    let Some(first_page) = decode_page(&first_parts, &body, response_format) else {
+        return Ok(http::Response::from_parts(
+            first_parts,
+            router::body::from_bytes(body),
+        ));
+    };
+
+    let mut items = Vec::new();
+    let mut request = clone_request(request);
+    let mut page = first_page;
+    let mut parts = first_parts.clone();
+    let mut cursor = None;
+    let mut page_number = 1;
+
+    let error = loop {
+        let page_items = page_items(pagination, &page);
+        let page_len = page_items.len();
+        items.extend(page_items);
+
+        let Some(next_request) =
+            next_page_request(pagination, &request, &parts, &page, page_len, &mut cursor)
+        else {
+            break None;
+        };
+        if page_number >= pagination.max_pages {
+            tracing::warn!(
+                "stopped requesting pages of {} after reaching `maxPages` ({}), the response is missing the remaining pages",
+                request.uri(),
+                pagination.max_pages
+            );
+            break None;
+        }
+
+        if request_limit
+            .as_ref()
+            .is_some_and(|request_limit| !request_limit.allow())
+        {
+            break Some(Error::RequestLimitExceeded);
+        }
+
+        page_number += 1;
+        request = clone_request(&next_request);
+        let (next_parts, body) = match send(next_request).await {
+            Ok(response) => response.into_parts(),
+            Err(error) => break Some(error),
+        };
+        let body = match into_bytes(body).await {
+            Ok(body) => body,
+            Err(error) => break Some(error),
+        };
+        let Some(next_page) = decode_page(&next_parts, &body, response_format) else {
+            break Some(Error::TransportFailure(if next_parts.status.is_success() {
+                format!("page {page_number} of the response could not be decoded")
+            } else {
+                format!(
+                    "page {page_number} of the response failed with status {}",
+                    next_parts.status
+                )
+            }));
+        };
+        page = next_page;
+        parts = next_parts;
+    };
+
+    let body = serde_json::to_vec(&Value::Array(items))
+        .map_err(|e| Error::TransportFailure(e.to_string()))?;
+    let mut response = http::Response::from_parts(first_parts, router::body::from_bytes(body));
+    response.headers_mut().remove(CONTENT_LENGTH);
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+    );
+    response.extensions_mut().insert(MergedPages { error });
+    Ok(response)
+}
+
+async fn into_bytes(body: RouterBody) -> Result<Bytes, Error> {
+    router::body::into_bytes(body)
+        .await
+        .map_err(|e| Error::TransportFailure(e.to_string()))
+}
+
+/// Decodes a successful page, returning `None` if it should be returned as is.
+fn decode_page(
+    parts: &http::response::Parts,
+    body: &[u8],
+    response_format: Option<&str>,
+) -> Option<Value> {
+    if !parts.status.is_success() {
+        return None;
+    }
+    let content_type = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok()?.parse::<Mime>().ok());
+    decoder_for_response(response_format, content_type.as_ref())?
+        .decode(body, content_type.as_ref())
+        .ok()
+}
+
+/// Selects the items of a page. Arrays are flattened and `null` holds no items.
+fn page_items(pagination: &ConnectPaginationArguments, page: &Value) -> Vec<Value> {
+    let items = match &pagination.items {
+        Some(selection) => selection.apply_to(page).0.unwrap_or(Value::Null),
+        None => page.clone(),
+    };
+    match items {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        item => vec![item],
+    }
+}
+
+/// Builds the request for the page after `page`, or returns `None` if it was the last page.
+fn next_page_request(
+    pagination: &ConnectPaginationArguments,
+    request: &http::Request<String>,
+    parts: &http::response::Parts,
+    page: &Value,
+    page_len: usize,
+    cursor: &mut Option<String>,
+) -> Option<http::Request<String>> {
+    let uri = match &pagination.mode {
+        PaginationMode::Cursor {
+            cursor: selection,
+            param,
+        } => {
+            let next_cursor = match selection.apply_to(page).0? {
+                Value::String(s) => s.as_str().to_string(),
+                Value::Number(n) => n.to_string(),
+                _ => return None,
+            };
+            // Stop rather than requesting the same page again
+            if next_cursor.is_empty() || cursor.as_ref() == Some(&next_cursor) {
+                return None;
+            }
+            let uri = with_query_param(request.uri(), param, &next_cursor)?;
+            *cursor = Some(next_cursor);
+            uri
+        }
+        PaginationMode::Offset { param, page_size } => {
+            if page_len < *page_size {
+                return None;
+            }
+            let offset = Url::parse(&request.uri().to_string())
+                .ok()?
+                .query_pairs()
+                .find(|(name, _)| name == param.as_str())
+                .and_then(|(_, value)| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            with_query_param(request.uri(), param, &(offset + page_size).to_string())?
+        }
+        PaginationMode::Link => {
+            let next = parts
+                .headers
+                .get_all(LINK)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .find_map(next_link)?;
+            let current = Url::parse(&request.uri().to_string()).ok()?;
+            let next = current.join(next).ok()?;
+            // The request is sent with the same headers, which can include credentials, so it
+            // must not go anywhere else
+            if next.scheme() != current.scheme()
+                || next.host_str() != current.host_str()
+                || next.port_or_known_default() != current.port_or_known_default()
+            {
+                tracing::warn!(
+                    "not following the next page link to {}, it is on a different origin than {}",
+                    next.origin().ascii_serialization(),
+                    current.origin().ascii_serialization()
+                );
+                return None;
+            }
+            next.as_str().parse().ok()?
+        }
+    };
+
+    let mut next_request = clone_request(request);
+    *next_request.uri_mut() = uri;
+    Some(next_request)
+}
+
+/// Sets a query parameter, replacing any existing values.
+fn with_query_param(uri: &Uri, name: &str, value: &str) -> Option<Uri> {
+    let mut url = Url::parse(&uri.to_string()).ok()?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != name)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(name, value);
+    url.as_str().parse().ok()
+}
+
+/// Finds the target of the `rel="next"` link in a `Link` header value.
+fn next_link(header: &str) -> Option<&str> {
+    let mut rest = header;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let target = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        let params = &rest[..rest.find('<').unwrap_or(rest.len())];
+        let is_next = params.split(';').any(|param| {
+            let Some((name, value)) = param.split_once('=') else {
+                return false;
+            };
+            name.trim().eq_ignore_ascii_case("rel")
+                && value
+                    .trim()
+                    .trim_matches('"')
+                    .split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next"))
+        });
+        if is_next {
+            return Some(target);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use apollo_federation::connectors::JSONSelection;
+    use serde_json_bytes::json;
+
+    use super::*;
+
+    fn pagination(mode: PaginationMode, items: Option<&str>) -> ConnectPaginationArguments {
+        ConnectPaginationArguments {
+            mode,
+            items: items.map(|items| JSONSelection::parse(items).unwrap()),
+            max_pages: 10,
+        }
+    }
+
+    fn json_response(status: u16, body: Value, link: Option<&str>) -> http::Response<RouterBody> {
+        let mut response = http::Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(link) = link {
+            response = response.header(LINK, link);
+        }
+        response
+            .body(router::body::from_bytes(serde_json::to_vec(&body).unwrap()))
+            .unwrap()
+    }
+
+    #[derive(Clone)]
+    struct Marker;
+
+    fn request(uri: &str) -> http::Request<String> {
+        http::Request::builder()
+            .uri(uri)
+            .header("x-custom", "value")
+            .extension(Marker)
+            .body(String::new())
+            .unwrap()
+    }
+
+    async fn body(response: http::Response<RouterBody>) -> Value {
+        let bytes = router::body::into_bytes(response.into_body())
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn cursor_pagination() {
+        let pagination = pagination(
+            PaginationMode::Cursor {
+                cursor: JSONSelection::parse("$.next").unwrap(),
+                param: "after".to_string(),
+            },
+            Some("$.results"),
+        );
+        let requests = parking_lot::Mutex::new(Vec::new());
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items?limit=2&after=x"),
+            json_response(200, json!({ "results": [1, 2], "next": "a" }), None),
+            None,
+            |request| {
+                assert_eq!(request.headers()["x-custom"], "value");
+                assert!(request.extensions().get::<Marker>().is_some());
+                let uri = request.uri().to_string();
+                requests.lock().push(uri.clone());
+                async move {
+                    Ok(if uri.ends_with("after=a") {
+                        json_response(200, json!({ "results": [3, 4], "next": "b" }), None)
+                    } else {
+                        json_response(200, json!({ "results": [5], "next": null }), None)
+                    })
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            response.extensions().get::<MergedPages>(),
+            Some(MergedPages { error: None })
+        ));
+        assert_eq!(body(response).await, json!([1, 2, 3, 4, 5]));
+        assert_eq!(
+            *requests.lock(),
+            vec![
+                "http://localhost/items?limit=2&after=a",
+                "http://localhost/items?limit=2&after=b"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn offset_pagination_stops_at_short_page() {
+        let pagination = pagination(
+            PaginationMode::Offset {
+                param: "offset".to_string(),
+                page_size: 2,
+            },
+            None,
+        );
+        let requests = AtomicUsize::new(0);
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items?offset=10"),
+            json_response(200, json!([1, 2]), None),
+            None,
+            |request| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                let uri = request.uri().to_string();
+                async move {
+                    Ok(if uri.ends_with("offset=12") {
+                        json_response(200, json!([3, 4]), None)
+                    } else {
+                        assert!(uri.ends_with("offset=14"));
+                        json_response(200, json!([5]), None)
+                    })
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(body(response).await, json!([1, 2, 3, 4, 5]));
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn link_pagination_respects_max_pages() {
+        let mut pagination = pagination(PaginationMode::Link, Some("$.data"));
+        pagination.max_pages = 2;
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items"),
+            json_response(
+                200,
+                json!({ "data": [1] }),
+                Some(r#"<http://localhost/items?page=1>; rel="prev first", </items?page=2>; rel="next""#),
+            ),
+            None,
+            |request| {
+                assert_eq!(request.uri(), "http://localhost/items?page=2");
+                async {
+                    Ok(json_response(
+                        200,
+                        json!({ "data": [2] }),
+                        Some(r#"</items?page=3>; rel="next""#),
+                    ))
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(body(response).await, json!([1, 2]));
+    }
+
+    #[tokio::test]
+    async fn link_pagination_stays_on_the_same_origin() {
+        let pagination = pagination(PaginationMode::Link, None);
+        let requests = AtomicUsize::new(0);
+
+        for link in [
+            r#"<https://localhost/items?page=2>; rel="next""#,
+            r#"<http://localhost:8080/items?page=2>; rel="next""#,
+            r#"<http://attacker.example/items?page=2>; rel="next""#,
+            r#"<//attacker.example/items?page=2>; rel="next""#,
+        ] {
+            let response = paginate(
+                &pagination,
+                None,
+                &request("http://localhost/items"),
+                json_response(200, json!([1]), Some(link)),
+                None,
+                |_| {
+                    requests.fetch_add(1, Ordering::Relaxed);
+                    async { Ok(json_response(200, json!([2]), None)) }
+                },
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(body(response).await, json!([1]));
+        }
+        assert_eq!(requests.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn returns_fetched_pages_with_the_error_of_a_failed_page() {
+        let pagination = pagination(PaginationMode::Link, None);
+        let link = Some(r#"<http://localhost/items?page=2>; rel=next"#);
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items"),
+            json_response(200, json!([1]), link),
+            None,
+            |request| async move {
+                Ok(if request.uri() == "http://localhost/items?page=2" {
+                    json_response(
+                        200,
+                        json!([2]),
+                        Some(r#"<http://localhost/items?page=3>; rel=next"#),
+                    )
+                } else {
+                    json_response(500, json!({ "error": "oops" }), None)
+                })
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(matches!(
+            response.extensions().get::<MergedPages>(),
+            Some(MergedPages { error: Some(Error::TransportFailure(message)) })
+                if message == "page 3 of the response failed with status 500 Internal Server Error"
+        ));
+        assert_eq!(body(response).await, json!([1, 2]));
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items"),
+            json_response(200, json!([1]), link),
+            None,
+            |_| async { Err(Error::GatewayTimeout) },
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            response.extensions().get::<MergedPages>(),
+            Some(MergedPages {
+                error: Some(Error::GatewayTimeout)
+            })
+        ));
+        assert_eq!(body(response).await, json!([1]));
+    }
+
+    #[tokio::test]
+    async fn returns_a_failed_first_page_as_is() {
+        let pagination = pagination(PaginationMode::Link, None);
+
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items"),
+            json_response(
+                500,
+                json!({ "error": "oops" }),
+                Some(r#"<http://localhost/items?page=2>; rel=next"#),
+            ),
+            None,
+            |_| async { Ok(json_response(200, json!([2]), None)) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 500);
+        assert!(response.extensions().get::<MergedPages>().is_none());
+        assert_eq!(body(response).await, json!({ "error": "oops" }));
+    }
+
+    #[tokio::test]
+    async fn enforces_request_limits() {
+        let pagination = pagination(PaginationMode::Link, None);
+        let link = Some(r#"<http://localhost/items?page=2>; rel=next"#);
+
+        let request_limit = Arc::new(RequestLimit::new(1));
+        assert!(request_limit.allow());
+        let response = paginate(
+            &pagination,
+            None,
+            &request("http://localhost/items"),
+            json_response(200, json!([1]), link),
+            Some(request_limit),
+            |_| async { Ok(json_response(200, json!([2]), None)) },
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            response.extensions().get::<MergedPages>(),
+            Some(MergedPages {
+                error: Some(Error::RequestLimitExceeded)
+            })
+        ));
+        assert_eq!(body(response).await, json!([1]));
+    }
+}
@@ -17,6 +17,7 @@ use crate::layers::ServiceExt;
 use crate::plugin::Plugin;
 use crate::plugin::PluginInit;
 use crate::plugins::connectors::configuration::ConnectorsConfig;
+use crate::plugins::connectors::http_cache::HttpCaches;
 use crate::plugins::connectors::request_limit::RequestLimits;
 use crate::register_plugin;
 use crate::services::connector_service::ConnectorSourceRef;
@@ -36,6 +37,7 @@ struct Connectors {
     debug_extensions: bool,
     max_requests: Option<usize>,
     expose_sources_in_context: bool,
+    http_caches: Option<Arc<HttpCaches>>,
 }
 
 #[async_trait::async_trait]
@@ -67,16 +69,41 @@ impl Plugin for Connectors {
                 .ok()
                 .and_then(|v| v.parse().ok()));
 
+        // TODO: remove the deprecated `subgraphs` sources after deprecation period
+        #[allow(deprecated)]
+        let deprecated_sources =
+            init.config
+                .subgraphs
+                .iter()
+                .flat_map(|(subgraph_name, subgraph)| {
+                    subgraph.sources.iter().map(move |(source_name, source)| {
+                        (
+                            format!("{subgraph_name}.{source_name}"),
+                            source.cache.clone(),
+                        )
+                    })
+                });
+        let http_caches = HttpCaches::new(
+            init.config
+                .sources
+                .iter()
+                .map(|(source, config)| (source.clone(), config.cache.clone()))
+                .chain(deprecated_sources),
+        )
+        .await?;
+
         Ok(Connectors {
             debug_extensions,
             max_requests,
             expose_sources_in_context: init.config.expose_sources_in_context,
+            http_caches: (!http_caches.is_empty()).then(|| Arc::new(http_caches)),
         })
     }
 
     fn supergraph_service(&self, service: supergraph::BoxService) -> supergraph::BoxService {
         let conf_enabled = self.debug_extensions;
         let max_requests = self.max_requests;
+        let http_caches = self.http_caches.clone();
         service
             .map_future_with_request_data(
                 move |req: &supergraph::Request| {
@@ -91,6 +118,9 @@ impl Plugin for Connectors {
                         lock.insert::<Arc<RequestLimits>>(Arc::new(RequestLimits::new(
                             max_requests,
                         )));
+                        if let Some(http_caches) = &http_caches {
+                            lock.insert::<Arc<HttpCaches>>(http_caches.clone());
+                        }
                         if is_debug_enabled {
                             lock.insert::<Arc<Mutex<ConnectorContext>>>(Arc::new(Mutex::new(
                                 ConnectorContext::default(),
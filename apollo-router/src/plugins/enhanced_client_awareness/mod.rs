@@ -10,8 +10,8 @@ use crate::plugins::telemetry::CLIENT_LIBRARY_NAME;
 use crate::plugins::telemetry::CLIENT_LIBRARY_VERSION;
 use crate::services::supergraph;
 
-const CLIENT_LIBRARY_KEY: &str = "clientLibrary";
-const CLIENT_LIBRARY_NAME_KEY: &str = "name";
+pub(crate) const CLIENT_LIBRARY_KEY: &str = "clientLibrary";
+pub(crate) const CLIENT_LIBRARY_NAME_KEY: &str = "name";
 const CLIENT_LIBRARY_VERSION_KEY: &str = "version";
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -0,0 +1,860 @@
+//! Fault injection for chaos testing.
+//!
+//! Injects latency, HTTP errors, timeouts, connection resets and GraphQL errors into subgraph and
+//! connector calls, to check how the router and its clients behave when the services behind it
+//! misbehave. Faults are injected inside traffic shaping, so its timeouts apply to them.
+//! You probably don’t want this in production!
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use apollo_federation::connectors::runtime::errors::Error;
+use apollo_federation::connectors::runtime::errors::RuntimeError;
+use apollo_federation::connectors::runtime::http_json_transport::HttpResponse;
+use apollo_federation::connectors::runtime::http_json_transport::TransportResponse;
+use apollo_federation::connectors::runtime::responses::MappedResponse;
+use http::StatusCode;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json_bytes::json;
+use tower::BoxError;
+use tower::ServiceBuilder;
+use tower::ServiceExt;
+use tower::timeout::error::Elapsed;
+
+use crate::error::FetchError;
+use crate::graphql;
+use crate::layers::ServiceBuilderExt;
+use crate::plugin::PluginInit;
+use crate::plugin::PluginPrivate;
+use crate::services::connector;
+use crate::services::subgraph;
+
+register_private_plugin!("apollo", "experimental_fault_injection", FaultInjection);
+
+const CONNECTION_RESET: &str = "connection reset by peer";
+
+/// Fault injection into subgraph and connector calls, for chaos testing.
+/// You probably don’t want this in production!
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct FaultInjectionConfig {
+    /// Faults are only injected when true. Defaults to false.
+    enabled: bool,
+    /// Rules checked in order for each subgraph and connector call. The first matching rule
+    /// that fires injects its fault.
+    rules: Vec<FaultRule>,
+}
+
+/// A fault injected into matching calls
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct FaultRule {
+    /// Name of the rule, reported in telemetry when it fires
+    name: String,
+    /// Subgraphs the rule applies to. Without `subgraphs` and `connector_sources`, the rule
+    /// applies to every subgraph and connector call.
+    #[serde(default)]
+    subgraphs: Option<Vec<String>>,
+    /// Connector sources the rule applies to, as `subgraph_name.source_name`
+    #[serde(default)]
+    connector_sources: Option<Vec<String>>,
+    /// Names of the client operations the rule applies to. Applies to every operation when
+    /// empty.
+    #[serde(default)]
+    operations: Vec<String>,
+    /// A header the client request must have for the rule to apply
+    #[serde(default)]
+    header: Option<HeaderMatch>,
+    /// Probability, between 0 and 1, that the rule fires for a matching call. Defaults to 1.
+    #[serde(default = "default_probability")]
+    probability: f64,
+    /// The fault injected when the rule fires
+    fault: Fault,
+}
+
+fn default_probability() -> f64 {
+    1.0
+}
+
+/// A header of the client request
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct HeaderMatch {
+    /// Name of the header
+    name: String,
+    /// Value the header must have. Any value matches when not set.
+    #[serde(default)]
+    value: Option<String>,
+}
+
+/// A fault injected into a subgraph or connector call
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+enum Fault {
+    /// Delays the call by this duration
+    Latency(
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
+    /// Answers with this HTTP error status code, without calling the service
+    HttpError { status_code: u16 },
+    /// Holds the call for this duration, then fails it like a timeout. A shorter traffic shaping
+    /// timeout ends the call first.
+    Timeout(
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
+    /// Fails the call like a connection reset by the service, without calling it
+    ConnectionReset,
+    /// Adds a GraphQL error to the response of a subgraph or connector, keeping its data
+    GraphqlError {
+        /// Message of the error
+        message: String,
+        /// Code of the error, in its `extensions`
+        #[serde(default)]
+        code: Option<String>,
+    },
+}
+
+impl Fault {
+    fn kind(&self) -> &'static str {
+        match self {
+            Fault::Latency(_) => "latency",
+            Fault::HttpError { .. } => "http_error",
+            Fault::Timeout(_) => "timeout",
+            Fault::ConnectionReset => "connection_reset",
+            Fault::GraphqlError { .. } => "graphql_error",
+        }
+    }
+}
+
+impl FaultRule {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(format!(
+                "probability must be between 0 and 1, got {}",
+                self.probability
+            ));
+        }
+        if let Fault::HttpError { status_code } = self.fault {
+            let valid = StatusCode::from_u16(status_code)
+                .is_ok_and(|status| status.is_client_error() || status.is_server_error());
+            if !valid {
+                return Err(format!(
+                    "status code {status_code} is not an HTTP error status code"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn applies_to_subgraph(&self, name: &str) -> bool {
+        match (&self.subgraphs, &self.connector_sources) {
+            (None, None) => true,
+            (subgraphs, _) => subgraphs.iter().flatten().any(|subgraph| subgraph == name),
+        }
+    }
+
+    fn applies_to_connector_source(&self, source_name: &str) -> bool {
+        match (&self.subgraphs, &self.connector_sources) {
+            (None, None) => true,
+            (_, sources) => sources.iter().flatten().any(|source| source == source_name),
+        }
+    }
+
+    fn matches(&self, request: &http::Request<graphql::Request>) -> bool {
+        if !self.operations.is_empty() {
+            let Some(operation_name) = request.body().operation_name.as_deref() else {
+                return false;
+            };
+            if !self
+                .operations
+                .iter()
+                .any(|operation| operation == operation_name)
+            {
+                return false;
+            }
+        }
+        if let Some(header) = &self.header {
+            let Some(value) = request.headers().get(header.name.as_str()) else {
+                return false;
+            };
+            if header
+                .value
+                .as_ref()
+                .is_some_and(|expected| value.as_bytes() != expected.as_bytes())
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The first matching rule that fires for this call, if any
+fn firing_rule(
+    rules: &[Arc<FaultRule>],
+    request: &http::Request<graphql::Request>,
+    rng: &mut SmallRng,
+) -> Option<Arc<FaultRule>> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(request) && rng.random_bool(rule.probability))
+        .cloned()
+}
+
+struct FaultInjection {
+    /// Empty unless fault injection is enabled
+    rules: Vec<Arc<FaultRule>>,
+}
+
+#[async_trait::async_trait]
+impl PluginPrivate for FaultInjection {
+    type Config = FaultInjectionConfig;
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        let config = init.config;
+        for rule in &config.rules {
+            rule.validate()
+                .map_err(|e| format!("invalid fault injection rule {}: {e}", rule.name))?;
+        }
+        if !config.enabled {
+            return Ok(Self { rules: Vec::new() });
+        }
+        if !config.rules.is_empty() {
+            tracing::warn!(
+                "fault injection is enabled with {} rules: subgraph and connector calls will fail on purpose",
+                config.rules.len()
+            );
+        }
+        Ok(Self {
+            rules: config.rules.into_iter().map(Arc::new).collect(),
+        })
+    }
+
+    fn subgraph_service(&self, name: &str, service: subgraph::BoxService) -> subgraph::BoxService {
+        let rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.applies_to_subgraph(name))
+            .cloned()
+            .collect();
+        if rules.is_empty() {
+            return service;
+        }
+
+        let service = ServiceBuilder::new().buffered().service(service);
+        let mut rng = SmallRng::from_rng(&mut rand::rng());
+        tower::service_fn(move |request: subgraph::Request| {
+            let rule = firing_rule(&rules, &request.supergraph_request, &mut rng);
+            let service = service.clone();
+            async move {
+                let Some(rule) = rule else {
+                    return service.oneshot(request).await;
+                };
+                u64_counter_with_unit!(
+                    "apollo.router.fault_injection.faults",
+                    "Number of faults injected into subgraph and connector calls",
+                    "{fault}",
+                    1,
+                    "fault.rule" = rule.name.clone(),
+                    "fault.kind" = rule.fault.kind(),
+                    "subgraph.name" = request.subgraph_name.clone()
+                );
+                tracing::info!(
+                    rule = %rule.name,
+                    fault = rule.fault.kind(),
+                    subgraph = %request.subgraph_name,
+                    "injecting fault into subgraph call"
+                );
+
+                match &rule.fault {
+                    Fault::Latency(duration) => {
+                        tokio::time::sleep(*duration).await;
+                        service.oneshot(request).await
+                    }
+                    Fault::HttpError { status_code } => {
+                        let status = StatusCode::from_u16(*status_code)?;
+                        // Like the subgraph service does for responses that are not 2xx
+                        let error = FetchError::SubrequestHttpError {
+                            service: request.subgraph_name.clone(),
+                            status_code: Some(status.as_u16()),
+                            reason: format!(
+                                "{}: {}",
+                                status.as_str(),
+                                status.canonical_reason().unwrap_or("Unknown")
+                            ),
+                        }
+                        .to_graphql_error(None);
+                        Ok(subgraph::Response::error_builder()
+                            .error(error)
+                            .status_code(status)
+                            .context(request.context)
+                            .subgraph_name(request.subgraph_name)
+                            .id(request.id)
+                            .build())
+                    }
+                    Fault::Timeout(duration) => {
+                        tokio::time::sleep(*duration).await;
+                        Err(Box::new(Elapsed::new()) as BoxError)
+                    }
+                    Fault::ConnectionReset => Err(Box::new(FetchError::SubrequestHttpError {
+                        service: request.subgraph_name,
+                        status_code: None,
+                        reason: CONNECTION_RESET.to_string(),
+                    }) as BoxError),
+                    Fault::GraphqlError { message, code } => {
+                        let mut response = service.oneshot(request).await?;
+                        response.response.body_mut().errors.push(
+                            graphql::Error::builder()
+                                .message(message.clone())
+                                .and_extension_code(code.clone())
+                                .build(),
+                        );
+                        Ok(response)
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    fn connector_request_service(
+        &self,
+        service: connector::request_service::BoxService,
+        source_name: String,
+    ) -> connector::request_service::BoxService {
+        let rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.applies_to_connector_source(&source_name))
+            .cloned()
+            .collect();
+        if rules.is_empty() {
+            return service;
+        }
+
+        let service = ServiceBuilder::new().buffered().service(service);
+        let mut rng = SmallRng::from_rng(&mut rand::rng());
+        tower::service_fn(move |request: connector::request_service::Request| {
+            let rule = firing_rule(&rules, &request.supergraph_request, &mut rng);
+            let service = service.clone();
+            let source_name = source_name.clone();
+            async move {
+                let Some(rule) = rule else {
+                    return service.oneshot(request).await;
+                };
+                u64_counter_with_unit!(
+                    "apollo.router.fault_injection.faults",
+                    "Number of faults injected into subgraph and connector calls",
+                    "{fault}",
+                    1,
+                    "fault.rule" = rule.name.clone(),
+                    "fault.kind" = rule.fault.kind(),
+                    "connector.source" = source_name.clone()
+                );
+                tracing::info!(
+                    rule = %rule.name,
+                    fault = rule.fault.kind(),
+                    connector_source = %source_name,
+                    "injecting fault into connector call"
+                );
+
+                match &rule.fault {
+                    Fault::Latency(duration) => {
+                        tokio::time::sleep(*duration).await;
+                        service.oneshot(request).await
+                    }
+                    Fault::HttpError { status_code } => {
+                        let status = StatusCode::from_u16(*status_code)?;
+                        let (parts, ()) = http::Response::builder()
+                            .status(status)
+                            .body(())?
+                            .into_parts();
+                        // Like the connector runtime does for responses that are not 2xx,
+                        // without error mappings
+                        let mut error = RuntimeError::new("Request failed", &request.key)
+                            .with_code("CONNECTOR_FETCH")
+                            .extension("http", json!({ "status": status.as_u16() }));
+                        error.subgraph_name = Some(request.connector.id.subgraph_name.clone());
+                        error.coordinate = Some(request.connector.id.coordinate());
+                        Ok(connector::request_service::Response {
+                            context: request.context,
+                            connector: request.connector,
+                            transport_result: Ok(TransportResponse::Http(HttpResponse {
+                                inner: parts,
+                            })),
+                            mapped_response: MappedResponse::Error {
+                                error,
+                                key: request.key,
+                            },
+                            upstream_body: None,
+                            errors: Vec::new(),
+                        })
+                    }
+                    Fault::Timeout(duration) => {
+                        tokio::time::sleep(*duration).await;
+                        Err(Box::new(Elapsed::new()) as BoxError)
+                    }
+                    Fault::ConnectionReset => {
+                        let error = Error::TransportFailure(CONNECTION_RESET.to_string());
+                        Ok(connector::request_service::Response::error_builder()
+                            .context(request.context)
+                            .connector(request.connector)
+                            .message(error.to_string())
+                            .error(error)
+                            .response_key(request.key)
+                            .build())
+                    }
+                    Fault::GraphqlError { message, code } => {
+                        let mut error = RuntimeError::new(message.clone(), &request.key);
+                        if let Some(code) = code {
+                            error = error.with_code(code.clone());
+                        }
+                        error.subgraph_name = Some(request.connector.id.subgraph_name.clone());
+                        error.coordinate = Some(request.connector.id.coordinate());
+                        let mut response = service.oneshot(request).await?;
+                        response.errors.push(error);
+                        Ok(response)
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::name;
+    use apollo_federation::connectors::ConnectId;
+    use apollo_federation::connectors::ConnectSpec;
+    use apollo_federation::connectors::Connector;
+    use apollo_federation::connectors::HttpJsonTransport;
+    use apollo_federation::connectors::JSONSelection;
+    use apollo_federation::connectors::runtime::http_json_transport::HttpRequest;
+    use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
+    use apollo_federation::connectors::runtime::key::ResponseKey;
+
+    use super::*;
+    use crate::Context;
+    use crate::plugins::test::PluginTestHarness;
+
+    async fn harness(config: &str) -> PluginTestHarness<FaultInjection> {
+        PluginTestHarness::<FaultInjection>::builder()
+            .config(config)
+            .build()
+            .await
+            .expect("test harness")
+    }
+
+    fn request(operation_name: &str, header: Option<(&str, &str)>) -> subgraph::Request {
+        let mut supergraph_request = http::Request::builder();
+        if let Some((name, value)) = header {
+            supergraph_request = supergraph_request.header(name, value);
+        }
+        subgraph::Request::fake_builder()
+            .subgraph_name("accounts")
+            .supergraph_request(Arc::new(
+                supergraph_request
+                    .body(
+                        graphql::Request::fake_builder()
+                            .query("query Me { me { id } }")
+                            .operation_name(operation_name)
+                            .build(),
+                    )
+                    .unwrap(),
+            ))
+            .build()
+    }
+
+    fn subgraph_response(request: subgraph::Request) -> subgraph::Response {
+        subgraph::Response::fake_builder()
+            .data(json!({ "me": { "id": "1" } }))
+            .context(request.context)
+            .subgraph_name(request.subgraph_name)
+            .build()
+    }
+
+    fn connector_request() -> connector::request_service::Request {
+        let connector = Connector {
+            spec: ConnectSpec::V0_1,
+            id: ConnectId::new(
+                "products".into(),
+                None,
+                name!(Query),
+                name!(product),
+                0,
+                "test label",
+            ),
+            transport: HttpJsonTransport {
+                source_template: "http://localhost/api".parse().ok(),
+                connect_template: "/product".parse().unwrap(),
+                ..Default::default()
+            },
+            selection: JSONSelection::parse("id").unwrap(),
+            entity_resolver: None,
+            config: Default::default(),
+            max_requests: None,
+            batch_settings: None,
+            request_headers: Default::default(),
+            response_headers: Default::default(),
+            request_variable_keys: Default::default(),
+            response_variable_keys: Default::default(),
+            error_settings: Default::default(),
+        };
+        connector::request_service::Request {
+            context: Context::new(),
+            connector: Arc::new(connector),
+            service_name: "products".to_string(),
+            transport_request: TransportRequest::Http(HttpRequest {
+                inner: http::Request::get("http://localhost/api/product")
+                    .body(String::new())
+                    .unwrap(),
+                debug: Default::default(),
+            }),
+            key: ResponseKey::RootField {
+                name: "product".to_string(),
+                inputs: Default::default(),
+                selection: Arc::new(JSONSelection::parse("id").unwrap()),
+            },
+            mapping_problems: vec![],
+            supergraph_request: Default::default(),
+            retain_upstream_body: false,
+        }
+    }
+
+    fn connector_response(
+        request: connector::request_service::Request,
+    ) -> connector::request_service::Response {
+        connector::request_service::Response::test_builder()
+            .context(request.context)
+            .connector(request.connector)
+            .response_key(request.key)
+            .data(json!({ "id": "1" }))
+            .build()
+    }
+
+    fn transport_status(response: &connector::request_service::Response) -> Option<StatusCode> {
+        match &response.transport_result {
+            Ok(TransportResponse::Http(http_response)) => Some(http_response.inner.status),
+            Err(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn injects_nothing_unless_enabled() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              rules:
+                - name: down
+                  fault:
+                    http_error:
+                      status_code: 503
+            "#,
+        )
+        .await;
+        let response = harness
+            .subgraph_service("accounts", |request| async move {
+                Ok(subgraph_response(request))
+            })
+            .call(request("Me", None))
+            .await
+            .unwrap();
+
+        assert_eq!(response.response.status(), StatusCode::OK);
+        assert!(response.response.body().errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn injects_http_errors_into_matching_calls() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: accounts-down
+                  subgraphs: [accounts]
+                  operations: [Me]
+                  header:
+                    name: x-chaos
+                    value: "on"
+                  fault:
+                    http_error:
+                      status_code: 503
+            "#,
+        )
+        .await;
+        let service = harness.subgraph_service("accounts", |request| async move {
+            Ok(subgraph_response(request))
+        });
+
+        for request in [
+            request("Me", None),
+            request("Other", Some(("x-chaos", "on"))),
+            request("Me", Some(("x-chaos", "off"))),
+        ] {
+            let response = service.call(request).await.unwrap();
+            assert_eq!(response.response.status(), StatusCode::OK);
+        }
+
+        let response = service
+            .call(request("Me", Some(("x-chaos", "on"))))
+            .await
+            .unwrap();
+        assert_eq!(response.response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.response.body().data, None);
+        assert_eq!(
+            response.response.body().errors[0].message,
+            "HTTP fetch failed from 'accounts': 503: Service Unavailable"
+        );
+    }
+
+    #[tokio::test]
+    async fn adds_graphql_errors_to_subgraph_responses() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: other-subgraph
+                  subgraphs: [products]
+                  fault: connection_reset
+                - name: partial
+                  fault:
+                    graphql_error:
+                      message: injected
+                      code: CHAOS
+            "#,
+        )
+        .await;
+        let response = harness
+            .subgraph_service("accounts", |request| async move {
+                Ok(subgraph_response(request))
+            })
+            .call(request("Me", None))
+            .await
+            .unwrap();
+
+        let body = response.response.body();
+        assert_eq!(body.data, Some(json!({ "me": { "id": "1" } })));
+        assert_eq!(body.errors.len(), 1);
+        assert_eq!(body.errors[0].message, "injected");
+        assert_eq!(body.errors[0].extensions["code"], json!("CHAOS"));
+    }
+
+    #[tokio::test]
+    async fn fails_calls_with_connection_resets_and_timeouts() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: reset
+                  operations: [Reset]
+                  fault: connection_reset
+                - name: timeout
+                  fault:
+                    timeout: 10ms
+            "#,
+        )
+        .await;
+        let service = harness.subgraph_service("accounts", |request| async move {
+            Ok(subgraph_response(request))
+        });
+
+        let error = service.call(request("Reset", None)).await.unwrap_err();
+        assert!(error.to_string().contains(CONNECTION_RESET), "{error}");
+        let error = service.call(request("Me", None)).await.unwrap_err();
+        assert!(error.is::<Elapsed>(), "{error}");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_rules() {
+        for config in [
+            "experimental_fault_injection: { rules: [{ name: a, probability: 2, fault: connection_reset }] }",
+            "experimental_fault_injection: { rules: [{ name: a, fault: { http_error: { status_code: 200 } } }] }",
+        ] {
+            assert!(
+                PluginTestHarness::<FaultInjection>::builder()
+                    .config(config)
+                    .build()
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delays_connector_calls() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: slow
+                  fault:
+                    latency: 2s
+            "#,
+        )
+        .await;
+        let start = tokio::time::Instant::now();
+        let response = harness
+            .call_connector_request_service(connector_request(), connector_response)
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert!(matches!(
+            response.mapped_response,
+            MappedResponse::Data { ref data, .. } if *data == json!({ "id": "1" })
+        ));
+    }
+
+    #[tokio::test]
+    async fn answers_connector_calls_with_http_errors() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: down
+                  fault:
+                    http_error:
+                      status_code: 503
+            "#,
+        )
+        .await;
+        let response = harness
+            .call_connector_request_service(connector_request(), |_| {
+                panic!("the connector must not be called")
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            transport_status(&response),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        let MappedResponse::Error { error, .. } = &response.mapped_response else {
+            panic!("expected an error");
+        };
+        assert_eq!(error.message, "Request failed");
+        assert_eq!(error.code(), "CONNECTOR_FETCH");
+        assert_eq!(error.extensions()["http"], json!({ "status": 503 }));
+    }
+
+    #[tokio::test]
+    async fn fails_connector_calls_with_connection_resets_and_timeouts() {
+        let reset = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: reset
+                  fault: connection_reset
+            "#,
+        )
+        .await;
+        let response = reset
+            .call_connector_request_service(connector_request(), |_| {
+                panic!("the connector must not be called")
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            &response.transport_result,
+            Err(Error::TransportFailure(message)) if message == CONNECTION_RESET
+        ));
+        assert!(matches!(
+            response.mapped_response,
+            MappedResponse::Error { .. }
+        ));
+
+        let timeout = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: timeout
+                  fault:
+                    timeout: 10ms
+            "#,
+        )
+        .await;
+        let error = timeout
+            .call_connector_request_service(connector_request(), connector_response)
+            .await
+            .unwrap_err();
+        assert!(error.is::<Elapsed>(), "{error}");
+    }
+
+    #[tokio::test]
+    async fn adds_graphql_errors_next_to_connector_data() {
+        let harness = harness(
+            r#"
+            experimental_fault_injection:
+              enabled: true
+              rules:
+                - name: partial
+                  fault:
+                    graphql_error:
+                      message: injected
+                      code: CHAOS
+            "#,
+        )
+        .await;
+        let response = harness
+            .call_connector_request_service(connector_request(), connector_response)
+            .await
+            .unwrap();
+
+        assert_eq!(transport_status(&response), Some(StatusCode::OK));
+        assert!(matches!(
+            response.mapped_response,
+            MappedResponse::Data { ref data, .. } if *data == json!({ "id": "1" })
+        ));
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].message, "injected");
+        assert_eq!(response.errors[0].code(), "CHAOS");
+        assert_eq!(
+            response.errors[0].extensions()["service"],
+            json!("products")
+        );
+    }
+
+    #[test]
+    fn scopes_rules_to_subgraphs_and_connector_sources() {
+        let rule = |subgraphs: Option<&[&str]>, sources: Option<&[&str]>| FaultRule {
+            name: "rule".to_string(),
+            subgraphs: subgraphs.map(|names| names.iter().map(|name| name.to_string()).collect()),
+            connector_sources: sources
+                .map(|names| names.iter().map(|name| name.to_string()).collect()),
+            operations: Vec::new(),
+            header: None,
+            probability: 1.0,
+            fault: Fault::ConnectionReset,
+        };
+
+        let everything = rule(None, None);
+        assert!(everything.applies_to_subgraph("accounts"));
+        assert!(everything.applies_to_connector_source("products.api"));
+
+        let subgraphs_only = rule(Some(&["accounts"]), None);
+        assert!(subgraphs_only.applies_to_subgraph("accounts"));
+        assert!(!subgraphs_only.applies_to_subgraph("reviews"));
+        assert!(!subgraphs_only.applies_to_connector_source("products.api"));
+
+        let sources_only = rule(None, Some(&["products.api"]));
+        assert!(!sources_only.applies_to_subgraph("accounts"));
+        assert!(sources_only.applies_to_connector_source("products.api"));
+        assert!(!sources_only.applies_to_connector_source("products.other"));
+    }
+}
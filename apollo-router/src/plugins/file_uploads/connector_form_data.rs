@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use apollo_federation::connectors::runtime::form_data::FormData;
+use apollo_federation::connectors::runtime::form_data::FormPart;
+use bytes::Bytes;
+use futures::Stream;
+use futures::stream::StreamExt;
+use itertools::Itertools;
+
+use super::MultipartRequest;
+use super::Result as UploadResult;
+use super::error::FileUploadError;
+
+/// Streams the body of a connector's `multipart/form-data` request, filling in its upload parts
+/// with the files of the client request as they arrive.
+pub(super) async fn connector_form_data_stream(
+    form: FormData,
+    mut multipart: MultipartRequest,
+) -> UploadResult<impl Stream<Item = UploadResult<Bytes>>> {
+    // Files are streamed straight from the client request, so each one can only be sent once
+    let mut part_names: HashMap<String, String> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for part in form.parts() {
+        if let FormPart::Upload { name, file } = part {
+            if part_names.insert(file.clone(), name.clone()).is_some() {
+                duplicates.push(format!("'{}'", file));
+            }
+        }
+    }
+    if !duplicates.is_empty() {
+        return Err(FileUploadError::DuplicateFileUsages(
+            duplicates.into_iter().unique().join(", "),
+        ));
+    }
+
+    let values = Bytes::from(form.encode_values());
+    let closing_delimiter = Bytes::from(form.closing_delimiter());
+    let file_names = part_names.keys().cloned().collect();
+    let file_prefix = move |field: &multer::Field<'static>| {
+        let name = field
+            .name()
+            .and_then(|file| part_names.get(file))
+            .map(String::as_str)
+            .unwrap_or_default();
+        let content_type = field.content_type().map(ToString::to_string);
+        Bytes::from(form.upload_part_headers(name, field.file_name(), content_type.as_deref()))
+    };
+
+    let files_stream = multipart.subgraph_stream(file_names, file_prefix).await;
+    Ok(tokio_stream::once(Ok(values))
+        .chain(files_stream)
+        .chain(tokio_stream::once(Ok(closing_delimiter))))
+}
@@ -50,6 +50,11 @@ pub(super) enum FileUploadError {
     #[error("Variables use mutiple time in the way that prevent streaming of files: {0}.")]
     DuplicateVariableUsages(String),
 
+    #[error(
+        "Files used multiple times in a connector request body, which prevents streaming them: {0}."
+    )]
+    DuplicateFileUsages(String),
+
     #[error("Exceeded the limit of {0} file uploads of files in a single request.")]
     MaxFilesLimitExceeded(usize),
 
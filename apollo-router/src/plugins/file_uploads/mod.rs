@@ -1,11 +1,18 @@
 use std::ops::ControlFlow;
 use std::sync::Arc;
 
+use apollo_federation::connectors::runtime::form_data::FormData;
+use apollo_federation::connectors::runtime::form_data::FormPart;
+use apollo_federation::connectors::runtime::form_data::upload_placeholder;
+use bytes::Bytes;
+use bytes::BytesMut;
 use futures::FutureExt;
+use futures::StreamExt;
 use http::HeaderName;
 use http::HeaderValue;
 use http::header::CONTENT_LENGTH;
 use http::header::CONTENT_TYPE;
+use itertools::Itertools;
 use mediatype::MediaType;
 use mediatype::ReadParams;
 use mediatype::names::BOUNDARY;
@@ -17,11 +24,13 @@ use tower::ServiceExt;
 
 use self::config::FileUploadsConfig;
 use self::config::MultipartRequestLimits;
+use self::connector_form_data::connector_form_data_stream;
 use self::error::FileUploadError;
 use self::map_field::MapField;
 use self::multipart_form_data::MultipartFormData;
 use self::multipart_request::MultipartRequest;
 use self::rearrange_query_plan::rearrange_query_plan;
+use crate::Context;
 use crate::json_ext;
 use crate::layers::ServiceBuilderExt;
 use crate::plugin::PluginInit;
@@ -34,6 +43,7 @@ use crate::services::subgraph;
 use crate::services::supergraph;
 
 mod config;
+mod connector_form_data;
 mod error;
 mod map_field;
 mod multipart_form_data;
@@ -232,9 +242,7 @@ async fn supergraph_layer(mut req: supergraph::Request) -> Result<supergraph::Re
                     replace_value_at_path(
                         variables,
                         variable_path,
-                        serde_json_bytes::Value::String(
-                            format!("<Placeholder for file '{}'>", filename).into(),
-                        ),
+                        serde_json_bytes::Value::String(upload_placeholder(filename).into()),
                     )
                     .map_err(|path| FileUploadError::InputValueNotFound(path.join(".")))?;
                 }
@@ -381,3 +389,129 @@ pub(crate) async fn http_request_wrapper(
     }
     req
 }
+
+/// Asks for the body of a connector request with upload parts to be kept once it has been
+/// streamed, so that the request can be sent again. The files of the client request can only be
+/// read once, so without it a replayed request would have no files to send.
+///
+/// Clones share the kept body, so it has to be added before the request is first cloned.
+#[derive(Clone, Default)]
+pub(crate) struct BufferedUploads(Arc<tokio::sync::OnceCell<Bytes>>);
+
+/// Keeps the body of `request` once it has been streamed if it has upload parts, for a request
+/// that may be sent more than once.
+pub(crate) fn buffer_uploads(request: &mut http::Request<String>) {
+    if has_uploads(request) && request.extensions().get::<BufferedUploads>().is_none() {
+        request.extensions_mut().insert(BufferedUploads::default());
+    }
+}
+
+/// Whether the body of a connector request streams files the client uploaded
+pub(crate) fn has_uploads(request: &http::Request<String>) -> bool {
+    request
+        .extensions()
+        .get::<FormData>()
+        .is_some_and(FormData::has_uploads)
+}
+
+/// Turns a connector request into one the HTTP client can send, streaming the files the client
+/// uploaded into its body if it is a `multipart/form-data` body with upload parts.
+pub(crate) async fn connector_request_wrapper(
+    req: http::Request<String>,
+    context: &Context,
+) -> std::result::Result<http::Request<RouterBody>, BoxError> {
+    let (mut request_parts, body) = req.into_parts();
+    let Some(form) = request_parts.extensions.remove::<FormData>() else {
+        return Ok(http::Request::from_parts(
+            request_parts,
+            router::body::from_bytes(body),
+        ));
+    };
+
+    let request_body = match request_parts.extensions.remove::<BufferedUploads>() {
+        Some(BufferedUploads(buffer)) => {
+            let body = buffer
+                .get_or_try_init(|| async {
+                    let multipart = client_files(&form, context)?;
+                    let mut stream =
+                        std::pin::pin!(connector_form_data_stream(form, multipart).await?);
+                    let mut body = BytesMut::new();
+                    while let Some(chunk) = stream.next().await {
+                        body.extend_from_slice(&chunk?);
+                    }
+                    Ok::<_, BoxError>(body.freeze())
+                })
+                .await?;
+            router::body::from_bytes(body.clone())
+        }
+        None => {
+            let multipart = client_files(&form, context)?;
+            router::body::from_result_stream(connector_form_data_stream(form, multipart).await?)
+        }
+    };
+    Ok(http::Request::from_parts(request_parts, request_body))
+}
+
+/// The files of the client request, for the upload parts of `form`
+fn client_files(form: &FormData, context: &Context) -> Result<MultipartRequest> {
+    let multipart = context.extensions().with_lock(|lock| {
+        lock.get::<SupergraphLayerResult>()
+            .map(|result| result.multipart.clone())
+    });
+    multipart.ok_or_else(|| {
+        // The placeholders didn't come from a multipart request, so there are no files to send
+        let files = form
+            .parts()
+            .iter()
+            .filter_map(|part| match part {
+                FormPart::Upload { file, .. } => Some(format!("'{}'", file)),
+                FormPart::Value { .. } => None,
+            })
+            .join(", ");
+        FileUploadError::MissingFiles(files)
+    })
+}
+
+/// A context holding the files of a client's multipart request, the way the plugin's layers
+/// leave it for connectors. `files` are the names and contents of the files.
+#[cfg(test)]
+pub(crate) async fn test_context(files: &[(&str, &str)]) -> Context {
+    let boundary = "client-boundary";
+    let map: serde_json::Map<_, _> = files
+        .iter()
+        .map(|(name, _)| {
+            (
+                name.to_string(),
+                serde_json::json!([format!("variables.{name}")]),
+            )
+        })
+        .collect();
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{{}}\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{}\r\n",
+        serde_json::Value::Object(map)
+    );
+    for (name, contents) in files {
+        body.push_str(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"; \
+             filename=\"{name}.txt\"\r\nContent-Type: text/plain\r\n\r\n{contents}\r\n"
+        ));
+    }
+    body.push_str(&format!("--{boundary}--\r\n"));
+
+    let mut multipart = MultipartRequest::new(
+        router::body::from_bytes(body),
+        boundary.to_string(),
+        MultipartRequestLimits::default(),
+    );
+    multipart.operations_field().await.unwrap();
+    let map = multipart.map_field().await.unwrap();
+    let context = Context::new();
+    context.extensions().with_lock(|lock| {
+        lock.insert(SupergraphLayerResult {
+            multipart,
+            map: Arc::new(map),
+        })
+    });
+    context
+}
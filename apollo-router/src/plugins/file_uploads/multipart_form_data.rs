@@ -5,7 +5,6 @@ use bytes::BytesMut;
 use futures::Stream;
 use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
-use http::HeaderMap;
 use http::HeaderValue;
 use http_body_util::BodyExt;
 use mediatype::MediaType;
@@ -71,12 +70,12 @@ impl MultipartFormData {
 
         let file_names = self.map.keys().cloned().collect();
         let boundary = self.boundary;
-        let file_prefix = move |headers: &HeaderMap| {
+        let file_prefix = move |field: &multer::Field<'static>| {
             let mut prefix = BytesMut::new();
             prefix.extend_from_slice(b"\r\n--");
             prefix.extend_from_slice(boundary.as_bytes());
             prefix.extend_from_slice(b"\r\n");
-            for (k, v) in headers.iter() {
+            for (k, v) in field.headers().iter() {
                 prefix.extend_from_slice(k.as_str().as_bytes());
                 prefix.extend_from_slice(b": ");
                 prefix.extend_from_slice(v.as_bytes());
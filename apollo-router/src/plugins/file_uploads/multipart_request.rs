@@ -7,7 +7,6 @@ use std::task::Poll;
 
 use bytes::Bytes;
 use futures::Stream;
-use http::HeaderMap;
 use http_body_util::BodyExt;
 use itertools::Itertools;
 use multer::Constraints;
@@ -130,7 +129,7 @@ impl MultipartRequest {
         file_prefix_fn: FilePrefixFn,
     ) -> SubgraphFileProxyStream<FilePrefixFn>
     where
-        FilePrefixFn: Fn(&HeaderMap) -> Bytes,
+        FilePrefixFn: Fn(&multer::Field<'static>) -> Bytes,
     {
         let state = self.state.clone().lock_owned().await;
         SubgraphFileProxyStream::new(state, file_names, file_prefix_fn)
@@ -150,7 +149,7 @@ pin_project! {
 
 impl<FilePrefixFn> SubgraphFileProxyStream<FilePrefixFn>
 where
-    FilePrefixFn: Fn(&HeaderMap) -> Bytes,
+    FilePrefixFn: Fn(&multer::Field<'static>) -> Bytes,
 {
     fn new(
         state: OwnedMutexGuard<MultipartRequestState>,
@@ -244,7 +243,7 @@ where
 
                         if let Some(name) = field.name() {
                             if self.file_names.remove(name) {
-                                let prefix = (self.file_prefix_fn)(field.headers());
+                                let prefix = (self.file_prefix_fn)(&field);
                                 self.current_field = Some(field);
                                 return Poll::Ready(Some(Ok(prefix)));
                             }
@@ -263,7 +262,7 @@ where
 
 impl<FilePrefixFn> Stream for SubgraphFileProxyStream<FilePrefixFn>
 where
-    FilePrefixFn: Fn(&HeaderMap) -> Bytes,
+    FilePrefixFn: Fn(&multer::Field<'static>) -> Bytes,
 {
     type Item = UploadResult<Bytes>;
 
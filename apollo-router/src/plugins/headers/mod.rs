@@ -1745,6 +1745,7 @@ mod test {
                     )
                     .expect("expecting valid request"),
             ),
+            retain_upstream_body: false,
         }
     }
 
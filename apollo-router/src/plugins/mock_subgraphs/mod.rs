@@ -1,5 +1,7 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::LazyLock;
 
@@ -15,6 +17,9 @@ use tower::BoxError;
 use tower::ServiceExt;
 
 use self::execution::resolver::ResolvedValue;
+use self::rules::Latency;
+use self::rules::Rule;
+use crate::error::FetchError;
 use crate::graphql;
 use crate::plugin::PluginInit;
 use crate::plugin::PluginPrivate;
@@ -23,6 +28,7 @@ use crate::plugins::response_cache::plugin::GRAPHQL_RESPONSE_EXTENSION_ROOT_FIEL
 use crate::services::subgraph;
 
 pub(crate) mod execution;
+mod rules;
 
 register_private_plugin!("apollo", "experimental_mock_subgraphs", MockSubgraphsPlugin);
 
@@ -45,6 +51,16 @@ register_private_plugin!("apollo", "experimental_mock_subgraphs", MockSubgraphsP
 ///         id: 4
 ///         field: [42, 7]
 ///         __cacheTags: ["something-4"]
+///     rules:
+///       - when:
+///           arguments:
+///             rootField: { id: 404 }
+///         errors:
+///           - message: "not found"
+///         latency:
+///           uniform: { min: 10ms, max: 50ms }
+///   subgraph2_name:
+///     file: fixtures/subgraph2.yaml
 /// ```
 //
 // If changing this, also update `dev-docs/mock_subgraphs_plugin.md`
@@ -83,6 +99,53 @@ struct SubgraphConfig {
     #[serde(default)]
     #[schemars(with = "Vec<OtherJsonMap>")]
     entities: Vec<JsonMap>,
+
+    /// Rules checked in order for each request. The first matching rule changes the response.
+    #[serde(default)]
+    rules: Vec<Rule>,
+
+    /// Latency of every response, unless a matching rule sets its own
+    #[serde(default)]
+    latency: Option<Latency>,
+
+    /// Path to a JSON or YAML file with the configuration for this subgraph, which replaces
+    /// the other keys. A relative path is resolved against the current working directory of the
+    /// Router, not the directory of the configuration file.
+    #[serde(default)]
+    file: Option<PathBuf>,
+}
+
+impl SubgraphConfig {
+    /// Loads the configuration from `file` if set, and validates it
+    fn load(self: Arc<Self>) -> Result<Arc<Self>, BoxError> {
+        let config = match &self.file {
+            Some(path) => Arc::new(load_fixture(path)?),
+            None => self,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            rule.validate()?;
+        }
+        if let Some(latency) = &self.latency {
+            latency.validate()?;
+        }
+        Ok(())
+    }
+}
+
+fn load_fixture(path: &Path) -> Result<SubgraphConfig, BoxError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read mock subgraph file {}: {e}", path.display()))?;
+    let config = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| e.to_string())
+    };
+    config.map_err(|e| format!("invalid mock subgraph file {}: {e}", path.display()).into())
 }
 
 type OtherJsonMap = serde_json::Map<String, serde_json::Value>;
@@ -106,9 +169,19 @@ impl PluginPrivate for MockSubgraphsPlugin {
     const HIDDEN_FROM_CONFIG_JSON_SCHEMA: bool = true;
 
     async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        let per_subgraph_config = init
+            .config
+            .into_iter()
+            .map(|(name, config)| {
+                let config = config
+                    .load()
+                    .map_err(|e| format!("invalid mock for subgraph {name}: {e}"))?;
+                Ok((name, config))
+            })
+            .collect::<Result<_, BoxError>>()?;
         Ok(Self {
             subgraph_schemas: init.subgraph_schemas.clone(),
-            per_subgraph_config: init.config,
+            per_subgraph_config,
         })
     }
 
@@ -121,13 +194,53 @@ impl PluginPrivate for MockSubgraphsPlugin {
             async move {
                 let mut response = http::Response::builder();
                 let body = if let Some(config) = &config {
-                    *response.headers_mut().unwrap() = config.headers.0.clone();
-                    subgraph_call(config, &subgraph_schema, request.subgraph_request.body())
-                        .unwrap_or_else(|e| {
+                    let mut headers = config.headers.0.clone();
+                    let (rule, mut body) = match subgraph_call(
+                        config,
+                        &subgraph_schema,
+                        request.subgraph_request.body(),
+                    ) {
+                        Ok(result) => result,
+                        Err(e) => (
+                            None,
                             graphql::Response::builder()
                                 .errors(e.into_iter().map(Into::into).collect())
-                                .build()
-                        })
+                                .build(),
+                        ),
+                    };
+                    if let Some(rule) = rule {
+                        for (name, value) in &rule.headers.0 {
+                            headers.insert(name.clone(), value.clone());
+                        }
+                        if let Some(status_code) = rule.status_code {
+                            let status = http::StatusCode::from_u16(status_code)?;
+                            response = response.status(status);
+                            // Like the subgraph service does for responses that are not 2xx
+                            if !status.is_success() {
+                                body.errors.insert(
+                                    0,
+                                    FetchError::SubrequestHttpError {
+                                        service: request.subgraph_name.clone(),
+                                        status_code: Some(status.as_u16()),
+                                        reason: format!(
+                                            "{}: {}",
+                                            status.as_str(),
+                                            status.canonical_reason().unwrap_or("Unknown")
+                                        ),
+                                    }
+                                    .to_graphql_error(None),
+                                );
+                            }
+                        }
+                    }
+                    let latency = rule
+                        .and_then(|rule| rule.latency.as_ref())
+                        .or(config.latency.as_ref());
+                    if let Some(latency) = latency {
+                        tokio::time::sleep(latency.sample()).await;
+                    }
+                    *response.headers_mut().unwrap() = headers;
+                    body
                 } else {
                     graphql::Response::builder()
                         .error(
@@ -138,8 +251,8 @@ impl PluginPrivate for MockSubgraphsPlugin {
                         )
                         .build()
                 };
-                let response = response.body(body).unwrap();
-                Ok(subgraph::Response::new_from_response(
+                let response = response.body(body)?;
+                Ok::<_, BoxError>(subgraph::Response::new_from_response(
                     response,
                     request.context,
                     request.subgraph_name,
@@ -158,14 +271,15 @@ pub fn testing_subgraph_call(
     request: &graphql::Request,
 ) -> Result<graphql::Response, Vec<GraphQLError>> {
     let config = serde_json_bytes::from_value(config).unwrap();
-    subgraph_call(&config, subgraph_schema, request)
+    subgraph_call(&config, subgraph_schema, request).map(|(_, response)| response)
 }
 
-fn subgraph_call(
-    config: &SubgraphConfig,
+/// Executes the request against the mocked data, returning the matching rule if any
+fn subgraph_call<'a>(
+    config: &'a SubgraphConfig,
     subgraph_schema: &Valid<Schema>,
     request: &graphql::Request,
-) -> Result<graphql::Response, Vec<GraphQLError>> {
+) -> Result<(Option<&'a Rule>, graphql::Response), Vec<GraphQLError>> {
     let query = request.query.as_deref().unwrap_or("");
     let doc = ExecutableDocument::parse_and_validate(subgraph_schema, query, "query")
         .map_err(|e| e.errors.iter().map(|e| e.to_json()).collect::<Vec<_>>())?;
@@ -175,25 +289,40 @@ fn subgraph_call(
         .map_err(|e| vec![e.to_graphql_error(&doc.sources)])?;
     let variable_values = coerce_variable_values(subgraph_schema, operation, &request.variables)
         .map_err(|e| vec![e.to_graphql_error(&doc.sources)])?;
+    let rule = config
+        .rules
+        .iter()
+        .find(|rule| rule.matches(subgraph_schema, &doc, operation, &variable_values, request));
+    if let Some(rule) = rule.filter(|rule| rule.errors_only()) {
+        let response = graphql::Response::builder()
+            .errors(rule.errors.clone())
+            .build();
+        return Ok((Some(rule), response));
+    }
+    let rule_data = rule.and_then(|rule| rule.data.as_ref());
     let object_type_name = operation.object_type();
     let plain_error = |message: &str| vec![GraphQLError::new(message, None, &doc.sources)];
     let root_operation_object_type_def = subgraph_schema
         .get_object(object_type_name)
         .ok_or_else(|| plain_error("undefined root operation object type"))?;
     let (mode, root_mocks) = match operation.operation_type {
-        OperationType::Query => (execution::engine::ExecutionMode::Normal, &config.query),
+        OperationType::Query => (
+            execution::engine::ExecutionMode::Normal,
+            rule_data.unwrap_or(&config.query),
+        ),
         OperationType::Mutation => (
             execution::engine::ExecutionMode::Sequential,
-            config
-                .mutation
-                .as_ref()
+            rule_data
+                .or(config.mutation.as_ref())
                 .ok_or_else(|| plain_error("mutation is not supported"))?,
         ),
         OperationType::Subscription => return Err(plain_error("subscription not supported")),
     };
     let initial_value = RootResolver {
         root_mocks,
-        entities: &config.entities,
+        entities: rule
+            .and_then(|rule| rule.entities.as_deref())
+            .unwrap_or(&config.entities),
     };
     let mut errors = Vec::new();
     let response_extensions = RefCell::new(JsonMap::new());
@@ -213,11 +342,16 @@ fn subgraph_call(
         Ok(map) => JsonValue::Object(map),
         Err(execution::engine::PropagateNull) => JsonValue::Null,
     };
-    Ok(graphql::Response::builder()
+    let mut errors: Vec<graphql::Error> = errors.into_iter().map(Into::into).collect();
+    if let Some(rule) = rule {
+        errors.extend(rule.errors.iter().cloned());
+    }
+    let response = graphql::Response::builder()
         .data(data)
-        .errors(errors.into_iter().map(Into::into).collect())
+        .errors(errors)
         .extensions(response_extensions.into_inner())
-        .build())
+        .build();
+    Ok((rule, response))
 }
 
 struct RootResolver<'a> {
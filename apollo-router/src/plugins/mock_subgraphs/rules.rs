@@ -0,0 +1,236 @@
+//! Rules that vary the mocked response of a subgraph by request.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use apollo_compiler::ExecutableDocument;
+use apollo_compiler::Schema;
+use apollo_compiler::executable::Field;
+use apollo_compiler::executable::Operation;
+use apollo_compiler::executable::Selection;
+use apollo_compiler::executable::SelectionSet;
+use apollo_compiler::response::JsonMap;
+use apollo_compiler::validation::Valid;
+use rand::Rng;
+
+use super::HeaderMap;
+use super::OtherJsonMap;
+use super::execution::input_coercion::coerce_argument_values;
+use crate::graphql;
+
+/// A rule replacing parts of the mocked response for matching requests
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct Rule {
+    /// Conditions a request must all meet for the rule to apply
+    #[serde(default)]
+    when: RuleMatch,
+
+    /// Data for `query` and `mutation` operations, replacing the data of the subgraph
+    #[serde(default)]
+    #[schemars(with = "Option<OtherJsonMap>")]
+    pub(super) data: Option<JsonMap>,
+
+    /// Entities replacing the entities of the subgraph
+    #[serde(default)]
+    #[schemars(with = "Option<Vec<OtherJsonMap>>")]
+    pub(super) entities: Option<Vec<JsonMap>>,
+
+    /// GraphQL errors added to the response. Without `data` or `entities`, the response only
+    /// contains these errors.
+    #[serde(default)]
+    #[schemars(with = "Vec<OtherJsonMap>")]
+    pub(super) errors: Vec<graphql::Error>,
+
+    /// HTTP status code of the response. A status code other than 2xx adds an error to the
+    /// response, like a failing subgraph would.
+    #[serde(default)]
+    pub(super) status_code: Option<u16>,
+
+    /// HTTP headers added to the response
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, String>")]
+    pub(super) headers: HeaderMap,
+
+    /// Latency of the response, replacing the latency of the subgraph
+    #[serde(default)]
+    pub(super) latency: Option<Latency>,
+}
+
+/// Conditions on a subgraph request
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RuleMatch {
+    /// Name of the operation
+    #[serde(default)]
+    operation_name: Option<String>,
+
+    /// Variables the request must contain, with these values
+    #[serde(default)]
+    #[schemars(with = "OtherJsonMap")]
+    variables: JsonMap,
+
+    /// Fields the operation must select, by name, with these argument values
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, OtherJsonMap>")]
+    arguments: HashMap<String, JsonMap>,
+}
+
+impl Rule {
+    pub(super) fn validate(&self) -> Result<(), String> {
+        if let Some(status_code) = self.status_code {
+            http::StatusCode::from_u16(status_code)
+                .map_err(|_| format!("invalid status code {status_code}"))?;
+        }
+        if let Some(latency) = &self.latency {
+            latency.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the rule only serves errors, without executing the operation
+    pub(super) fn errors_only(&self) -> bool {
+        !self.errors.is_empty() && self.data.is_none() && self.entities.is_none()
+    }
+
+    pub(super) fn matches(
+        &self,
+        schema: &Valid<Schema>,
+        document: &Valid<ExecutableDocument>,
+        operation: &Operation,
+        variable_values: &Valid<JsonMap>,
+        request: &graphql::Request,
+    ) -> bool {
+        let when = &self.when;
+        if let Some(operation_name) = &when.operation_name {
+            let name = request
+                .operation_name
+                .as_deref()
+                .or(operation.name.as_ref().map(|name| name.as_str()));
+            if name != Some(operation_name.as_str()) {
+                return false;
+            }
+        }
+
+        if !when
+            .variables
+            .iter()
+            .all(|(name, value)| request.variables.get(name) == Some(value))
+        {
+            return false;
+        }
+
+        when.arguments.iter().all(|(field_name, expected)| {
+            let mut found = false;
+            for_each_field(
+                document,
+                &operation.selection_set,
+                &mut HashSet::new(),
+                &mut |field| {
+                    if found || field.name.as_str() != field_name {
+                        return;
+                    }
+                    let Ok(arguments) = coerce_argument_values(
+                        schema,
+                        document,
+                        variable_values,
+                        &mut Vec::new(),
+                        None,
+                        &field.definition,
+                        field,
+                    ) else {
+                        return;
+                    };
+                    found = expected
+                        .iter()
+                        .all(|(name, value)| arguments.get(name) == Some(value));
+                },
+            );
+            found
+        })
+    }
+}
+
+fn for_each_field<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    visited_fragments: &mut HashSet<&'a str>,
+    f: &mut impl FnMut(&'a Field),
+) {
+    for selection in &selection_set.selections {
+        match selection {
+            Selection::Field(field) => {
+                f(field);
+                for_each_field(document, &field.selection_set, visited_fragments, f);
+            }
+            Selection::InlineFragment(inline) => {
+                for_each_field(document, &inline.selection_set, visited_fragments, f);
+            }
+            Selection::FragmentSpread(spread) => {
+                if !visited_fragments.insert(spread.fragment_name.as_str()) {
+                    continue;
+                }
+                if let Some(fragment) = document.fragments.get(&spread.fragment_name) {
+                    for_each_field(document, &fragment.selection_set, visited_fragments, f);
+                }
+            }
+        }
+    }
+}
+
+/// Latency added to mocked responses
+#[derive(Clone, Debug, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(super) enum Latency {
+    /// Always the same duration
+    Fixed(
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
+    /// Uniformly distributed between `min` and `max`
+    Uniform {
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        min: Duration,
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        max: Duration,
+    },
+    /// Normally distributed around `mean`, never below zero
+    Normal {
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        mean: Duration,
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        std_dev: Duration,
+    },
+}
+
+impl Latency {
+    pub(super) fn validate(&self) -> Result<(), String> {
+        match self {
+            Latency::Uniform { min, max } if min > max => Err(format!(
+                "uniform latency has a minimum of {min:?} above its maximum of {max:?}"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn sample(&self) -> Duration {
+        match self {
+            Latency::Fixed(duration) => *duration,
+            Latency::Uniform { min, max } => rand::rng().random_range(*min..=*max),
+            Latency::Normal { mean, std_dev } => {
+                // Box-Muller transform, `1 - u` avoids taking the logarithm of zero
+                let mut rng = rand::rng();
+                let u1 = 1.0 - rng.random::<f64>();
+                let u2 = rng.random::<f64>();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                Duration::from_secs_f64((mean.as_secs_f64() + z * std_dev.as_secs_f64()).max(0.0))
+            }
+        }
+    }
+}
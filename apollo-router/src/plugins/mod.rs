@@ -29,6 +29,7 @@ pub(crate) mod csrf;
 pub(crate) mod demand_control;
 pub(crate) mod enhanced_client_awareness;
 pub(crate) mod expose_query_plan;
+mod fault_injection;
 pub(crate) mod file_uploads;
 mod fleet_detector;
 mod forbid_mutations;
@@ -40,7 +41,7 @@ pub(crate) mod limits;
 pub(crate) mod mock_subgraphs;
 pub(crate) mod override_url;
 pub(crate) mod progressive_override;
-mod record_replay;
+pub(crate) mod record_replay;
 pub(crate) mod response_cache;
 pub(crate) mod rhai;
 pub(crate) mod subscription;
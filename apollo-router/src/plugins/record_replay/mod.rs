@@ -1,3 +1,5 @@
 mod record;
 mod recording;
+mod redaction;
 mod replay;
+pub(crate) mod runner;
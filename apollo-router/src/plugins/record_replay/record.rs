@@ -2,9 +2,11 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use bytesize::ByteSize;
 use futures::StreamExt;
 use futures::stream::once;
 use http_body_util::BodyExt;
+use rand::Rng;
 use tokio::fs;
 use tower::BoxError;
 use tower::ServiceBuilder;
@@ -14,9 +16,13 @@ use super::recording::Recording;
 use super::recording::RequestDetails;
 use super::recording::ResponseDetails;
 use super::recording::Subgraph;
+use super::redaction::Redaction;
 use crate::layers::ServiceBuilderExt;
 use crate::plugin::Plugin;
 use crate::plugin::PluginInit;
+use crate::plugins::telemetry::config_new::Stage;
+use crate::plugins::telemetry::config_new::conditions::Condition;
+use crate::plugins::telemetry::config_new::supergraph::selectors::SupergraphSelector;
 use crate::services::execution;
 use crate::services::external::externalize_header_map;
 use crate::services::router;
@@ -34,6 +40,54 @@ struct RecordConfig {
     /// The path to the directory where recordings will be stored. Defaults to
     /// the current working directory.
     storage_path: Option<PathBuf>,
+    /// Which requests are recorded.
+    #[serde(default)]
+    sampling: RecordSampling,
+    /// Values removed from recordings before they are written.
+    #[serde(default)]
+    redact: Redaction,
+    /// Recordings larger than this size, such as `1MB`, are not written. Defaults to no limit.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    max_recording_size: Option<ByteSize>,
+    /// The maximum number of recordings kept in the storage directory. The oldest recordings are
+    /// removed when new ones are written. Defaults to no limit.
+    #[serde(default)]
+    max_recordings: Option<usize>,
+}
+
+/// Which requests are recorded.
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+struct RecordSampling {
+    /// Record requests carrying the `x-apollo-router-record` header. Defaults to true.
+    header: bool,
+    /// The share of requests, between 0 and 1, recorded without the header. Defaults to 0.
+    rate: f64,
+    /// Only requests matching this condition are recorded. Defaults to all requests.
+    condition: Condition<SupergraphSelector>,
+}
+
+impl Default for RecordSampling {
+    fn default() -> Self {
+        Self {
+            header: true,
+            rate: 0.0,
+            condition: Condition::True,
+        }
+    }
+}
+
+impl RecordSampling {
+    fn should_record(&self, request: &supergraph::Request) -> bool {
+        let requested = self.header
+            && request
+                .supergraph_request
+                .headers()
+                .contains_key(RECORD_HEADER);
+        let sampled = self.rate > 0.0 && rand::rng().random_bool(self.rate);
+        (requested || sampled) && self.condition.clone().evaluate_request(request) == Some(true)
+    }
 }
 
 fn default_storage_path() -> PathBuf {
@@ -45,6 +99,10 @@ struct Record {
     enabled: bool,
     supergraph_sdl: Arc<String>,
     storage_path: Arc<Path>,
+    sampling: Arc<RecordSampling>,
+    redact: Arc<Redaction>,
+    max_recording_size: Option<ByteSize>,
+    max_recordings: Option<usize>,
 }
 
 register_plugin!("experimental", "record", Record);
@@ -54,18 +112,33 @@ impl Plugin for Record {
     type Config = RecordConfig;
 
     async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
-        let storage_path = init
-            .config
-            .storage_path
-            .unwrap_or_else(default_storage_path);
+        let config = init.config;
+        if !(0.0..=1.0).contains(&config.sampling.rate) {
+            return Err("sampling rate must be between 0 and 1".into());
+        }
+        config
+            .sampling
+            .condition
+            .validate(Some(Stage::Request))
+            .map_err(|e| format!("invalid sampling condition: {e}"))?;
+        config
+            .redact
+            .validate()
+            .map_err(|e| format!("invalid redaction: {e}"))?;
+
+        let storage_path = config.storage_path.unwrap_or_else(default_storage_path);
 
         let plugin = Self {
-            enabled: init.config.enabled,
+            enabled: config.enabled,
             supergraph_sdl: init.supergraph_sdl.clone(),
             storage_path: storage_path.clone().into(),
+            sampling: Arc::new(config.sampling),
+            redact: Arc::new(config.redact),
+            max_recording_size: config.max_recording_size,
+            max_recordings: config.max_recordings,
         };
 
-        if init.config.enabled {
+        if config.enabled {
             write_file(
                 storage_path.into(),
                 &PathBuf::from("README.md"),
@@ -83,10 +156,14 @@ impl Plugin for Record {
         }
 
         let dir = self.storage_path.clone();
+        let redact = self.redact.clone();
+        let max_recording_size = self.max_recording_size;
+        let max_recordings = self.max_recordings;
 
         ServiceBuilder::new()
             .map_future(move |future| {
                 let dir = dir.clone();
+                let redact = redact.clone();
 
                 async move {
                     let res: router::Response = future.await?;
@@ -103,19 +180,29 @@ impl Plugin for Record {
                         if let Some(mut recording) = recording {
                             let res_headers = externalize_header_map(&headers)?;
                             recording.client_response.headers = res_headers;
+                            redact.apply(&mut recording);
 
                             let filename = recording.filename();
-                            let contents = serde_json::to_value(recording)?;
+                            let contents = serde_json::to_string_pretty(&recording)?;
+
+                            let size = ByteSize::b(contents.len() as u64);
+                            if let Some(max_size) = max_recording_size.filter(|max| size > *max) {
+                                tracing::warn!(
+                                    "Skipping recording {:?}: its size of {} exceeds the limit of {}",
+                                    filename,
+                                    size,
+                                    max_size
+                                );
+                                return Ok(None);
+                            }
 
                             tokio::spawn(async move {
                                 tracing::info!("Writing recording to {:?}", filename);
 
-                                write_file(
-                                    dir,
-                                    &filename,
-                                    serde_json::to_string_pretty(&contents)?.as_bytes(),
-                                )
-                                .await?;
+                                write_file(dir.clone(), &filename, contents.as_bytes()).await?;
+                                if let Some(max_recordings) = max_recordings {
+                                    rotate(&dir, max_recordings).await?;
+                                }
 
                                 Ok::<(), BoxError>(())
                             })
@@ -146,6 +233,7 @@ impl Plugin for Record {
         }
 
         let supergraph_sdl = self.supergraph_sdl.clone();
+        let sampling = self.sampling.clone();
 
         ServiceBuilder::new()
             .map_request(move |req: supergraph::Request| {
@@ -153,21 +241,20 @@ impl Plugin for Record {
                     return req;
                 }
 
-                let recording_enabled =
-                    if req.supergraph_request.headers().contains_key(RECORD_HEADER) {
-                        req.context.extensions().with_lock(|lock| {
-                            lock.insert(Recording {
-                                supergraph_sdl: supergraph_sdl.clone().to_string(),
-                                client_request: Default::default(),
-                                client_response: Default::default(),
-                                formatted_query_plan: Default::default(),
-                                subgraph_fetches: Default::default(),
-                            })
-                        });
-                        true
-                    } else {
-                        false
-                    };
+                let recording_enabled = if sampling.should_record(&req) {
+                    req.context.extensions().with_lock(|lock| {
+                        lock.insert(Recording {
+                            supergraph_sdl: supergraph_sdl.clone().to_string(),
+                            client_request: Default::default(),
+                            client_response: Default::default(),
+                            formatted_query_plan: Default::default(),
+                            subgraph_fetches: Default::default(),
+                        })
+                    });
+                    true
+                } else {
+                    false
+                };
 
                 if recording_enabled {
                     let query = req.supergraph_request.body().query.clone();
@@ -287,6 +374,37 @@ impl Plugin for Record {
     }
 }
 
+/// Removes the oldest recordings from the directory, keeping at most `max_recordings`. Only the
+/// files named like recordings are considered, since the directory defaults to the current one.
+async fn rotate(dir: &Path, max_recordings: usize) -> Result<(), BoxError> {
+    let mut recordings = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_recording = entry
+            .file_name()
+            .to_str()
+            .is_some_and(Recording::is_recording_filename);
+        if is_recording {
+            recordings.push((entry.metadata().await?.modified()?, path));
+        }
+    }
+    if recordings.len() <= max_recordings {
+        return Ok(());
+    }
+
+    recordings.sort();
+    let excess = recordings.len() - max_recordings;
+    for (_, path) in recordings.into_iter().take(excess) {
+        // Concurrent rotations may have removed the recording already
+        fs::remove_file(&path).await.or_else(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Ok(()),
+            _ => Err(e),
+        })?;
+    }
+    Ok(())
+}
+
 async fn write_file(dir: Arc<Path>, path: &PathBuf, contents: &[u8]) -> Result<(), BoxError> {
     let path = dir.join(path);
     let dir = path.parent().ok_or("invalid record directory")?;
@@ -307,3 +425,98 @@ fn is_introspection(request: &supergraph::Request) -> bool {
             })
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(record_header: bool) -> supergraph::Request {
+        let mut request = supergraph::Request::fake_builder()
+            .query("{ me }")
+            .build()
+            .unwrap();
+        if record_header {
+            request
+                .supergraph_request
+                .headers_mut()
+                .insert(RECORD_HEADER, http::HeaderValue::from_static("true"));
+        }
+        request
+    }
+
+    #[test]
+    fn samples_requests() {
+        let sampling = RecordSampling::default();
+        assert!(sampling.should_record(&request(true)));
+        assert!(!sampling.should_record(&request(false)));
+
+        let sampling = RecordSampling {
+            header: false,
+            ..Default::default()
+        };
+        assert!(!sampling.should_record(&request(true)));
+
+        let sampling = RecordSampling {
+            rate: 1.0,
+            ..Default::default()
+        };
+        assert!(sampling.should_record(&request(false)));
+
+        let sampling = RecordSampling {
+            rate: 1.0,
+            condition: Condition::False,
+            ..Default::default()
+        };
+        assert!(!sampling.should_record(&request(true)));
+    }
+
+    fn recording_filename(operation_name: &str, digit: char) -> String {
+        format!("{operation_name}-{}.json", digit.to_string().repeat(64))
+    }
+
+    #[tokio::test]
+    async fn rotates_oldest_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        // Older than the recordings, but not named like one
+        std::fs::write(dir.path().join("config.json"), "{}").unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        for name in ["First", "Second", "Third"] {
+            std::fs::write(dir.path().join(recording_filename(name, 'a')), "{}").unwrap();
+            // Modification times need to differ for the files to be ordered
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+
+        rotate(dir.path(), 2).await.unwrap();
+
+        let mut remaining = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            [
+                "README.md".to_string(),
+                recording_filename("Second", 'a'),
+                recording_filename("Third", 'a'),
+                "config.json".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn recognizes_recording_filenames() {
+        assert!(Recording::is_recording_filename(&recording_filename(
+            "MyQuery", '0'
+        )));
+        assert!(Recording::is_recording_filename(&recording_filename(
+            "My-Query", 'f'
+        )));
+        assert!(!Recording::is_recording_filename("config.json"));
+        assert!(!Recording::is_recording_filename("MyQuery-abc.json"));
+        assert!(!Recording::is_recording_filename(&recording_filename(
+            "MyQuery", 'g'
+        )));
+    }
+}
@@ -36,6 +36,19 @@ impl Recording {
 
         PathBuf::from(format!("{}-{}.json", operation_name, hash))
     }
+
+    /// Whether `file_name` follows the naming scheme of [`Recording::filename`]
+    pub(super) fn is_recording_filename(file_name: &str) -> bool {
+        file_name
+            .strip_suffix(".json")
+            .and_then(|name| name.rsplit_once('-'))
+            .is_some_and(|(_, hash)| {
+                hash.len() == 64
+                    && hash
+                        .bytes()
+                        .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+            })
+    }
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,233 @@
+//! Removes sensitive values from recordings before they are written.
+
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json_bytes::Value;
+
+use super::recording::Recording;
+use super::recording::RequestDetails;
+use super::recording::ResponseDetails;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Values removed from recordings before they are written.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, default)]
+pub(super) struct Redaction {
+    /// Names of the headers redacted in client and subgraph requests and responses.
+    headers: Vec<String>,
+    /// Paths of the variables redacted in client and subgraph requests, such as
+    /// `input.password`. `@` matches every element of a list.
+    variables: Vec<String>,
+    /// Paths of the values redacted in client and subgraph responses, such as `data.me.email`.
+    /// Paths start with `data` or `extensions`, and `@` matches every element of a list.
+    response_paths: Vec<String>,
+}
+
+impl Redaction {
+    pub(super) fn validate(&self) -> Result<(), String> {
+        for path in &self.response_paths {
+            if !matches!(path.split('.').next(), Some("data" | "extensions")) {
+                return Err(format!(
+                    "response path `{path}` must start with `data` or `extensions`"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn apply(&self, recording: &mut Recording) {
+        self.redact_request(&mut recording.client_request);
+        self.redact_response(&mut recording.client_response);
+        for fetch in recording
+            .subgraph_fetches
+            .iter_mut()
+            .flat_map(|fetches| fetches.values_mut())
+        {
+            self.redact_request(&mut fetch.request);
+            self.redact_response(&mut fetch.response);
+        }
+    }
+
+    fn redact_headers(&self, headers: &mut HashMap<String, Vec<String>>) {
+        for (name, values) in headers.iter_mut() {
+            if self
+                .headers
+                .iter()
+                .any(|redacted| redacted.eq_ignore_ascii_case(name))
+            {
+                values.iter_mut().for_each(|value| *value = REDACTED.into());
+            }
+        }
+    }
+
+    fn redact_request(&self, request: &mut RequestDetails) {
+        self.redact_headers(&mut request.headers);
+        for path in &self.variables {
+            let segments = path.split('.').collect::<Vec<_>>();
+            if let Some((name, rest)) = segments.split_first() {
+                if let Some(value) = request.variables.get_mut(*name) {
+                    redact(value, rest);
+                }
+            }
+        }
+    }
+
+    fn redact_response(&self, response: &mut ResponseDetails) {
+        self.redact_headers(&mut response.headers);
+        for chunk in &mut response.chunks {
+            for path in &self.response_paths {
+                let segments = path.split('.').collect::<Vec<_>>();
+                match segments.split_first() {
+                    Some((&"data", rest)) => {
+                        if let Some(data) = &mut chunk.data {
+                            redact(data, rest);
+                        }
+                    }
+                    Some((&"extensions", [key, rest @ ..])) => {
+                        if let Some(value) = chunk.extensions.get_mut(*key) {
+                            redact(value, rest);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn redact(value: &mut Value, path: &[&str]) {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = Value::String(REDACTED.into());
+        return;
+    };
+    match value {
+        Value::Array(items) if *segment == "@" => {
+            items.iter_mut().for_each(|item| redact(item, rest));
+        }
+        Value::Array(items) => {
+            if let Some(item) = segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+            {
+                redact(item, rest);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(value) = object.get_mut(*segment) {
+                redact(value, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::graphql;
+    use crate::plugins::record_replay::recording::Subgraph;
+
+    fn redaction(headers: &[&str], variables: &[&str], response_paths: &[&str]) -> Redaction {
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        Redaction {
+            headers: strings(headers),
+            variables: strings(variables),
+            response_paths: strings(response_paths),
+        }
+    }
+
+    fn recording() -> Recording {
+        let request = RequestDetails {
+            variables: json!({
+                "input": { "email": "ada@example.com", "password": "secret" },
+                "ids": [1, 2]
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+            headers: HashMap::from([
+                ("authorization".to_string(), vec!["Bearer abc".to_string()]),
+                ("x-client".to_string(), vec!["web".to_string()]),
+            ]),
+            ..Default::default()
+        };
+        let response = ResponseDetails {
+            chunks: vec![
+                graphql::Response::builder()
+                    .data(json!({
+                        "me": { "name": "Ada", "email": "ada@example.com" },
+                        "friends": [{ "email": "grace@example.com" }, { "email": null }]
+                    }))
+                    .extension("token", json!("abc"))
+                    .build(),
+            ],
+            headers: HashMap::from([("set-cookie".to_string(), vec!["session=1".to_string()])]),
+        };
+        Recording {
+            supergraph_sdl: String::new(),
+            client_request: request.clone(),
+            client_response: response.clone(),
+            formatted_query_plan: None,
+            subgraph_fetches: Some(HashMap::from([(
+                "Me__accounts__0".to_string(),
+                Subgraph {
+                    subgraph_name: "accounts".to_string(),
+                    request,
+                    response,
+                },
+            )])),
+        }
+    }
+
+    #[test]
+    fn redacts_headers_variables_and_response_paths() {
+        let mut recording = recording();
+        redaction(
+            &["Authorization", "set-cookie"],
+            &["input.password", "ids.@"],
+            &["data.me.email", "data.friends.@.email", "extensions.token"],
+        )
+        .apply(&mut recording);
+
+        let fetch = recording.subgraph_fetches.as_ref().unwrap()["Me__accounts__0"].clone();
+        for (request, response) in [
+            (&recording.client_request, &recording.client_response),
+            (&fetch.request, &fetch.response),
+        ] {
+            assert_eq!(request.headers["authorization"], vec![REDACTED]);
+            assert_eq!(request.headers["x-client"], vec!["web"]);
+            assert_eq!(
+                Value::Object(request.variables.clone()),
+                json!({
+                    "input": { "email": "ada@example.com", "password": REDACTED },
+                    "ids": [REDACTED, REDACTED]
+                })
+            );
+            assert_eq!(response.headers["set-cookie"], vec![REDACTED]);
+            assert_eq!(
+                response.chunks[0].data,
+                Some(json!({
+                    "me": { "name": "Ada", "email": REDACTED },
+                    "friends": [{ "email": REDACTED }, { "email": REDACTED }]
+                }))
+            );
+            assert_eq!(response.chunks[0].extensions["token"], json!(REDACTED));
+        }
+    }
+
+    #[test]
+    fn rejects_response_paths_outside_data_and_extensions() {
+        assert!(
+            redaction(&[], &[], &["data.me", "extensions.token"])
+                .validate()
+                .is_ok()
+        );
+        assert!(redaction(&[], &[], &["me.email"]).validate().is_err());
+    }
+}
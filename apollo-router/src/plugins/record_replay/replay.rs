@@ -3,8 +3,13 @@ use std::collections::HashSet;
 use std::ops::ControlFlow;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use console::style;
+use futures::StreamExt;
+use futures::future;
+use futures::stream;
 use http::Method;
 use http::Uri;
 use multimap::MultiMap;
@@ -35,7 +40,6 @@ pub(crate) struct Replay {
     pub(crate) report: Arc<Mutex<Vec<ReplayReport>>>,
 }
 
-#[allow(dead_code)]
 impl Replay {
     pub(crate) async fn from_file(recording_file: &Path) -> Result<Self, BoxError> {
         let recording = fs::read_to_string(recording_file).await?;
@@ -62,18 +66,17 @@ impl Replay {
         }
 
         let req = supergraph::Request::builder()
-            .query(client_request.query.unwrap().clone())
+            .query(
+                client_request
+                    .query
+                    .ok_or("the recorded client request has no query")?,
+            )
             .and_operation_name(client_request.operation_name.clone())
             .variables(client_request.variables.clone())
             .headers(request_headers)
             .context(Context::default())
-            .uri(client_request.uri.parse::<Uri>().expect("uri is valid"))
-            .method(
-                client_request
-                    .method
-                    .parse::<Method>()
-                    .expect("method is valid"),
-            )
+            .uri(client_request.uri.parse::<Uri>()?)
+            .method(client_request.method.parse::<Method>()?)
             .build()?;
 
         Ok(req.try_into()?)
@@ -132,31 +135,59 @@ impl Plugin for Replay {
 
     fn supergraph_service(&self, service: supergraph::BoxService) -> supergraph::BoxService {
         let report = self.report.clone();
-        let recorded_chunks = self.recording.client_response.chunks.clone();
+        let recorded_chunks = Arc::new(self.recording.client_response.chunks.clone());
 
         ServiceBuilder::new()
-            .map_response(|res: supergraph::Response| {
-                let mut i = 0;
-                res.map_stream(move |chunk| {
-                    let recorded_chunk = &recorded_chunks[i];
-                    let chunk = chunk.clone();
-
-                    // TODO - json string equality is sufficient?
-                    let recorded_chunk_str = serde_json::to_string_pretty(&recorded_chunk).unwrap();
-                    let chunk_str = serde_json::to_string_pretty(&chunk).unwrap();
-
-                    if recorded_chunk_str != chunk_str {
-                        report
-                            .lock()
-                            .push(ReplayReport::ClientResponseChunkDifference(
+            .map_response(move |res: supergraph::Response| {
+                let report = report.clone();
+                let recorded_chunks = recorded_chunks.clone();
+                res.map(move |chunks| {
+                    let received = Arc::new(AtomicUsize::new(0));
+
+                    let compared = {
+                        let report = report.clone();
+                        let recorded_chunks = recorded_chunks.clone();
+                        let received = received.clone();
+                        chunks.map(move |chunk| {
+                            let i = received.fetch_add(1, Ordering::SeqCst);
+
+                            // TODO - json string equality is sufficient?
+                            let recorded_chunk_str = recorded_chunks
+                                .get(i)
+                                .map(|recorded_chunk| {
+                                    serde_json::to_string_pretty(recorded_chunk).unwrap()
+                                })
+                                .unwrap_or_default();
+                            let chunk_str = serde_json::to_string_pretty(&chunk).unwrap();
+
+                            if recorded_chunk_str != chunk_str {
+                                report
+                                    .lock()
+                                    .push(ReplayReport::ClientResponseChunkDifference(
+                                        i,
+                                        recorded_chunk_str,
+                                        chunk_str,
+                                    ));
+                            }
+
+                            chunk
+                        })
+                    };
+
+                    // Once the stream ends, every recorded chunk that never arrived is a difference
+                    let missed = stream::once(async move {
+                        let received = received.load(Ordering::SeqCst);
+                        for (i, recorded_chunk) in recorded_chunks.iter().enumerate().skip(received)
+                        {
+                            report.lock().push(ReplayReport::ClientResponseChunkMissed(
                                 i,
-                                recorded_chunk_str.clone(),
-                                chunk_str.clone(),
+                                serde_json::to_string_pretty(recorded_chunk).unwrap(),
                             ));
-                    }
+                        }
+                    })
+                    .filter_map(|()| future::ready(None));
 
-                    i += 1;
-                    chunk
+                    compared.chain(missed).boxed()
                 })
             })
             .service(service)
@@ -168,18 +199,20 @@ impl Plugin for Replay {
         let report = self.report.clone();
         ServiceBuilder::new()
             .map_request(move |req: execution::Request| {
-                let recorded = recorded.clone().unwrap_or_default();
-                let runtime = req
-                    .query_plan
-                    .formatted_query_plan
-                    .clone()
-                    .unwrap_or_default();
-
-                if recorded != runtime {
-                    report.lock().push(ReplayReport::QueryPlanDifference(
-                        recorded.clone(),
-                        runtime.clone(),
-                    ));
+                // Recordings may leave the query plan out, in which case it is not compared
+                if let Some(recorded) = &recorded {
+                    let runtime = req
+                        .query_plan
+                        .formatted_query_plan
+                        .clone()
+                        .unwrap_or_default();
+
+                    if *recorded != runtime {
+                        report.lock().push(ReplayReport::QueryPlanDifference(
+                            recorded.clone(),
+                            runtime.clone(),
+                        ));
+                    }
                 }
 
                 req
@@ -212,12 +245,30 @@ impl Plugin for Replay {
                 // would mismatch if the request is wrong
                 if let Some(fetch) = fetches.get(&operation_name) {
                     let subgraph_response = subgraph::Response::new_from_response(
-                        http::Response::new(fetch.response.chunks[0].clone()),
+                        http::Response::new(
+                            fetch.response.chunks.first().cloned().unwrap_or_default(),
+                        ),
                         req.context.clone(),
                         subgraph_name.clone(),
                         req.id.clone(),
                     );
 
+                    if let Some(recorded_query) = &fetch.request.query {
+                        let runtime_query = req
+                            .subgraph_request
+                            .body()
+                            .query
+                            .clone()
+                            .unwrap_or_default();
+                        if *recorded_query != runtime_query {
+                            report.lock().push(ReplayReport::SubgraphQueryDifference {
+                                name: operation_name.clone(),
+                                recorded: recorded_query.clone(),
+                                runtime: runtime_query,
+                            });
+                        }
+                    }
+
                     let runtime_variables = req.subgraph_request.body().variables.clone();
                     let recorded_variables = fetch.request.variables.clone();
 
@@ -251,7 +302,13 @@ impl Plugin for Replay {
 pub(crate) enum ReplayReport {
     QueryPlanDifference(Arc<String>, Arc<String>),
     ClientResponseChunkDifference(usize, String, String),
+    ClientResponseChunkMissed(usize, String),
     SubgraphRequestMissed(String, String),
+    SubgraphQueryDifference {
+        name: String,
+        recorded: String,
+        runtime: String,
+    },
     HeaderDifference {
         name: String,
         recorded: Vec<String>,
@@ -265,7 +322,6 @@ pub(crate) enum ReplayReport {
 }
 
 // Aspects of this are liberally borrowed from [insta](https://insta.rs/)
-#[allow(dead_code)]
 impl ReplayReport {
     pub(crate) fn print(&self) {
         match self {
@@ -291,6 +347,14 @@ impl ReplayReport {
                     "From Runtime",
                 )
             }
+            ReplayReport::ClientResponseChunkMissed(index, recorded) => {
+                println!(
+                    "{}{}",
+                    style("Missing client response chunk #").bold(),
+                    style(index).red().bold()
+                );
+                self.print_changeset(recorded.as_str(), "", "From Recording", "From Runtime")
+            }
             ReplayReport::SubgraphRequestMissed(_subgraph, operation_name) => {
                 println!(
                     "{} {}",
@@ -299,6 +363,23 @@ impl ReplayReport {
                 );
                 print_line(74);
             }
+            ReplayReport::SubgraphQueryDifference {
+                name,
+                recorded,
+                runtime,
+            } => {
+                println!(
+                    "{} {}",
+                    style("Mismatched subgraph query:").bold(),
+                    style(name).red().bold()
+                );
+                self.print_changeset(
+                    recorded.as_str(),
+                    runtime.as_str(),
+                    "From Recording",
+                    "From Runtime",
+                );
+            }
             ReplayReport::HeaderDifference {
                 name,
                 recorded,
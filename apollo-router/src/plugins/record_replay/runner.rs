@@ -0,0 +1,258 @@
+//! Replay recordings made by the record plugin, outside of a running router.
+//!
+//! Each recording is replayed through a router built with the given configuration, with the
+//! subgraphs mocked by the recorded subgraph responses. The client response and the subgraph
+//! requests are compared with the recording, and differences are reported.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tower::BoxError;
+use tower::ServiceExt;
+
+use super::replay::Replay;
+use super::replay::ReplayReport;
+use crate::TestHarness;
+
+/// Options for a replay run
+pub(crate) struct ReplayOptions {
+    /// A recording file, or a directory of recording files
+    pub(crate) recordings: PathBuf,
+    /// The router configuration, as YAML. Defaults to an empty configuration.
+    pub(crate) configuration: Option<String>,
+    /// The supergraph schema. Defaults to the schema stored in each recording.
+    pub(crate) supergraph_sdl: Option<String>,
+}
+
+/// The outcome of a replay run
+#[derive(Debug, Default)]
+pub(crate) struct ReplaySummary {
+    pub(crate) matched: usize,
+    pub(crate) differed: usize,
+    pub(crate) failed: usize,
+}
+
+impl fmt::Display for ReplaySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = if self.differed + self.failed > 0 {
+            "DIFFERED"
+        } else {
+            "ok"
+        };
+        writeln!(
+            f,
+            "\nreplay result: {result}. {} matched; {} differed; {} failed",
+            self.matched, self.differed, self.failed
+        )
+    }
+}
+
+/// Replay every recording and print the differences found.
+pub(crate) async fn run(options: ReplayOptions) -> Result<ReplaySummary, BoxError> {
+    let files = recording_files(&options.recordings)?;
+    println!(
+        "replaying {} recordings from {}",
+        files.len(),
+        options.recordings.display()
+    );
+
+    let mut summary = ReplaySummary::default();
+    for path in files {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match replay_file(&path, &options).await {
+            Ok(report) if report.is_empty() => {
+                println!("replay {name} ... ok");
+                summary.matched += 1;
+            }
+            Ok(report) => {
+                println!("replay {name} ... DIFFERED\n");
+                for item in report {
+                    item.print();
+                    println!();
+                }
+                summary.differed += 1;
+            }
+            Err(error) => {
+                println!("replay {name} ... FAILED\n  recording could not be replayed: {error}");
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn recording_files(path: &Path) -> Result<Vec<PathBuf>, BoxError> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = std::fs::read_dir(path)
+        .map_err(|e| {
+            format!(
+                "could not read recordings directory {}: {e}",
+                path.display()
+            )
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(format!("no recordings found in {}", path.display()).into());
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn replay_file(path: &Path, options: &ReplayOptions) -> Result<Vec<ReplayReport>, BoxError> {
+    let replay = Replay::from_file(path).await?;
+    let request = replay.make_client_request()?;
+    let report = replay.report.clone();
+    let supergraph_sdl = options
+        .supergraph_sdl
+        .clone()
+        .unwrap_or_else(|| replay.supergraph_sdl());
+
+    let mut harness = TestHarness::builder().schema(&supergraph_sdl);
+    if let Some(configuration) = &options.configuration {
+        harness = harness.configuration_yaml(configuration)?;
+    }
+    let router = harness.extra_plugin(replay).build_router().await?;
+
+    let mut response = router.oneshot(request).await?;
+    while response.next_response().await.is_some() {}
+
+    Ok(std::mem::take(&mut *report.lock()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::graphql;
+    use crate::plugins::record_replay::recording::Recording;
+    use crate::plugins::record_replay::recording::RequestDetails;
+    use crate::plugins::record_replay::recording::ResponseDetails;
+    use crate::plugins::record_replay::recording::Subgraph;
+
+    fn recording(recorded_me: &str) -> Recording {
+        let response = |me: &str| ResponseDetails {
+            chunks: vec![
+                graphql::Response::builder()
+                    .data(json!({ "me": me }))
+                    .build(),
+            ],
+            headers: HashMap::new(),
+        };
+        Recording {
+            supergraph_sdl: include_str!("../../testdata/minimal_supergraph.graphql").to_string(),
+            client_request: RequestDetails {
+                query: Some("query Me { me }".to_string()),
+                operation_name: Some("Me".to_string()),
+                headers: HashMap::from([(
+                    "content-type".to_string(),
+                    vec!["application/json".to_string()],
+                )]),
+                method: "POST".to_string(),
+                uri: "http://localhost/".to_string(),
+                ..Default::default()
+            },
+            client_response: response(recorded_me),
+            formatted_query_plan: None,
+            subgraph_fetches: Some(HashMap::from([(
+                "Me__subgraph_a__0".to_string(),
+                Subgraph {
+                    subgraph_name: "subgraph-a".to_string(),
+                    request: RequestDetails::default(),
+                    response: response("Ada"),
+                },
+            )])),
+        }
+    }
+
+    fn write(dir: &Path, name: &str, recording: &Recording) {
+        std::fs::write(dir.join(name), serde_json::to_string(recording).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_differences_with_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "matching.json", &recording("Ada"));
+        write(dir.path(), "differing.json", &recording("Grace"));
+        std::fs::write(dir.path().join("broken.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("README.md"), "not a recording").unwrap();
+
+        let summary = run(ReplayOptions {
+            recordings: dir.path().to_path_buf(),
+            configuration: None,
+            supergraph_sdl: None,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(summary.matched, 1);
+        assert_eq!(summary.differed, 1);
+        assert_eq!(summary.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn reports_recorded_chunks_that_never_arrived() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recording = recording("Ada");
+        recording.client_response.chunks.push(
+            graphql::Response::builder()
+                .data(json!({ "me": "Grace" }))
+                .build(),
+        );
+        write(dir.path(), "missing.json", &recording);
+
+        let path = dir.path().join("missing.json");
+        let report = replay_file(
+            &path,
+            &ReplayOptions {
+                recordings: path.clone(),
+                configuration: None,
+                supergraph_sdl: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            matches!(
+                report.as_slice(),
+                [ReplayReport::ClientResponseChunkMissed(1, _)]
+            ),
+            "{report:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_a_single_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "matching.json", &recording("Ada"));
+
+        let summary = run(ReplayOptions {
+            recordings: dir.path().join("matching.json"),
+            configuration: Some("supergraph:\n  introspection: false\n".to_string()),
+            supergraph_sdl: None,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(summary.matched, 1);
+        assert_eq!(summary.differed + summary.failed, 0);
+    }
+
+    #[test]
+    fn rejects_empty_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(recording_files(dir.path()).is_err());
+    }
+}
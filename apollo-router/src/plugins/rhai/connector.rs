@@ -0,0 +1,139 @@
+//! connector module
+
+use std::ops::ControlFlow;
+
+use apollo_federation::connectors::runtime::errors::Error;
+use apollo_federation::connectors::runtime::errors::RuntimeError;
+use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
+use apollo_federation::connectors::runtime::http_json_transport::TransportResponse;
+use apollo_federation::connectors::runtime::responses::MappedResponse;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::Method;
+use http::Uri;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use serde_json_bytes::Value;
+use tower::BoxError;
+
+use super::ErrorDetails;
+pub(crate) use crate::services::connector::request_service::*;
+
+/// A snapshot of the HTTP request which will be sent to a connector source.
+///
+/// Rhai scripts interact with a copy of the request. Any changes are written back to the
+/// transport request when the `connector` property of the connector request is set.
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectorHttpRequest {
+    pub(crate) method: Method,
+    pub(crate) uri: Uri,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Value,
+}
+
+impl ConnectorHttpRequest {
+    pub(super) fn from_request(request: &Request) -> Self {
+        let TransportRequest::Http(ref http_request) = request.transport_request;
+        let inner = &http_request.inner;
+        let body = if inner.body().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(inner.body())
+                .unwrap_or_else(|_| Value::String(inner.body().clone().into()))
+        };
+        Self {
+            method: inner.method().clone(),
+            uri: inner.uri().clone(),
+            headers: inner.headers().clone(),
+            body,
+        }
+    }
+
+    pub(super) fn apply_to(self, request: &mut Request) -> Result<(), BoxError> {
+        let TransportRequest::Http(ref mut http_request) = request.transport_request;
+        let is_json = self
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .map(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+            .unwrap_or(true);
+        let body = match self.body {
+            Value::Null => String::new(),
+            Value::String(s) if !is_json => s.as_str().to_string(),
+            body => serde_json::to_string(&body)?,
+        };
+
+        let inner = &mut http_request.inner;
+        *inner.method_mut() = self.method;
+        *inner.uri_mut() = self.uri;
+        *inner.headers_mut() = self.headers;
+        // Keep the content length in sync with the (possibly) modified body
+        if inner.headers().contains_key(CONTENT_LENGTH) {
+            inner
+                .headers_mut()
+                .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        }
+        *inner.body_mut() = body;
+        Ok(())
+    }
+}
+
+fn runtime_error(
+    connector: &apollo_federation::connectors::Connector,
+    key: &apollo_federation::connectors::runtime::key::ResponseKey,
+    error_details: ErrorDetails,
+) -> RuntimeError {
+    let message = error_details
+        .message
+        .or_else(|| {
+            error_details
+                .body
+                .as_ref()
+                .and_then(|body| body.errors.first().map(|error| error.message.clone()))
+        })
+        .unwrap_or_default();
+    let mut error = RuntimeError::new(message, key).with_code("RHAI_ERROR");
+    error.subgraph_name = Some(connector.id.subgraph_name.clone());
+    error.coordinate = Some(connector.id.coordinate());
+    error.extension(
+        "http",
+        serde_json_bytes::json!({ "status": error_details.status.as_u16() }),
+    )
+}
+
+pub(super) fn request_failure(
+    request: Request,
+    error_details: ErrorDetails,
+) -> Result<ControlFlow<Response, Request>, BoxError> {
+    let error = runtime_error(&request.connector, &request.key, error_details);
+    Ok(ControlFlow::Break(Response {
+        context: request.context,
+        connector: request.connector,
+        transport_result: Err(Error::TransportFailure(error.message.clone())),
+        mapped_response: MappedResponse::Error {
+            error,
+            key: request.key,
+        },
+        upstream_body: None,
+        errors: Vec::new(),
+    }))
+}
+
+pub(super) fn response_failure(response: Response, error_details: ErrorDetails) -> Response {
+    let key = match response.mapped_response {
+        MappedResponse::Error { key, .. } | MappedResponse::Data { key, .. } => key,
+    };
+    let error = runtime_error(&response.connector, &key, error_details);
+    Response {
+        mapped_response: MappedResponse::Error { error, key },
+        ..response
+    }
+}
+
+pub(super) fn response_headers(response: &Response) -> HeaderMap {
+    match response.transport_result {
+        Ok(TransportResponse::Http(ref http_response)) => http_response.inner.headers.clone(),
+        Err(_) => HeaderMap::new(),
+    }
+}
@@ -3,6 +3,8 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::SystemTime;
 
+use apollo_federation::connectors::runtime::http_json_transport::TransportResponse;
+use apollo_federation::connectors::runtime::responses::MappedResponse;
 use base64::Engine as _;
 use base64::prelude::BASE64_STANDARD;
 use base64::prelude::BASE64_STANDARD_NO_PAD;
@@ -37,6 +39,8 @@ use uuid::Uuid;
 
 use super::Rhai;
 use super::ServiceStep;
+use super::connector;
+use super::connector::ConnectorHttpRequest;
 use super::execution;
 use super::router;
 use super::subgraph;
@@ -688,6 +692,211 @@ mod router_plugin {
     }
     // End of SubgraphRequest specific section
 
+    // The next group of functions are specifically for interacting
+    // with connector requests and responses.
+    #[rhai_fn(get = "context", pure)]
+    pub(crate) fn get_connector_request_context(
+        obj: &mut SharedMut<connector::Request>,
+    ) -> Context {
+        obj.with_mut(|request| request.context.clone())
+    }
+
+    #[rhai_fn(set = "context")]
+    pub(crate) fn set_connector_request_context(
+        obj: &mut SharedMut<connector::Request>,
+        context: Context,
+    ) {
+        obj.with_mut(|request| request.context = context);
+    }
+
+    #[rhai_fn(get = "id", pure)]
+    pub(crate) fn get_connector_request_id(obj: &mut SharedMut<connector::Request>) -> String {
+        obj.with_mut(|request| request.context.id.clone())
+    }
+
+    #[rhai_fn(get = "source_name", pure)]
+    pub(crate) fn get_connector_request_source_name(
+        obj: &mut SharedMut<connector::Request>,
+    ) -> Dynamic {
+        obj.with_mut(|request| {
+            request
+                .connector
+                .id
+                .source_name
+                .as_ref()
+                .map(|name| Dynamic::from(name.to_string()))
+                .unwrap_or_default()
+        })
+    }
+
+    #[rhai_fn(get = "headers", pure)]
+    pub(crate) fn get_connector_originating_headers(
+        obj: &mut SharedMut<connector::Request>,
+    ) -> HeaderMap {
+        obj.with_mut(|request| request.supergraph_request.headers().clone())
+    }
+
+    #[rhai_fn(get = "connector", pure)]
+    pub(crate) fn get_connector(obj: &mut SharedMut<connector::Request>) -> ConnectorHttpRequest {
+        obj.with_mut(|request| ConnectorHttpRequest::from_request(request))
+    }
+
+    #[rhai_fn(set = "connector", return_raw)]
+    pub(crate) fn set_connector(
+        obj: &mut SharedMut<connector::Request>,
+        http_request: ConnectorHttpRequest,
+    ) -> Result<(), Box<EvalAltResult>> {
+        obj.with_mut(|request| http_request.apply_to(request))
+            .map_err(|e| e.to_string().into())
+    }
+
+    #[rhai_fn(get = "headers", pure)]
+    pub(crate) fn get_connector_http_headers(obj: &mut ConnectorHttpRequest) -> HeaderMap {
+        obj.headers.clone()
+    }
+
+    #[rhai_fn(set = "headers")]
+    pub(crate) fn set_connector_http_headers(obj: &mut ConnectorHttpRequest, headers: HeaderMap) {
+        obj.headers = headers;
+    }
+
+    #[rhai_fn(get = "method", pure)]
+    pub(crate) fn get_connector_http_method(obj: &mut ConnectorHttpRequest) -> Method {
+        obj.method.clone()
+    }
+
+    #[rhai_fn(set = "method", return_raw)]
+    pub(crate) fn set_connector_http_method(
+        obj: &mut ConnectorHttpRequest,
+        method: &str,
+    ) -> Result<(), Box<EvalAltResult>> {
+        obj.method = Method::from_str(&method.to_uppercase()).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[rhai_fn(get = "uri", pure)]
+    pub(crate) fn get_connector_http_uri(obj: &mut ConnectorHttpRequest) -> Uri {
+        obj.uri.clone()
+    }
+
+    #[rhai_fn(set = "uri")]
+    pub(crate) fn set_connector_http_uri(obj: &mut ConnectorHttpRequest, uri: Uri) {
+        obj.uri = uri;
+    }
+
+    #[rhai_fn(get = "body", pure, return_raw)]
+    pub(crate) fn get_connector_http_body(
+        obj: &mut ConnectorHttpRequest,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        to_dynamic(obj.body.clone())
+    }
+
+    #[rhai_fn(set = "body", return_raw)]
+    pub(crate) fn set_connector_http_body(
+        obj: &mut ConnectorHttpRequest,
+        body: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        obj.body = from_dynamic(&body)?;
+        Ok(())
+    }
+
+    #[rhai_fn(name = "to_string", pure)]
+    pub(crate) fn connector_http_request_to_string(obj: &mut ConnectorHttpRequest) -> String {
+        format!("{obj:?}")
+    }
+
+    #[rhai_fn(get = "context", pure)]
+    pub(crate) fn get_connector_response_context(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> Context {
+        obj.with_mut(|response| response.context.clone())
+    }
+
+    #[rhai_fn(set = "context")]
+    pub(crate) fn set_connector_response_context(
+        obj: &mut SharedMut<connector::Response>,
+        context: Context,
+    ) {
+        obj.with_mut(|response| response.context = context);
+    }
+
+    #[rhai_fn(get = "id", pure)]
+    pub(crate) fn get_connector_response_id(obj: &mut SharedMut<connector::Response>) -> String {
+        obj.with_mut(|response| response.context.id.clone())
+    }
+
+    #[rhai_fn(get = "source_name", pure)]
+    pub(crate) fn get_connector_response_source_name(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> Dynamic {
+        obj.with_mut(|response| {
+            response
+                .connector
+                .id
+                .source_name
+                .as_ref()
+                .map(|name| Dynamic::from(name.to_string()))
+                .unwrap_or_default()
+        })
+    }
+
+    #[rhai_fn(get = "status_code", pure)]
+    pub(crate) fn get_connector_response_status_code(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> Dynamic {
+        obj.with_mut(|response| match response.transport_result {
+            Ok(TransportResponse::Http(ref http_response)) => {
+                Dynamic::from(http_response.inner.status)
+            }
+            Err(_) => Dynamic::UNIT,
+        })
+    }
+
+    #[rhai_fn(get = "headers", pure)]
+    pub(crate) fn get_connector_response_headers(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> HeaderMap {
+        obj.with_mut(|response| connector::response_headers(response))
+    }
+
+    #[rhai_fn(get = "upstream_body", pure, return_raw)]
+    pub(crate) fn get_connector_response_upstream_body(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        obj.with_mut(|response| match response.upstream_body {
+            Some(ref body) => to_dynamic(body.clone()),
+            None => Ok(Dynamic::UNIT),
+        })
+    }
+
+    #[rhai_fn(get = "body", pure, return_raw)]
+    pub(crate) fn get_connector_response_body(
+        obj: &mut SharedMut<connector::Response>,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        obj.with_mut(|response| match response.mapped_response {
+            MappedResponse::Data { ref data, .. } => to_dynamic(data.clone()),
+            MappedResponse::Error { .. } => Ok(Dynamic::UNIT),
+        })
+    }
+
+    #[rhai_fn(set = "body", return_raw)]
+    pub(crate) fn set_connector_response_body(
+        obj: &mut SharedMut<connector::Response>,
+        body: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let body: serde_json_bytes::Value = from_dynamic(&body)?;
+        obj.with_mut(|response| match response.mapped_response {
+            MappedResponse::Data { ref mut data, .. } => {
+                *data = body;
+                Ok(())
+            }
+            MappedResponse::Error { .. } => {
+                Err("cannot set the body of a failed connector response".into())
+            }
+        })
+    }
+    // End of connector specific section
+
     #[rhai_fn(get = "headers", pure, return_raw)]
     pub(crate) fn get_originating_headers_router_response(
         obj: &mut SharedMut<router::FirstResponse>,
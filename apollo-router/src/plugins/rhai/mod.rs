@@ -30,19 +30,21 @@ use self::engine::RhaiService;
 use self::engine::SharedMut;
 use crate::error::Error;
 use crate::layers::ServiceBuilderExt;
-use crate::plugin::Plugin;
 use crate::plugin::PluginInit;
+use crate::plugin::PluginPrivate;
 use crate::plugins::rhai::engine::OptionDance;
-use crate::register_plugin;
+use crate::register_private_plugin;
 
 mod engine;
 
 pub(crate) const RHAI_SPAN_NAME: &str = "rhai_plugin";
 
+mod connector;
 mod execution;
 mod router;
 mod subgraph;
 mod supergraph;
+pub(crate) mod test_runner;
 
 /// Plugin which implements Rhai functionality
 struct Rhai {
@@ -62,7 +64,7 @@ pub(crate) struct Conf {
 }
 
 #[async_trait::async_trait]
-impl Plugin for Rhai {
+impl PluginPrivate for Rhai {
     type Config = Conf;
 
     async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
@@ -190,6 +192,32 @@ impl Plugin for Rhai {
         }
         shared_service.take_unwrap()
     }
+
+    fn connector_request_service(
+        &self,
+        service: connector::BoxService,
+        source_name: String,
+    ) -> connector::BoxService {
+        const FUNCTION_NAME_SERVICE: &str = "connector_service";
+        if !self.ast_has_function(FUNCTION_NAME_SERVICE) {
+            return service;
+        }
+        tracing::debug!("connector_service function found");
+        let shared_service = Arc::new(Mutex::new(Some(service)));
+        if let Err(error) = self.run_rhai_service(
+            FUNCTION_NAME_SERVICE,
+            Some(&source_name),
+            ServiceStep::Connector(shared_service.clone()),
+            self.scope.clone(),
+        ) {
+            tracing::error!(
+                service = "ConnectorService",
+                source = source_name,
+                "service callback failed: {error}"
+            );
+        }
+        shared_service.take_unwrap()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -198,6 +226,7 @@ pub(crate) enum ServiceStep {
     Supergraph(SharedMut<supergraph::BoxService>),
     Execution(SharedMut<execution::BoxService>),
     Subgraph(SharedMut<subgraph::BoxService>),
+    Connector(SharedMut<connector::BoxService>),
 }
 
 // Actually use the checkpoint function so that we can shortcut requests which fail
@@ -588,6 +617,68 @@ macro_rules! gen_map_deferred_response {
     };
 }
 
+// Connector requests and responses can't be rebuilt from a context alone, so the failure
+// functions are handed the whole request or response.
+macro_rules! gen_map_connector_request {
+    ($base: ident, $borrow: ident, $rhai_service: ident, $callback: ident) => {
+        $borrow.replace(|service| {
+            fn rhai_service_span() -> impl Fn(&$base::Request) -> tracing::Span + Clone {
+                move |_request: &$base::Request| {
+                    tracing::info_span!(
+                        RHAI_SPAN_NAME,
+                        "rhai service" = stringify!($base::Request),
+                        "otel.kind" = "INTERNAL"
+                    )
+                }
+            }
+            ServiceBuilder::new()
+                .instrument(rhai_service_span())
+                .checkpoint(move |request: $base::Request| {
+                    let shared_request = Shared::new(Mutex::new(Some(request)));
+                    let result: Result<Dynamic, Box<EvalAltResult>> =
+                        execute(&$rhai_service, &$callback, (shared_request.clone(),));
+                    let mut guard = shared_request.lock();
+                    let request = guard.take().unwrap();
+                    if let Err(error) = result {
+                        let error_details = process_error(error);
+                        tracing::error!("map_request callback failed: {error_details:#?}");
+                        return $base::request_failure(request, error_details);
+                    }
+                    Ok(ControlFlow::Continue(request))
+                })
+                .service(service)
+                .boxed()
+        })
+    };
+}
+
+macro_rules! gen_map_connector_response {
+    ($base: ident, $borrow: ident, $rhai_service: ident, $callback: ident) => {
+        $borrow.replace(|service| {
+            service
+                .map_request(|mut request: $base::Request| {
+                    // Scripts can read the response body before it was mapped
+                    request.retain_upstream_body = true;
+                    request
+                })
+                .map_response(move |response: $base::Response| {
+                    let shared_response = Shared::new(Mutex::new(Some(response)));
+                    let result: Result<Dynamic, Box<EvalAltResult>> =
+                        execute(&$rhai_service, &$callback, (shared_response.clone(),));
+                    let mut guard = shared_response.lock();
+                    let response = guard.take().unwrap();
+                    if let Err(error) = result {
+                        tracing::error!("map_response callback failed: {error}");
+                        let error_details = process_error(error);
+                        return $base::response_failure(response, error_details);
+                    }
+                    response
+                })
+                .boxed()
+        })
+    };
+}
+
 impl ServiceStep {
     fn map_request(&mut self, rhai_service: RhaiService, callback: FnPtr) {
         match self {
@@ -603,6 +694,9 @@ impl ServiceStep {
             ServiceStep::Subgraph(service) => {
                 gen_map_request!(subgraph, service, rhai_service, callback);
             }
+            ServiceStep::Connector(service) => {
+                gen_map_connector_request!(connector, service, rhai_service, callback);
+            }
         }
     }
 
@@ -620,6 +714,9 @@ impl ServiceStep {
             ServiceStep::Subgraph(service) => {
                 gen_map_response!(subgraph, service, rhai_service, callback);
             }
+            ServiceStep::Connector(service) => {
+                gen_map_connector_response!(connector, service, rhai_service, callback);
+            }
         }
     }
 }
@@ -703,7 +800,7 @@ fn execute(
     }
 }
 
-register_plugin!("apollo", "rhai", Rhai);
+register_private_plugin!("apollo", "rhai", Rhai);
 
 #[cfg(test)]
 mod tests;
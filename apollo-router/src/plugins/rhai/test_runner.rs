@@ -0,0 +1,646 @@
+//! Run Rhai scripts against fixture requests and responses, outside of a running router.
+//!
+//! Each fixture file in the fixtures directory holds a list of test cases. A test case sends a
+//! request through one of the Rhai service hooks, with a mocked downstream service returning the
+//! fixture response, and then checks the request seen by the downstream service and the
+//! response returned by the hook against the expectations.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::Uri;
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json_bytes::Map;
+use serde_json_bytes::Value;
+use tower::BoxError;
+use tower::ServiceExt;
+
+use super::Conf;
+use super::Rhai;
+use crate::Context;
+use crate::graphql;
+use crate::plugin::PluginInit;
+use crate::plugin::PluginPrivate;
+use crate::services::router;
+use crate::services::subgraph;
+use crate::services::supergraph;
+
+const FIXTURE_EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+/// Options for a Rhai test run
+pub(crate) struct TestOptions {
+    /// The directory where Rhai scripts can be found
+    pub(crate) scripts: PathBuf,
+    /// The main entry point for Rhai script evaluation
+    pub(crate) main: String,
+    /// The directory containing fixture files
+    pub(crate) fixtures: PathBuf,
+    /// The supergraph schema exposed to scripts as `apollo_sdl`
+    pub(crate) supergraph_sdl: Option<String>,
+}
+
+/// A file of test cases
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct FixtureFile {
+    tests: Vec<TestCase>,
+}
+
+/// The service hook a test case is run through
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum Stage {
+    Router,
+    Supergraph,
+    Subgraph,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct TestCase {
+    /// Name of the test case, used in the report
+    name: String,
+    /// The service hook to run
+    service: Stage,
+    /// The subgraph name passed to `subgraph_service`
+    #[serde(default)]
+    subgraph: Option<String>,
+    /// The request sent to the service hook
+    #[serde(default)]
+    request: RequestFixture,
+    /// The response returned by the mocked downstream service
+    #[serde(default)]
+    response: ResponseFixture,
+    /// What the downstream service and the caller should see
+    #[serde(default)]
+    expect: Expectations,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct RequestFixture {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    headers: IndexMap<String, String>,
+    /// Headers of the client request, for `subgraph` test cases
+    #[serde(default)]
+    supergraph_headers: IndexMap<String, String>,
+    #[serde(default)]
+    context: Map<String, Value>,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct ResponseFixture {
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default)]
+    headers: IndexMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct Expectations {
+    /// The request seen by the mocked downstream service
+    #[serde(default)]
+    request: Option<Expected>,
+    /// The response returned by the service hook
+    #[serde(default)]
+    response: Option<Expected>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    #[serde(default)]
+    status: Option<u16>,
+    /// A `null` value asserts that the header is absent
+    #[serde(default)]
+    headers: IndexMap<String, Option<String>>,
+    /// A `null` value asserts that the key is absent
+    #[serde(default)]
+    context: Map<String, Value>,
+    /// Compared with the whole body
+    #[serde(default)]
+    body: Option<Value>,
+    /// Compared with the GraphQL errors of the body. Each expected error only needs to contain
+    /// the fields it lists.
+    #[serde(default)]
+    errors: Option<Vec<Value>>,
+}
+
+/// A request or response as seen by the test runner
+struct Observed {
+    status: Option<StatusCode>,
+    headers: HeaderMap,
+    context: Context,
+    body: Value,
+}
+
+/// The outcome of a single test case
+pub(crate) struct TestOutcome {
+    name: String,
+    failures: Vec<String>,
+}
+
+/// The outcome of a test run
+pub(crate) struct TestReport {
+    fixtures: PathBuf,
+    outcomes: Vec<TestOutcome>,
+}
+
+impl TestReport {
+    pub(crate) fn failed(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| !outcome.failures.is_empty())
+            .count()
+    }
+
+    pub(crate) fn passed(&self) -> usize {
+        self.outcomes.len() - self.failed()
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "running {} Rhai tests from {}",
+            self.outcomes.len(),
+            self.fixtures.display()
+        )?;
+        for outcome in &self.outcomes {
+            let status = if outcome.failures.is_empty() {
+                "ok"
+            } else {
+                "FAILED"
+            };
+            writeln!(f, "test {} ... {status}", outcome.name)?;
+        }
+
+        if self.failed() > 0 {
+            writeln!(f, "\nfailures:")?;
+            for outcome in self.outcomes.iter().filter(|o| !o.failures.is_empty()) {
+                writeln!(f, "\n---- {} ----", outcome.name)?;
+                for failure in &outcome.failures {
+                    writeln!(f, "  {failure}")?;
+                }
+            }
+        }
+
+        let result = if self.failed() > 0 { "FAILED" } else { "ok" };
+        writeln!(
+            f,
+            "\ntest result: {result}. {} passed; {} failed",
+            self.passed(),
+            self.failed()
+        )
+    }
+}
+
+/// Load the scripts and run every test case found in the fixtures directory.
+pub(crate) async fn run(options: TestOptions) -> Result<TestReport, BoxError> {
+    let init = PluginInit::fake_builder()
+        .config(Conf {
+            scripts: Some(options.scripts.clone()),
+            main: Some(options.main.clone()),
+        })
+        .supergraph_sdl(Arc::new(options.supergraph_sdl.unwrap_or_default()))
+        .build();
+    let rhai = Rhai::new(init).await?;
+
+    let mut outcomes = Vec::new();
+    for path in fixture_files(&options.fixtures)? {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for case in load_fixture(&path)?.tests {
+            let name = format!("{file_name}: {}", case.name);
+            let failures = match run_case(&rhai, &case).await {
+                Ok(failures) => failures,
+                Err(error) => vec![format!("test case could not run: {error}")],
+            };
+            outcomes.push(TestOutcome { name, failures });
+        }
+    }
+
+    Ok(TestReport {
+        fixtures: options.fixtures,
+        outcomes,
+    })
+}
+
+fn fixture_files(dir: &Path) -> Result<Vec<PathBuf>, BoxError> {
+    let mut files = std::fs::read_dir(dir)
+        .map_err(|e| format!("could not read fixtures directory {}: {e}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| FIXTURE_EXTENSIONS.contains(&ext))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+fn load_fixture(path: &Path) -> Result<FixtureFile, BoxError> {
+    let content = std::fs::read_to_string(path)?;
+    let fixture = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| e.to_string())
+    };
+    fixture.map_err(|e| format!("invalid fixture file {}: {e}", path.display()).into())
+}
+
+async fn run_case(rhai: &Rhai, case: &TestCase) -> Result<Vec<String>, BoxError> {
+    let (downstream, response) = match case.service {
+        Stage::Router => run_router(rhai, case).await?,
+        Stage::Supergraph => run_supergraph(rhai, case).await?,
+        Stage::Subgraph => run_subgraph(rhai, case).await?,
+    };
+
+    let mut failures = Vec::new();
+    if let Some(expected) = &case.expect.request {
+        match downstream {
+            Some(observed) => compare("request", expected, &observed, &mut failures),
+            None => failures.push(
+                "request: the downstream service was not called, the request was short-circuited"
+                    .to_string(),
+            ),
+        }
+    }
+    if let Some(expected) = &case.expect.response {
+        compare("response", expected, &response, &mut failures);
+    }
+    Ok(failures)
+}
+
+type Captured = Arc<Mutex<Option<Observed>>>;
+
+async fn run_router(
+    rhai: &Rhai,
+    case: &TestCase,
+) -> Result<(Option<Observed>, Observed), BoxError> {
+    let captured = Captured::default();
+    let inner = {
+        let captured = captured.clone();
+        let fixture = case.response.clone();
+        tower::service_fn(move |request: router::Request| {
+            let captured = captured.clone();
+            let fixture = fixture.clone();
+            async move {
+                let (parts, body) = request.router_request.into_parts();
+                let body = router::body::into_bytes(body).await?;
+                *captured.lock() = Some(Observed {
+                    status: None,
+                    headers: parts.headers,
+                    context: request.context.clone(),
+                    body: bytes_to_value(&body),
+                });
+
+                let mut response = http::Response::builder()
+                    .status(status(fixture.status)?)
+                    .body(router::body::from_bytes(serde_json::to_vec(&fixture.body)?))?;
+                *response.headers_mut() = header_map(&fixture.headers)?;
+                Ok::<_, BoxError>(router::Response {
+                    response,
+                    context: request.context,
+                })
+            }
+        })
+        .boxed()
+    };
+
+    let mut http_request = http::Request::builder()
+        .method(method(&case.request.method, Method::POST)?)
+        .uri(uri(&case.request.uri)?)
+        .body(router::body::from_bytes(serde_json::to_vec(
+            &case.request.body,
+        )?))?;
+    *http_request.headers_mut() = header_map(&case.request.headers)?;
+    let request = router::Request {
+        router_request: http_request,
+        context: context(&case.request.context),
+    };
+
+    let response = rhai.router_service(inner).oneshot(request).await?;
+    let (parts, body) = response.response.into_parts();
+    let body = router::body::into_bytes(body).await?;
+    let observed = Observed {
+        status: Some(parts.status),
+        headers: parts.headers,
+        context: response.context,
+        body: bytes_to_value(&body),
+    };
+    let downstream = captured.lock().take();
+    Ok((downstream, observed))
+}
+
+async fn run_supergraph(
+    rhai: &Rhai,
+    case: &TestCase,
+) -> Result<(Option<Observed>, Observed), BoxError> {
+    let captured = Captured::default();
+    let inner = {
+        let captured = captured.clone();
+        let fixture = case.response.clone();
+        tower::service_fn(move |request: supergraph::Request| {
+            let captured = captured.clone();
+            let fixture = fixture.clone();
+            async move {
+                let (parts, body) = request.supergraph_request.into_parts();
+                *captured.lock() = Some(Observed {
+                    status: None,
+                    headers: parts.headers,
+                    context: request.context.clone(),
+                    body: serde_json_bytes::to_value(body)?,
+                });
+
+                let graphql_response = graphql::Response::from_value(fixture.body)?;
+                let mut response = supergraph::Response::new_from_graphql_response(
+                    graphql_response,
+                    request.context,
+                );
+                *response.response.status_mut() = status(fixture.status)?;
+                *response.response.headers_mut() = header_map(&fixture.headers)?;
+                Ok::<_, BoxError>(response)
+            }
+        })
+        .boxed()
+    };
+
+    let mut http_request = http::Request::builder()
+        .method(method(&case.request.method, Method::POST)?)
+        .uri(uri(&case.request.uri)?)
+        .body(graphql_request(&case.request.body)?)?;
+    *http_request.headers_mut() = header_map(&case.request.headers)?;
+    let request = supergraph::Request {
+        supergraph_request: http_request,
+        context: context(&case.request.context),
+    };
+
+    let response = rhai.supergraph_service(inner).oneshot(request).await?;
+    let (parts, mut stream) = response.response.into_parts();
+    // Only the primary response is checked
+    let body = match stream.next().await {
+        Some(graphql_response) => serde_json_bytes::to_value(graphql_response)?,
+        None => Value::Null,
+    };
+    let observed = Observed {
+        status: Some(parts.status),
+        headers: parts.headers,
+        context: response.context,
+        body,
+    };
+    let downstream = captured.lock().take();
+    Ok((downstream, observed))
+}
+
+async fn run_subgraph(
+    rhai: &Rhai,
+    case: &TestCase,
+) -> Result<(Option<Observed>, Observed), BoxError> {
+    let subgraph_name = case
+        .subgraph
+        .clone()
+        .ok_or("`subgraph` is required for subgraph test cases")?;
+    let captured = Captured::default();
+    let inner = {
+        let captured = captured.clone();
+        let fixture = case.response.clone();
+        let subgraph_name = subgraph_name.clone();
+        tower::service_fn(move |request: subgraph::Request| {
+            let captured = captured.clone();
+            let fixture = fixture.clone();
+            let subgraph_name = subgraph_name.clone();
+            async move {
+                let (parts, body) = request.subgraph_request.into_parts();
+                *captured.lock() = Some(Observed {
+                    status: None,
+                    headers: parts.headers,
+                    context: request.context.clone(),
+                    body: serde_json_bytes::to_value(body)?,
+                });
+
+                let mut response = http::Response::builder()
+                    .status(status(fixture.status)?)
+                    .body(graphql::Response::from_value(fixture.body)?)?;
+                *response.headers_mut() = header_map(&fixture.headers)?;
+                Ok::<_, BoxError>(subgraph::Response::new_from_response(
+                    response,
+                    request.context,
+                    subgraph_name,
+                    request.id,
+                ))
+            }
+        })
+        .boxed()
+    };
+
+    let mut subgraph_request = http::Request::builder()
+        .method(method(&case.request.method, Method::POST)?)
+        .uri(uri(&case.request.uri)?)
+        .body(graphql_request(&case.request.body)?)?;
+    *subgraph_request.headers_mut() = header_map(&case.request.headers)?;
+    let mut supergraph_request = http::Request::builder()
+        .method(Method::POST)
+        .uri(Uri::from_static("http://localhost/"))
+        .body(graphql::Request::default())?;
+    *supergraph_request.headers_mut() = header_map(&case.request.supergraph_headers)?;
+    let request = subgraph::Request::fake_builder()
+        .supergraph_request(Arc::new(supergraph_request))
+        .subgraph_request(subgraph_request)
+        .subgraph_name(subgraph_name.clone())
+        .context(context(&case.request.context))
+        .build();
+
+    let response = rhai
+        .subgraph_service(&subgraph_name, inner)
+        .oneshot(request)
+        .await?;
+    let (parts, body) = response.response.into_parts();
+    let observed = Observed {
+        status: Some(parts.status),
+        headers: parts.headers,
+        context: response.context,
+        body: serde_json_bytes::to_value(body)?,
+    };
+    let downstream = captured.lock().take();
+    Ok((downstream, observed))
+}
+
+fn compare(label: &str, expected: &Expected, observed: &Observed, failures: &mut Vec<String>) {
+    if let Some(expected_status) = expected.status {
+        let actual = observed.status.map(|status| status.as_u16());
+        if actual != Some(expected_status) {
+            failures.push(format!(
+                "{label}.status: expected {expected_status}, found {}",
+                actual.map_or_else(|| "none".to_string(), |status| status.to_string())
+            ));
+        }
+    }
+
+    for (name, expected_value) in &expected.headers {
+        let values = observed
+            .headers
+            .get_all(name.as_str())
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect::<Vec<_>>();
+        let actual = (!values.is_empty()).then(|| values.join(", "));
+        if &actual != expected_value {
+            failures.push(format!(
+                "{label}.headers.{name}: expected {}, found {}",
+                describe_header(expected_value),
+                describe_header(&actual)
+            ));
+        }
+    }
+
+    for (key, expected_value) in &expected.context {
+        let actual = observed
+            .context
+            .get_json_value(key.as_str())
+            .unwrap_or(Value::Null);
+        if &actual != expected_value {
+            failures.push(format!(
+                "{label}.context.{key}: expected {expected_value}, found {actual}"
+            ));
+        }
+    }
+
+    if let Some(expected_body) = &expected.body {
+        if &observed.body != expected_body {
+            failures.push(format!(
+                "{label}.body: expected {expected_body}, found {}",
+                observed.body
+            ));
+        }
+    }
+
+    if let Some(expected_errors) = &expected.errors {
+        let actual_errors = match observed
+            .body
+            .as_object()
+            .and_then(|body| body.get("errors"))
+        {
+            Some(Value::Array(errors)) => errors.clone(),
+            _ => Vec::new(),
+        };
+        if actual_errors.len() != expected_errors.len() {
+            failures.push(format!(
+                "{label}.errors: expected {} errors, found {}: {}",
+                expected_errors.len(),
+                actual_errors.len(),
+                Value::Array(actual_errors.clone())
+            ));
+        } else {
+            for (index, (expected_error, actual_error)) in
+                expected_errors.iter().zip(actual_errors.iter()).enumerate()
+            {
+                if !is_subset(expected_error, actual_error) {
+                    failures.push(format!(
+                        "{label}.errors[{index}]: expected {expected_error}, found {actual_error}"
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Objects match if every expected field matches, other values must be equal.
+fn is_subset(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            expected.iter().all(|(key, expected_value)| {
+                actual
+                    .get(key)
+                    .is_some_and(|actual_value| is_subset(expected_value, actual_value))
+            })
+        }
+        _ => expected == actual,
+    }
+}
+
+fn describe_header(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("{value:?}"),
+        None => "no header".to_string(),
+    }
+}
+
+fn bytes_to_value(bytes: &[u8]) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned().into()))
+}
+
+fn graphql_request(body: &Value) -> Result<graphql::Request, BoxError> {
+    if body.is_null() {
+        return Ok(graphql::Request::default());
+    }
+    Ok(serde_json_bytes::from_value(body.clone())?)
+}
+
+fn context(entries: &Map<String, Value>) -> Context {
+    let context = Context::new();
+    for (key, value) in entries {
+        context.insert_json_value(key.as_str(), value.clone());
+    }
+    context
+}
+
+fn header_map(headers: &IndexMap<String, String>) -> Result<HeaderMap, BoxError> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.append(
+            HeaderName::try_from(name.as_str())?,
+            HeaderValue::try_from(value.as_str())?,
+        );
+    }
+    Ok(map)
+}
+
+fn method(method: &Option<String>, default: Method) -> Result<Method, BoxError> {
+    match method {
+        Some(method) => Ok(Method::from_bytes(method.to_uppercase().as_bytes())?),
+        None => Ok(default),
+    }
+}
+
+fn uri(uri: &Option<String>) -> Result<Uri, BoxError> {
+    match uri {
+        Some(uri) => Ok(uri.parse()?),
+        None => Ok(Uri::from_static("http://localhost/")),
+    }
+}
+
+fn status(status: Option<u16>) -> Result<StatusCode, BoxError> {
+    Ok(status
+        .map(StatusCode::from_u16)
+        .transpose()?
+        .unwrap_or(StatusCode::OK))
+}
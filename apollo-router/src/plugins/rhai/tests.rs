@@ -4,14 +4,28 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::SystemTime;
 
+use apollo_compiler::name;
+use apollo_federation::connectors::ConnectId;
+use apollo_federation::connectors::ConnectSpec;
+use apollo_federation::connectors::Connector;
+use apollo_federation::connectors::HttpJsonTransport;
+use apollo_federation::connectors::JSONSelection;
+use apollo_federation::connectors::SourceName;
+use apollo_federation::connectors::runtime::http_json_transport::HttpRequest;
+use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
+use apollo_federation::connectors::runtime::key::ResponseKey;
+use apollo_federation::connectors::runtime::responses::MappedResponse;
 use http::HeaderMap;
 use http::HeaderValue;
 use http::Method;
 use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
 use parking_lot::Mutex;
 use rhai::Engine;
 use rhai::EvalAltResult;
 use serde_json::Value;
+use serde_json_bytes::json;
 use sha2::Digest;
 use tower::BoxError;
 use tower::Service;
@@ -22,6 +36,7 @@ use uuid::Uuid;
 
 use super::PathBuf;
 use super::Rhai;
+use super::connector;
 use super::process_error;
 use super::subgraph;
 use crate::Context;
@@ -912,3 +927,130 @@ async fn test_rhai_header_removal_with_non_utf8_header() -> Result<(), BoxError>
 
     Ok(())
 }
+
+fn connector_request() -> connector::Request {
+    let connector = Connector {
+        spec: ConnectSpec::V0_1,
+        id: ConnectId::new(
+            "subgraph".into(),
+            Some(SourceName::cast("json")),
+            name!(Query),
+            name!(a),
+            0,
+            "test label",
+        ),
+        transport: HttpJsonTransport {
+            source_template: "http://localhost/api".parse().ok(),
+            connect_template: "/path".parse().unwrap(),
+            ..Default::default()
+        },
+        selection: JSONSelection::parse("$").unwrap(),
+        entity_resolver: None,
+        config: Default::default(),
+        max_requests: None,
+        batch_settings: None,
+        request_headers: Default::default(),
+        response_headers: Default::default(),
+        request_variable_keys: Default::default(),
+        response_variable_keys: Default::default(),
+        error_settings: Default::default(),
+    };
+    let http_request = http::Request::builder()
+        .method(Method::POST)
+        .uri("http://localhost/api/path")
+        .header(CONTENT_TYPE, "application/json")
+        .header(CONTENT_LENGTH, "24")
+        .body(r#"{"id":1,"name":"ada"}"#.to_string())
+        .unwrap();
+
+    connector::Request {
+        context: Context::new(),
+        connector: Arc::new(connector),
+        service_name: String::from("test"),
+        transport_request: HttpRequest {
+            inner: http_request,
+            debug: Default::default(),
+        }
+        .into(),
+        key: ResponseKey::RootField {
+            name: "a".to_string(),
+            inputs: Default::default(),
+            selection: Arc::new(JSONSelection::parse("$").unwrap()),
+        },
+        mapping_problems: Default::default(),
+        supergraph_request: Arc::new(
+            http::Request::builder()
+                .body(graphql::Request::builder().query("{ a }").build())
+                .unwrap(),
+        ),
+        retain_upstream_body: false,
+    }
+}
+
+#[tokio::test]
+async fn it_can_process_connector_request_and_response() -> Result<(), BoxError> {
+    let dyn_plugin: Box<dyn DynPlugin> = crate::plugin::plugins()
+        .find(|factory| factory.name == "apollo.rhai")
+        .expect("Plugin not found")
+        .create_instance_without_schema(
+            &Value::from_str(r#"{"scripts":"tests/fixtures", "main":"connector_service.rhai"}"#)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let service = dyn_plugin.connector_request_service(
+        tower::service_fn(|request: connector::Request| async move {
+            let TransportRequest::Http(ref http_request) = request.transport_request;
+            assert_eq!(
+                http_request.inner.headers().get("x-rhai-source").unwrap(),
+                "subgraph.json"
+            );
+            assert_eq!(http_request.inner.body(), r#"{"id":1,"name":"ADA"}"#);
+            assert_eq!(
+                http_request.inner.headers().get(CONTENT_LENGTH).unwrap(),
+                "21"
+            );
+            assert!(request.retain_upstream_body);
+
+            let mut response = connector::Response::test_builder()
+                .context(request.context)
+                .connector(request.connector)
+                .response_key(request.key)
+                .problems(Default::default())
+                .data(json!({ "hello": "world" }))
+                .build();
+            response.upstream_body = Some(json!({ "value": "raw" }));
+            Ok::<_, BoxError>(response)
+        })
+        .boxed(),
+        "subgraph.json".to_string(),
+    );
+
+    let response = service.oneshot(connector_request()).await?;
+    let MappedResponse::Data { data, .. } = response.mapped_response else {
+        panic!("expected a successful connector response");
+    };
+    assert_eq!(data, json!({ "hello": "world", "upstream": "raw" }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn it_runs_fixture_tests_against_scripts() -> Result<(), BoxError> {
+    let report = super::test_runner::run(super::test_runner::TestOptions {
+        scripts: PathBuf::from("tests/fixtures/rhai_test_runner"),
+        main: "main.rhai".to_string(),
+        fixtures: PathBuf::from("tests/fixtures/rhai_test_runner/tests"),
+        supergraph_sdl: None,
+    })
+    .await?;
+
+    assert_eq!(report.passed(), 3);
+    assert_eq!(report.failed(), 1);
+    let output = report.to_string();
+    assert!(output.contains("test supergraph.yaml: reports a wrong expectation ... FAILED"));
+    assert!(output.contains(r#"request.context.client: expected "web", found "ios""#));
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use apollo_federation::connectors::runtime::http_json_transport::TransportResponse;
 use opentelemetry::Key;
 use opentelemetry::KeyValue;
 use schemars::JsonSchema;
@@ -5,6 +6,7 @@ use serde::Deserialize;
 use tower::BoxError;
 
 use crate::Context;
+use crate::plugins::connectors::http_cache::CacheStatus;
 use crate::plugins::telemetry::config_new::DefaultForLevel;
 use crate::plugins::telemetry::config_new::Selectors;
 use crate::plugins::telemetry::config_new::attributes::DefaultAttributeRequirementLevel;
@@ -14,6 +16,7 @@ use crate::plugins::telemetry::config_new::connector::ConnectorResponse;
 use crate::plugins::telemetry::config_new::subgraph::attributes::SUBGRAPH_NAME;
 use crate::plugins::telemetry::otlp::TelemetryDataKind;
 
+const CONNECTOR_HTTP_CACHE: Key = Key::from_static_str("connector.http.cache");
 const CONNECTOR_HTTP_METHOD: Key = Key::from_static_str("connector.http.method");
 const CONNECTOR_SOURCE_NAME: Key = Key::from_static_str("connector.source.name");
 const CONNECTOR_URL_TEMPLATE: Key = Key::from_static_str("connector.url.template");
@@ -57,6 +60,17 @@ pub(crate) struct ConnectorAttributes {
     /// Requirement level: Required
     #[serde(rename = "connector.url.template")]
     connector_url_template: Option<StandardAttribute>,
+
+    /// Whether the response was served from the HTTP cache of the connector source
+    /// Examples:
+    ///
+    /// * hit
+    /// * miss
+    /// * revalidated
+    ///
+    /// Requirement level: Conditionally Required: If the HTTP cache is enabled for the connector source
+    #[serde(rename = "connector.http.cache")]
+    connector_http_cache: Option<StandardAttribute>,
 }
 
 impl DefaultForLevel for ConnectorAttributes {
@@ -137,8 +151,22 @@ impl Selectors<ConnectorRequest, ConnectorResponse, ()> for ConnectorAttributes
         attrs
     }
 
-    fn on_response(&self, _response: &ConnectorResponse) -> Vec<KeyValue> {
-        Vec::default()
+    fn on_response(&self, response: &ConnectorResponse) -> Vec<KeyValue> {
+        let mut attrs = Vec::new();
+
+        if let Some(key) = self
+            .connector_http_cache
+            .as_ref()
+            .and_then(|a| a.key(CONNECTOR_HTTP_CACHE))
+        {
+            if let Ok(TransportResponse::Http(http_response)) = &response.transport_result {
+                if let Some(status) = http_response.inner.extensions.get::<CacheStatus>() {
+                    attrs.push(KeyValue::new(key, status.as_str()));
+                }
+            }
+        }
+
+        attrs
     }
 
     fn on_error(&self, _error: &BoxError, _ctx: &Context) -> Vec<KeyValue> {
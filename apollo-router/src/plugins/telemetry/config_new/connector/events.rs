@@ -210,6 +210,7 @@ mod tests {
                 key: response_key.clone(),
                 mapping_problems: vec![],
                 supergraph_request: Default::default(),
+                retain_upstream_body: false,
             };
             test_harness
                 .call_connector_request_service(connector_request, |request| Response {
@@ -231,6 +232,8 @@ mod tests {
                         key: request.key.clone(),
                         problems: vec![],
                     },
+                    upstream_body: None,
+                    errors: Vec::new(),
                 })
                 .await
                 .expect("expecting successful response");
@@ -296,6 +299,7 @@ mod tests {
                 key: response_key.clone(),
                 mapping_problems: vec![],
                 supergraph_request: Default::default(),
+                retain_upstream_body: false,
             };
             test_harness
                 .call_connector_request_service(connector_request, |request| Response {
@@ -317,6 +321,8 @@ mod tests {
                         key: request.key.clone(),
                         problems: vec![],
                     },
+                    upstream_body: None,
+                    errors: Vec::new(),
                 })
                 .await
                 .expect("expecting successful response");
@@ -407,6 +407,7 @@ mod tests {
             key: response_key(),
             mapping_problems,
             supergraph_request: Default::default(),
+            retain_upstream_body: false,
         }
     }
 
@@ -436,6 +437,8 @@ mod tests {
                 key: response_key(),
                 problems: mapping_problems,
             },
+            upstream_body: None,
+            errors: Vec::new(),
         }
     }
 
@@ -459,6 +462,8 @@ mod tests {
                 key: response_key(),
                 problems: vec![],
             },
+            upstream_body: None,
+            errors: Vec::new(),
         }
     }
 
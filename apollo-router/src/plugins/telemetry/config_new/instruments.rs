@@ -3118,6 +3118,7 @@ mod tests {
                                         key: response_key.clone(),
                                         mapping_problems,
                                         supergraph_request: Default::default(),
+                                        retain_upstream_body: false,
                                     };
                                     connector_instruments = Some({
                                         let connector_instruments = config
@@ -3195,6 +3196,8 @@ mod tests {
                                             key: response_key,
                                             problems: mapping_problems,
                                         },
+                                        upstream_body: None,
+                                        errors: Vec::new(),
                                     };
                                     connector_instruments
                                         .take()
@@ -874,6 +874,7 @@ connector:
                         },
                     ],
                     supergraph_request: Default::default(),
+                    retain_upstream_body: false,
                 };
                 let mut connector_events = event_config.new_connector_events();
                 connector_events.on_request(&connector_request);
@@ -913,6 +914,8 @@ connector:
                             },
                         ],
                     },
+                    upstream_body: None,
+                    errors: Vec::new(),
                 };
                 connector_events.on_response(&connector_response);
             },
@@ -1227,6 +1230,7 @@ subgraph:
                         },
                     ],
                     supergraph_request: Default::default(),
+                    retain_upstream_body: false,
                 };
                 let mut connector_events = event_config.new_connector_events();
                 connector_events.on_request(&connector_request);
@@ -1266,6 +1270,8 @@ subgraph:
                             },
                         ],
                     },
+                    upstream_body: None,
+                    errors: Vec::new(),
                 };
                 connector_events.on_response(&connector_response);
             },
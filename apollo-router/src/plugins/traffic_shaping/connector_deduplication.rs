@@ -0,0 +1,283 @@
+//! De-duplicate connector HTTP requests in flight.
+//!
+//! Identical requests to a connector source share the response of the first one while it is in
+//! flight. Unlike subgraph deduplication, this happens on the HTTP requests sent by the connector
+//! request service, before the responses are mapped, so that connectors with different selections
+//! can share a request to the same URL.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use apollo_federation::connectors::runtime::errors::Error;
+use apollo_federation::connectors::runtime::form_data::FormData;
+use bytes::Bytes;
+use futures::lock::Mutex;
+use http::HeaderMap;
+use http::Method;
+use http::StatusCode;
+use http::Version;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::Sender;
+use tokio::sync::oneshot;
+
+use crate::services::router;
+use crate::services::router::body::RouterBody;
+
+/// The request headers are part of the key, sorted by name, as they can change the response
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct CacheKey {
+    method: Method,
+    uri: String,
+    headers: Vec<(String, Vec<u8>)>,
+    body: String,
+}
+
+impl From<&http::Request<String>> for CacheKey {
+    fn from(request: &http::Request<String>) -> Self {
+        let mut headers: Vec<_> = request
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+            .collect();
+        headers.sort();
+        Self {
+            method: request.method().clone(),
+            uri: request.uri().to_string(),
+            headers,
+            body: request.body().clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SharedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl SharedResponse {
+    fn to_response(&self) -> http::Response<RouterBody> {
+        let mut response = http::Response::new(router::body::from_bytes(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+type WaitMap = Arc<Mutex<HashMap<CacheKey, Sender<Result<SharedResponse, Error>>>>>;
+
+/// Deduplicates the in-flight HTTP requests of a connector source. The traffic shaping plugin
+/// adds it to the extensions of the HTTP requests of the sources with `deduplicate_query`.
+#[derive(Clone)]
+pub(crate) struct ConnectorRequestDeduplication {
+    methods: Arc<Vec<Method>>,
+    wait_map: WaitMap,
+}
+
+impl ConnectorRequestDeduplication {
+    pub(crate) fn new(methods: Vec<Method>) -> Self {
+        Self {
+            methods: Arc::new(methods),
+            wait_map: Default::default(),
+        }
+    }
+
+    /// Sends a request with `send`, or waits for the response of an identical request in
+    /// flight. Only requests with one of the configured methods are deduplicated, and never those
+    /// with uploads, whose files are streamed into the body when the request is sent.
+    pub(crate) async fn send<F, Fut>(
+        &self,
+        request: http::Request<String>,
+        send: F,
+    ) -> Result<http::Response<RouterBody>, Error>
+    where
+        F: FnOnce(http::Request<String>) -> Fut,
+        Fut: Future<Output = Result<http::Response<RouterBody>, Error>>,
+    {
+        let has_uploads = request
+            .extensions()
+            .get::<FormData>()
+            .is_some_and(FormData::has_uploads);
+        if has_uploads || !self.methods.contains(request.method()) {
+            return send(request).await;
+        }
+
+        let cache_key = CacheKey::from(&request);
+        loop {
+            let mut locked_wait_map = self.wait_map.lock().await;
+            match locked_wait_map.get(&cache_key) {
+                Some(waiter) => {
+                    // Register interest in key
+                    let mut receiver = waiter.subscribe();
+                    drop(locked_wait_map);
+
+                    match receiver.recv().await {
+                        Ok(value) => return value.map(|response| response.to_response()),
+                        // there was an issue with the broadcast channel, retry fetching
+                        Err(_) => continue,
+                    }
+                }
+                None => {
+                    let (tx, _rx) = broadcast::channel(1);
+                    locked_wait_map.insert(cache_key.clone(), tx.clone());
+                    drop(locked_wait_map);
+
+                    let (res, handle) = {
+                        // when _drop_signal is dropped, either by getting out of the block or by
+                        // cancellation, the drop_sentinel future will return with Err(), then we
+                        // remove the entry from the wait map
+                        let (_drop_signal, drop_sentinel) = oneshot::channel::<()>();
+                        let wait_map = self.wait_map.clone();
+                        let handle = tokio::task::spawn(async move {
+                            let _ = drop_sentinel.await;
+                            let mut locked_wait_map = wait_map.lock().await;
+                            locked_wait_map.remove(&cache_key);
+                        });
+
+                        (Self::send_and_read(request, send).await, handle)
+                    };
+
+                    // Make sure that our spawned task has completed, so that no more receivers
+                    // can register interest in this request
+                    let _ = handle.await;
+                    if tx.receiver_count() > 0 {
+                        // Ignore the result of send, receivers may drop...
+                        let _ = tx.send(res.clone());
+                    }
+
+                    return res.map(|response| response.to_response());
+                }
+            }
+        }
+    }
+
+    async fn send_and_read<F, Fut>(
+        request: http::Request<String>,
+        send: F,
+    ) -> Result<SharedResponse, Error>
+    where
+        F: FnOnce(http::Request<String>) -> Fut,
+        Fut: Future<Output = Result<http::Response<RouterBody>, Error>>,
+    {
+        let (parts, body) = send(request).await?.into_parts();
+        let body = router::body::into_bytes(body)
+            .await
+            .map_err(|e| Error::TransportFailure(e.to_string()))?;
+        Ok(SharedResponse {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use apollo_federation::connectors::runtime::form_data::upload_placeholder;
+
+    use super::*;
+
+    async fn send(
+        deduplication: &ConnectorRequestDeduplication,
+        request: http::Request<String>,
+        calls: &AtomicUsize,
+    ) -> String {
+        let response = deduplication
+            .send(request, |_| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(http::Response::new(router::body::from_bytes("response")))
+            })
+            .await
+            .unwrap();
+        router::body::into_string(response.into_body())
+            .await
+            .unwrap()
+    }
+
+    fn request(method: Method, uri: &str) -> http::Request<String> {
+        http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(String::new())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn deduplicates_identical_requests_in_flight() {
+        let deduplication = ConnectorRequestDeduplication::new(vec![Method::GET]);
+        let calls = AtomicUsize::new(0);
+
+        let (first, second, other) = tokio::join!(
+            send(
+                &deduplication,
+                request(Method::GET, "http://localhost/a"),
+                &calls
+            ),
+            send(
+                &deduplication,
+                request(Method::GET, "http://localhost/a"),
+                &calls
+            ),
+            send(
+                &deduplication,
+                request(Method::GET, "http://localhost/b"),
+                &calls
+            ),
+        );
+        assert_eq!(first, "response");
+        assert_eq!(second, "response");
+        assert_eq!(other, "response");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn only_deduplicates_configured_methods() {
+        let deduplication = ConnectorRequestDeduplication::new(vec![Method::GET]);
+        let calls = AtomicUsize::new(0);
+
+        tokio::join!(
+            send(
+                &deduplication,
+                request(Method::POST, "http://localhost/a"),
+                &calls
+            ),
+            send(
+                &deduplication,
+                request(Method::POST, "http://localhost/a"),
+                &calls
+            ),
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_deduplicate_uploads() {
+        let deduplication = ConnectorRequestDeduplication::new(vec![Method::POST]);
+        let calls = AtomicUsize::new(0);
+        let form = FormData::from_json(&serde_json_bytes::json!({
+            "file": upload_placeholder("0"),
+        }))
+        .unwrap();
+        let upload = || {
+            let mut request = request(Method::POST, "http://localhost/a");
+            request.extensions_mut().insert(form.clone());
+            request
+        };
+
+        tokio::join!(
+            send(&deduplication, upload(), &calls),
+            send(&deduplication, upload(), &calls),
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
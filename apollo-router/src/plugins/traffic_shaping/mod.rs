@@ -6,6 +6,7 @@
 //! * Compression
 //! * Rate limiting
 //!
+pub(crate) mod connector_deduplication;
 mod deduplication;
 
 use std::collections::HashMap;
@@ -15,6 +16,7 @@ use std::time::Duration;
 use apollo_federation::connectors::runtime::errors::Error;
 use apollo_federation::connectors::runtime::http_json_transport::TransportRequest;
 use http::HeaderValue;
+use http::Method;
 use http::StatusCode;
 use http::header::CONTENT_ENCODING;
 use parking_lot::Mutex;
@@ -29,6 +31,7 @@ use tower::load_shed::error::Overloaded;
 use tower::timeout::TimeoutLayer;
 use tower::timeout::error::Elapsed;
 
+use self::connector_deduplication::ConnectorRequestDeduplication;
 use self::deduplication::QueryDeduplicationLayer;
 use crate::configuration::shared::DnsResolutionStrategy;
 use crate::graphql;
@@ -143,6 +146,10 @@ struct ConnectorsShapingConfig {
 #[derive(PartialEq, Debug, Clone, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct ConnectorShaping {
+    /// Enable deduplication of identical in-flight requests
+    deduplicate_query: Option<bool>,
+    /// HTTP methods of the requests to deduplicate (default: GET)
+    deduplicate_methods: Option<Vec<String>>,
     /// Enable compression for connectors (available compressions are deflate, br, gzip)
     compression: Option<Compression>,
     /// Enable global rate limiting
@@ -157,11 +164,33 @@ struct ConnectorShaping {
     dns_resolution_strategy: Option<DnsResolutionStrategy>,
 }
 
+impl ConnectorShaping {
+    fn deduplicate_methods(&self) -> Result<Vec<Method>, BoxError> {
+        match &self.deduplicate_methods {
+            Some(methods) => methods
+                .iter()
+                .map(|method| {
+                    Method::from_bytes(method.to_ascii_uppercase().as_bytes()).map_err(|_| {
+                        format!("invalid HTTP method in deduplicate_methods: {method}").into()
+                    })
+                })
+                .collect(),
+            None => Ok(vec![Method::GET]),
+        }
+    }
+}
+
 impl Merge for ConnectorShaping {
     fn merge(&self, fallback: Option<&Self>) -> Self {
         match fallback {
             None => self.clone(),
             Some(fallback) => ConnectorShaping {
+                deduplicate_query: self.deduplicate_query.or(fallback.deduplicate_query),
+                deduplicate_methods: self
+                    .deduplicate_methods
+                    .as_ref()
+                    .or(fallback.deduplicate_methods.as_ref())
+                    .cloned(),
                 compression: self.compression.or(fallback.compression),
                 timeout: self.timeout.or(fallback.timeout),
                 global_rate_limit: self
@@ -253,6 +282,11 @@ impl PluginPrivate for TrafficShaping {
     type Config = Config;
 
     async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        let connector = &init.config.connector;
+        for config in connector.all.iter().chain(connector.sources.values()) {
+            config.deduplicate_methods()?;
+        }
+
         Ok(Self {
             config: init.config,
             rate_limit_subgraphs: Mutex::new(HashMap::new()),
@@ -476,6 +510,13 @@ impl PluginPrivate for TrafficShaping {
                     .clone()
             });
 
+            let deduplication = config
+                .deduplicate_query
+                .unwrap_or_default()
+                .then(|| config.deduplicate_methods().ok())
+                .flatten()
+                .map(ConnectorRequestDeduplication::new);
+
             ServiceBuilder::new()
                 .map_future_with_request_data(
                     |req: &Request| (req.context.clone(), req.connector.clone(), req.key.clone()),
@@ -515,11 +556,14 @@ impl PluginPrivate for TrafficShaping {
                 ))
                 .option_layer(rate_limit)
                 .map_request(move |mut req: connector::request_service::Request| {
+                    let TransportRequest::Http(ref mut http_request) = req.transport_request;
                     if let Some(compression) = config.compression {
-                        let TransportRequest::Http(ref mut http_request) = req.transport_request;
                         let compression_header_val = HeaderValue::from_str(&compression.to_string()).expect("compression is manually implemented and already have the right values; qed");
                         http_request.inner.headers_mut().insert(CONTENT_ENCODING, compression_header_val);
                     }
+                    if let Some(deduplication) = &deduplication {
+                        http_request.inner.extensions_mut().insert(deduplication.clone());
+                    }
                     req
                 })
                 .buffered()
@@ -818,6 +862,7 @@ mod test {
             key,
             mapping_problems,
             supergraph_request: Default::default(),
+            retain_upstream_body: false,
         }
     }
 
@@ -909,6 +954,69 @@ mod test {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn it_deduplicates_connector_requests_when_enabled() {
+        let config = serde_yaml::from_str::<serde_json::Value>(
+            r#"
+        connector:
+            all:
+                deduplicate_query: true
+                deduplicate_methods: [get, post]
+        "#,
+        )
+        .unwrap();
+
+        let plugin = get_traffic_shaping_plugin(&config).await;
+        let request = get_fake_connector_request(
+            "test_subgraph.test_sourcename".into(),
+            None,
+            "testing".to_string(),
+        );
+
+        let test_service =
+            MockConnector::new(HashMap::new()).map_request(|req: ConnectorRequest| {
+                let TransportRequest::Http(ref http_request) = req.transport_request;
+                assert!(
+                    http_request
+                        .inner
+                        .extensions()
+                        .get::<ConnectorRequestDeduplication>()
+                        .is_some()
+                );
+                req
+            });
+
+        let _response = plugin
+            .connector_request_service(
+                test_service.boxed(),
+                "test_subgraph.test_sourcename".to_string(),
+            )
+            .oneshot(request)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_rejects_invalid_connector_deduplication_methods() {
+        let config = serde_yaml::from_str::<serde_json::Value>(
+            r#"
+        connector:
+            sources:
+                test_subgraph.test_sourcename:
+                    deduplicate_query: true
+                    deduplicate_methods: ["not a method"]
+        "#,
+        )
+        .unwrap();
+
+        let result = crate::plugin::plugins()
+            .find(|factory| factory.name == APOLLO_TRAFFIC_SHAPING)
+            .expect("Plugin not found")
+            .create_instance_without_schema(&config)
+            .await;
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_merge_config() {
         let config = serde_yaml::from_str::<Config>(
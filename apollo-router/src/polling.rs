@@ -0,0 +1,27 @@
+//! Helpers shared by the pollers of remote sources, such as supergraph schema URLs and persisted
+//! query manifests.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// The default maximum random delay added to each poll interval.
+pub(crate) const DEFAULT_POLL_JITTER: Duration = Duration::from_secs(5);
+
+/// Adds a random delay of up to `jitter` to `interval`, so that router instances don't all poll
+/// at the same time.
+pub(crate) fn jittered_interval(interval: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return interval;
+    }
+    interval + rand::rng().random_range(Duration::ZERO..=jitter)
+}
+
+/// The scheme, host and port of a URL, which identify a remote source without leaking any
+/// credentials or signatures from its path or query string.
+pub(crate) fn url_origin(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(url) => url.origin().ascii_serialization(),
+        Err(_) => "invalid".to_string(),
+    }
+}
@@ -0,0 +1,365 @@
+//! Safe schema rollout.
+//!
+//! Before a new supergraph schema is activated, the operations the router already knows about
+//! (from the persisted query manifest and the query plan cache, like the cache warm up) are
+//! validated and planned against it. If too many of them fail, the reload is rejected and the
+//! router keeps the current schema.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+use derive_more::Display;
+use tower::ServiceExt;
+
+use crate::compute_job::ComputeBackPressureError;
+use crate::compute_job::ComputeJobType;
+use crate::compute_job::MaybeBackPressureError;
+use crate::configuration::safe_rollout::SafeRollout;
+use crate::plugins::authorization::CacheKeyMetadata;
+use crate::query_planner::CachingQueryKey;
+use crate::query_planner::InMemoryCachePlanner;
+use crate::query_planner::QueryPlannerService;
+use crate::services::QueryPlannerRequest;
+use crate::services::layers::persisted_queries::PersistedQueryLayer;
+use crate::services::layers::query_analysis::QueryAnalysisLayer;
+use crate::services::query_planner::PlanOptions;
+
+/// An operation the current schema serves.
+pub(crate) struct KnownOperation {
+    query: String,
+    operation_name: Option<String>,
+    metadata: CacheKeyMetadata,
+    plan_options: PlanOptions,
+    source: OperationSource,
+}
+
+#[derive(Clone, Copy, Debug, Display)]
+enum OperationSource {
+    #[display("persisted query")]
+    PersistedQuery,
+    #[display("query plan cache")]
+    QueryPlanCache,
+}
+
+#[derive(Debug)]
+struct OperationFailure {
+    operation: String,
+    source: OperationSource,
+    reason: String,
+}
+
+/// The new schema breaks more known operations than allowed.
+#[derive(Debug)]
+pub(crate) struct SchemaRolloutRejected {
+    checked: usize,
+    failures: Vec<OperationFailure>,
+    max_failure_ratio: f64,
+    max_reported_failures: usize,
+}
+
+impl fmt::Display for SchemaRolloutRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the new supergraph schema was rejected: {} of {} known operations failed, more than the allowed ratio of {}",
+            self.failures.len(),
+            self.checked,
+            self.max_failure_ratio
+        )?;
+        for failure in self.failures.iter().take(self.max_reported_failures) {
+            write!(
+                f,
+                "\n- {} ({}): {}",
+                failure.operation, failure.source, failure.reason
+            )?;
+        }
+        if self.failures.len() > self.max_reported_failures {
+            write!(
+                f,
+                "\n- and {} more",
+                self.failures.len() - self.max_reported_failures
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaRolloutRejected {}
+
+/// Collects the operations from the persisted query manifest and the query plan cache of the
+/// current schema. Operations that already failed with the current schema are skipped.
+pub(crate) async fn known_operations(
+    persisted_query_layer: &PersistedQueryLayer,
+    previous_cache: &InMemoryCachePlanner,
+) -> Vec<KnownOperation> {
+    let mut seen = HashSet::new();
+    let mut operations = Vec::new();
+
+    for query in persisted_query_layer.all_operations().unwrap_or_default() {
+        if seen.insert((query.clone(), None)) {
+            operations.push(KnownOperation {
+                query,
+                operation_name: None,
+                metadata: CacheKeyMetadata::default(),
+                plan_options: PlanOptions::default(),
+                source: OperationSource::PersistedQuery,
+            });
+        }
+    }
+
+    let cache = previous_cache.lock().await;
+    for (
+        CachingQueryKey {
+            query,
+            operation,
+            metadata,
+            plan_options,
+            ..
+        },
+        result,
+    ) in cache.iter()
+    {
+        if result.is_ok() && seen.insert((query.clone(), operation.clone())) {
+            operations.push(KnownOperation {
+                query: query.clone(),
+                operation_name: operation.clone(),
+                metadata: metadata.clone(),
+                plan_options: plan_options.clone(),
+                source: OperationSource::QueryPlanCache,
+            });
+        }
+    }
+
+    operations
+}
+
+/// Validates and plans the known operations against the planner of the new schema.
+pub(crate) async fn check(
+    config: &SafeRollout,
+    planner: &QueryPlannerService,
+    query_analysis: &QueryAnalysisLayer,
+    operations: Vec<KnownOperation>,
+) -> Result<(), SchemaRolloutRejected> {
+    let checked = operations.len();
+    tracing::info!(
+        "checking {checked} known operations against the new supergraph schema before activating it"
+    );
+
+    let mut failures = Vec::new();
+    for operation in operations {
+        if let Err(reason) = check_operation(planner, query_analysis, &operation).await {
+            failures.push(OperationFailure {
+                operation: display_name(&operation),
+                source: operation.source,
+                reason,
+            });
+        }
+    }
+
+    let failed = failures.len();
+    u64_counter_with_unit!(
+        "apollo.router.schema.rollout.operations",
+        "Number of known operations checked against a new supergraph schema before activating it",
+        "{operation}",
+        (checked - failed) as u64,
+        result = "passed"
+    );
+    u64_counter_with_unit!(
+        "apollo.router.schema.rollout.operations",
+        "Number of known operations checked against a new supergraph schema before activating it",
+        "{operation}",
+        failed as u64,
+        result = "failed"
+    );
+
+    if failed == 0 || failed as f64 <= checked as f64 * config.max_failure_ratio {
+        if failed > 0 {
+            tracing::warn!(
+                "{failed} of {checked} known operations fail on the new supergraph schema, within the allowed ratio of {}",
+                config.max_failure_ratio
+            );
+        }
+        return Ok(());
+    }
+
+    Err(SchemaRolloutRejected {
+        checked,
+        failures,
+        max_failure_ratio: config.max_failure_ratio,
+        max_reported_failures: config.max_reported_failures,
+    })
+}
+
+async fn check_operation(
+    planner: &QueryPlannerService,
+    query_analysis: &QueryAnalysisLayer,
+    operation: &KnownOperation,
+) -> Result<(), String> {
+    // Like the cache warm up, checks use low priority compute jobs so that requests served by
+    // the current schema are prioritized
+    let document = loop {
+        match query_analysis
+            .parse_document(
+                &operation.query,
+                operation.operation_name.as_deref(),
+                ComputeJobType::QueryParsingWarmup,
+            )
+            .await
+        {
+            Ok(document) => break document,
+            Err(MaybeBackPressureError::PermanentError(error)) => {
+                return Err(format!("validation failed: {error}"));
+            }
+            Err(MaybeBackPressureError::TemporaryError(ComputeBackPressureError)) => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    };
+
+    loop {
+        let request = QueryPlannerRequest {
+            query: operation.query.clone(),
+            operation_name: operation.operation_name.clone(),
+            document: document.clone(),
+            metadata: operation.metadata.clone(),
+            plan_options: operation.plan_options.clone(),
+            compute_job_type: ComputeJobType::QueryPlanningWarmup,
+        };
+        match planner.clone().oneshot(request).await {
+            Ok(_) => return Ok(()),
+            Err(MaybeBackPressureError::PermanentError(error)) => {
+                return Err(format!("planning failed: {error}"));
+            }
+            Err(MaybeBackPressureError::TemporaryError(ComputeBackPressureError)) => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+fn display_name(operation: &KnownOperation) -> String {
+    if let Some(name) = &operation.operation_name {
+        return name.clone();
+    }
+    let query = operation
+        .query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.chars().count() > 80 {
+        format!("{}…", query.chars().take(80).collect::<String>())
+    } else {
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Configuration;
+    use crate::spec::Schema;
+
+    fn operation(query: &str, operation_name: Option<&str>) -> KnownOperation {
+        KnownOperation {
+            query: query.to_string(),
+            operation_name: operation_name.map(str::to_string),
+            metadata: CacheKeyMetadata::default(),
+            plan_options: PlanOptions::default(),
+            source: OperationSource::QueryPlanCache,
+        }
+    }
+
+    async fn check_operations(
+        config: SafeRollout,
+        operations: Vec<KnownOperation>,
+    ) -> Result<(), SchemaRolloutRejected> {
+        let configuration = Arc::new(Configuration::default());
+        let schema = Arc::new(
+            Schema::parse(
+                include_str!("../testdata/minimal_supergraph.graphql"),
+                &configuration,
+            )
+            .unwrap(),
+        );
+        let planner = QueryPlannerService::new(schema.clone(), configuration.clone())
+            .await
+            .unwrap();
+        let query_analysis = QueryAnalysisLayer::new(schema, configuration).await;
+        check(&config, &planner, &query_analysis, operations).await
+    }
+
+    #[tokio::test]
+    async fn rejects_schema_breaking_operations() {
+        let error = check_operations(
+            SafeRollout {
+                enabled: true,
+                ..Default::default()
+            },
+            vec![
+                operation("query Me { me }", Some("Me")),
+                operation("query Removed { removed }", Some("Removed")),
+            ],
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.checked, 2);
+        assert_eq!(error.failures.len(), 1);
+        let report = error.to_string();
+        assert!(
+            report.starts_with(
+                "the new supergraph schema was rejected: 1 of 2 known operations failed"
+            ),
+            "{report}"
+        );
+        assert!(
+            report.contains("\n- Removed (query plan cache): validation failed:"),
+            "{report}"
+        );
+    }
+
+    #[tokio::test]
+    async fn accepts_failures_within_ratio() {
+        check_operations(
+            SafeRollout {
+                enabled: true,
+                max_failure_ratio: 0.5,
+                ..Default::default()
+            },
+            vec![
+                operation("query Me { me }", Some("Me")),
+                operation("{ removed }", None),
+            ],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn limits_reported_failures() {
+        let error = check_operations(
+            SafeRollout {
+                enabled: true,
+                max_reported_failures: 1,
+                ..Default::default()
+            },
+            vec![
+                operation("{ removed }", None),
+                operation("{ alsoRemoved }", None),
+            ],
+        )
+        .await
+        .unwrap_err();
+
+        let report = error.to_string();
+        assert!(
+            report.contains("\n- { removed } (query plan cache)"),
+            "{report}"
+        );
+        assert!(!report.contains("alsoRemoved"), "{report}");
+        assert!(report.ends_with("\n- and 1 more"), "{report}");
+    }
+}
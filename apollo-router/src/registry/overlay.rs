@@ -0,0 +1,277 @@
+//! Router configuration carried by OCI release bundles.
+//!
+//! When the supergraph schema is sourced from an OCI bundle, the bundle may also carry a router
+//! configuration overlay and persisted query manifests. They are applied over the router
+//! configuration together with the schema, so that one bundle promotes a whole release.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures::prelude::*;
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+use tempfile::TempDir;
+use tokio::sync::watch;
+use tokio_stream::wrappers::WatchStream;
+
+use super::OciContent;
+use super::OciError;
+
+/// The schema and router configuration of the release bundle currently in use.
+#[derive(Debug)]
+pub(crate) struct ReleaseOverlay {
+    schema: String,
+    config: Option<Value>,
+    persisted_query_manifests: Vec<PathBuf>,
+    /// The directory holding the manifests, removed with the overlay
+    _manifests_directory: Option<TempDir>,
+}
+
+impl ReleaseOverlay {
+    fn new(content: &OciContent) -> Result<Self, OciError> {
+        let config = content
+            .config_overlay
+            .as_deref()
+            .map(serde_yaml::from_str::<Value>)
+            .transpose()
+            .map_err(OciError::ConfigOverlay)?;
+        if config
+            .as_ref()
+            .is_some_and(|config| !config.is_mapping() && !config.is_null())
+        {
+            return Err(OciError::ConfigOverlay(serde::de::Error::custom(
+                "the router configuration layer must be a YAML mapping",
+            )));
+        }
+
+        // Manifests are handed to the router as local manifests, stored in a new directory that
+        // only this process can access, so that a new release never overwrites the files of the
+        // one in use.
+        let mut persisted_query_manifests = Vec::new();
+        let mut manifests_directory = None;
+        if !content.persisted_query_manifests.is_empty() {
+            let directory = tempfile::Builder::new()
+                .prefix("apollo-router-release-")
+                .tempdir()
+                .map_err(OciError::PersistedQueries)?;
+            for (index, manifest) in content.persisted_query_manifests.iter().enumerate() {
+                let path = directory
+                    .path()
+                    .join(format!("persisted-queries-{index}.json"));
+                std::fs::write(&path, manifest).map_err(OciError::PersistedQueries)?;
+                persisted_query_manifests.push(path);
+            }
+            manifests_directory = Some(directory);
+        }
+
+        Ok(Self {
+            schema: content.schema.clone(),
+            config,
+            persisted_query_manifests,
+            _manifests_directory: manifests_directory,
+        })
+    }
+
+    /// The supergraph schema of the release bundle
+    pub(crate) fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Applies the overlay to a YAML router configuration. Mappings are merged recursively, other
+    /// values in the overlay replace the ones in the configuration. Persisted query manifests are
+    /// appended to `persisted_queries.local_manifests`.
+    pub(crate) fn apply(&self, raw_yaml: &str) -> Result<String, serde_yaml::Error> {
+        let mut config: Value = if raw_yaml.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_yaml::from_str(raw_yaml)?
+        };
+        if config.is_null() {
+            config = Value::Mapping(Mapping::new());
+        }
+        if let Some(overlay) = &self.config {
+            merge(&mut config, overlay.clone());
+        }
+
+        if !self.persisted_query_manifests.is_empty() {
+            let local_manifests = entry(entry(&mut config, "persisted_queries"), "local_manifests");
+            if !local_manifests.is_sequence() {
+                *local_manifests = Value::Sequence(Vec::new());
+            }
+            if let Value::Sequence(local_manifests) = local_manifests {
+                local_manifests.extend(
+                    self.persisted_query_manifests
+                        .iter()
+                        .map(|path| Value::String(path.to_string_lossy().into_owned())),
+                );
+            }
+        }
+
+        serde_yaml::to_string(&config)
+    }
+}
+
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn entry<'a>(value: &'a mut Value, key: &str) -> &'a mut Value {
+    if !value.is_mapping() {
+        *value = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(mapping) = value else {
+        unreachable!("value was just replaced with a mapping")
+    };
+    let key = Value::String(key.to_string());
+    if !mapping.contains_key(&key) {
+        mapping.insert(key.clone(), Value::Null);
+    }
+    mapping.get_mut(&key).expect("key was just inserted")
+}
+
+/// The release overlays of one router: its schema source publishes the overlay of each release
+/// bundle it fetches, and its configuration source applies them.
+#[derive(Clone, Debug)]
+pub(crate) struct ReleaseOverlays(watch::Sender<Option<Arc<ReleaseOverlay>>>);
+
+impl ReleaseOverlays {
+    pub(crate) fn new() -> Self {
+        Self(watch::channel(None).0)
+    }
+
+    /// Stores the overlay of a newly fetched release bundle, and notifies the configuration
+    /// source.
+    pub(crate) fn publish(&self, content: &OciContent) -> Result<(), OciError> {
+        let overlay = ReleaseOverlay::new(content)?;
+        self.0.send_replace(Some(Arc::new(overlay)));
+        Ok(())
+    }
+
+    /// The overlay of the last release bundle fetched, if any.
+    pub(crate) fn current(&self) -> Option<Arc<ReleaseOverlay>> {
+        self.0.borrow().clone()
+    }
+
+    /// A stream of the release overlays, yielding every time a new one is published.
+    pub(crate) fn changes(&self) -> impl Stream<Item = Arc<ReleaseOverlay>> + Send + 'static {
+        WatchStream::new(self.0.subscribe()).filter_map(future::ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlay(config: Option<&str>, persisted_query_manifests: Vec<&str>) -> ReleaseOverlay {
+        ReleaseOverlay {
+            schema: String::new(),
+            config: config.map(|config| serde_yaml::from_str(config).unwrap()),
+            persisted_query_manifests: persisted_query_manifests
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            _manifests_directory: None,
+        }
+    }
+
+    fn parse(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn merges_mappings_recursively() {
+        let overlay = overlay(
+            Some("supergraph:\n  introspection: true\ninclude_subgraph_errors:\n  all: true\n"),
+            vec![],
+        );
+        let applied = overlay
+            .apply("supergraph:\n  listen: 0.0.0.0:4000\n  introspection: false\n")
+            .unwrap();
+        assert_eq!(
+            parse(&applied),
+            parse(
+                "supergraph:\n  listen: 0.0.0.0:4000\n  introspection: true\ninclude_subgraph_errors:\n  all: true\n"
+            )
+        );
+    }
+
+    #[test]
+    fn appends_persisted_query_manifests() {
+        let overlay = overlay(
+            Some("persisted_queries:\n  enabled: true\n"),
+            vec!["/tmp/bundle/persisted-queries-0.json"],
+        );
+        let applied = overlay
+            .apply("persisted_queries:\n  local_manifests:\n    - local.json\n")
+            .unwrap();
+        assert_eq!(
+            parse(&applied),
+            parse(
+                "persisted_queries:\n  enabled: true\n  local_manifests:\n    - local.json\n    - /tmp/bundle/persisted-queries-0.json\n"
+            )
+        );
+
+        // An empty configuration file gets the manifests as well
+        let applied = overlay.apply("").unwrap();
+        assert_eq!(
+            parse(&applied),
+            parse(
+                "persisted_queries:\n  enabled: true\n  local_manifests:\n    - /tmp/bundle/persisted-queries-0.json\n"
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_config_layer() {
+        let content = OciContent {
+            schema: String::new(),
+            digest: "sha256:0".to_string(),
+            config_overlay: Some("- not\n- a mapping\n".to_string()),
+            persisted_query_manifests: Vec::new(),
+        };
+        assert!(matches!(
+            ReleaseOverlay::new(&content),
+            Err(OciError::ConfigOverlay(_))
+        ));
+    }
+
+    #[test]
+    fn stores_manifests_in_a_private_directory() {
+        let content = OciContent {
+            schema: "type Query { a: Int }".to_string(),
+            digest: "sha256:0".to_string(),
+            config_overlay: None,
+            persisted_query_manifests: vec!["{}".to_string()],
+        };
+        let overlay = ReleaseOverlay::new(&content).unwrap();
+        let manifest = overlay.persisted_query_manifests[0].clone();
+        assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "{}");
+        assert_eq!(overlay.schema(), "type Query { a: Int }");
+
+        let directory = manifest.parent().unwrap().to_path_buf();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&directory).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        // Another bundle with the same digest gets its own directory
+        let other = ReleaseOverlay::new(&content).unwrap();
+        assert_ne!(other.persisted_query_manifests[0], manifest);
+
+        drop(overlay);
+        assert!(!directory.exists());
+    }
+}
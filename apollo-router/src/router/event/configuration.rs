@@ -9,11 +9,17 @@ use derive_more::From;
 use futures::prelude::*;
 
 use crate::Configuration;
+use crate::configuration::layers::LayeredConfiguration;
+use crate::configuration::secrets::Secrets;
+use crate::registry::overlay::ReleaseOverlay;
+use crate::registry::overlay::ReleaseOverlays;
 use crate::router::Event;
 use crate::router::Event::NoMoreConfiguration;
 use crate::router::Event::RhaiReload;
 use crate::router::Event::UpdateConfiguration;
+use crate::router::Event::UpdateSchemaAndConfiguration;
 use crate::uplink::UplinkConfig;
+use crate::uplink::schema::SchemaState;
 
 type ConfigurationStream = Pin<Box<dyn Stream<Item = Configuration> + Send>>;
 
@@ -44,6 +50,16 @@ pub enum ConfigurationSource {
         /// `true` to watch the file for changes and hot apply them.
         watch: bool,
     },
+
+    /// Yaml files deep-merged in order, that may be watched for changes
+    #[display("Files")]
+    Files {
+        /// The paths of the configuration files, each one overriding the ones before it.
+        paths: Vec<PathBuf>,
+
+        /// `true` to watch the files, and the files they include, for changes and hot apply them.
+        watch: bool,
+    },
 }
 
 impl Default for ConfigurationSource {
@@ -54,108 +70,338 @@ impl Default for ConfigurationSource {
 
 impl ConfigurationSource {
     /// Convert this config into a stream regardless of if is static or not. Allows for unified handling later.
+    ///
+    /// With release `overlays`, the router configuration of each release bundle is applied over
+    /// the configuration, together with the schema of the bundle.
     pub(crate) fn into_stream(
         self,
         uplink_config: Option<UplinkConfig>,
+        overlays: Option<ReleaseOverlays>,
     ) -> impl Stream<Item = Event> {
         match self {
             ConfigurationSource::Static(mut instance) => {
                 instance.uplink = uplink_config;
-                stream::iter(vec![UpdateConfiguration(instance.into())]).boxed()
+                let configurations = stream::iter(vec![*instance]);
+                ConfigurationSource::instances_stream(configurations, overlays)
             }
-            ConfigurationSource::Stream(stream) => stream
-                .map(move |mut c| {
+            ConfigurationSource::Stream(stream) => {
+                let configurations = stream.map(move |mut c| {
                     c.uplink = uplink_config.clone();
-                    UpdateConfiguration(Arc::new(c))
-                })
-                .boxed(),
+                    c
+                });
+                ConfigurationSource::instances_stream(configurations, overlays)
+            }
             ConfigurationSource::File { path, watch } => {
-                // Sanity check, does the config file exists, if it doesn't then bail.
-                if !path.exists() {
-                    tracing::error!(
-                        "configuration file at path '{}' does not exist.",
-                        path.to_string_lossy()
-                    );
-                    stream::empty().boxed()
-                } else {
-                    match ConfigurationSource::read_config(&path) {
-                        Ok(mut configuration) => {
-                            if watch {
-                                let config_watcher = crate::files::watch(&path)
-                                    .filter_map(move |_| {
-                                        let path = path.clone();
-                                        let uplink_config = uplink_config.clone();
-                                        async move {
-                                            match ConfigurationSource::read_config_async(&path)
-                                                .await
-                                            {
-                                                Ok(mut configuration) => {
-                                                    configuration.uplink = uplink_config.clone();
-                                                    Some(UpdateConfiguration(Arc::new(
-                                                        configuration,
-                                                    )))
-                                                }
-                                                Err(err) => {
-                                                    tracing::error!("{}", err);
-                                                    None
-                                                }
-                                            }
-                                        }
-                                    })
-                                    .boxed();
-                                if let Some(rhai_plugin) =
-                                    configuration.apollo_plugins.plugins.get("rhai")
-                                {
-                                    let scripts_path = match rhai_plugin["scripts"].as_str() {
-                                        Some(path) => Path::new(path),
-                                        None => Path::new("rhai"),
-                                    };
-                                    // If our path is relative, add it to the current dir
-                                    let scripts_watch = if scripts_path.is_relative() {
-                                        let current_directory = std::env::current_dir();
-                                        if current_directory.is_err() {
-                                            tracing::error!("No current directory found",);
-                                            return stream::empty().boxed();
-                                        }
-                                        current_directory.unwrap().join(scripts_path)
-                                    } else {
-                                        scripts_path.into()
-                                    };
-                                    let rhai_watcher = crate::files::watch_rhai(&scripts_watch)
-                                        .filter_map(move |_| future::ready(Some(RhaiReload)))
-                                        .boxed();
-                                    // Select across both our streams
-                                    futures::stream::select(config_watcher, rhai_watcher).boxed()
-                                } else {
-                                    config_watcher
+                ConfigurationSource::files_stream(vec![path], watch, uplink_config, overlays)
+            }
+            ConfigurationSource::Files { paths, watch } => {
+                ConfigurationSource::files_stream(paths, watch, uplink_config, overlays)
+            }
+        }
+        .chain(stream::iter(vec![NoMoreConfiguration]))
+        .boxed()
+    }
+
+    /// Emits configuration instances, with the release overlays applied if any. Nothing is
+    /// emitted until a release bundle was fetched, then the last configuration is emitted again
+    /// with the schema of each new bundle.
+    fn instances_stream(
+        configurations: impl Stream<Item = Configuration> + Send + 'static,
+        overlays: Option<ReleaseOverlays>,
+    ) -> Pin<Box<dyn Stream<Item = Event> + Send>> {
+        let Some(overlays) = overlays else {
+            return configurations
+                .map(|configuration| UpdateConfiguration(Arc::new(configuration)))
+                .boxed();
+        };
+
+        enum Change {
+            Configuration(Configuration),
+            Overlay(Arc<ReleaseOverlay>),
+        }
+        let overlay_changes = overlays.changes().map(Change::Overlay);
+        stream::select(configurations.map(Change::Configuration), overlay_changes)
+            .scan(None, move |last: &mut Option<Configuration>, change| {
+                let overlaid = match change {
+                    Change::Configuration(configuration) => {
+                        let overlaid = overlays.current().and_then(|overlay| {
+                            ConfigurationSource::apply_overlay(&configuration, overlay)
+                        });
+                        *last = Some(configuration);
+                        overlaid
+                    }
+                    Change::Overlay(overlay) => last.as_ref().and_then(|configuration| {
+                        ConfigurationSource::apply_overlay(configuration, overlay)
+                    }),
+                };
+                future::ready(Some(overlaid))
+            })
+            .filter_map(future::ready)
+            .boxed()
+    }
+
+    /// Applies the router configuration of a release bundle over a configuration instance,
+    /// returning the event applying them with the schema of the bundle.
+    fn apply_overlay(configuration: &Configuration, overlay: Arc<ReleaseOverlay>) -> Option<Event> {
+        let yaml = match &configuration.validated_yaml {
+            Some(yaml) => serde_yaml::to_string(yaml),
+            None => serde_yaml::to_string(configuration),
+        };
+        let overlaid = yaml
+            .map_err(ReadConfigError::Overlay)
+            .and_then(|yaml| ConfigurationSource::parse_config(yaml, Some(&overlay), None));
+        match overlaid {
+            Ok(mut overlaid) => {
+                overlaid.uplink = configuration.uplink.clone();
+                let schema = SchemaState {
+                    sdl: overlay.schema().to_string(),
+                    launch_id: None,
+                };
+                Some(UpdateSchemaAndConfiguration(schema, Arc::new(overlaid)))
+            }
+            Err(err) => {
+                tracing::error!("{}", err);
+                None
+            }
+        }
+    }
+
+    fn files_stream(
+        paths: Vec<PathBuf>,
+        watch: bool,
+        uplink_config: Option<UplinkConfig>,
+        overlays: Option<ReleaseOverlays>,
+    ) -> Pin<Box<dyn Stream<Item = Event> + Send>> {
+        // The secrets of this source, which track the secrets its configuration uses
+        let secrets = match Secrets::from_env() {
+            Ok(secrets) => secrets,
+            Err(err) => {
+                tracing::error!("Failed to read configuration: {}", err);
+                return stream::empty().boxed();
+            }
+        };
+        let overlay_secrets = secrets.clone();
+        let overlay_changes = overlays.as_ref().map(ReleaseOverlays::changes);
+        let overlay_paths = paths.clone();
+        let overlay_uplink_config = uplink_config.clone();
+
+        // Sanity check, do the config files exist, if they don't then bail.
+        let file_stream = if let Some(path) = paths.iter().find(|path| !path.exists()) {
+            tracing::error!(
+                "configuration file at path '{}' does not exist.",
+                path.to_string_lossy()
+            );
+            stream::empty().boxed()
+        } else {
+            match ConfigurationSource::read_config(
+                &paths,
+                overlays.as_ref().and_then(ReleaseOverlays::current),
+                secrets.clone(),
+            ) {
+                Ok((mut configuration, files)) => {
+                    if watch {
+                        let rhai_plugin = configuration.apollo_plugins.plugins.get("rhai").cloned();
+                        configuration.uplink = uplink_config.clone();
+                        let config_watcher = ConfigurationSource::watch_files(
+                            configuration,
+                            paths,
+                            files,
+                            uplink_config,
+                            secrets,
+                            overlays.clone(),
+                        );
+                        if let Some(rhai_plugin) = rhai_plugin {
+                            let scripts_path = match rhai_plugin["scripts"].as_str() {
+                                Some(path) => Path::new(path),
+                                None => Path::new("rhai"),
+                            };
+                            // If our path is relative, add it to the current dir
+                            let scripts_watch = if scripts_path.is_relative() {
+                                let current_directory = std::env::current_dir();
+                                if current_directory.is_err() {
+                                    tracing::error!("No current directory found",);
+                                    return stream::empty().boxed();
                                 }
+                                current_directory.unwrap().join(scripts_path)
                             } else {
-                                configuration.uplink = uplink_config.clone();
-                                stream::once(future::ready(UpdateConfiguration(Arc::new(
-                                    configuration,
-                                ))))
-                                .boxed()
+                                scripts_path.into()
+                            };
+                            let rhai_watcher = crate::files::watch_rhai(&scripts_watch)
+                                .filter_map(move |_| future::ready(Some(RhaiReload)))
+                                .boxed();
+                            // Select across both our streams
+                            futures::stream::select(config_watcher, rhai_watcher).boxed()
+                        } else {
+                            config_watcher
+                        }
+                    } else {
+                        configuration.uplink = uplink_config.clone();
+                        stream::once(future::ready(UpdateConfiguration(Arc::new(configuration))))
+                            .boxed()
+                    }
+                }
+                Err(err) => {
+                    tracing::error!("Failed to read configuration: {}", err);
+                    stream::empty().boxed()
+                }
+            }
+        };
+
+        match overlay_changes {
+            None => file_stream,
+            Some(overlay_changes) => {
+                // The configuration is applied together with the release bundle overlay, so
+                // nothing is applied until a bundle was fetched, and it is applied again
+                // each time a new bundle is fetched.
+                let file_stream = file_stream.filter(move |event| {
+                    future::ready(
+                        !matches!(event, UpdateConfiguration(_))
+                            || overlays
+                                .as_ref()
+                                .is_some_and(|overlays| overlays.current().is_some()),
+                    )
+                });
+                let overlay_stream = overlay_changes.filter_map(move |overlay| {
+                    let paths = overlay_paths.clone();
+                    let uplink_config = overlay_uplink_config.clone();
+                    let secrets = overlay_secrets.clone();
+                    async move {
+                        let schema = SchemaState {
+                            sdl: overlay.schema().to_string(),
+                            launch_id: None,
+                        };
+                        match ConfigurationSource::read_config_async(paths, Some(overlay), secrets)
+                            .await
+                        {
+                            Ok((mut configuration, _)) => {
+                                configuration.uplink = uplink_config;
+                                Some(UpdateSchemaAndConfiguration(
+                                    schema,
+                                    Arc::new(configuration),
+                                ))
+                            }
+                            Err(err) => {
+                                tracing::error!("{}", err);
+                                None
                             }
                         }
-                        Err(err) => {
-                            tracing::error!("Failed to read configuration: {}", err);
-                            stream::empty().boxed()
+                    }
+                });
+                stream::select(file_stream, overlay_stream).boxed()
+            }
+        }
+    }
+
+    /// Emits the configuration, then reads it again each time one of its files changes or one of
+    /// its secrets rotates. The files that are watched follow the includes of the last
+    /// configuration that could be read.
+    fn watch_files(
+        configuration: Configuration,
+        paths: Vec<PathBuf>,
+        files: Vec<PathBuf>,
+        uplink_config: Option<UplinkConfig>,
+        secrets: Option<Arc<Secrets>>,
+        overlays: Option<ReleaseOverlays>,
+    ) -> Pin<Box<dyn Stream<Item = Event> + Send>> {
+        let watcher = ConfigurationSource::file_watcher(&files, secrets.clone());
+        let changes = stream::unfold((watcher, files), move |(mut watcher, files)| {
+            let paths = paths.clone();
+            let uplink_config = uplink_config.clone();
+            let secrets = secrets.clone();
+            let overlays = overlays.clone();
+            async move {
+                loop {
+                    watcher.next().await?;
+                    let overlay = overlays.as_ref().and_then(ReleaseOverlays::current);
+                    match ConfigurationSource::read_config_async(
+                        paths.clone(),
+                        overlay,
+                        secrets.clone(),
+                    )
+                    .await
+                    {
+                        Ok((mut configuration, new_files)) => {
+                            configuration.uplink = uplink_config;
+                            let watcher = if new_files != files {
+                                ConfigurationSource::file_watcher(&new_files, secrets)
+                            } else {
+                                watcher
+                            };
+                            return Some((
+                                UpdateConfiguration(Arc::new(configuration)),
+                                (watcher, new_files),
+                            ));
                         }
+                        Err(err) => tracing::error!("{}", err),
                     }
                 }
             }
+        });
+        stream::once(future::ready(UpdateConfiguration(Arc::new(configuration))))
+            .chain(changes)
+            .boxed()
+    }
+
+    /// Changes of the files, and rotations of the secrets they expand.
+    fn file_watcher(
+        files: &[PathBuf],
+        secrets: Option<Arc<Secrets>>,
+    ) -> Pin<Box<dyn Stream<Item = ()> + Send>> {
+        let mut files = files.to_vec();
+        files.sort();
+        files.dedup();
+        // Every watch starts with an event for the initial read, which was already done
+        let mut watchers = files
+            .iter()
+            .map(|file| crate::files::watch(file).skip(1).boxed())
+            .collect::<Vec<_>>();
+        if let Some(secrets) = secrets {
+            watchers.push(secrets.rotations().boxed());
         }
-        .chain(stream::iter(vec![NoMoreConfiguration]))
-        .boxed()
+        stream::select_all(watchers).boxed()
     }
 
-    fn read_config(path: &Path) -> Result<Configuration, ReadConfigError> {
-        let config = std::fs::read_to_string(path)?;
-        config.parse().map_err(ReadConfigError::Validation)
+    /// Reads the configuration with the release bundle overlay, and returns it with every file it
+    /// was read from. Its secrets are read from `secrets`.
+    fn read_config(
+        paths: &[PathBuf],
+        overlay: Option<Arc<ReleaseOverlay>>,
+        secrets: Option<Arc<Secrets>>,
+    ) -> Result<(Configuration, Vec<PathBuf>), ReadConfigError> {
+        let layers = LayeredConfiguration::load(paths).map_err(ReadConfigError::Validation)?;
+        let configuration = ConfigurationSource::parse_config(
+            layers.to_yaml().map_err(ReadConfigError::Validation)?,
+            overlay.as_deref(),
+            secrets,
+        )?;
+        Ok((configuration, layers.files().to_vec()))
     }
-    async fn read_config_async(path: &Path) -> Result<Configuration, ReadConfigError> {
-        let config = tokio::fs::read_to_string(path).await?;
-        config.parse().map_err(ReadConfigError::Validation)
+    async fn read_config_async(
+        paths: Vec<PathBuf>,
+        overlay: Option<Arc<ReleaseOverlay>>,
+        secrets: Option<Arc<Secrets>>,
+    ) -> Result<(Configuration, Vec<PathBuf>), ReadConfigError> {
+        tokio::task::spawn_blocking(move || {
+            ConfigurationSource::read_config(&paths, overlay, secrets)
+        })
+        .await
+        .map_err(|e| ReadConfigError::Io(std::io::Error::other(e)))?
+    }
+
+    fn parse_config(
+        config: String,
+        overlay: Option<&ReleaseOverlay>,
+        secrets: Option<Arc<Secrets>>,
+    ) -> Result<Configuration, ReadConfigError> {
+        let config = match overlay {
+            Some(overlay) => overlay.apply(&config).map_err(ReadConfigError::Overlay)?,
+            None => config,
+        };
+        match secrets {
+            Some(secrets) => Configuration::from_str_with_secrets(&config, secrets),
+            None => config.parse(),
+        }
+        .map_err(ReadConfigError::Validation)
     }
 }
 
@@ -165,17 +411,23 @@ enum ReadConfigError {
     Io(std::io::Error),
     /// {0}
     Validation(crate::configuration::ConfigurationError),
+    /// could not apply the release bundle configuration: {0}
+    Overlay(serde_yaml::Error),
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::env::temp_dir;
+    use std::time::Duration;
 
     use futures::StreamExt;
 
     use super::*;
+    use crate::configuration::secrets::tests::MockProvider;
     use crate::files::tests::create_temp_file;
     use crate::files::tests::write_and_flush;
+    use crate::registry::OciContent;
     use crate::uplink::UplinkConfig;
 
     #[tokio::test(flavor = "multi_thread")]
@@ -184,7 +436,7 @@ mod tests {
         let contents = include_str!("../../testdata/supergraph_config.router.yaml");
         write_and_flush(&mut file, contents).await;
         let mut stream = ConfigurationSource::File { path, watch: true }
-            .into_stream(Some(UplinkConfig::default()))
+            .into_stream(Some(UplinkConfig::default()), None)
             .boxed();
 
         // First update is guaranteed
@@ -208,13 +460,93 @@ mod tests {
         assert!(event.is_none() || matches!(event, Some((Some(NoMoreConfiguration), _))));
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn config_by_files_watching_includes() {
+        let (included, mut included_file) = create_temp_file();
+        write_and_flush(&mut included_file, "supergraph:\n  introspection: false\n").await;
+        let (base, mut base_file) = create_temp_file();
+        let include = format!("include: {}\n", included.display());
+        write_and_flush(&mut base_file, &include).await;
+        let (overlay, mut overlay_file) = create_temp_file();
+        write_and_flush(&mut overlay_file, "supergraph:\n  path: /graph\n").await;
+        let mut stream = ConfigurationSource::Files {
+            paths: vec![base, overlay],
+            watch: true,
+        }
+        .into_stream(Some(UplinkConfig::default()), None)
+        .boxed();
+
+        let UpdateConfiguration(configuration) = stream.next().await.unwrap() else {
+            panic!("expected a configuration");
+        };
+        assert!(!configuration.supergraph.introspection);
+        assert_eq!(configuration.supergraph.path, "/graph");
+
+        // Changing an included file reloads the merged configuration
+        write_and_flush(&mut included_file, "supergraph:\n  introspection: true\n").await;
+        let UpdateConfiguration(configuration) = stream.next().await.unwrap() else {
+            panic!("expected a configuration");
+        };
+        assert!(configuration.supergraph.introspection);
+        assert_eq!(configuration.supergraph.path, "/graph");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn config_reloaded_when_a_secret_rotates() {
+        let (path, mut file) = create_temp_file();
+        let contents = include_str!("../../testdata/supergraph_config.router.yaml");
+        write_and_flush(&mut file, contents).await;
+        let provider = MockProvider::default();
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "old".into());
+        let secrets = Arc::new(Secrets::new(
+            Box::new(provider.clone()),
+            Duration::from_secs(60),
+            Duration::from_secs(10),
+        ));
+        secrets.get("kv/redis#password").unwrap();
+        secrets.track(HashSet::from(["kv/redis#password".to_string()]));
+        let (configuration, files) = ConfigurationSource::read_config(&[path.clone()], None, None)
+            .unwrap_or_else(|_| panic!("the configuration must be valid"));
+        let mut stream = ConfigurationSource::watch_files(
+            configuration,
+            vec![path],
+            files,
+            None,
+            Some(secrets),
+            None,
+        );
+
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            UpdateConfiguration(_)
+        ));
+        // Nothing changed, so the configuration isn't read again
+        assert!(
+            tokio::time::timeout(Duration::from_secs(25), stream.next())
+                .await
+                .is_err()
+        );
+
+        provider
+            .secrets
+            .lock()
+            .insert("kv/redis#password".into(), "new".into());
+        let event = tokio::time::timeout(Duration::from_secs(10), stream.next())
+            .await
+            .expect("the rotation must reload the configuration");
+        assert!(matches!(event, Some(UpdateConfiguration(_))));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn config_by_file_missing() {
         let mut stream = ConfigurationSource::File {
             path: temp_dir().join("does_not_exit"),
             watch: true,
         }
-        .into_stream(Some(UplinkConfig::default()));
+        .into_stream(Some(UplinkConfig::default()), None);
 
         // First update fails because the file is invalid.
         assert!(matches!(stream.next().await.unwrap(), NoMoreConfiguration));
@@ -225,7 +557,7 @@ mod tests {
         let (path, mut file) = create_temp_file();
         write_and_flush(&mut file, "Garbage").await;
         let mut stream = ConfigurationSource::File { path, watch: true }
-            .into_stream(Some(UplinkConfig::default()));
+            .into_stream(Some(UplinkConfig::default()), None);
 
         // First update fails because the file is invalid.
         assert!(matches!(stream.next().await.unwrap(), NoMoreConfiguration));
@@ -238,11 +570,40 @@ mod tests {
         write_and_flush(&mut file, contents).await;
 
         let mut stream = ConfigurationSource::File { path, watch: false }
-            .into_stream(Some(UplinkConfig::default()));
+            .into_stream(Some(UplinkConfig::default()), None);
         assert!(matches!(
             stream.next().await.unwrap(),
             UpdateConfiguration(_)
         ));
         assert!(matches!(stream.next().await.unwrap(), NoMoreConfiguration));
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn static_config_is_applied_with_release_overlays() {
+        let overlays = ReleaseOverlays::new();
+        let mut stream = ConfigurationSource::Static(Default::default())
+            .into_stream(Some(UplinkConfig::default()), Some(overlays.clone()));
+
+        // Nothing is applied until a release bundle was fetched
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), stream.next())
+                .await
+                .is_err()
+        );
+
+        overlays
+            .publish(&OciContent {
+                schema: "type Query { a: Int }".to_string(),
+                digest: "sha256:0".to_string(),
+                config_overlay: Some("supergraph:\n  introspection: true\n".to_string()),
+                persisted_query_manifests: Vec::new(),
+            })
+            .unwrap();
+        let Some(UpdateSchemaAndConfiguration(schema, configuration)) = stream.next().await else {
+            panic!("the release bundle must be applied with the configuration");
+        };
+        assert_eq!(schema.sdl, "type Query { a: Int }");
+        assert!(configuration.supergraph.introspection);
+        assert!(configuration.uplink.is_some());
+    }
 }
@@ -23,6 +23,7 @@ use self::Event::Shutdown;
 use self::Event::UpdateConfiguration;
 use self::Event::UpdateLicense;
 use self::Event::UpdateSchema;
+use self::Event::UpdateSchemaAndConfiguration;
 use crate::Configuration;
 use crate::uplink::license_enforcement::LicenseState;
 use crate::uplink::schema::SchemaState;
@@ -41,6 +42,9 @@ pub(crate) enum Event {
     /// There are no more updates to the schema
     NoMoreSchema,
 
+    /// The schema and the configuration were updated together, by a release bundle.
+    UpdateSchemaAndConfiguration(SchemaState, Arc<Configuration>),
+
     /// Update license {}
     UpdateLicense(LicenseState),
 
@@ -72,6 +76,9 @@ impl Debug for Event {
             NoMoreSchema => {
                 write!(f, "NoMoreSchema")
             }
+            UpdateSchemaAndConfiguration(..) => {
+                write!(f, "UpdateSchemaAndConfiguration(<redacted>)")
+            }
             UpdateLicense(e) => {
                 write!(f, "UpdateLicense({e:?})")
             }
@@ -6,13 +6,23 @@ use derivative::Derivative;
 use derive_more::Display;
 use derive_more::From;
 use futures::prelude::*;
+use reqwest::StatusCode;
+use reqwest::header::ETAG;
+use reqwest::header::HeaderValue;
+use reqwest::header::IF_MODIFIED_SINCE;
+use reqwest::header::IF_NONE_MATCH;
+use reqwest::header::LAST_MODIFIED;
 use url::Url;
 
+use crate::Configuration;
+use crate::polling::jittered_interval;
 use crate::registry::OciConfig;
-use crate::registry::fetch_oci;
+use crate::registry::overlay::ReleaseOverlays;
+use crate::registry::stream_oci;
 use crate::router::Event;
 use crate::router::Event::NoMoreSchema;
 use crate::router::Event::UpdateSchema;
+use crate::spec::Schema;
 use crate::uplink::UplinkConfig;
 use crate::uplink::schema::SchemaState;
 use crate::uplink::schema_stream::SupergraphSdlQuery;
@@ -52,6 +62,15 @@ pub enum SchemaSource {
     URLs {
         /// The URLs to fetch the schema from.
         urls: Vec<Url>,
+
+        /// `true` to poll the URLs for changes and hot apply them.
+        watch: bool,
+
+        /// How often the URLs are polled when watching.
+        poll_interval: Duration,
+
+        /// The maximum random delay added to each poll interval.
+        jitter: Duration,
     },
 
     #[display("Registry")]
@@ -68,7 +87,13 @@ impl From<&'_ str> for SchemaSource {
 
 impl SchemaSource {
     /// Convert this schema into a stream regardless of if is static or not. Allows for unified handling later.
-    pub(crate) fn into_stream(self) -> impl Stream<Item = Event> {
+    ///
+    /// The release bundles of an OCI source are published to `overlays`, if any, for the
+    /// configuration source to apply together with their router configuration.
+    pub(crate) fn into_stream(
+        self,
+        overlays: Option<ReleaseOverlays>,
+    ) -> impl Stream<Item = Event> {
         match self {
             SchemaSource::Static { schema_sdl: schema } => {
                 let update_schema = UpdateSchema(SchemaState {
@@ -152,31 +177,67 @@ impl SchemaSource {
                     })
                     .boxed()
             }
-            SchemaSource::URLs { urls } => {
-                futures::stream::once(async move {
-                    fetch_supergraph_from_first_viable_url(&urls).await
+            SchemaSource::URLs {
+                urls,
+                watch,
+                poll_interval,
+                jitter,
+            } => match supergraph_http_client() {
+                None => stream::empty().boxed(),
+                Some(client) if watch => {
+                    poll_supergraph_urls(client, urls, poll_interval, jitter).boxed()
+                }
+                Some(client) => futures::stream::once(async move {
+                    let mut urls = urls.into_iter().map(SupergraphUrl::new).collect::<Vec<_>>();
+                    match fetch_supergraph_from_first_viable_url(&client, &mut urls, None).await {
+                        UrlsFetch::Fetched { schema, .. } => Some(SchemaState {
+                            sdl: schema,
+                            launch_id: None,
+                        }),
+                        UrlsFetch::NotModified | UrlsFetch::Failed => None,
+                    }
                 })
                 .filter_map(|s| async move { s.map(Event::UpdateSchema) })
-                .boxed()
-            }
-            SchemaSource::OCI(oci_config) => {
-                futures::stream::once(async move {
-                    match fetch_oci(oci_config).await {
-                        Ok(oci_result) => {
-                            Some(SchemaState {
-                                sdl: oci_result.schema,
-                                launch_id: None,
-                            })
+                .boxed(),
+            },
+            SchemaSource::OCI(oci_config) => match overlays {
+                // The configuration source applies the schema of each release bundle together
+                // with its router configuration, so that a release is applied in one reload
+                Some(overlays) => {
+                    let published = stream_oci(oci_config).filter_map({
+                        let overlays = overlays.clone();
+                        move |result| {
+                            if let Err(err) = result.and_then(|content| overlays.publish(&content)) {
+                                tracing::error!("{}", err);
+                            }
+                            future::ready(None::<Event>)
                         }
-                        Err(err) => {
-                            tracing::error!("{}", err);
-                            None
+                    });
+                    // Once a bundle was published, its schema is for the configuration source to
+                    // apply, which must not race with the end of the schema updates
+                    let end = stream::once(async move {
+                        if overlays.current().is_some() {
+                            future::pending::<()>().await;
                         }
-                    }
-                })
-                    .filter_map(|s| async move { s.map(Event::UpdateSchema) })
-                    .boxed()
-            }
+                    })
+                    .filter_map(|()| future::ready(None));
+                    published.chain(end).boxed()
+                }
+                None => stream_oci(oci_config)
+                    .filter_map(|result| {
+                        future::ready(match result {
+                            Ok(content) => Some(UpdateSchema(SchemaState {
+                                sdl: content.schema,
+                                launch_id: None,
+                            })),
+                            Err(err) => {
+                                tracing::error!("{}", err);
+                                None
+                            }
+                        })
+                    })
+                    .boxed(),
+            },
         }
         .chain(stream::iter(vec![NoMoreSchema]))
         .boxed()
@@ -189,38 +250,91 @@ enum FetcherError {
     InitializationError(#[from] reqwest::Error),
 }
 
-// Encapsulates fetching the schema from the first viable url.
-// It will try each url in order until it finds one that works.
-async fn fetch_supergraph_from_first_viable_url(urls: &[Url]) -> Option<SchemaState> {
-    let Ok(client) = reqwest::Client::builder()
+fn supergraph_http_client() -> Option<reqwest::Client> {
+    match reqwest::Client::builder()
         .no_gzip()
         .timeout(Duration::from_secs(10))
         .build()
-    else {
-        tracing::error!("failed to create HTTP client to fetch supergraph schema");
-        return None;
-    };
-    for url in urls {
-        match client
-            .get(reqwest::Url::parse(url.as_ref()).unwrap())
-            .send()
-            .await
-        {
-            Ok(res) if res.status().is_success() => match res.text().await {
-                Ok(schema) => {
-                    return Some(SchemaState {
-                        sdl: schema,
-                        launch_id: None,
-                    });
-                }
-                Err(err) => {
-                    tracing::warn!(
-                        url.full = %url,
-                        reason = %err,
-                        "failed to fetch supergraph schema"
-                    )
+    {
+        Ok(client) => Some(client),
+        Err(_) => {
+            tracing::error!("failed to create HTTP client to fetch supergraph schema");
+            None
+        }
+    }
+}
+
+/// A supergraph URL, with the validators used to revalidate the schema it last returned.
+struct SupergraphUrl {
+    url: Url,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl SupergraphUrl {
+    fn new(url: Url) -> Self {
+        Self {
+            url,
+            etag: None,
+            last_modified: None,
+        }
+    }
+}
+
+enum UrlsFetch {
+    /// The schema was downloaded from the URL at `index`.
+    Fetched { index: usize, schema: String },
+    /// The URL the current schema came from reported that it did not change.
+    NotModified,
+    /// None of the URLs could be fetched.
+    Failed,
+}
+
+// Encapsulates fetching the schema from the first viable url.
+// It will try each url in order until it finds one that works.
+//
+// `revalidate` is the index of the URL the current schema was fetched from, if any: that URL is
+// sent the validators of its last response, so that it can answer with `304 Not Modified`.
+async fn fetch_supergraph_from_first_viable_url(
+    client: &reqwest::Client,
+    urls: &mut [SupergraphUrl],
+    revalidate: Option<usize>,
+) -> UrlsFetch {
+    for (index, supergraph_url) in urls.iter_mut().enumerate() {
+        let url = &supergraph_url.url;
+        let mut request = client.get(reqwest::Url::parse(url.as_ref()).unwrap());
+        if revalidate == Some(index) {
+            if let Some(etag) = &supergraph_url.etag {
+                request = request.header(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &supergraph_url.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+
+        match request.send().await {
+            Ok(res) if res.status() == StatusCode::NOT_MODIFIED => {
+                record_supergraph_fetch("not_modified");
+                return UrlsFetch::NotModified;
+            }
+            Ok(res) if res.status().is_success() => {
+                let etag = res.headers().get(ETAG).cloned();
+                let last_modified = res.headers().get(LAST_MODIFIED).cloned();
+                match res.text().await {
+                    Ok(schema) => {
+                        supergraph_url.etag = etag;
+                        supergraph_url.last_modified = last_modified;
+                        return UrlsFetch::Fetched { index, schema };
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            url.full = %url,
+                            reason = %err,
+                            "failed to fetch supergraph schema"
+                        )
+                    }
                 }
-            },
+            }
             Ok(res) => tracing::warn!(
                 http.response.status_code = res.status().as_u16(),
                 url.full = %url,
@@ -232,9 +346,107 @@ async fn fetch_supergraph_from_first_viable_url(urls: &[Url]) -> Option<SchemaSt
                 "failed to fetch supergraph schema"
             ),
         }
+        record_supergraph_fetch("error");
     }
     tracing::error!("failed to fetch supergraph schema from all urls");
-    None
+    UrlsFetch::Failed
+}
+
+/// The schema currently in use, and the index of the URL it was fetched from.
+struct PolledSchema {
+    sdl: String,
+    source: usize,
+}
+
+/// Polls the URLs for schema changes, only emitting an update when a valid supergraph schema that
+/// differs from the current one is downloaded. Failed or invalid downloads keep the current schema.
+///
+/// The first schema downloaded is emitted without being checked, so that the router fails to
+/// start if it is invalid rather than waiting for a valid one.
+fn poll_supergraph_urls(
+    client: reqwest::Client,
+    urls: Vec<Url>,
+    poll_interval: Duration,
+    jitter: Duration,
+) -> impl Stream<Item = Event> {
+    let urls = urls.into_iter().map(SupergraphUrl::new).collect::<Vec<_>>();
+    stream::unfold(
+        (urls, None, false),
+        move |(mut urls, mut current, mut started): (
+            Vec<SupergraphUrl>,
+            Option<PolledSchema>,
+            bool,
+        )| {
+            let client = client.clone();
+            async move {
+                loop {
+                    if started {
+                        tokio::time::sleep(jittered_interval(poll_interval, jitter)).await;
+                    }
+                    started = true;
+
+                    let revalidate = current.as_ref().map(|current| current.source);
+                    let (index, schema) = match fetch_supergraph_from_first_viable_url(
+                        &client, &mut urls, revalidate,
+                    )
+                    .await
+                    {
+                        UrlsFetch::Fetched { index, schema } => (index, schema),
+                        UrlsFetch::NotModified | UrlsFetch::Failed => continue,
+                    };
+
+                    if let Some(current) = current.as_mut().filter(|current| current.sdl == schema)
+                    {
+                        current.source = index;
+                        record_supergraph_fetch("unchanged");
+                        continue;
+                    }
+                    let validation = if current.is_some() {
+                        let sdl = schema.clone();
+                        tokio::task::spawn_blocking(move || {
+                            Schema::parse(&sdl, &Configuration::default())
+                                .map(|_| ())
+                                .map_err(|err| err.to_string())
+                        })
+                        .await
+                        .unwrap_or_else(|err| Err(err.to_string()))
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(err) = validation {
+                        record_supergraph_fetch("error");
+                        tracing::error!(
+                            url.full = %urls[index].url,
+                            reason = %err,
+                            "downloaded supergraph schema is invalid, keeping the current schema"
+                        );
+                        continue;
+                    }
+
+                    record_supergraph_fetch("updated");
+                    current = Some(PolledSchema {
+                        sdl: schema.clone(),
+                        source: index,
+                    });
+                    let update_schema = UpdateSchema(SchemaState {
+                        sdl: schema,
+                        launch_id: None,
+                    });
+                    return Some((update_schema, (urls, current, started)));
+                }
+            }
+        },
+    )
+}
+
+fn record_supergraph_fetch(result: &'static str) {
+    u64_counter_with_unit!(
+        "apollo.router.supergraph.urls.fetch",
+        "Number of supergraph schema fetches from URLs",
+        "{fetch}",
+        1,
+        result = result
+    );
 }
 
 #[cfg(test)]
@@ -246,6 +458,7 @@ mod tests {
     use wiremock::Mock;
     use wiremock::MockServer;
     use wiremock::ResponseTemplate;
+    use wiremock::matchers::header;
     use wiremock::matchers::method;
     use wiremock::matchers::path;
 
@@ -260,7 +473,7 @@ mod tests {
         let schema = include_str!("../../testdata/supergraph.graphql");
         write_and_flush(&mut file, schema).await;
         let mut stream = SchemaSource::File { path, watch: true }
-            .into_stream()
+            .into_stream(None)
             .boxed();
 
         // First update is guaranteed
@@ -279,7 +492,7 @@ mod tests {
         let schema = include_str!("../../testdata/supergraph.graphql");
         write_and_flush(&mut file, schema).await;
 
-        let mut stream = SchemaSource::File { path, watch: false }.into_stream();
+        let mut stream = SchemaSource::File { path, watch: false }.into_stream(None);
         assert!(matches!(stream.next().await.unwrap(), UpdateSchema(_)));
         assert!(matches!(stream.next().await.unwrap(), NoMoreSchema));
     }
@@ -290,7 +503,7 @@ mod tests {
             path: temp_dir().join("does_not_exist"),
             watch: true,
         }
-        .into_stream();
+        .into_stream(None);
 
         // First update fails because the file is invalid.
         assert!(matches!(stream.next().await.unwrap(), NoMoreSchema));
@@ -318,8 +531,11 @@ mod tests {
                     Url::parse(&format!("http://{}/schema1", mock_server.address())).unwrap(),
                     Url::parse(&format!("http://{}/schema2", mock_server.address())).unwrap(),
                 ],
+                watch: false,
+                poll_interval: Duration::from_secs(10),
+                jitter: Duration::ZERO,
             }
-            .into_stream();
+            .into_stream(None);
 
             assert!(
                 matches!(stream.next().await.unwrap(), UpdateSchema(schema) if schema.sdl == SCHEMA_1)
@@ -350,8 +566,11 @@ mod tests {
                     Url::parse(&format!("http://{}/schema1", mock_server.address())).unwrap(),
                     Url::parse(&format!("http://{}/schema2", mock_server.address())).unwrap(),
                 ],
+                watch: false,
+                poll_interval: Duration::from_secs(10),
+                jitter: Duration::ZERO,
             }
-            .into_stream();
+            .into_stream(None);
 
             assert!(
                 matches!(stream.next().await.unwrap(), UpdateSchema(schema) if schema.sdl == SCHEMA_2)
@@ -384,8 +603,11 @@ mod tests {
                     Url::parse(&format!("http://{}/schema1", mock_server.address())).unwrap(),
                     Url::parse(&format!("http://{}/schema2", mock_server.address())).unwrap(),
                 ],
+                watch: false,
+                poll_interval: Duration::from_secs(10),
+                jitter: Duration::ZERO,
             }
-            .into_stream();
+            .into_stream(None);
 
             assert!(matches!(stream.next().await.unwrap(), NoMoreSchema));
         }
@@ -394,4 +616,166 @@ mod tests {
         }))
         .await;
     }
+
+    fn polled_urls(mock_server: &MockServer, paths: &[&str]) -> SchemaSource {
+        SchemaSource::URLs {
+            urls: paths
+                .iter()
+                .map(|path| Url::parse(&format!("{}{path}", mock_server.uri())).unwrap())
+                .collect(),
+            watch: true,
+            poll_interval: Duration::from_millis(50),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn schema_by_url_polling_revalidates_with_etag() {
+        let schema_1 = include_str!("../../testdata/supergraph.graphql");
+        let schema_2 = include_str!("../../testdata/minimal_supergraph.graphql");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_string(schema_1),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(schema_2))
+            .mount(&mock_server)
+            .await;
+
+        let mut stream = polled_urls(&mock_server, &["/schema"]).into_stream(None);
+
+        assert!(
+            matches!(stream.next().await.unwrap(), UpdateSchema(schema) if schema.sdl == schema_1)
+        );
+        // The schema is only reloaded once the server stops answering `304 Not Modified`.
+        assert!(
+            matches!(stream.next().await.unwrap(), UpdateSchema(schema) if schema.sdl == schema_2)
+        );
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests.len() >= 4);
+        assert!(requests[0].headers.get("if-none-match").is_none());
+        assert!(requests[1].headers.get("if-none-match").is_some());
+    }
+
+    #[test(tokio::test)]
+    async fn schema_by_url_polling_keeps_schema_on_bad_download() {
+        let schema = include_str!("../../testdata/supergraph.graphql");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schema1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(schema))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema1"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("}}}"))
+            .mount(&mock_server)
+            .await;
+
+        let mut stream = polled_urls(&mock_server, &["/schema1", "/schema2"]).into_stream(None);
+
+        assert!(matches!(stream.next().await.unwrap(), UpdateSchema(s) if s.sdl == schema));
+        // The first URL fails and the second one serves an invalid schema: no reload happens.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), stream.next())
+                .await
+                .is_err()
+        );
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(
+            requests
+                .iter()
+                .any(|request| request.url.path() == "/schema2")
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn schema_by_url_polling_rejects_schemas_that_are_not_supergraphs() {
+        let schema = include_str!("../../testdata/supergraph.graphql");
+        let updated_schema = format!("{schema}\n# updated\n");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(schema))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("type Query { a: String }"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(updated_schema.clone()))
+            .mount(&mock_server)
+            .await;
+
+        let mut stream = polled_urls(&mock_server, &["/schema"]).into_stream(None);
+
+        assert!(matches!(stream.next().await.unwrap(), UpdateSchema(s) if s.sdl == schema));
+        // The second download parses, but isn't a supergraph: it is skipped for the next one
+        assert!(matches!(stream.next().await.unwrap(), UpdateSchema(s) if s.sdl == updated_schema));
+        assert!(mock_server.received_requests().await.unwrap().len() > 2);
+    }
+
+    #[test(tokio::test)]
+    async fn schema_by_url_polling_emits_the_first_schema_even_if_invalid() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("type Query { a: String }"))
+            .mount(&mock_server)
+            .await;
+
+        let mut stream = polled_urls(&mock_server, &["/schema"]).into_stream(None);
+
+        // Startup fails on this schema, instead of waiting for a valid one
+        assert!(
+            matches!(stream.next().await.unwrap(), UpdateSchema(s) if s.sdl == "type Query { a: String }")
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn schema_by_url_polling_ignores_unchanged_schema() {
+        let schema = include_str!("../../testdata/supergraph.graphql");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schema"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(schema))
+            .mount(&mock_server)
+            .await;
+
+        let mut stream = polled_urls(&mock_server, &["/schema"]).into_stream(None);
+
+        assert!(matches!(stream.next().await.unwrap(), UpdateSchema(s) if s.sdl == schema));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), stream.next())
+                .await
+                .is_err()
+        );
+        assert!(mock_server.received_requests().await.unwrap().len() > 1);
+    }
 }
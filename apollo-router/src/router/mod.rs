@@ -30,6 +30,7 @@ use tracing_futures::WithSubscriber;
 use crate::axum_factory::AxumHttpServerFactory;
 use crate::configuration::ListenAddr;
 use crate::orbiter::OrbiterRouterSuperServiceFactory;
+use crate::registry::overlay::ReleaseOverlays;
 use crate::router_factory::YamlRouterFactory;
 use crate::state_machine::ListenAddresses;
 use crate::state_machine::StateMachine;
@@ -232,16 +233,20 @@ fn generate_event_stream(
     shutdown_receiver: oneshot::Receiver<()>,
 ) -> impl Stream<Item = Event> {
     let reload_source = ReloadSource::default();
+    // The release bundles of an OCI schema source carry router configuration as well
+    let overlays = matches!(schema, SchemaSource::OCI(_)).then(ReleaseOverlays::new);
 
     stream::select_all(vec![
         shutdown.into_stream().boxed(),
-        schema.into_stream().boxed(),
+        schema.into_stream(overlays.clone()).boxed(),
         license.into_stream().boxed(),
         reload_source.clone().into_stream().boxed(),
         configuration
-            .into_stream(uplink_config)
+            .into_stream(uplink_config, overlays)
             .map(move |config_event| {
-                if let Event::UpdateConfiguration(config) = &config_event {
+                if let Event::UpdateConfiguration(config)
+                | Event::UpdateSchemaAndConfiguration(_, config) = &config_event
+                {
                     reload_source.set_period(&config.experimental_chaos.force_reload)
                 }
                 config_event
@@ -5,6 +5,7 @@ use std::sync::Arc;
 
 use apollo_compiler::validation::Valid;
 use axum::response::IntoResponse;
+use futures::future::BoxFuture;
 use http::StatusCode;
 use indexmap::IndexMap;
 use multimap::MultiMap;
@@ -33,6 +34,7 @@ use crate::plugins::telemetry::reload::apollo_opentelemetry_initialized;
 use crate::plugins::traffic_shaping::APOLLO_TRAFFIC_SHAPING;
 use crate::plugins::traffic_shaping::TrafficShaping;
 use crate::query_planner::QueryPlannerService;
+use crate::query_planner::safe_rollout;
 use crate::services::HasSchema;
 use crate::services::PluggableSupergraphServiceBuilder;
 use crate::services::Plugins;
@@ -111,6 +113,12 @@ pub(crate) trait RouterFactory:
     fn web_endpoints(&self) -> MultiMap<ListenAddr, Endpoint>;
 
     fn pipeline_ref(&self) -> Arc<PipelineRef>;
+
+    /// Writes out anything that must survive the router shutting down, such as the persisted
+    /// queries learned so far.
+    fn shutdown(&self) -> BoxFuture<'static, ()> {
+        Box::pin(futures::future::ready(()))
+    }
 }
 
 /// Factory for creating a RouterFactory
@@ -219,8 +227,44 @@ impl YamlRouterFactory {
         extra_plugins: Option<Vec<(String, Box<dyn DynPlugin>)>>,
         license: LicenseState,
     ) -> Result<RouterCreator, BoxError> {
+        let planner = Self::create_query_planner(&configuration, &schema).await?;
+
+        // Instantiate the parser here so we can use it to warm up the planner below
+        let query_analysis_layer =
+            QueryAnalysisLayer::new(schema.clone(), Arc::clone(&configuration)).await;
+
+        let persisted_query_layer = Arc::new(
+            PersistedQueryLayer::new_with_previous(
+                &configuration,
+                previous_router
+                    .map(|previous_router| previous_router.persisted_query_layer.as_ref()),
+            )
+            .await?,
+        );
+
+        // A new schema must pass the rollout checks before anything else (such as plugins) is
+        // created for it
+        let safe_rollout = &configuration
+            .supergraph
+            .query_planning
+            .experimental_safe_rollout;
+        if let Some(previous_router) = previous_router.filter(|previous_router| {
+            safe_rollout.enabled
+                && previous_router.supergraph_creator.schema().schema_id != schema.schema_id
+        }) {
+            let operations = safe_rollout::known_operations(
+                &persisted_query_layer,
+                &previous_router.previous_cache(),
+            )
+            .await;
+            safe_rollout::check(safe_rollout, &planner, &query_analysis_layer, operations)
+                .instrument(tracing::info_span!("safe_schema_rollout"))
+                .await?;
+        }
+
         let mut supergraph_creator = self
-            .inner_create_supergraph(
+            .inner_create_supergraph_with_planner(
+                planner,
                 configuration.clone(),
                 schema,
                 initial_telemetry_plugin,
@@ -229,12 +273,6 @@ impl YamlRouterFactory {
             )
             .await?;
 
-        // Instantiate the parser here so we can use it to warm up the planner below
-        let query_analysis_layer =
-            QueryAnalysisLayer::new(supergraph_creator.schema(), Arc::clone(&configuration)).await;
-
-        let persisted_query_layer = Arc::new(PersistedQueryLayer::new(&configuration).await?);
-
         if let Some(previous_router) = previous_router {
             let previous_cache = previous_router.previous_cache();
 
@@ -287,12 +325,40 @@ impl YamlRouterFactory {
         extra_plugins: Option<Vec<(String, Box<dyn DynPlugin>)>>,
         license: LicenseState,
     ) -> Result<SupergraphCreator, BoxError> {
+        let planner = Self::create_query_planner(&configuration, &schema).await?;
+        self.inner_create_supergraph_with_planner(
+            planner,
+            configuration,
+            schema,
+            initial_telemetry_plugin,
+            extra_plugins,
+            license,
+        )
+        .await
+    }
+
+    async fn create_query_planner(
+        configuration: &Arc<Configuration>,
+        schema: &Arc<Schema>,
+    ) -> Result<QueryPlannerService, BoxError> {
         let query_planner_span = tracing::info_span!("query_planner_creation");
         // QueryPlannerService takes an UnplannedRequest and outputs PlannedRequest
-        let planner = QueryPlannerService::new(schema.clone(), configuration.clone())
-            .instrument(query_planner_span)
-            .await?;
+        Ok(
+            QueryPlannerService::new(schema.clone(), configuration.clone())
+                .instrument(query_planner_span)
+                .await?,
+        )
+    }
 
+    async fn inner_create_supergraph_with_planner(
+        &mut self,
+        planner: QueryPlannerService,
+        configuration: Arc<Configuration>,
+        schema: Arc<Schema>,
+        initial_telemetry_plugin: Option<Box<dyn DynPlugin>>,
+        extra_plugins: Option<Vec<(String, Box<dyn DynPlugin>)>>,
+        license: LicenseState,
+    ) -> Result<SupergraphCreator, BoxError> {
         let span = tracing::info_span!("plugins");
 
         // Process the plugins.
@@ -735,6 +801,8 @@ pub(crate) async fn create_plugins(
     add_optional_apollo_plugin!("experimental_response_cache");
     add_mandatory_apollo_plugin!("progressive_override");
     add_optional_apollo_plugin!("demand_control");
+    // Injected faults must go through traffic shaping, so that its timeouts apply to them
+    add_optional_apollo_plugin!("experimental_fault_injection");
 
     // This relative ordering is documented in `docs/source/customizations/native.mdx`:
     add_optional_apollo_plugin!("connectors");
@@ -33,8 +33,12 @@ use crate::error::FetchError;
 use crate::graphql;
 use crate::layers::DEFAULT_BUFFER_SIZE;
 use crate::plugins::connectors::handle_responses::process_response;
+use crate::plugins::connectors::http_cache::HttpCaches;
+use crate::plugins::connectors::http_request::clone_request;
+use crate::plugins::connectors::pagination::paginate;
 use crate::plugins::connectors::request_limit::RequestLimits;
 use crate::plugins::connectors::tracing::CONNECTOR_TYPE_HTTP;
+use crate::plugins::file_uploads;
 use crate::plugins::telemetry::config_new::attributes::HTTP_REQUEST_BODY;
 use crate::plugins::telemetry::config_new::attributes::HTTP_REQUEST_HEADERS;
 use crate::plugins::telemetry::config_new::attributes::HTTP_REQUEST_URI;
@@ -42,9 +46,9 @@ use crate::plugins::telemetry::config_new::attributes::HTTP_REQUEST_VERSION;
 use crate::plugins::telemetry::config_new::connector::events::ConnectorEventRequest;
 use crate::plugins::telemetry::config_new::events::EventLevel;
 use crate::plugins::telemetry::config_new::events::log_event;
+use crate::plugins::traffic_shaping::connector_deduplication::ConnectorRequestDeduplication;
 use crate::services::Plugins;
 use crate::services::http::HttpClientServiceFactory;
-use crate::services::router;
 
 pub(crate) type BoxService = tower::util::BoxService<Request, Response, BoxError>;
 pub(crate) type ServiceResult = Result<Response, BoxError>;
@@ -80,6 +84,10 @@ pub struct Request {
 
     /// Original request to the Router.
     pub(crate) supergraph_request: Arc<http::Request<graphql::Request>>,
+
+    /// Whether to retain the response body before it was mapped on the [`Response`]. The body is
+    /// not kept by default to avoid a clone.
+    pub(crate) retain_upstream_body: bool,
 }
 
 /// Response type for a connector
@@ -99,6 +107,13 @@ pub struct Response {
 
     /// The mapped response, including any mapping problems encountered when processing the response
     pub(crate) mapped_response: MappedResponse,
+
+    /// The response body before the selection mapping was applied. This is only retained when
+    /// [`Request::retain_upstream_body`] is set.
+    pub(crate) upstream_body: Option<Value>,
+
+    /// Errors reported next to the mapped response, which keeps its data
+    pub(crate) errors: Vec<RuntimeError>,
 }
 
 #[buildstructor::buildstructor]
@@ -123,6 +138,8 @@ impl Response {
             connector,
             transport_result: Err(error),
             mapped_response,
+            upstream_body: None,
+            errors: Vec::new(),
         }
     }
 
@@ -155,6 +172,8 @@ impl Response {
             connector,
             transport_result: Ok(http_response.into()),
             mapped_response,
+            upstream_body: None,
+            errors: Vec::new(),
         }
     }
 }
@@ -223,7 +242,7 @@ impl tower::Service<Request> for ConnectorRequestService {
         let http_client_service_factory = self.http_client_service_factory.clone();
 
         // Load the information needed from the context
-        let (debug, connector_request_event, request_limit) =
+        let (debug, connector_request_event, request_limit, http_caches) =
             request.context.extensions().with_lock(|lock| {
                 (
                     lock.get::<Arc<Mutex<ConnectorContext>>>().cloned(),
@@ -236,6 +255,7 @@ impl tower::Service<Request> for ConnectorRequestService {
                             )
                         })
                         .unwrap_or(None),
+                    lock.get::<Arc<HttpCaches>>().cloned(),
                 )
             });
 
@@ -249,11 +269,14 @@ impl tower::Service<Request> for ConnectorRequestService {
 
         Box::pin(async move {
             let mut debug_request = (None, Default::default());
-            let result = if request_limit.is_some_and(|request_limit| !request_limit.allow()) {
+            let result = if request_limit
+                .as_ref()
+                .is_some_and(|request_limit| !request_limit.allow())
+            {
                 Err(Error::RequestLimitExceeded)
             } else {
                 let result = match request.transport_request {
-                    TransportRequest::Http(http_request) => {
+                    TransportRequest::Http(mut http_request) => {
                         debug_request = http_request.debug;
 
                         log_request(
@@ -263,27 +286,96 @@ impl tower::Service<Request> for ConnectorRequestService {
                         );
 
                         let source_name = request.connector.source_config_key();
+                        let deduplication = http_request
+                            .inner
+                            .extensions()
+                            .get::<ConnectorRequestDeduplication>()
+                            .cloned();
 
                         if let Some(http_client_service_factory) =
                             http_client_service_factory.get(&source_name).cloned()
                         {
-                            let (parts, body) = http_request.inner.into_parts();
-                            let http_request =
-                                http::Request::from_parts(parts, router::body::from_bytes(body));
-
-                            http_client_service_factory
-                                .create(&original_subgraph_name)
-                                .oneshot(crate::services::http::HttpRequest {
-                                    http_request,
-                                    context: request.context.clone(),
-                                })
-                                .await
-                                .map(|result| result.http_response)
-                                .map_err(|e|
-                                    // Note: this previously used `#[from] BoxError` but when we moved `Error` into the
-                                    // `apollo-federation` crate, we could longer reference `BoxError` from there.
-                                    Error::TransportFailure((replace_subgraph_name(e, &request.connector)).to_string())
-                                )
+                            let send_uncached = |http_request: http::Request<String>| {
+                                let http_client =
+                                    http_client_service_factory.create(&original_subgraph_name);
+                                let context = request.context.clone();
+                                let connector = request.connector.clone();
+
+                                async move {
+                                    // stream any files uploaded by the client into the body
+                                    let http_request = file_uploads::connector_request_wrapper(
+                                        http_request,
+                                        &context,
+                                    )
+                                    .await
+                                    .map_err(|e| Error::TransportFailure(e.to_string()))?;
+                                    http_client
+                                        .oneshot(crate::services::http::HttpRequest {
+                                            http_request,
+                                            context,
+                                        })
+                                        .await
+                                        .map(|result| result.http_response)
+                                        .map_err(|e|
+                                            // Note: this previously used `#[from] BoxError` but when we moved `Error` into the
+                                            // `apollo-federation` crate, we could longer reference `BoxError` from there.
+                                            Error::TransportFailure((replace_subgraph_name(e, &connector)).to_string())
+                                        )
+                                }
+                            };
+
+                            let send_uncached = &send_uncached;
+                            let deduplication = deduplication.as_ref();
+                            let send_deduplicated = move |http_request: http::Request<String>| async move {
+                                match deduplication {
+                                    Some(deduplication) => {
+                                        deduplication.send(http_request, send_uncached).await
+                                    }
+                                    None => send_uncached(http_request).await,
+                                }
+                            };
+
+                            let http_cache = http_caches
+                                .as_ref()
+                                .and_then(|http_caches| http_caches.get(&source_name));
+                            let send_deduplicated = &send_deduplicated;
+                            let connector = &request.connector;
+                            let send = move |http_request: http::Request<String>| async move {
+                                match http_cache {
+                                    Some(http_cache) => {
+                                        http_cache
+                                            .send(http_request, connector, send_deduplicated)
+                                            .await
+                                    }
+                                    None => send_deduplicated(http_request).await,
+                                }
+                            };
+
+                            match &request.connector.transport.pagination {
+                                Some(pagination) => {
+                                    // every page is requested with the same body
+                                    file_uploads::buffer_uploads(&mut http_request.inner);
+                                    match send(clone_request(&http_request.inner)).await {
+                                        Ok(response) => {
+                                            paginate(
+                                                pagination,
+                                                request
+                                                    .connector
+                                                    .transport
+                                                    .response_format
+                                                    .as_deref(),
+                                                &http_request.inner,
+                                                response,
+                                                request_limit,
+                                                send,
+                                            )
+                                            .await
+                                        }
+                                        Err(error) => Err(error),
+                                    }
+                                }
+                                None => send(http_request.inner).await,
+                            }
                         } else {
                             Err(Error::TransportFailure("no http client found".into()))
                         }
@@ -309,6 +401,7 @@ impl tower::Service<Request> for ConnectorRequestService {
                 debug_request,
                 &debug,
                 request.supergraph_request,
+                request.retain_upstream_body,
             )
             .await)
         })
@@ -214,17 +214,16 @@ async fn execute(
             }
         });
 
-    aggregate_responses(
-        futures::future::try_join_all(tasks)
-            .await
-            .map(|responses| {
-                responses
-                    .into_iter()
-                    .map(|response| response.mapped_response)
-                    .collect()
-            })?,
-    )
-    .map_err(BoxError::from)
+    let mut errors = Vec::new();
+    let responses = futures::future::try_join_all(tasks)
+        .await?
+        .into_iter()
+        .map(|response| {
+            errors.extend(response.errors);
+            response.mapped_response
+        })
+        .collect();
+    aggregate_responses(responses, errors).map_err(BoxError::from)
 }
 
 #[derive(Clone)]
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use apollo_compiler::ast;
@@ -40,6 +41,7 @@ impl FreeformGraphQLBehavior {
     pub(super) fn action_for_freeform_graphql(
         &self,
         ast: Result<&ast::Document, &str>,
+        client_name: Option<&str>,
     ) -> FreeformGraphQLAction {
         match self {
             FreeformGraphQLBehavior::AllowAll { .. } => FreeformGraphQLAction {
@@ -57,7 +59,7 @@ impl FreeformGraphQLBehavior {
                 log_unknown,
                 ..
             } => {
-                if safelist.is_allowed(ast) {
+                if safelist.is_allowed(ast, client_name) {
                     FreeformGraphQLAction {
                         should_allow: true,
                         should_log: false,
@@ -72,7 +74,7 @@ impl FreeformGraphQLBehavior {
             FreeformGraphQLBehavior::LogUnlessInSafelist { safelist, .. } => {
                 FreeformGraphQLAction {
                     should_allow: true,
-                    should_log: !safelist.is_allowed(ast),
+                    should_log: !safelist.is_allowed(ast, client_name),
                 }
             }
         }
@@ -97,77 +99,96 @@ impl FreeformGraphQLBehavior {
 /// importantly, once we're doing any normalization at all, it's much easier to
 /// normalize to the default formatting instead of trying to preserve
 /// formatting.
+///
+/// Operations which the manifest restricts to a client are only allowed for that client.
 #[derive(Debug)]
 pub(crate) struct FreeformGraphQLSafelist {
     normalized_bodies: HashSet<String>,
+    client_normalized_bodies: HashMap<String, HashSet<String>>,
 }
 
 impl FreeformGraphQLSafelist {
     pub(super) fn new(manifest: &PersistedQueryManifest) -> Self {
         let mut safelist = Self {
             normalized_bodies: HashSet::new(),
+            client_normalized_bodies: HashMap::new(),
         };
 
-        for body in manifest.values() {
-            safelist.insert_from_manifest(body);
+        for (id, body) in manifest.iter() {
+            safelist.insert_from_manifest(body, id.client_name.as_deref());
         }
 
         safelist
     }
 
-    fn insert_from_manifest(&mut self, body_from_manifest: &str) {
-        self.normalized_bodies.insert(
-            self.normalize_body(
-                ast::Document::parse(body_from_manifest, "from_manifest")
-                    .as_ref()
-                    .map_err(|_| body_from_manifest),
-            ),
+    fn insert_from_manifest(&mut self, body_from_manifest: &str, client_name: Option<&str>) {
+        let normalized_body = normalize_body(
+            ast::Document::parse(body_from_manifest, "from_manifest")
+                .as_ref()
+                .map_err(|_| body_from_manifest),
         );
+        match client_name {
+            None => self.normalized_bodies.insert(normalized_body),
+            Some(client_name) => self
+                .client_normalized_bodies
+                .entry(client_name.to_string())
+                .or_default()
+                .insert(normalized_body),
+        };
     }
 
-    pub(super) fn is_allowed(&self, ast: Result<&ast::Document, &str>) -> bool {
+    pub(super) fn is_allowed(
+        &self,
+        ast: Result<&ast::Document, &str>,
+        client_name: Option<&str>,
+    ) -> bool {
         // Note: consider adding an LRU cache that caches this function's return
         // value based solely on body_from_request without needing to normalize
         // the body.
-        self.normalized_bodies.contains(&self.normalize_body(ast))
+        let normalized_body = normalize_body(ast);
+        self.normalized_bodies.contains(&normalized_body)
+            || client_name
+                .and_then(|client_name| self.client_normalized_bodies.get(client_name))
+                .is_some_and(|bodies| bodies.contains(&normalized_body))
     }
+}
 
-    pub(super) fn normalize_body(&self, ast: Result<&ast::Document, &str>) -> String {
-        match ast {
-            Err(body_from_request) => {
-                // If we can't parse the operation (whether from the PQ list or the
-                // incoming request), then we can't normalize it. We keep it around
-                // unnormalized, so that it at least works as a byte-for-byte
-                // safelist entry.
-                body_from_request.to_string()
-            }
-            Ok(ast) => {
-                let mut operations = vec![];
-                let mut fragments = vec![];
-
-                for definition in &ast.definitions {
-                    match definition {
-                        ast::Definition::OperationDefinition(def) => operations.push(def.clone()),
-                        ast::Definition::FragmentDefinition(def) => fragments.push(def.clone()),
-                        _ => {}
-                    }
+/// Normalizes an operation body as described on [`FreeformGraphQLSafelist`].
+pub(super) fn normalize_body(ast: Result<&ast::Document, &str>) -> String {
+    match ast {
+        Err(body_from_request) => {
+            // If we can't parse the operation (whether from the PQ list or the
+            // incoming request), then we can't normalize it. We keep it around
+            // unnormalized, so that it at least works as a byte-for-byte
+            // safelist entry.
+            body_from_request.to_string()
+        }
+        Ok(ast) => {
+            let mut operations = vec![];
+            let mut fragments = vec![];
+
+            for definition in &ast.definitions {
+                match definition {
+                    ast::Definition::OperationDefinition(def) => operations.push(def.clone()),
+                    ast::Definition::FragmentDefinition(def) => fragments.push(def.clone()),
+                    _ => {}
                 }
+            }
 
-                let mut new_document = ast::Document::new();
+            let mut new_document = ast::Document::new();
 
-                // First include operation definitions, sorted by name.
-                operations.sort_by_key(|x| x.name.clone());
-                new_document
-                    .definitions
-                    .extend(operations.into_iter().map(Into::into));
+            // First include operation definitions, sorted by name.
+            operations.sort_by_key(|x| x.name.clone());
+            new_document
+                .definitions
+                .extend(operations.into_iter().map(Into::into));
 
-                // Next include fragment definitions, sorted by name.
-                fragments.sort_by_key(|x| x.name.clone());
-                new_document
-                    .definitions
-                    .extend(fragments.into_iter().map(Into::into));
-                new_document.to_string()
-            }
+            // Next include fragment definitions, sorted by name.
+            fragments.sort_by_key(|x| x.name.clone());
+            new_document
+                .definitions
+                .extend(fragments.into_iter().map(Into::into));
+            new_document.to_string()
         }
     }
 }
@@ -229,7 +250,10 @@ mod tests {
         ]));
 
         let is_allowed = |body: &str| -> bool {
-            safelist.is_allowed(ast::Document::parse(body, "").as_ref().map_err(|_| body))
+            safelist.is_allowed(
+                ast::Document::parse(body, "").as_ref().map_err(|_| body),
+                None,
+            )
         };
 
         // Precise string matches.
@@ -257,6 +281,38 @@ mod tests {
         assert!(is_allowed("}}}"));
     }
 
+    #[test]
+    fn safelist_client_scoping() {
+        let safelist = FreeformGraphQLSafelist::new(&PersistedQueryManifest::from(vec![
+            ManifestOperation {
+                id: "shared".to_string(),
+                body: "query Shared { a }".to_string(),
+                client_name: None,
+            },
+            ManifestOperation {
+                id: "web-only".to_string(),
+                body: "query Web { b }".to_string(),
+                client_name: Some("web".to_string()),
+            },
+        ]));
+
+        let is_allowed = |body: &str, client_name: Option<&str>| -> bool {
+            safelist.is_allowed(
+                ast::Document::parse(body, "").as_ref().map_err(|_| body),
+                client_name,
+            )
+        };
+
+        // Operations without a client are allowed for every client.
+        assert!(is_allowed("query Shared { a }", None));
+        assert!(is_allowed("query Shared { a }", Some("mobile")));
+
+        // Client scoped operations are only allowed for their client.
+        assert!(is_allowed("query Web { b }", Some("web")));
+        assert!(!is_allowed("query Web { b }", Some("mobile")));
+        assert!(!is_allowed("query Web { b }", None));
+    }
+
     fn freeform_behavior_from_pq_options(
         safe_list: bool,
         require_id: Option<bool>,
@@ -0,0 +1,541 @@
+//! Persisted query learning mode.
+//!
+//! Records the distinct freeform operations sent by each client, and periodically exports them as
+//! a persisted query manifest. The exported file can be used as a local manifest once every client
+//! operation has been seen, so that safelisting can be turned on.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use apollo_compiler::ast;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tower::BoxError;
+
+use super::freeform_graphql_behavior::normalize_body;
+use crate::configuration::PersistedQueriesLearning;
+use crate::services::layers::query_analysis::ParsedDocument;
+
+const MANIFEST_FORMAT: &str = "apollo-persisted-query-manifest";
+
+/// How many operations can wait to be recorded. Operations seen while the buffer is full are not
+/// recorded, so that learning never slows down requests.
+const OBSERVATION_BUFFER_SIZE: usize = 1024;
+
+/// Records freeform operations and exports them as a persisted query manifest.
+///
+/// Operations are sent to a task which normalizes and records them, and writes the manifest
+/// every `export_interval` if new operations were seen, once more when the learner is dropped, and
+/// when [`export`][Self::export] is called on shutdown. A learner is owned by the persisted query
+/// layer of a router, and handed over to the next layer on reload, so that the operations recorded
+/// so far survive configuration and schema reloads.
+#[derive(Debug)]
+pub(crate) struct OperationLearner {
+    output_path: PathBuf,
+    max_operations: Arc<AtomicUsize>,
+    sender: mpsc::Sender<LearnerMessage>,
+}
+
+#[derive(Debug)]
+enum LearnerMessage {
+    Record {
+        document: ParsedDocument,
+        client_name: Option<String>,
+        client_version: Option<String>,
+    },
+    Export(oneshot::Sender<()>),
+}
+
+#[derive(Debug, Default)]
+struct LearnerState {
+    operations: HashMap<LearnedOperationKey, LearnedOperation>,
+    changed: bool,
+    limit_reached_logged: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LearnedOperationKey {
+    body: String,
+    client_name: Option<String>,
+    client_version: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct LearnedOperation {
+    name: Option<String>,
+    operation_type: String,
+    count: u64,
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+}
+
+/// The exported file: a persisted query manifest whose operations carry extra usage fields.
+#[derive(Debug, Deserialize, Serialize)]
+struct LearnedManifest {
+    format: String,
+    version: u64,
+    operations: Vec<LearnedManifestOperation>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LearnedManifestOperation {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(rename = "type")]
+    operation_type: String,
+    body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client_version: Option<String>,
+    count: u64,
+    #[serde(with = "humantime_serde")]
+    first_seen: SystemTime,
+    #[serde(with = "humantime_serde")]
+    last_seen: SystemTime,
+}
+
+impl OperationLearner {
+    /// Returns the learner of the previous persisted query layer if it writes to the configured
+    /// output path, or a new learner (and its task) otherwise.
+    pub(crate) fn new_or_reuse(
+        config: &PersistedQueriesLearning,
+        previous: Option<&Arc<Self>>,
+    ) -> Arc<Self> {
+        if let Some(previous) =
+            previous.filter(|previous| previous.output_path == config.output_path)
+        {
+            previous
+                .max_operations
+                .store(config.max_operations, Ordering::Relaxed);
+            return previous.clone();
+        }
+        Arc::new(Self::new(config))
+    }
+
+    fn new(config: &PersistedQueriesLearning) -> Self {
+        let (sender, receiver) = mpsc::channel(OBSERVATION_BUFFER_SIZE);
+        let max_operations = Arc::new(AtomicUsize::new(config.max_operations));
+        tokio::task::spawn(run_learner(
+            LearnerState::load(&config.output_path),
+            config.output_path.clone(),
+            max_operations.clone(),
+            receiver,
+            config.export_interval,
+        ));
+        Self {
+            output_path: config.output_path.clone(),
+            max_operations,
+            sender,
+        }
+    }
+
+    /// Records one execution of a freeform operation document, unless too many operations are
+    /// already waiting to be recorded.
+    pub(crate) fn record(
+        &self,
+        document: ParsedDocument,
+        client_name: Option<String>,
+        client_version: Option<String>,
+    ) {
+        let _ = self.sender.try_send(LearnerMessage::Record {
+            document,
+            client_name,
+            client_version,
+        });
+    }
+
+    /// Records the operations waiting to be recorded, and writes the manifest if anything was
+    /// recorded since the last export. This is done when the router shuts down, since the manifest
+    /// is only written periodically otherwise.
+    pub(crate) async fn export(&self) {
+        let (done, exported) = oneshot::channel();
+        if self.sender.send(LearnerMessage::Export(done)).await.is_ok() {
+            let _ = exported.await;
+        }
+    }
+}
+
+/// Records the operations sent to a learner and exports them, until the learner is dropped.
+async fn run_learner(
+    mut state: LearnerState,
+    output_path: PathBuf,
+    max_operations: Arc<AtomicUsize>,
+    mut receiver: mpsc::Receiver<LearnerMessage>,
+    export_interval: Duration,
+) {
+    let mut interval = tokio::time::interval(export_interval);
+    // The first tick completes immediately, and there is nothing to export yet.
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(LearnerMessage::Record {
+                    document,
+                    client_name,
+                    client_version,
+                }) => state.record(
+                    &document.ast,
+                    client_name,
+                    client_version,
+                    max_operations.load(Ordering::Relaxed),
+                ),
+                Some(LearnerMessage::Export(done)) => {
+                    state.export_or_log(&output_path).await;
+                    let _ = done.send(());
+                }
+                // No layer uses the learner anymore
+                None => break,
+            },
+            _ = interval.tick() => state.export_or_log(&output_path).await,
+        }
+    }
+    state.export_or_log(&output_path).await;
+}
+
+impl LearnerState {
+    /// Loads the operations exported by a previous learner, if any.
+    fn load(output_path: &Path) -> Self {
+        let mut state = Self::default();
+        if !output_path.exists() {
+            return state;
+        }
+        match load_manifest(output_path) {
+            Ok(manifest) => {
+                for operation in manifest.operations {
+                    state.operations.insert(
+                        LearnedOperationKey {
+                            body: operation.body,
+                            client_name: operation.client_name,
+                            client_version: operation.client_version,
+                        },
+                        LearnedOperation {
+                            name: operation.name,
+                            operation_type: operation.operation_type,
+                            count: operation.count,
+                            first_seen: operation.first_seen,
+                            last_seen: operation.last_seen,
+                        },
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "could not load previously learned persisted queries from {}, they will be overwritten: {e}",
+                    output_path.display()
+                );
+            }
+        }
+        state
+    }
+
+    /// Records one execution of a freeform operation document.
+    fn record(
+        &mut self,
+        document: &ast::Document,
+        client_name: Option<String>,
+        client_version: Option<String>,
+        max_operations: usize,
+    ) {
+        let key = LearnedOperationKey {
+            body: normalize_body(Ok(document)),
+            client_name,
+            client_version,
+        };
+        let now = SystemTime::now();
+
+        let operations_count = self.operations.len();
+        if let Some(operation) = self.operations.get_mut(&key) {
+            operation.count += 1;
+            operation.last_seen = now;
+        } else if operations_count < max_operations {
+            let (name, operation_type) = document
+                .definitions
+                .iter()
+                .find_map(|definition| match definition {
+                    ast::Definition::OperationDefinition(operation) => Some((
+                        operation.name.as_ref().map(|name| name.to_string()),
+                        operation.operation_type.name().to_string(),
+                    )),
+                    _ => None,
+                })
+                .unwrap_or_else(|| (None, ast::OperationType::Query.name().to_string()));
+            self.operations.insert(
+                key,
+                LearnedOperation {
+                    name,
+                    operation_type,
+                    count: 1,
+                    first_seen: now,
+                    last_seen: now,
+                },
+            );
+        } else {
+            if !self.limit_reached_logged {
+                tracing::warn!(
+                    "persisted query learning reached its limit of {max_operations} operations, new operations will not be recorded"
+                );
+                self.limit_reached_logged = true;
+            }
+            return;
+        }
+        self.changed = true;
+    }
+
+    /// Writes the learned manifest to the output path if anything was recorded since the last
+    /// export.
+    async fn export_or_log(&mut self, output_path: &Path) {
+        if !self.changed {
+            return;
+        }
+        self.changed = false;
+        let manifest = to_manifest(&self.operations);
+        let path = output_path.to_path_buf();
+        let result = tokio::task::spawn_blocking(move || write_manifest(&path, &manifest))
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+        if let Err(e) = result {
+            // Try again on the next export
+            self.changed = true;
+            tracing::error!(
+                "could not write learned persisted queries to {}: {e}",
+                output_path.display()
+            );
+        }
+    }
+}
+
+fn write_manifest(output_path: &Path, manifest: &LearnedManifest) -> Result<(), BoxError> {
+    let contents = serde_json::to_vec_pretty(manifest)?;
+    // Write to a temporary file first so that readers never see a partially written manifest
+    let mut temporary_path = output_path.to_path_buf().into_os_string();
+    temporary_path.push(".tmp");
+    std::fs::write(&temporary_path, contents)?;
+    std::fs::rename(&temporary_path, output_path)?;
+    Ok(())
+}
+
+fn load_manifest(path: &Path) -> Result<LearnedManifest, BoxError> {
+    let manifest: LearnedManifest = serde_json::from_slice(&std::fs::read(path)?)?;
+    if manifest.format != MANIFEST_FORMAT {
+        return Err(format!("manifest format is not '{MANIFEST_FORMAT}'").into());
+    }
+    if manifest.version != 1 {
+        return Err("persisted query manifest version is not 1".into());
+    }
+    Ok(manifest)
+}
+
+fn to_manifest(operations: &HashMap<LearnedOperationKey, LearnedOperation>) -> LearnedManifest {
+    let mut operations: Vec<_> = operations
+        .iter()
+        .map(|(key, operation)| LearnedManifestOperation {
+            id: hex::encode(Sha256::digest(key.body.as_bytes())),
+            name: operation.name.clone(),
+            operation_type: operation.operation_type.clone(),
+            body: key.body.clone(),
+            client_name: key.client_name.clone(),
+            client_version: key.client_version.clone(),
+            count: operation.count,
+            first_seen: operation.first_seen,
+            last_seen: operation.last_seen,
+        })
+        .collect();
+    // Keep the output stable, so that the file can be diffed and checked in
+    operations.sort_by(|a, b| {
+        (&a.client_name, &a.client_version, &a.name, &a.id).cmp(&(
+            &b.client_name,
+            &b.client_version,
+            &b.name,
+            &b.id,
+        ))
+    });
+
+    LearnedManifest {
+        format: MANIFEST_FORMAT.to_string(),
+        version: 1,
+        operations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::layers::persisted_queries::manifest::SignedUrlChunk;
+    use crate::services::layers::query_analysis::ParsedDocumentInner;
+
+    fn parse(body: &str) -> ast::Document {
+        ast::Document::parse(body, "test.graphql").unwrap()
+    }
+
+    fn parsed_document(body: &str) -> ParsedDocument {
+        let ast = parse(&format!("type Query {{ a: Int b: Int }} {body}"));
+        let (_schema, executable) = ast.to_mixed_validate().unwrap();
+        ParsedDocumentInner::new(parse(body), Arc::new(executable), None, Default::default())
+            .unwrap()
+    }
+
+    fn export(state: &mut LearnerState, output_path: &Path) {
+        state.changed = false;
+        write_manifest(output_path, &to_manifest(&state.operations)).unwrap();
+    }
+
+    #[test]
+    fn records_normalized_operations_per_client() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("learned.json");
+        let mut state = LearnerState::default();
+
+        let client = || Some("web".to_string());
+        state.record(&parse("query A { a }"), client(), Some("1.0".into()), 100);
+        state.record(
+            &parse("query A {\n  a\n}"),
+            client(),
+            Some("1.0".into()),
+            100,
+        );
+        state.record(&parse("query A { a }"), client(), Some("1.1".into()), 100);
+        state.record(&parse("mutation B { b }"), None, None, 100);
+        export(&mut state, &output_path);
+
+        let manifest = load_manifest(&output_path).unwrap();
+        let summary: Vec<_> = manifest
+            .operations
+            .iter()
+            .map(|op| {
+                (
+                    op.client_name.as_deref(),
+                    op.client_version.as_deref(),
+                    op.name.as_deref(),
+                    op.operation_type.as_str(),
+                    op.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (None, None, Some("B"), "mutation", 1),
+                (Some("web"), Some("1.0"), Some("A"), "query", 2),
+                (Some("web"), Some("1.1"), Some("A"), "query", 1),
+            ]
+        );
+
+        // The exported file is a valid persisted query manifest.
+        let chunk =
+            SignedUrlChunk::parse_and_validate(&std::fs::read_to_string(&output_path).unwrap())
+                .unwrap();
+        assert_eq!(chunk.operations.len(), 3);
+        assert_eq!(
+            chunk.operations[1].id,
+            hex::encode(Sha256::digest(chunk.operations[1].body.as_bytes()))
+        );
+    }
+
+    #[test]
+    fn stops_recording_new_operations_at_the_limit() {
+        let mut state = LearnerState::default();
+
+        state.record(&parse("{ a }"), None, None, 1);
+        state.record(&parse("{ b }"), None, None, 1);
+        state.record(&parse("{ a }"), None, None, 1);
+
+        assert_eq!(state.operations.len(), 1);
+        assert_eq!(state.operations.values().next().unwrap().count, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exports_learners_that_are_no_longer_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PersistedQueriesLearning::builder()
+            .output_path(dir.path().join("learned.json"))
+            .export_interval(Duration::from_secs(10))
+            .build();
+        let learner = OperationLearner::new_or_reuse(&config, None);
+        learner.record(parsed_document("{ a }"), None, None);
+        drop(learner);
+
+        // Let the learner task write the manifest, which is renamed into place once written
+        for _ in 0..100 {
+            if config.output_path.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let manifest = load_manifest(&config.output_path).unwrap();
+        assert_eq!(manifest.operations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn exports_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PersistedQueriesLearning::builder()
+            .output_path(dir.path().join("learned.json"))
+            .build();
+        let learner = OperationLearner::new_or_reuse(&config, None);
+        learner.record(parsed_document("{ a }"), None, None);
+
+        learner.export().await;
+        let manifest = load_manifest(&config.output_path).unwrap();
+        assert_eq!(manifest.operations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reuses_the_previous_learner_for_the_same_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PersistedQueriesLearning::builder()
+            .output_path(dir.path().join("learned.json"))
+            .build();
+        let learner = OperationLearner::new_or_reuse(&config, None);
+
+        let reused = OperationLearner::new_or_reuse(
+            &PersistedQueriesLearning::builder()
+                .output_path(config.output_path.clone())
+                .max_operations(1)
+                .build(),
+            Some(&learner),
+        );
+        assert!(Arc::ptr_eq(&learner, &reused));
+        assert_eq!(learner.max_operations.load(Ordering::Relaxed), 1);
+
+        let other = OperationLearner::new_or_reuse(
+            &PersistedQueriesLearning::builder()
+                .output_path(dir.path().join("other.json"))
+                .build(),
+            Some(&learner),
+        );
+        assert!(!Arc::ptr_eq(&learner, &other));
+    }
+
+    #[test]
+    fn resumes_from_previous_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("learned.json");
+        {
+            let mut state = LearnerState::load(&output_path);
+            state.record(&parse("{ a }"), None, None, 100);
+            export(&mut state, &output_path);
+        }
+
+        let mut state = LearnerState::load(&output_path);
+        state.record(&parse("{ a }"), None, None, 100);
+        export(&mut state, &output_path);
+
+        let manifest = load_manifest(&output_path).unwrap();
+        assert_eq!(manifest.operations.len(), 1);
+        assert_eq!(manifest.operations[0].count, 2);
+        assert!(manifest.operations[0].first_seen <= manifest.operations[0].last_seen);
+    }
+}
@@ -49,6 +49,16 @@ impl SignedUrlChunk {
         Ok(self)
     }
 
+    /// Restricts every operation of the chunk to the given client, if any.
+    pub(crate) fn scoped_to_client(mut self, client_name: Option<&str>) -> Self {
+        if let Some(client_name) = client_name {
+            for operation in &mut self.operations {
+                operation.client_name = Some(client_name.to_string());
+            }
+        }
+        self
+    }
+
     pub(crate) fn parse_and_validate(raw_chunk: &str) -> Result<Self, BoxError> {
         let parsed_chunk =
             serde_json::from_str::<SignedUrlChunk>(raw_chunk).map_err(|e| -> BoxError {
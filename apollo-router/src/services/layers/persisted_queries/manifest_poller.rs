@@ -3,11 +3,26 @@
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use apollo_compiler::ast;
 use futures::prelude::*;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::MeterProvider as _;
+use opentelemetry::metrics::ObservableGauge;
+use parking_lot::Mutex;
 use parking_lot::RwLock;
 use reqwest::Client;
+use reqwest::StatusCode;
+use reqwest::header::ETAG;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use reqwest::header::IF_MODIFIED_SINCE;
+use reqwest::header::IF_NONE_MATCH;
+use reqwest::header::LAST_MODIFIED;
 use tokio::fs::read_to_string;
 use tokio::sync::mpsc;
 use tower::BoxError;
@@ -19,6 +34,11 @@ use super::manifest::FullPersistedQueryOperationId;
 use super::manifest::PersistedQueryManifest;
 use super::manifest::SignedUrlChunk;
 use crate::Configuration;
+use crate::configuration::PersistedQueriesLocalManifest;
+use crate::configuration::PersistedQueriesRemoteManifests;
+use crate::metrics::meter_provider;
+use crate::polling::jittered_interval;
+use crate::polling::url_origin;
 use crate::uplink::UplinkConfig;
 use crate::uplink::persisted_queries_manifest_stream::MaybePersistedQueriesManifestChunks;
 use crate::uplink::persisted_queries_manifest_stream::PersistedQueriesManifestChunk;
@@ -31,11 +51,24 @@ pub(crate) struct PersistedQueryManifestPollerState {
     pub(crate) freeform_graphql_behavior: FreeformGraphQLBehavior,
 }
 
+/// Statistics about the manifest in use, reported as gauges.
+#[derive(Debug, Default)]
+struct ManifestStats {
+    /// Number of operations in the manifest
+    operations: AtomicU64,
+    /// Size in bytes of the manifests fetched from remote URLs
+    size: AtomicU64,
+    /// Where each manifest in use was fetched from, with its version: the ETag of remote
+    /// manifests, and the chunk IDs of the uplink manifest. Local manifests have no version.
+    versions: Mutex<Vec<(String, String)>>,
+}
+
 /// Manages polling uplink for persisted query chunks and unpacking those chunks into a [`PersistedQueryManifest`].
 #[derive(Debug)]
 pub(crate) struct PersistedQueryManifestPoller {
     pub(crate) state: Arc<RwLock<PersistedQueryManifestPollerState>>,
     _drop_signal: mpsc::Sender<()>,
+    _gauges: Vec<ObservableGauge<u64>>,
 }
 
 impl PersistedQueryManifestPoller {
@@ -44,7 +77,8 @@ impl PersistedQueryManifestPoller {
     /// and the [`PersistedQueryManifest`] has been fully populated.
     pub(crate) async fn new(config: Configuration) -> Result<Self, BoxError> {
         let manifest_source = ManifestSource::from_config(&config)?;
-        let manifest_stream = create_manifest_stream(manifest_source).await?;
+        let stats = Arc::new(ManifestStats::default());
+        let manifest_stream = create_manifest_stream(manifest_source, stats.clone()).await?;
 
         // Initialize state
         let state = Arc::new(RwLock::new(PersistedQueryManifestPollerState {
@@ -58,12 +92,14 @@ impl PersistedQueryManifestPoller {
 
         let state_clone = state.clone();
         let config_clone = config.clone();
+        let stats_clone = stats.clone();
 
         tokio::task::spawn(async move {
             poll_manifest_stream(
                 manifest_stream,
                 state_clone,
                 config_clone,
+                stats_clone,
                 ready_sender,
                 drop_receiver,
             )
@@ -74,6 +110,7 @@ impl PersistedQueryManifestPoller {
             Some(ManifestPollResultOnStartup::LoadedOperations) => Ok(Self {
                 state,
                 _drop_signal,
+                _gauges: create_manifest_gauges(stats),
             }),
             Some(ManifestPollResultOnStartup::Err(e)) => Err(e),
             None => Err("could not receive ready event for persisted query layer".into()),
@@ -116,11 +153,12 @@ impl PersistedQueryManifestPoller {
     pub(crate) fn action_for_freeform_graphql(
         &self,
         ast: Result<&ast::Document, &str>,
+        client_name: Option<&str>,
     ) -> FreeformGraphQLAction {
         let state = self.state.read();
         state
             .freeform_graphql_behavior
-            .action_for_freeform_graphql(ast)
+            .action_for_freeform_graphql(ast, client_name)
     }
 
     // Some(bool) means "never allows freeform GraphQL, bool is whether or not to log"
@@ -240,16 +278,63 @@ pub(crate) enum ManifestPollResultOnStartup {
     Err(BoxError),
 }
 
+fn create_manifest_gauges(stats: Arc<ManifestStats>) -> Vec<ObservableGauge<u64>> {
+    let meter = meter_provider().meter("apollo/router");
+    let versions_stats = stats.clone();
+    let operations_stats = stats.clone();
+    vec![
+        meter
+            .u64_observable_gauge("apollo.router.persisted_queries.manifest.info")
+            .with_description(
+                "Always 1, with the source and version of each persisted query manifest in use as attributes",
+            )
+            .with_callback(move |m| {
+                for (source, version) in versions_stats.versions.lock().iter() {
+                    m.observe(
+                        1,
+                        &[
+                            KeyValue::new("source", source.clone()),
+                            KeyValue::new("version", version.clone()),
+                        ],
+                    );
+                }
+            })
+            .init(),
+        meter
+            .u64_observable_gauge("apollo.router.persisted_queries.manifest.operations")
+            .with_description("Number of operations in the persisted query manifest")
+            .with_callback(move |m| {
+                m.observe(operations_stats.operations.load(Ordering::Relaxed), &[])
+            })
+            .init(),
+        meter
+            .u64_observable_gauge("apollo.router.persisted_queries.manifest.size")
+            .with_description("Size of the persisted query manifests fetched from remote URLs")
+            .with_unit("By")
+            .with_callback(move |m| m.observe(stats.size.load(Ordering::Relaxed), &[]))
+            .init(),
+    ]
+}
+
 /// The source of persisted query manifests
 #[derive(Debug)]
 enum ManifestSource {
-    LocalStatic(Vec<String>),
-    LocalHotReload(Vec<String>),
+    LocalStatic(Vec<PersistedQueriesLocalManifest>),
+    LocalHotReload(Vec<PersistedQueriesLocalManifest>),
+    Remote(PersistedQueriesRemoteManifests),
     Uplink(UplinkConfig),
 }
 
 impl ManifestSource {
     fn from_config(config: &Configuration) -> Result<Self, BoxError> {
+        if config.persisted_queries.remote_manifests.is_some()
+            && config.persisted_queries.local_manifests.is_some()
+        {
+            return Err(
+                "`persisted_queries.remote_manifests` cannot be used with `local_manifests`".into(),
+            );
+        }
+
         let source = if config.persisted_queries.hot_reload {
             if let Some(paths) = &config.persisted_queries.local_manifests {
                 ManifestSource::LocalHotReload(paths.clone())
@@ -258,11 +343,13 @@ impl ManifestSource {
             }
         } else if let Some(paths) = &config.persisted_queries.local_manifests {
             ManifestSource::LocalStatic(paths.clone())
+        } else if let Some(remote_manifests) = &config.persisted_queries.remote_manifests {
+            ManifestSource::Remote(remote_manifests.clone())
         } else if let Some(uplink_config) = config.uplink.as_ref() {
             ManifestSource::Uplink(uplink_config.clone())
         } else {
             return Err(
-                "persisted queries requires either local_manifests or Apollo GraphOS configuration"
+                "persisted queries requires either local_manifests, remote_manifests or Apollo GraphOS configuration"
                     .into(),
             );
         };
@@ -276,16 +363,24 @@ type ManifestStream = dyn Stream<Item = Result<PersistedQueryManifest, BoxError>
 
 async fn create_manifest_stream(
     source: ManifestSource,
+    stats: Arc<ManifestStats>,
 ) -> Result<Pin<Box<ManifestStream>>, BoxError> {
     match source {
         ManifestSource::LocalStatic(paths) => Ok(stream::once(load_local_manifests(paths)).boxed()),
         ManifestSource::LocalHotReload(paths) => Ok(create_hot_reload_stream(paths).boxed()),
+        ManifestSource::Remote(remote_manifests) => {
+            let client = Client::builder()
+                .timeout(remote_manifests.timeout)
+                .gzip(true)
+                .build()?;
+            Ok(create_remote_stream(remote_manifests, client, stats)?.boxed())
+        }
         ManifestSource::Uplink(uplink_config) => {
             let client = Client::builder()
                 .timeout(uplink_config.timeout)
                 .gzip(true)
                 .build()?;
-            Ok(create_uplink_stream(uplink_config, client).boxed())
+            Ok(create_uplink_stream(uplink_config, client, stats).boxed())
         }
     }
 }
@@ -294,6 +389,7 @@ async fn poll_manifest_stream(
     mut manifest_stream: Pin<Box<ManifestStream>>,
     state: Arc<RwLock<PersistedQueryManifestPollerState>>,
     config: Configuration,
+    stats: Arc<ManifestStats>,
     ready_sender: mpsc::Sender<ManifestPollResultOnStartup>,
     mut drop_receiver: mpsc::Receiver<()>,
 ) {
@@ -312,6 +408,12 @@ async fn poll_manifest_stream(
                             persisted_query_manifest: new_manifest,
                             freeform_graphql_behavior,
                         };
+                        stats.operations.store(operation_count as u64, Ordering::Relaxed);
+                        u64_counter!(
+                            "apollo.router.persisted_queries.manifest.loads",
+                            "Number of times this router loaded a new persisted query manifest",
+                            1
+                        );
                         tracing::info!("persisted query manifest successfully updated ({} operations total)", operation_count);
 
                         if let Some(sender) = ready_sender.take() {
@@ -333,10 +435,13 @@ async fn poll_manifest_stream(
     }
 }
 
-async fn load_local_manifests(paths: Vec<String>) -> Result<PersistedQueryManifest, BoxError> {
+async fn load_local_manifests(
+    manifests: Vec<PersistedQueriesLocalManifest>,
+) -> Result<PersistedQueryManifest, BoxError> {
     let mut complete_manifest = PersistedQueryManifest::default();
 
-    for path in paths.iter() {
+    for manifest in manifests.iter() {
+        let path = manifest.path();
         let raw_file_contents = read_to_string(path).await.map_err(|e| -> BoxError {
             format!(
                 "Failed to read persisted query list file at path: {}, {}",
@@ -345,7 +450,8 @@ async fn load_local_manifests(paths: Vec<String>) -> Result<PersistedQueryManife
             .into()
         })?;
 
-        let chunk = SignedUrlChunk::parse_and_validate(&raw_file_contents)?;
+        let chunk = SignedUrlChunk::parse_and_validate(&raw_file_contents)?
+            .scoped_to_client(manifest.client_name());
         complete_manifest.add_chunk(&chunk);
     }
 
@@ -360,6 +466,7 @@ async fn load_local_manifests(paths: Vec<String>) -> Result<PersistedQueryManife
 fn create_uplink_stream(
     uplink_config: UplinkConfig,
     http_client: Client,
+    stats: Arc<ManifestStats>,
 ) -> impl Stream<Item = Result<PersistedQueryManifest, BoxError>> {
     stream_from_uplink_transforming_new_response::<
         PersistedQueriesManifestQuery,
@@ -367,12 +474,21 @@ fn create_uplink_stream(
         Option<PersistedQueryManifest>,
     >(uplink_config, move |response| {
         let http_client = http_client.clone();
+        let stats = stats.clone();
         Box::new(Box::pin(async move {
             match response {
-                Some(chunks) => manifest_from_uplink_chunks(chunks, http_client)
-                    .await
-                    .map(Some)
-                    .map_err(|e| -> BoxError { e }),
+                Some(chunks) => {
+                    let version = chunks
+                        .iter()
+                        .map(|chunk| chunk.id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    let manifest = manifest_from_uplink_chunks(chunks, http_client).await;
+                    if manifest.is_ok() {
+                        *stats.versions.lock() = vec![("uplink".to_string(), version)];
+                    }
+                    manifest.map(Some).map_err(|e| -> BoxError { e })
+                }
                 None => Ok(None),
             }
         }))
@@ -387,17 +503,21 @@ fn create_uplink_stream(
 }
 
 fn create_hot_reload_stream(
-    paths: Vec<String>,
+    manifests: Vec<PersistedQueriesLocalManifest>,
 ) -> impl Stream<Item = Result<PersistedQueryManifest, BoxError>> {
     // Create file watchers for each path
-    let file_watchers = paths.into_iter().map(|raw_path| {
+    let file_watchers = manifests.into_iter().map(|manifest| {
+        let raw_path = manifest.path().to_string();
         crate::files::watch(std::path::Path::new(&raw_path.clone())).then(move |_| {
             let raw_path = raw_path.clone();
+            let client_name = manifest.client_name().map(str::to_string);
             async move {
                 match read_to_string(&raw_path).await {
                     Ok(raw_file_contents) => {
                         match SignedUrlChunk::parse_and_validate(&raw_file_contents) {
-                            Ok(chunk) => Ok((raw_path, chunk)),
+                            Ok(chunk) => {
+                                Ok((raw_path, chunk.scoped_to_client(client_name.as_deref())))
+                            }
                             Err(e) => Err(e),
                         }
                     }
@@ -431,14 +551,230 @@ fn create_hot_reload_stream(
     })
 }
 
+/// A manifest polled from a URL, with the validators used to revalidate it.
+struct RemoteManifest {
+    url: String,
+    client_name: Option<String>,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    /// The last manifest successfully fetched from the URL
+    chunk: Option<SignedUrlChunk>,
+    size: usize,
+}
+
+enum RemoteFetch {
+    Modified {
+        chunk: SignedUrlChunk,
+        etag: Option<HeaderValue>,
+        last_modified: Option<HeaderValue>,
+        size: usize,
+    },
+    NotModified,
+}
+
+fn create_remote_stream(
+    config: PersistedQueriesRemoteManifests,
+    http_client: Client,
+    stats: Arc<ManifestStats>,
+) -> Result<impl Stream<Item = Result<PersistedQueryManifest, BoxError>>, BoxError> {
+    if config.urls.is_empty() {
+        return Err("`persisted_queries.remote_manifests.urls` must not be empty".into());
+    }
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        headers.insert(
+            HeaderName::try_from(name.as_str())?,
+            HeaderValue::try_from(value.as_str())?,
+        );
+    }
+
+    let manifests = config
+        .urls
+        .iter()
+        .map(|manifest| RemoteManifest {
+            url: manifest.url().to_string(),
+            client_name: manifest.client_name().map(str::to_string),
+            etag: None,
+            last_modified: None,
+            chunk: None,
+            size: 0,
+        })
+        .collect::<Vec<_>>();
+    let poll_interval = config.poll_interval;
+    let jitter = config.jitter;
+
+    Ok(stream::unfold(
+        (manifests, None),
+        move |(mut manifests, delay): (Vec<RemoteManifest>, Option<Duration>)| {
+            let http_client = http_client.clone();
+            let headers = headers.clone();
+            let stats = stats.clone();
+            async move {
+                let mut delay = delay;
+                loop {
+                    if let Some(delay) = delay {
+                        tokio::time::sleep(delay).await;
+                    }
+                    delay = Some(jittered_interval(poll_interval, jitter));
+
+                    match poll_remote_manifests(&http_client, &headers, &mut manifests).await {
+                        Ok(true) => {
+                            let mut manifest = PersistedQueryManifest::default();
+                            for chunk in manifests.iter().filter_map(|m| m.chunk.as_ref()) {
+                                manifest.add_chunk(chunk);
+                            }
+                            let size = manifests.iter().map(|m| m.size as u64).sum();
+                            stats.size.store(size, Ordering::Relaxed);
+                            *stats.versions.lock() = manifests
+                                .iter()
+                                .filter_map(|m| {
+                                    let version = m.etag.as_ref()?.to_str().ok()?;
+                                    Some((url_origin(&m.url), version.to_string()))
+                                })
+                                .collect();
+                            return Some((Ok(manifest), (manifests, delay)));
+                        }
+                        // Nothing changed, wait for the next poll
+                        Ok(false) => continue,
+                        Err(e) => return Some((Err(e), (manifests, delay))),
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// Poll every remote manifest, returning whether any of them changed.
+///
+/// A manifest which fails to update keeps its last good version. Failing to fetch a
+/// manifest which was never loaded is an error.
+async fn poll_remote_manifests(
+    http_client: &Client,
+    headers: &HeaderMap,
+    manifests: &mut [RemoteManifest],
+) -> Result<bool, BoxError> {
+    let mut changed = false;
+    for manifest in manifests.iter_mut() {
+        match fetch_remote_manifest(http_client, headers, manifest).await {
+            Ok(RemoteFetch::Modified {
+                chunk,
+                etag,
+                last_modified,
+                size,
+            }) => {
+                record_remote_fetch(&manifest.url, "updated");
+                tracing::info!(
+                    "fetched persisted query manifest from {} ({} operations, {} bytes)",
+                    manifest.url,
+                    chunk.operations.len(),
+                    size
+                );
+                manifest.chunk = Some(chunk);
+                manifest.etag = etag;
+                manifest.last_modified = last_modified;
+                manifest.size = size;
+                changed = true;
+            }
+            Ok(RemoteFetch::NotModified) => {
+                record_remote_fetch(&manifest.url, "not_modified");
+            }
+            Err(e) => {
+                record_remote_fetch(&manifest.url, "error");
+                if manifest.chunk.is_none() {
+                    return Err(e);
+                }
+                tracing::error!("{}, keeping the last good persisted query manifest", e);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+async fn fetch_remote_manifest(
+    http_client: &Client,
+    headers: &HeaderMap,
+    manifest: &RemoteManifest,
+) -> Result<RemoteFetch, BoxError> {
+    let mut request = http_client.get(&manifest.url).headers(headers.clone());
+    // Only revalidate once we have a manifest to fall back to
+    if manifest.chunk.is_some() {
+        if let Some(etag) = &manifest.etag {
+            request = request.header(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &manifest.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| -> BoxError {
+            format!(
+                "error fetching persisted query manifest from {}: {}",
+                manifest.url, e
+            )
+            .into()
+        })?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(RemoteFetch::NotModified);
+    }
+
+    let etag = response.headers().get(ETAG).cloned();
+    let last_modified = response.headers().get(LAST_MODIFIED).cloned();
+    let body = response.text().await.map_err(|e| -> BoxError {
+        format!(
+            "error reading body of persisted query manifest from {}: {}",
+            manifest.url, e
+        )
+        .into()
+    })?;
+    let chunk = SignedUrlChunk::parse_and_validate(&body)
+        .map_err(|e| -> BoxError {
+            format!(
+                "invalid persisted query manifest from {}: {}",
+                manifest.url, e
+            )
+            .into()
+        })?
+        .scoped_to_client(manifest.client_name.as_deref());
+
+    Ok(RemoteFetch::Modified {
+        chunk,
+        etag,
+        last_modified,
+        size: body.len(),
+    })
+}
+
+fn record_remote_fetch(url: &str, result: &'static str) {
+    u64_counter_with_unit!(
+        "apollo.router.persisted_queries.manifest.fetch",
+        "Number of persisted query manifest fetches from remote URLs",
+        "{fetch}",
+        1,
+        origin = url_origin(url),
+        result = result
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use tokio::io::AsyncWriteExt;
     use url::Url;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
 
     use super::*;
     use crate::configuration::Apq;
     use crate::configuration::PersistedQueries;
+    use crate::configuration::PersistedQueriesRemoteManifests;
+    use crate::metrics::FutureMetricsExt;
     use crate::test_harness::mocks::persisted_queries::*;
     use crate::uplink::Endpoints;
 
@@ -558,6 +894,42 @@ mod tests {
         assert_eq!(manifest_manager.get_operation_body(&id, None), Some(body));
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn local_manifest_scoped_to_client() {
+        let (_, body, _) = fake_manifest();
+        let id = "5678";
+
+        let persisted_queries: PersistedQueries = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "local_manifests": [
+                {
+                    "path": "tests/fixtures/persisted-queries-manifest.json",
+                    "client_name": "web"
+                }
+            ]
+        }))
+        .unwrap();
+        let manifest_manager = PersistedQueryManifestPoller::new(
+            Configuration::fake_builder()
+                .apq(Apq::fake_new(Some(false)))
+                .persisted_query(persisted_queries)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            manifest_manager.get_operation_body(id, Some("web".to_string())),
+            Some(body)
+        );
+        assert_eq!(
+            manifest_manager.get_operation_body(id, Some("mobile".to_string())),
+            None
+        );
+        assert_eq!(manifest_manager.get_operation_body(id, None), None);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn handles_empty_pq_manifest_from_uplink() {
         let (_mock_guard, uplink_config) = mock_empty_pq_uplink().await;
@@ -666,4 +1038,138 @@ mod tests {
             Some(body)
         );
     }
+
+    fn remote_manifests_config(server: &MockServer) -> Configuration {
+        Configuration::fake_builder()
+            .apq(Apq::fake_new(Some(false)))
+            .persisted_query(
+                PersistedQueries::builder()
+                    .enabled(true)
+                    .remote_manifests(
+                        PersistedQueriesRemoteManifests::builder()
+                            .urls(vec![format!("{}/manifest.json", server.uri())])
+                            .header("authorization", "Bearer token")
+                            .poll_interval(Duration::from_millis(50))
+                            .jitter(Duration::ZERO)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn remote_manifest_is_revalidated_with_etag() {
+        let (_, body, _) = fake_manifest();
+        let id = "5678".to_string();
+        let manifest =
+            std::fs::read_to_string("tests/fixtures/persisted-queries-manifest.json").unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(header("authorization", "Bearer token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_string(manifest),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let manifest_manager = PersistedQueryManifestPoller::new(remote_manifests_config(&server))
+            .await
+            .unwrap();
+        assert_eq!(
+            manifest_manager.get_operation_body(&id, None),
+            Some(body.clone())
+        );
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests.len() > 1);
+        assert!(
+            requests[1..]
+                .iter()
+                .all(|request| request.headers.get("if-none-match").is_some())
+        );
+        assert_eq!(manifest_manager.get_operation_body(&id, None), Some(body));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn remote_manifest_version_is_reported() {
+        async {
+            let manifest =
+                std::fs::read_to_string("tests/fixtures/persisted-queries-manifest.json").unwrap();
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("etag", "\"v1\"")
+                        .set_body_string(manifest),
+                )
+                .mount(&server)
+                .await;
+
+            let _manifest_manager =
+                PersistedQueryManifestPoller::new(remote_manifests_config(&server))
+                    .await
+                    .unwrap();
+            assert_gauge!(
+                "apollo.router.persisted_queries.manifest.info",
+                1,
+                "source" = server.uri(),
+                "version" = "\"v1\""
+            );
+        }
+        .with_metrics()
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn remote_manifest_keeps_last_good_version_on_failure() {
+        let (_, body, _) = fake_manifest();
+        let id = "5678".to_string();
+        let manifest =
+            std::fs::read_to_string("tests/fixtures/persisted-queries-manifest.json").unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(manifest))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let manifest_manager = PersistedQueryManifestPoller::new(remote_manifests_config(&server))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(server.received_requests().await.unwrap().len() > 1);
+        assert_eq!(manifest_manager.get_operation_body(&id, None), Some(body));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn remote_manifest_fails_startup_when_unavailable() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        assert!(
+            PersistedQueryManifestPoller::new(remote_manifests_config(&server))
+                .await
+                .is_err()
+        );
+    }
 }
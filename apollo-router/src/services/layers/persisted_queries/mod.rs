@@ -2,16 +2,17 @@
 
 mod freeform_graphql_behavior;
 mod id_extractor;
+mod learning;
 mod manifest;
 mod manifest_poller;
 
-#[cfg(test)]
 use std::sync::Arc;
 
 use http::HeaderValue;
 use http::StatusCode;
 use http::header::CACHE_CONTROL;
 use id_extractor::PersistedQueryIdExtractor;
+use learning::OperationLearner;
 pub use manifest::FullPersistedQueryOperationId;
 pub use manifest::ManifestOperation;
 pub use manifest::PersistedQueryManifest;
@@ -20,8 +21,12 @@ use tower::BoxError;
 
 use super::query_analysis::ParsedDocument;
 use crate::Configuration;
+use crate::configuration::PersistedQueriesClientName;
 use crate::graphql::Error as GraphQLError;
+use crate::plugins::enhanced_client_awareness::CLIENT_LIBRARY_KEY;
+use crate::plugins::enhanced_client_awareness::CLIENT_LIBRARY_NAME_KEY;
 use crate::plugins::telemetry::CLIENT_NAME;
+use crate::plugins::telemetry::CLIENT_VERSION;
 use crate::services::SupergraphRequest;
 use crate::services::SupergraphResponse;
 
@@ -52,6 +57,10 @@ pub(crate) struct PersistedQueryLayer {
     /// Manages polling uplink for persisted queries and caches the current
     /// value of the manifest and projected safelist. None if the layer is disabled.
     pub(crate) manifest_poller: Option<PersistedQueryManifestPoller>,
+    /// Records freeform operations when learning mode is enabled.
+    learner: Option<Arc<OperationLearner>>,
+    /// Where the client name used to scope operations is read from.
+    client_name_config: PersistedQueriesClientName,
     introspection_enabled: bool,
 }
 
@@ -67,21 +76,93 @@ impl PersistedQueryLayer {
     /// Create a new [`PersistedQueryLayer`] from CLI options, YAML configuration,
     /// and optionally, an existing persisted query manifest poller.
     pub(crate) async fn new(configuration: &Configuration) -> Result<Self, BoxError> {
+        Self::new_with_previous(configuration, None).await
+    }
+
+    /// Create a new [`PersistedQueryLayer`] which takes over the operations learned by the layer
+    /// of the previous router, if learning is still enabled with the same output path.
+    pub(crate) async fn new_with_previous(
+        configuration: &Configuration,
+        previous: Option<&Self>,
+    ) -> Result<Self, BoxError> {
         if configuration.persisted_queries.enabled {
             Ok(Self {
                 manifest_poller: Some(
                     PersistedQueryManifestPoller::new(configuration.clone()).await?,
                 ),
+                learner: configuration
+                    .persisted_queries
+                    .experimental_learning
+                    .as_ref()
+                    .map(|config| {
+                        OperationLearner::new_or_reuse(
+                            config,
+                            previous.and_then(|previous| previous.learner.as_ref()),
+                        )
+                    }),
+                client_name_config: configuration.persisted_queries.client_name.clone(),
                 introspection_enabled: configuration.supergraph.introspection,
             })
         } else {
             Ok(Self {
                 manifest_poller: None,
+                learner: None,
+                client_name_config: configuration.persisted_queries.client_name.clone(),
                 introspection_enabled: configuration.supergraph.introspection,
             })
         }
     }
 
+    /// Writes the operations learned so far, if learning is enabled. This is done when the router
+    /// shuts down, since they are only written periodically otherwise.
+    pub(crate) async fn export_learned_operations(&self) {
+        if let Some(learner) = &self.learner {
+            learner.export().await;
+        }
+    }
+
+    /// Returns the name of the client sending the request, which scopes the operations it
+    /// can use.
+    fn client_name(&self, request: &SupergraphRequest) -> Option<String> {
+        // Use the first one of these that exists:
+        // - The PQL-specific context name entry
+        //   `apollo_persisted_queries::client_name` (which can be set
+        //   by router_service plugins)
+        // - The header named by `persisted_queries.client_name.header`
+        // - The `clientLibrary` extension used by enhanced client awareness,
+        //   if `persisted_queries.client_name.client_library` is enabled
+        // - The same name used by telemetry (ie, the value of the
+        //   header named by `telemetry.apollo.client_name_header`,
+        //   which defaults to `apollographql-client-name` by default)
+        request
+            .context
+            .get(PERSISTED_QUERIES_CLIENT_NAME_CONTEXT_KEY)
+            .unwrap_or_default()
+            .or_else(|| {
+                let header = self.client_name_config.header.as_deref()?;
+                request
+                    .supergraph_request
+                    .headers()
+                    .get(header)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            })
+            .or_else(|| {
+                if !self.client_name_config.client_library {
+                    return None;
+                }
+                request
+                    .supergraph_request
+                    .body()
+                    .extensions
+                    .get(CLIENT_LIBRARY_KEY)?
+                    .get(CLIENT_LIBRARY_NAME_KEY)?
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .or_else(|| request.context.get(CLIENT_NAME).unwrap_or_default())
+    }
+
     /// Handles pre-parsing work for requests using persisted queries.
     ///
     /// Takes care of:
@@ -118,7 +199,11 @@ impl PersistedQueryLayer {
                         // *don't* require IDs and skip_enforcement is set, we
                         // *do* call log_unknown_operation on unknown
                         // operations.
-                        log_unknown_operation(operation_body, false);
+                        log_unknown_operation(
+                            operation_body,
+                            false,
+                            self.client_name(&request).as_deref(),
+                        );
                     }
                 }
                 Err(supergraph_err_pq_id_required(request))
@@ -160,21 +245,10 @@ impl PersistedQueryLayer {
         } else {
             // if there is no query, look up the persisted query in the manifest
             // and put the body on the `supergraph_request`
-            if let Some(persisted_query_body) = manifest_poller.get_operation_body(
-                persisted_query_id,
-                // Use the first one of these that exists:
-                // - The PQL-specific context name entry
-                //   `apollo_persisted_queries::client_name` (which can be set
-                //   by router_service plugins)
-                // - The same name used by telemetry (ie, the value of the
-                //   header named by `telemetry.apollo.client_name_header`,
-                //   which defaults to `apollographql-client-name` by default)
-                request
-                    .context
-                    .get(PERSISTED_QUERIES_CLIENT_NAME_CONTEXT_KEY)
-                    .unwrap_or_default()
-                    .or_else(|| request.context.get(CLIENT_NAME).unwrap_or_default()),
-            ) {
+            let client_name = self.client_name(&request);
+            if let Some(persisted_query_body) =
+                manifest_poller.get_operation_body(persisted_query_id, client_name.clone())
+            {
                 let body = request.supergraph_request.body_mut();
                 body.query = Some(persisted_query_body);
                 body.extensions.remove("persistedQuery");
@@ -209,6 +283,7 @@ impl PersistedQueryLayer {
                 Err(supergraph_err_operation_not_found(
                     request,
                     persisted_query_id,
+                    client_name,
                 ))
             }
         }
@@ -287,8 +362,18 @@ impl PersistedQueryLayer {
             return Ok(request);
         }
 
+        let client_name = self.client_name(&request);
+        if let Some(learner) = &self.learner {
+            learner.record(
+                doc.clone(),
+                client_name.clone(),
+                request.context.get(CLIENT_VERSION).unwrap_or_default(),
+            );
+        }
+
         let mut metric_attributes = vec![];
-        let freeform_graphql_action = manifest_poller.action_for_freeform_graphql(Ok(&doc.ast));
+        let freeform_graphql_action =
+            manifest_poller.action_for_freeform_graphql(Ok(&doc.ast), client_name.as_deref());
         let skip_enforcement = skip_enforcement(&request);
         let allow = skip_enforcement || freeform_graphql_action.should_allow;
         if !allow {
@@ -303,7 +388,7 @@ impl PersistedQueryLayer {
             ));
         }
         if freeform_graphql_action.should_log {
-            log_unknown_operation(operation_body, skip_enforcement);
+            log_unknown_operation(operation_body, skip_enforcement, client_name.as_deref());
             metric_attributes.push(opentelemetry::KeyValue::new(
                 "persisted_queries.logged".to_string(),
                 true,
@@ -319,7 +404,10 @@ impl PersistedQueryLayer {
         if allow {
             Ok(request)
         } else {
-            Err(supergraph_err_operation_not_in_safelist(request))
+            Err(supergraph_err_operation_not_in_safelist(
+                request,
+                client_name,
+            ))
         }
     }
 
@@ -330,11 +418,16 @@ impl PersistedQueryLayer {
     }
 }
 
-fn log_unknown_operation(operation_body: &str, enforcement_skipped: bool) {
+fn log_unknown_operation(
+    operation_body: &str,
+    enforcement_skipped: bool,
+    client_name: Option<&str>,
+) {
     tracing::warn!(
         message = "unknown operation",
         operation_body,
-        enforcement_skipped
+        enforcement_skipped,
+        client_name
     );
 }
 
@@ -374,6 +467,7 @@ impl ErrorCacheStrategy {
 fn graphql_err_operation_not_found(
     persisted_query_id: &str,
     operation_name: Option<String>,
+    client_name: Option<String>,
 ) -> GraphQLError {
     let mut builder = GraphQLError::builder()
         .extension_code("PERSISTED_QUERY_NOT_IN_LIST")
@@ -383,17 +477,22 @@ fn graphql_err_operation_not_found(
     if let Some(operation_name) = operation_name {
         builder = builder.extension("operation_name", operation_name);
     }
+    if let Some(client_name) = client_name {
+        builder = builder.extension("client_name", client_name);
+    }
     builder.build()
 }
 
 fn supergraph_err_operation_not_found(
     request: SupergraphRequest,
     persisted_query_id: &str,
+    client_name: Option<String>,
 ) -> SupergraphResponse {
     supergraph_err(
         graphql_err_operation_not_found(
             persisted_query_id,
             request.supergraph_request.body().operation_name.clone(),
+            client_name,
         ),
         request,
         ErrorCacheStrategy::DontCache,
@@ -419,16 +518,28 @@ fn supergraph_err_cannot_send_id_and_body_with_apq_disabled(
     )
 }
 
-fn graphql_err_operation_not_in_safelist() -> GraphQLError {
-    graphql_err(
-        "QUERY_NOT_IN_SAFELIST",
-        "The operation body was not found in the persisted query safelist",
-    )
+fn graphql_err_operation_not_in_safelist(client_name: Option<String>) -> GraphQLError {
+    match client_name {
+        None => graphql_err(
+            "QUERY_NOT_IN_SAFELIST",
+            "The operation body was not found in the persisted query safelist",
+        ),
+        Some(client_name) => GraphQLError::builder()
+            .extension_code("QUERY_NOT_IN_SAFELIST")
+            .message(format!(
+                "The operation body was not found in the persisted query safelist for client '{client_name}'"
+            ))
+            .extension("client_name", client_name)
+            .build(),
+    }
 }
 
-fn supergraph_err_operation_not_in_safelist(request: SupergraphRequest) -> SupergraphResponse {
+fn supergraph_err_operation_not_in_safelist(
+    request: SupergraphRequest,
+    client_name: Option<String>,
+) -> SupergraphResponse {
     supergraph_err(
-        graphql_err_operation_not_in_safelist(),
+        graphql_err_operation_not_in_safelist(client_name),
         request,
         ErrorCacheStrategy::DontCache,
         StatusCode::FORBIDDEN,
@@ -718,7 +829,7 @@ mod tests {
             .expect("could not get response from pq layer");
         assert_errors_eq_ignoring_id!(
             response.errors,
-            [graphql_err_operation_not_found(invalid_id, None)]
+            [graphql_err_operation_not_found(invalid_id, None, None)]
         );
     }
 
@@ -850,7 +961,10 @@ mod tests {
             .next_response()
             .await
             .expect("could not get response from pq layer");
-        assert_errors_eq_ignoring_id!(response.errors, [graphql_err_operation_not_in_safelist()]);
+        assert_errors_eq_ignoring_id!(
+            response.errors,
+            [graphql_err_operation_not_in_safelist(None)]
+        );
         let mut metric_attributes = vec![opentelemetry::KeyValue::new(
             "persisted_queries.safelist.rejected.unknown".to_string(),
             true,
@@ -1094,10 +1208,112 @@ mod tests {
             [graphql_err_operation_not_found(
                 invalid_id,
                 Some("SomeOperation".to_string()),
+                None,
             )]
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pq_layer_freeform_graphql_with_client_scoped_safelist() {
+        let manifest = PersistedQueryManifest::from(vec![
+            ManifestOperation {
+                id: "shared".to_string(),
+                body: "query Shared { me { id } }".to_string(),
+                client_name: None,
+            },
+            ManifestOperation {
+                id: "web-only".to_string(),
+                body: "query WebOnly { me { name } }".to_string(),
+                client_name: Some("web".to_string()),
+            },
+        ]);
+        let (_mock_guard, uplink_config) = mock_pq_uplink(&manifest).await;
+
+        let config = Configuration::fake_builder()
+            .persisted_query(
+                PersistedQueries::builder()
+                    .enabled(true)
+                    .safelist(PersistedQueriesSafelist::builder().enabled(true).build())
+                    .client_name(
+                        PersistedQueriesClientName::builder()
+                            .header("x-app-name".to_string())
+                            .client_library(true)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .uplink(uplink_config)
+            .apq(Apq::fake_builder().enabled(false).build())
+            .build()
+            .unwrap();
+        let pq_layer = PersistedQueryLayer::new(&config).await.unwrap();
+        let schema = Arc::new(
+            Schema::parse(
+                include_str!("../../../testdata/supergraph.graphql"),
+                &Default::default(),
+            )
+            .unwrap(),
+        );
+        let query_analysis_layer = QueryAnalysisLayer::new(schema, Arc::new(config)).await;
+
+        let pq_layer = &pq_layer;
+        let query_analysis_layer = &query_analysis_layer;
+        let run = |request: SupergraphRequest| async move {
+            let request = pq_layer
+                .supergraph_request(request)
+                .expect("pq layer returned error response instead of returning a request");
+            let request = query_analysis_layer
+                .supergraph_request(request)
+                .await
+                .expect("QA layer returned error response instead of returning a request");
+            pq_layer
+                .supergraph_request_with_analyzed_query(request)
+                .await
+        };
+        let from_header = |body: &str, client_name: &str| {
+            SupergraphRequest::fake_builder()
+                .query(body)
+                .header("x-app-name", client_name)
+                .build()
+                .unwrap()
+        };
+
+        // Unscoped operations are allowed for every client.
+        assert!(
+            run(from_header("query Shared { me { id } }", "mobile"))
+                .await
+                .is_ok()
+        );
+
+        // Scoped operations are only allowed for their client.
+        assert!(
+            run(from_header("query WebOnly { me { name } }", "web"))
+                .await
+                .is_ok()
+        );
+        let mut response = run(from_header("query WebOnly { me { name } }", "mobile"))
+            .await
+            .expect_err("pq layer allowed an operation scoped to another client");
+        let response = response.next_response().await.unwrap();
+        assert_errors_eq_ignoring_id!(
+            response.errors,
+            [graphql_err_operation_not_in_safelist(Some(
+                "mobile".to_string()
+            ))]
+        );
+
+        // The client name can also come from the client library extension.
+        assert!(
+            run(SupergraphRequest::fake_builder()
+                .query("query WebOnly { me { name } }")
+                .extension("clientLibrary", json!({"name": "web", "version": "1.0"}))
+                .build()
+                .unwrap())
+            .await
+            .is_ok()
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn apq_and_pq_safelisting_is_invalid_config() {
         let (_mock_guard, uplink_config) = mock_empty_pq_uplink().await;
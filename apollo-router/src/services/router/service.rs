@@ -865,6 +865,7 @@ pub(crate) fn process_vary_header(headers: &mut HeaderMap<HeaderValue>) {
 #[derive(Clone)]
 pub(crate) struct RouterCreator {
     pub(crate) supergraph_creator: Arc<SupergraphCreator>,
+    pub(crate) persisted_query_layer: Arc<PersistedQueryLayer>,
     sb: Buffer<router::Request, BoxFuture<'static, router::ServiceResult>>,
     pipeline_handle: Arc<PipelineHandle>,
 }
@@ -895,6 +896,11 @@ impl RouterFactory for RouterCreator {
     fn pipeline_ref(&self) -> Arc<PipelineRef> {
         self.pipeline_handle.pipeline_ref.clone()
     }
+
+    fn shutdown(&self) -> BoxFuture<'static, ()> {
+        let persisted_query_layer = self.persisted_query_layer.clone();
+        Box::pin(async move { persisted_query_layer.export_learned_operations().await })
+    }
 }
 
 impl RouterCreator {
@@ -936,7 +942,7 @@ impl RouterCreator {
         let router_service = content_negotiation::RouterLayer::default().layer(RouterService::new(
             supergraph_creator.create(),
             apq_layer,
-            persisted_query_layer,
+            persisted_query_layer.clone(),
             query_analysis_layer,
             configuration.batching.clone(),
             TelemetryConfig::apollo(&configuration),
@@ -959,6 +965,7 @@ impl RouterCreator {
 
         Ok(Self {
             supergraph_creator,
+            persisted_query_layer,
             sb,
             pipeline_handle: Arc::new(pipeline_handle),
         })
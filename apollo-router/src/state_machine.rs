@@ -28,6 +28,7 @@ use super::router::ApolloRouterError::NoSchema;
 use super::router::ApolloRouterError::{self};
 use super::router::Event::UpdateConfiguration;
 use super::router::Event::UpdateSchema;
+use super::router::Event::UpdateSchemaAndConfiguration;
 use super::router::Event::{self};
 use crate::ApolloRouterError::NoLicense;
 use crate::configuration::Configuration;
@@ -292,6 +293,7 @@ impl<FA: RouterSuperServiceFactory> State<FA> {
             Running {
                 server_handle: Some(server_handle),
                 mut all_connections_stopped_signals,
+                router_service_factory,
                 ..
             } => {
                 tracing::info!("shutting down");
@@ -306,6 +308,7 @@ impl<FA: RouterSuperServiceFactory> State<FA> {
                 // We ignore the results of recv()
                 let _: Vec<_> = futs.collect().await;
                 tracing::info!("all connections shut down");
+                router_service_factory.shutdown().await;
                 state
             }
             _ => Stopped,
@@ -593,6 +596,17 @@ where
                         .await
                 }
                 NoMoreSchema => state.no_more_schema().await,
+                UpdateSchemaAndConfiguration(schema, configuration) => {
+                    state
+                        .update_inputs(
+                            &mut self,
+                            Some(Arc::new(schema)),
+                            Some(configuration),
+                            None,
+                            false,
+                        )
+                        .await
+                }
                 UpdateLicense(license) => {
                     state
                         .update_inputs(&mut self, None, None, Some(license), false)
@@ -958,6 +972,38 @@ mod tests {
         assert_eq!(shutdown_receivers.0.lock().len(), 2);
     }
 
+    #[test(tokio::test)]
+    async fn startup_reload_schema_and_configuration() {
+        // A release changing both the schema and the configuration is applied in one reload
+        let router_factory = create_mock_router_configurator(2);
+        let (server_factory, shutdown_receivers) = create_mock_server_factory(2);
+        let minimal_schema = include_str!("testdata/minimal_supergraph.graphql");
+        assert_matches!(
+            execute(
+                server_factory,
+                router_factory,
+                stream::iter(vec![
+                    UpdateSchemaAndConfiguration(
+                        SchemaState {
+                            sdl: minimal_schema.to_owned(),
+                            launch_id: None
+                        },
+                        Arc::new(Configuration::builder().build().unwrap())
+                    ),
+                    UpdateLicense(Default::default()),
+                    UpdateSchemaAndConfiguration(
+                        example_schema(),
+                        Arc::new(Configuration::builder().build().unwrap())
+                    ),
+                    Shutdown
+                ])
+            )
+            .await,
+            Ok(())
+        );
+        assert_eq!(shutdown_receivers.0.lock().len(), 2);
+    }
+
     #[test(tokio::test)]
     async fn startup_no_reload_schema() {
         let router_factory = create_mock_router_configurator(1);
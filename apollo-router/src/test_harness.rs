@@ -42,6 +42,8 @@ use crate::uplink::license_enforcement::LicenseState;
 /// Mocks for services the Apollo Router must integrate with.
 pub mod mocks;
 
+mod contracts;
+
 #[cfg(test)]
 pub(crate) mod http_client;
 
@@ -94,6 +96,7 @@ pub struct TestHarness<'a> {
     configuration: Option<Arc<Configuration>>,
     extra_plugins: Vec<(String, Box<dyn DynPlugin>)>,
     subgraph_network_requests: bool,
+    subgraph_contract_checks: bool,
 }
 
 // Not using buildstructor because `extra_plugin` has non-trivial signature and behavior
@@ -105,6 +108,7 @@ impl<'a> TestHarness<'a> {
             configuration: None,
             extra_plugins: Vec::new(),
             subgraph_network_requests: false,
+            subgraph_contract_checks: false,
         }
     }
 
@@ -270,6 +274,22 @@ impl<'a> TestHarness<'a> {
         self
     }
 
+    /// Checks every subgraph response against the schema of its subgraph, as extracted from the
+    /// supergraph, for the subgraph query the router actually sent.
+    ///
+    /// Subgraph responses from [`MockedSubgraphs`], [`subgraph_hook`][Self::subgraph_hook] or
+    /// other plugins given to [`extra_plugin`][Self::extra_plugin] are checked, so that mocks
+    /// can't drift from what the subgraphs return. A field missing from a response, a field that
+    /// was not selected, a value of the wrong type or a null value for a non-null type fails the
+    /// subgraph request, and is reported as a GraphQL error in the response.
+    ///
+    /// Dropping the built service panics if any response didn't match, so that the test fails
+    /// even if it doesn't check the errors of its responses.
+    pub fn with_subgraph_contract_checks(mut self) -> Self {
+        self.subgraph_contract_checks = true;
+        self
+    }
+
     pub(crate) async fn build_common(
         self,
     ) -> Result<(Arc<Configuration>, Arc<Schema>, SupergraphCreator), BoxError> {
@@ -294,12 +314,28 @@ impl<'a> TestHarness<'a> {
         let canned_schema = include_str!("../testing_schema.graphql");
         let schema = self.schema.unwrap_or(canned_schema);
         let schema = Arc::new(Schema::parse(schema, &config)?);
+        let mut extra_plugins = self.extra_plugins;
+        if self.subgraph_contract_checks {
+            // First, so that it sees the responses of the other extra plugins and mocks
+            let contracts =
+                contracts::SubgraphContracts::from_supergraph(schema.federation_supergraph())?;
+            extra_plugins.insert(
+                0,
+                (
+                    format!(
+                        "extra_plugins.{}",
+                        std::any::type_name::<contracts::SubgraphContracts>()
+                    ),
+                    contracts.into(),
+                ),
+            );
+        }
         let supergraph_creator = YamlRouterFactory
             .inner_create_supergraph(
                 config.clone(),
                 schema.clone(),
                 None,
-                Some(self.extra_plugins),
+                Some(extra_plugins),
                 Default::default(),
             )
             .await?;
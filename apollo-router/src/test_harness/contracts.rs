@@ -0,0 +1,682 @@
+//! Contract checks of subgraph responses against subgraph schemas.
+//!
+//! Every subgraph response, mocked or not, is checked against the schema of its subgraph as
+//! extracted from the supergraph, for the subgraph query the router actually sent. Responses that
+//! don't match, such as a missing field or a value of the wrong type, are replaced with a
+//! [`ContractViolation`] error, which the router reports as a GraphQL error.
+//!
+//! Violations are also recorded, and dropping the router panics if any were, so that a test
+//! fails even when it doesn't check the errors of its responses.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use apollo_compiler::ExecutableDocument;
+use apollo_compiler::Name;
+use apollo_compiler::Schema;
+use apollo_compiler::ast;
+use apollo_compiler::executable::DirectiveList;
+use apollo_compiler::executable::Field;
+use apollo_compiler::executable::Selection;
+use apollo_compiler::executable::SelectionSet;
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::validation::Valid;
+use apollo_federation::Supergraph;
+use apollo_federation::error::FederationError;
+use indexmap::IndexMap;
+use parking_lot::Mutex;
+use serde_json_bytes::Value;
+use tower::BoxError;
+use tower::ServiceBuilder;
+use tower::ServiceExt;
+
+use crate::graphql;
+use crate::json_ext::Object;
+use crate::layers::ServiceBuilderExt;
+use crate::plugin::Plugin;
+use crate::plugin::PluginInit;
+use crate::services::subgraph;
+
+/// Checks subgraph responses against the subgraph schemas extracted from the supergraph
+pub(crate) struct SubgraphContracts {
+    schemas: HashMap<String, Arc<Valid<Schema>>>,
+    violations: Arc<Mutex<Vec<String>>>,
+}
+
+impl SubgraphContracts {
+    pub(crate) fn from_supergraph(supergraph: &Supergraph) -> Result<Self, FederationError> {
+        let schemas = supergraph
+            .extract_subgraphs()?
+            .into_iter()
+            .map(|(name, subgraph)| (name.to_string(), Arc::new(subgraph.schema.schema().clone())))
+            .collect();
+        Ok(Self {
+            schemas,
+            violations: Default::default(),
+        })
+    }
+}
+
+impl Drop for SubgraphContracts {
+    fn drop(&mut self) {
+        let violations = std::mem::take(&mut *self.violations.lock());
+        if !violations.is_empty() && !std::thread::panicking() {
+            panic!(
+                "subgraph responses did not match their schemas:\n\n{}",
+                violations.join("\n\n")
+            );
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for SubgraphContracts {
+    type Config = ();
+
+    async fn new(init: PluginInit<Self::Config>) -> Result<Self, BoxError> {
+        Ok(Self {
+            schemas: (*init.subgraph_schemas).clone(),
+            violations: Default::default(),
+        })
+    }
+
+    fn subgraph_service(
+        &self,
+        subgraph_name: &str,
+        service: subgraph::BoxService,
+    ) -> subgraph::BoxService {
+        let Some(schema) = self.schemas.get(subgraph_name).cloned() else {
+            return service;
+        };
+        let subgraph_name = subgraph_name.to_string();
+        let recorded = self.violations.clone();
+        ServiceBuilder::new()
+            .map_future_with_request_data(
+                |request: &subgraph::Request| request.subgraph_request.body().clone(),
+                move |request: graphql::Request, future| {
+                    let schema = schema.clone();
+                    let subgraph_name = subgraph_name.clone();
+                    let recorded = recorded.clone();
+                    async move {
+                        let response: subgraph::Response = future.await?;
+                        let violations = check(&schema, &request, response.response.body());
+                        if !violations.is_empty() {
+                            let violation = ContractViolation {
+                                subgraph_name,
+                                violations,
+                                query: request.query.unwrap_or_default(),
+                                response: serde_json::to_string(response.response.body())
+                                    .unwrap_or_default(),
+                            };
+                            recorded.lock().push(violation.to_string());
+                            return Err(BoxError::from(violation));
+                        }
+                        Ok(response)
+                    }
+                },
+            )
+            .service(service)
+            .boxed()
+    }
+}
+
+/// A subgraph response that does not match the schema of its subgraph
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "the response of subgraph `{subgraph_name}` does not match its schema:\n- {}\n\nquery: {query}\nresponse: {response}",
+    .violations.join("\n- ")
+)]
+pub(crate) struct ContractViolation {
+    subgraph_name: String,
+    violations: Vec<String>,
+    query: String,
+    response: String,
+}
+
+/// Checks a subgraph response against the subgraph schema, for the query that was sent.
+/// Returns a description of each mismatch.
+pub(crate) fn check(
+    schema: &Valid<Schema>,
+    request: &graphql::Request,
+    response: &graphql::Response,
+) -> Vec<String> {
+    let Some(query) = request.query.as_deref() else {
+        return vec!["the subgraph request has no query".to_string()];
+    };
+    let document = match ExecutableDocument::parse_and_validate(schema, query, "subgraph_query") {
+        Ok(document) => document,
+        Err(e) => {
+            return vec![format!(
+                "the subgraph query is not valid for the subgraph schema: {}",
+                e.errors
+            )];
+        }
+    };
+    let operation = match document.operations.get(request.operation_name.as_deref()) {
+        Ok(operation) => operation,
+        Err(e) => return vec![e.to_graphql_error(&document.sources).message],
+    };
+    let data = match &response.data {
+        None | Some(Value::Null) => return Vec::new(),
+        Some(data) => data,
+    };
+
+    let mut checker = Checker {
+        schema,
+        document: &document,
+        variables: &request.variables,
+        violations: Vec::new(),
+    };
+    checker.check_object(
+        "data",
+        operation.object_type(),
+        &[&operation.selection_set],
+        data,
+        None,
+    );
+    checker.violations
+}
+
+/// Fields selected for a response key
+struct CollectedFields<'a> {
+    fields: Vec<&'a Field>,
+    /// False when the field might not be selected, for example when its fragment applies to
+    /// a type that is not known
+    required: bool,
+}
+
+struct Checker<'a> {
+    schema: &'a Valid<Schema>,
+    document: &'a ExecutableDocument,
+    variables: &'a Object,
+    violations: Vec<String>,
+}
+
+impl<'a> Checker<'a> {
+    fn check_object(
+        &mut self,
+        path: &str,
+        type_name: &Name,
+        selection_sets: &[&'a SelectionSet],
+        value: &Value,
+        typename_hint: Option<&str>,
+    ) {
+        let Value::Object(object) = value else {
+            self.violations.push(format!(
+                "`{path}` is not an object of type {type_name}: {value}"
+            ));
+            return;
+        };
+
+        let typename = object.get("__typename").and_then(Value::as_str);
+        let runtime_type = if self.schema.get_object(type_name.as_str()).is_some() {
+            if typename.is_some_and(|typename| typename != type_name.as_str()) {
+                self.violations.push(format!(
+                    "`{path}.__typename` is not {type_name}: {}",
+                    typename.unwrap_or_default()
+                ));
+            }
+            Some(type_name.as_str())
+        } else {
+            let runtime_type = typename.or(typename_hint);
+            if let Some(runtime_type) = runtime_type {
+                if !self.schema.is_subtype(type_name.as_str(), runtime_type) {
+                    self.violations.push(format!(
+                        "`{path}` has type {runtime_type}, which is not a possible type of {type_name}"
+                    ));
+                    return;
+                }
+            }
+            runtime_type
+        };
+
+        let mut collected = IndexMap::new();
+        for selection_set in selection_sets {
+            self.collect_fields(
+                selection_set,
+                type_name.as_str(),
+                runtime_type,
+                true,
+                &mut collected,
+                &mut HashSet::new(),
+            );
+        }
+
+        for (response_key, CollectedFields { fields, required }) in &collected {
+            let field_path = format!("{path}.{response_key}");
+            let Some(field_value) = object.get(*response_key) else {
+                if *required {
+                    self.violations
+                        .push(format!("`{field_path}` is missing from the response"));
+                }
+                continue;
+            };
+            let field = fields[0];
+            if field.name.as_str() == "__typename" {
+                if !field_value.is_string() {
+                    self.violations
+                        .push(format!("`{field_path}` is not a string: {field_value}"));
+                }
+                continue;
+            }
+            let representations = if field.name.as_str() == "_entities" {
+                self.representations(field)
+            } else {
+                None
+            };
+            let selection_sets = fields
+                .iter()
+                .map(|&field| &field.selection_set)
+                .collect::<Vec<_>>();
+            self.check_value(
+                &field_path,
+                field.ty(),
+                &selection_sets,
+                field_value,
+                representations,
+                None,
+            );
+        }
+
+        for key in object.keys() {
+            if !collected.contains_key(key.as_str()) {
+                self.violations.push(format!(
+                    "`{path}.{}` is in the response but was not selected",
+                    key.as_str()
+                ));
+            }
+        }
+    }
+
+    fn check_value(
+        &mut self,
+        path: &str,
+        ty: &ast::Type,
+        selection_sets: &[&'a SelectionSet],
+        value: &Value,
+        representations: Option<&[Value]>,
+        typename_hint: Option<&str>,
+    ) {
+        if value.is_null() {
+            if ty.is_non_null() {
+                self.violations
+                    .push(format!("`{path}` is null but has the non-null type {ty}"));
+            }
+            return;
+        }
+
+        if ty.is_list() {
+            let Value::Array(items) = value else {
+                self.violations
+                    .push(format!("`{path}` is not a list of type {ty}: {value}"));
+                return;
+            };
+            for (index, item) in items.iter().enumerate() {
+                // Entities are returned in the order of their representations
+                let typename_hint = representations
+                    .and_then(|representations| representations.get(index))
+                    .and_then(|representation| representation.get("__typename"))
+                    .and_then(Value::as_str);
+                self.check_value(
+                    &format!("{path}.{index}"),
+                    ty.item_type(),
+                    selection_sets,
+                    item,
+                    None,
+                    typename_hint,
+                );
+            }
+            return;
+        }
+
+        let type_name = ty.inner_named_type();
+        match self.schema.types.get(type_name) {
+            Some(ExtendedType::Scalar(_)) => {
+                if !scalar_matches(type_name, value) {
+                    self.violations.push(format!(
+                        "`{path}` is not a value of type {type_name}: {value}"
+                    ));
+                }
+            }
+            Some(ExtendedType::Enum(enum_type)) => {
+                if !value
+                    .as_str()
+                    .is_some_and(|value| enum_type.values.contains_key(value))
+                {
+                    self.violations.push(format!(
+                        "`{path}` is not a value of enum {type_name}: {value}"
+                    ));
+                }
+            }
+            Some(ExtendedType::Object(_) | ExtendedType::Interface(_) | ExtendedType::Union(_)) => {
+                self.check_object(path, type_name, selection_sets, value, typename_hint);
+            }
+            Some(ExtendedType::InputObject(_)) | None => {}
+        }
+    }
+
+    fn collect_fields(
+        &self,
+        selection_set: &'a SelectionSet,
+        type_name: &str,
+        runtime_type: Option<&str>,
+        required: bool,
+        collected: &mut IndexMap<&'a str, CollectedFields<'a>>,
+        visited_fragments: &mut HashSet<&'a str>,
+    ) {
+        for selection in &selection_set.selections {
+            match selection {
+                Selection::Field(field) => {
+                    let Some(included) = self.included(&field.directives, required) else {
+                        continue;
+                    };
+                    let entry = collected
+                        .entry(field.response_key().as_str())
+                        .or_insert_with(|| CollectedFields {
+                            fields: Vec::new(),
+                            required: false,
+                        });
+                    entry.fields.push(field);
+                    entry.required |= included;
+                }
+                Selection::InlineFragment(inline) => {
+                    let Some(included) = self.included(&inline.directives, required) else {
+                        continue;
+                    };
+                    let Some(applies) = self.fragment_applies(
+                        inline.type_condition.as_ref(),
+                        type_name,
+                        runtime_type,
+                    ) else {
+                        continue;
+                    };
+                    self.collect_fields(
+                        &inline.selection_set,
+                        type_name,
+                        runtime_type,
+                        included && applies,
+                        collected,
+                        visited_fragments,
+                    );
+                }
+                Selection::FragmentSpread(spread) => {
+                    let Some(included) = self.included(&spread.directives, required) else {
+                        continue;
+                    };
+                    if !visited_fragments.insert(spread.fragment_name.as_str()) {
+                        continue;
+                    }
+                    let Some(fragment) = self.document.fragments.get(&spread.fragment_name) else {
+                        continue;
+                    };
+                    let Some(applies) = self.fragment_applies(
+                        Some(fragment.type_condition()),
+                        type_name,
+                        runtime_type,
+                    ) else {
+                        continue;
+                    };
+                    self.collect_fields(
+                        &fragment.selection_set,
+                        type_name,
+                        runtime_type,
+                        included && applies,
+                        collected,
+                        visited_fragments,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Whether a fragment applies: `None` if it does not, `Some(false)` if it is not known.
+    fn fragment_applies(
+        &self,
+        type_condition: Option<&Name>,
+        type_name: &str,
+        runtime_type: Option<&str>,
+    ) -> Option<bool> {
+        let Some(type_condition) = type_condition else {
+            return Some(true);
+        };
+        match runtime_type {
+            Some(runtime_type) => (type_condition.as_str() == runtime_type
+                || self
+                    .schema
+                    .is_subtype(type_condition.as_str(), runtime_type))
+            .then_some(true),
+            None => Some(type_condition.as_str() == type_name),
+        }
+    }
+
+    /// Whether a selection is included with `@skip` and `@include`: `None` if it is not,
+    /// `Some(false)` if it is not known or `required` is false.
+    fn included(&self, directives: &DirectiveList, required: bool) -> Option<bool> {
+        let condition = |name: &str, default: bool| {
+            let Some(directive) = directives.get(name) else {
+                return Some(default);
+            };
+            match directive
+                .specified_argument_by_name("if")
+                .map(|value| &**value)
+            {
+                Some(ast::Value::Boolean(value)) => Some(*value),
+                Some(ast::Value::Variable(variable)) => self
+                    .variables
+                    .get(variable.as_str())
+                    .and_then(Value::as_bool),
+                _ => None,
+            }
+        };
+        let skip = condition("skip", false);
+        let include = condition("include", true);
+        match (skip, include) {
+            (Some(true), _) | (_, Some(false)) => None,
+            (Some(false), Some(true)) => Some(required),
+            _ => Some(false),
+        }
+    }
+
+    /// The representations sent to the `_entities` field
+    fn representations(&self, field: &Field) -> Option<&'a [Value]> {
+        match &**field.specified_argument_by_name("representations")? {
+            ast::Value::Variable(variable) => self
+                .variables
+                .get(variable.as_str())?
+                .as_array()
+                .map(Vec::as_slice),
+            _ => None,
+        }
+    }
+}
+
+fn scalar_matches(type_name: &str, value: &Value) -> bool {
+    match type_name {
+        "Int" => value
+            .as_i64()
+            .is_some_and(|value| i32::try_from(value).is_ok()),
+        "Float" => value.is_number(),
+        "String" => value.is_string(),
+        "Boolean" => value.as_bool().is_some(),
+        "ID" => value.is_string() || value.is_i64() || value.is_u64(),
+        // Custom scalars may be represented by any JSON value
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json_bytes::json;
+
+    use super::*;
+    use crate::TestHarness;
+    use crate::services::supergraph;
+
+    const SCHEMA: &str = r#"
+        type Query {
+          me: User
+          topProducts: [Product!]!
+          node(id: ID!): Node
+          _entities(representations: [_Any!]!): [_Entity]!
+        }
+        scalar _Any
+        union _Entity = User | Product
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name: String reviewCount: Int status: Status }
+        type Product implements Node { id: ID! upc: String! price: Float }
+        enum Status { ACTIVE INACTIVE }
+    "#;
+
+    fn check_response(query: &str, variables: Value, data: Value) -> Vec<String> {
+        let schema = Schema::parse_and_validate(SCHEMA, "schema.graphql").unwrap();
+        let request = graphql::Request::fake_builder()
+            .query(query)
+            .variables(variables.as_object().cloned().unwrap_or_default())
+            .build();
+        let response = graphql::Response::builder().data(data).build();
+        check(&schema, &request, &response)
+    }
+
+    #[test]
+    fn accepts_matching_responses() {
+        let violations = check_response(
+            r#"query($withName: Boolean!) {
+              me { id name @include(if: $withName) reviewCount status }
+              topProducts { __typename upc price }
+              node(id: "1") { __typename id ... on Product { upc } ...UserName }
+            }
+            fragment UserName on User { name }"#,
+            json!({ "withName": false }),
+            json!({
+                "me": { "id": 1, "reviewCount": 3, "status": "ACTIVE" },
+                "topProducts": [{ "__typename": "Product", "upc": "1", "price": 12 }],
+                "node": { "__typename": "User", "id": "1", "name": "Ada" },
+            }),
+        );
+        assert_eq!(violations, Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_mismatched_shapes_and_types() {
+        let violations = check_response(
+            "{ me { id name reviewCount status } topProducts { upc } node(id: \"1\") { id } }",
+            json!({}),
+            json!({
+                "me": { "id": "1", "reviewCount": 1.5, "status": "DELETED", "email": "a@b.c" },
+                "topProducts": [{ "upc": null }, null],
+                "node": { "__typename": "Review", "id": "1" },
+            }),
+        );
+        assert_eq!(
+            violations,
+            vec![
+                "`data.me.name` is missing from the response",
+                "`data.me.reviewCount` is not a value of type Int: 1.5",
+                "`data.me.status` is not a value of enum Status: \"DELETED\"",
+                "`data.me.email` is in the response but was not selected",
+                "`data.topProducts.0.upc` is null but has the non-null type String!",
+                "`data.topProducts.1` is null but has the non-null type Product!",
+                "`data.node` has type Review, which is not a possible type of Node",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_entities_against_the_type_of_their_representation() {
+        let query = r#"query($representations: [_Any!]!) {
+          _entities(representations: $representations) {
+            ... on User { name }
+            ... on Product { upc }
+          }
+        }"#;
+        let variables = json!({ "representations": [
+            { "__typename": "User", "id": "1" },
+            { "__typename": "Product", "id": "2" },
+        ] });
+
+        let violations = check_response(
+            query,
+            variables.clone(),
+            json!({ "_entities": [{ "name": "Ada" }, { "upc": "2" }] }),
+        );
+        assert_eq!(violations, Vec::<String>::new());
+
+        let violations = check_response(
+            query,
+            variables,
+            json!({ "_entities": [{ "upc": "2" }, { "name": "Ada" }] }),
+        );
+        assert_eq!(
+            violations,
+            vec![
+                "`data._entities.0.name` is missing from the response",
+                "`data._entities.0.upc` is in the response but was not selected",
+                "`data._entities.1.upc` is missing from the response",
+                "`data._entities.1.name` is in the response but was not selected",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_queries() {
+        let violations = check_response("{ me { email } }", json!({}), json!({ "me": null }));
+        assert_eq!(violations.len(), 1);
+        assert!(
+            violations[0].starts_with("the subgraph query is not valid for the subgraph schema"),
+            "{violations:?}"
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "subgraph responses did not match their schemas")]
+    async fn reports_drifting_mocks_as_errors() {
+        let router = TestHarness::builder()
+            .schema(include_str!("../../testing_schema.graphql"))
+            .with_subgraph_contract_checks()
+            .subgraph_hook(|name, service| {
+                if name != "products" {
+                    return service;
+                }
+                tower::service_fn(|request: subgraph::Request| async move {
+                    Ok(subgraph::Response::fake_builder()
+                        .data(json!({ "topProducts": [{ "upc": 1, "name": "Table" }] }))
+                        .context(request.context)
+                        .subgraph_name(request.subgraph_name)
+                        .build())
+                })
+                .boxed()
+            })
+            .build_supergraph()
+            .await
+            .unwrap();
+
+        let request = supergraph::Request::fake_builder()
+            .query("{ topProducts { upc name } }")
+            .build()
+            .unwrap();
+        let response = router
+            .clone()
+            .oneshot(request)
+            .await
+            .unwrap()
+            .next_response()
+            .await
+            .unwrap();
+        let error = &response.errors[0];
+        assert!(
+            error
+                .message
+                .contains("the response of subgraph `products` does not match its schema"),
+            "{error:?}"
+        );
+        assert!(
+            error
+                .message
+                .contains("`data.topProducts.0.upc` is not a value of type String: 1"),
+            "{error:?}"
+        );
+        // Fails the test even though the errors were checked
+        drop(router);
+    }
+}
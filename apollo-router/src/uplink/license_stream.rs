@@ -510,6 +510,7 @@ mod test {
         NoMoreConfiguration,
         UpdateSchema,
         NoMoreSchema,
+        UpdateSchemaAndConfiguration,
         UpdateLicense,
         HaltLicense,
         WarnLicense,
@@ -525,6 +526,9 @@ mod test {
                 Event::NoMoreConfiguration => SimpleEvent::NoMoreConfiguration,
                 Event::UpdateSchema(_) => SimpleEvent::UpdateSchema,
                 Event::NoMoreSchema => SimpleEvent::NoMoreSchema,
+                Event::UpdateSchemaAndConfiguration(..) => {
+                    SimpleEvent::UpdateSchemaAndConfiguration
+                }
                 Event::UpdateLicense(LicenseState::LicensedHalt { limits: _ }) => {
                     SimpleEvent::HaltLicense
                 }
@@ -1,6 +1,9 @@
 use apollo_router::_private::mock_subgraphs_subgraph_call;
+use apollo_router::TestHarness;
 use apollo_router::graphql::Request;
+use apollo_router::services::supergraph;
 use serde_json_bytes::json;
+use tower::ServiceExt;
 
 #[test]
 fn test_cache_tags() {
@@ -72,3 +75,126 @@ fn test_cache_tags() {
         - - product-1
     "###);
 }
+
+#[test]
+fn test_rules() {
+    let sdl = include_str!("../fixtures/supergraph.graphql");
+    let supergraph = apollo_federation::Supergraph::new(sdl).unwrap();
+    let subgraphs = supergraph.extract_subgraphs().unwrap();
+    let schema = subgraphs.get("products").unwrap().schema.schema();
+    let config = json!({
+        "query": {
+            "topProducts": [{"upc": "1"}, {"upc": "2"}],
+        },
+        "rules": [
+            {
+                "when": {"arguments": {"topProducts": {"first": 1}}},
+                "data": {"topProducts": [{"upc": "1"}]},
+            },
+            {
+                "when": {"operation_name": "Failing"},
+                "errors": [{"message": "products are unavailable"}],
+            },
+            {
+                "when": {"variables": {"first": 0}},
+                "data": {"topProducts": []},
+                "errors": [{"message": "no products requested"}],
+            },
+        ],
+    });
+
+    // Default arguments do not match the first rule
+    let request = Request::fake_builder()
+        .query("{ topProducts { upc } }")
+        .build();
+    let response = mock_subgraphs_subgraph_call(config.clone(), schema, &request).unwrap();
+    insta::assert_yaml_snapshot!(response, @r###"
+    data:
+      topProducts:
+        - upc: "1"
+        - upc: "2"
+    "###);
+
+    // Arguments are matched after variables are applied
+    let request = Request::fake_builder()
+        .query("query Top($first: Int) { topProducts(first: $first) { upc } }")
+        .variable("first", 1)
+        .build();
+    let response = mock_subgraphs_subgraph_call(config.clone(), schema, &request).unwrap();
+    insta::assert_yaml_snapshot!(response, @r###"
+    data:
+      topProducts:
+        - upc: "1"
+    "###);
+
+    let request = Request::fake_builder()
+        .query("query Failing { topProducts { upc } }")
+        .operation_name("Failing")
+        .build();
+    let response = mock_subgraphs_subgraph_call(config.clone(), schema, &request).unwrap();
+    insta::assert_yaml_snapshot!(response, @r###"
+    errors:
+      - message: products are unavailable
+    "###);
+
+    let request = Request::fake_builder()
+        .query("query Top($first: Int) { topProducts(first: $first) { upc } }")
+        .variable("first", 0)
+        .build();
+    let response = mock_subgraphs_subgraph_call(config.clone(), schema, &request).unwrap();
+    insta::assert_yaml_snapshot!(response, @r###"
+    data:
+      topProducts: []
+    errors:
+      - message: no products requested
+    "###);
+}
+
+#[tokio::test]
+async fn test_status_code_and_fixture_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = dir.path().join("products.yaml");
+    std::fs::write(
+        &fixture,
+        "query:\n  topProducts: []\nrules:\n  - status_code: 503\n    latency:\n      fixed: 10ms\n",
+    )
+    .unwrap();
+
+    let router = TestHarness::builder()
+        .schema(include_str!("../fixtures/supergraph.graphql"))
+        .configuration_json(serde_json::json!({
+            "include_subgraph_errors": { "all": true },
+            "experimental_mock_subgraphs": {
+                "products": { "file": fixture },
+            },
+        }))
+        .unwrap()
+        .build_supergraph()
+        .await
+        .unwrap();
+
+    let request = supergraph::Request::fake_builder()
+        .query("{ topProducts { upc } }")
+        .build()
+        .unwrap();
+    let start = std::time::Instant::now();
+    let response = router
+        .oneshot(request)
+        .await
+        .unwrap()
+        .next_response()
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(10));
+    assert_eq!(
+        response.errors[0].extensions["code"],
+        "SUBREQUEST_HTTP_ERROR"
+    );
+    assert!(
+        response.errors[0]
+            .message
+            .contains("'products': 503: Service Unavailable"),
+        "{:?}",
+        response.errors
+    );
+}